@@ -34,6 +34,7 @@ enum OpType {
   Equal,
   NotEqual,
   Distinct,
+  Coalesce,
   In,
   NotIn,
 };
@@ -55,7 +56,7 @@ enum LitType {
   Struct,
   Array
 };
-enum ExpressionType { BinOp, Variadic, Literal, Unary, Column };
+enum ExpressionType { BinOp, Variadic, Literal, Unary, Column, RowIndex, Cast };
 enum VariadicType {
   And,
   Or,
@@ -82,6 +83,10 @@ struct Unary {
   enum UnaryType type;
   ExpressionItemList sub_expr;
 };
+struct Cast {
+  char* data_type;
+  ExpressionItemList sub_expr;
+};
 struct BinaryData {
   uint8_t* buf;
   uintptr_t len;
@@ -167,6 +172,7 @@ DEFINE_BINOP(visit_expr_ge, GreaterThaneOrEqual)
 DEFINE_BINOP(visit_expr_eq, Equal)
 DEFINE_BINOP(visit_expr_ne, NotEqual)
 DEFINE_BINOP(visit_expr_distinct, Distinct)
+DEFINE_BINOP(visit_expr_coalesce, Coalesce)
 DEFINE_BINOP(visit_expr_in, In)
 DEFINE_BINOP(visit_expr_not_in, NotIn)
 #undef DEFINE_BINOP
@@ -304,6 +310,26 @@ void visit_expr_column(void* data, uintptr_t sibling_id_list, KernelStringSlice
   put_expr_item(data, sibling_id_list, column_name, Column);
 }
 
+/*************************************************************
+ * Row Index Expression
+ ************************************************************/
+void visit_expr_row_index(void* data, uintptr_t sibling_id_list) {
+  put_expr_item(data, sibling_id_list, NULL, RowIndex);
+}
+
+/*************************************************************
+ * Cast Expression
+ ************************************************************/
+void visit_expr_cast(void* data,
+                     uintptr_t sibling_list_id,
+                     uintptr_t child_list_id,
+                     KernelStringSlice data_type) {
+  struct Cast* cast = malloc(sizeof(struct Cast));
+  cast->data_type = allocate_string(data_type);
+  cast->sub_expr = get_expr_list(data, child_list_id);
+  put_expr_item(data, sibling_list_id, cast, Cast);
+}
+
 /*************************************************************
  * EngineExpressionVisitor Implementation
  ************************************************************/
@@ -350,6 +376,7 @@ ExpressionItemList construct_predicate(SharedExpression* predicate) {
     .visit_eq = visit_expr_eq,
     .visit_ne = visit_expr_ne,
     .visit_distinct = visit_expr_distinct,
+    .visit_coalesce = visit_expr_coalesce,
     .visit_in = visit_expr_in,
     .visit_not_in = visit_expr_not_in,
     .visit_add = visit_expr_add,
@@ -358,6 +385,8 @@ ExpressionItemList construct_predicate(SharedExpression* predicate) {
     .visit_divide = visit_expr_divide,
     .visit_column = visit_expr_column,
     .visit_struct_expr = visit_expr_struct_expr,
+    .visit_row_index = visit_expr_row_index,
+    .visit_cast = visit_expr_cast,
   };
   uintptr_t top_level_id = visit_expression(&predicate, &visitor);
   ExpressionItemList top_level_expr = data.lists[top_level_id];
@@ -430,6 +459,15 @@ void free_expression_item(ExpressionItem ref) {
       free(ref.ref);
       break;
     }
+    case RowIndex:
+      break;
+    case Cast: {
+      struct Cast* cast = ref.ref;
+      free(cast->data_type);
+      free_expression_list(cast->sub_expr);
+      free(cast);
+      break;
+    }
   }
 }
 void free_expression_list(ExpressionItemList list) {
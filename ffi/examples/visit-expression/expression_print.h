@@ -75,6 +75,9 @@ void print_tree_helper(ExpressionItem ref, int depth) {
         case Distinct:
           printf("Distinct\n");
           break;
+        case Coalesce:
+          printf("Coalesce\n");
+          break;
       }
       print_expression_item_list(op->exprs, depth + 1);
       break;
@@ -198,6 +201,17 @@ void print_tree_helper(ExpressionItem ref, int depth) {
       char* column_name = ref.ref;
       printf("Column(%s)\n", column_name);
       break;
+    case RowIndex:
+      print_n_spaces(depth);
+      printf("RowIndex\n");
+      break;
+    case Cast: {
+      print_n_spaces(depth);
+      struct Cast* cast = ref.ref;
+      printf("Cast(%s)\n", cast->data_type);
+      print_expression_item_list(cast->sub_expr, depth + 1);
+      break;
+    }
   }
 }
 
@@ -53,6 +53,15 @@ pub enum KernelError {
     ChangeDataFeedIncompatibleSchema,
     InvalidCheckpoint,
     LiteralExpressionTransformError,
+    InvalidTransactionError,
+    TransactionConflictError,
+    TableAlreadyExistsError,
+    InvalidPartitionColumnError,
+    TimestampOutOfRangeError,
+    InvalidClusteringColumnError,
+    InvalidIdentityColumnError,
+    InvalidGeneratedColumnError,
+    InvalidConstraintError,
 }
 
 impl From<Error> for KernelError {
@@ -114,6 +123,15 @@ impl From<Error> for KernelError {
             Error::LiteralExpressionTransformError(_) => {
                 KernelError::LiteralExpressionTransformError
             }
+            Error::InvalidTransaction(_) => KernelError::InvalidTransactionError,
+            Error::TransactionConflict(_) => KernelError::TransactionConflictError,
+            Error::TableAlreadyExists(_) => KernelError::TableAlreadyExistsError,
+            Error::InvalidPartitionColumn(_) => KernelError::InvalidPartitionColumnError,
+            Error::TimestampOutOfRange(_) => KernelError::TimestampOutOfRangeError,
+            Error::InvalidClusteringColumn(_) => KernelError::InvalidClusteringColumnError,
+            Error::InvalidIdentityColumn(_) => KernelError::InvalidIdentityColumnError,
+            Error::InvalidGeneratedColumn(_) => KernelError::InvalidGeneratedColumnError,
+            Error::InvalidConstraint(_) => KernelError::InvalidConstraintError,
         }
     }
 }
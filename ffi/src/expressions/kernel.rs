@@ -5,8 +5,8 @@ use std::ffi::c_void;
 
 use crate::{handle::Handle, kernel_string_slice, KernelStringSlice};
 use delta_kernel::expressions::{
-    ArrayData, BinaryExpression, BinaryOperator, Expression, Scalar, StructData, UnaryExpression,
-    UnaryOperator, VariadicExpression, VariadicOperator,
+    ArrayData, BinaryExpression, BinaryOperator, CastExpression, Expression, Scalar, StructData,
+    UnaryExpression, UnaryOperator, VariadicExpression, VariadicOperator,
 };
 
 /// Free the memory the passed SharedExpression
@@ -149,6 +149,9 @@ pub struct EngineExpressionVisitor {
     /// Visits the `Distinct` binary operator belonging to the list identified by `sibling_list_id`.
     /// The operands will be in a _two_ item list identified by `child_list_id`
     pub visit_distinct: VisitBinaryOpFn,
+    /// Visits the `Coalesce` binary operator belonging to the list identified by `sibling_list_id`.
+    /// The operands will be in a _two_ item list identified by `child_list_id`
+    pub visit_coalesce: VisitBinaryOpFn,
     /// Visits the `In` binary operator belonging to the list identified by `sibling_list_id`.
     /// The operands will be in a _two_ item list identified by `child_list_id`
     pub visit_in: VisitBinaryOpFn,
@@ -174,6 +177,17 @@ pub struct EngineExpressionVisitor {
     /// The sub-expressions of the `StructExpression` are in a list identified by `child_list_id`
     pub visit_struct_expr:
         extern "C" fn(data: *mut c_void, sibling_list_id: usize, child_list_id: usize),
+    /// Visits a `RowIndex` expression belonging to the list identified by `sibling_list_id`.
+    pub visit_row_index: extern "C" fn(data: *mut c_void, sibling_list_id: usize),
+    /// Visits a `Cast` expression belonging to the list identified by `sibling_list_id`, which
+    /// casts its operand to `data_type`, e.g. `date` or `timestamp`. The operand will be in a
+    /// _one_ item list identified by `child_list_id`
+    pub visit_cast: extern "C" fn(
+        data: *mut c_void,
+        sibling_list_id: usize,
+        child_list_id: usize,
+        data_type: KernelStringSlice,
+    ),
 }
 
 /// Visit the expression of the passed [`SharedExpression`] Handle using the provided `visitor`.
@@ -368,6 +382,7 @@ fn visit_expression_internal(
                     BinaryOperator::Equal => visitor.visit_eq,
                     BinaryOperator::NotEqual => visitor.visit_ne,
                     BinaryOperator::Distinct => visitor.visit_distinct,
+                    BinaryOperator::Coalesce => visitor.visit_coalesce,
                     BinaryOperator::In => visitor.visit_in,
                     BinaryOperator::NotIn => visitor.visit_not_in,
                 };
@@ -385,6 +400,20 @@ fn visit_expression_internal(
             Expression::Variadic(VariadicExpression { op, exprs }) => {
                 visit_expression_variadic(visitor, op, exprs, sibling_list_id)
             }
+            Expression::Cast(CastExpression { expr, data_type }) => {
+                let child_list_id = call!(visitor, make_field_list, 1);
+                visit_expression_impl(visitor, expr, child_list_id);
+                let data_type = data_type.to_string();
+                let data_type = kernel_string_slice!(data_type);
+                call!(
+                    visitor,
+                    visit_cast,
+                    sibling_list_id,
+                    child_list_id,
+                    data_type
+                )
+            }
+            Expression::RowIndex => call!(visitor, visit_row_index, sibling_list_id),
         }
     }
     let top_level = call!(visitor, make_field_list, 1);
@@ -4,14 +4,22 @@ use std::io::{Cursor, Read};
 use std::sync::Arc;
 
 use bytes::Bytes;
-use roaring::RoaringTreemap;
+use roaring::{RoaringBitmap, RoaringTreemap};
 use url::Url;
+use uuid::Uuid;
 
 use delta_kernel_derive::Schema;
 
 use crate::utils::require;
 use crate::{DeltaResult, Error, FileSystemClient};
 
+/// Magic number prefixing a `RoaringBitmapArray` serialized in the "portable" format
+const PORTABLE_ROARING_BITMAP_MAGIC: u32 = 1681511377;
+/// Magic number prefixing a `RoaringBitmapArray` serialized in the "native" format
+const NATIVE_ROARING_BITMAP_MAGIC: u32 = 1681511376;
+/// Version of the deletion vector file format, written as the first byte of every DV file
+const DV_FILE_FORMAT_VERSION: u8 = 1;
+
 #[derive(Debug, Clone, PartialEq, Eq, Schema)]
 #[cfg_attr(test, derive(serde::Serialize), serde(rename_all = "camelCase"))]
 pub struct DeletionVectorDescriptor {
@@ -113,11 +121,11 @@ impl DeletionVectorDescriptor {
                     .map_err(|_| Error::deletion_vector("Failed to decode DV"))?;
                 let magic = slice_to_u32(&byte_slice[0..4], Endian::Little)?;
                 match magic {
-                    1681511377 => RoaringTreemap::deserialize_from(&byte_slice[4..])
-                        .map_err(|err| Error::DeletionVector(err.to_string())),
-                    1681511376 => {
-                        todo!("Don't support native serialization in inline bitmaps yet");
+                    PORTABLE_ROARING_BITMAP_MAGIC => {
+                        RoaringTreemap::deserialize_from(&byte_slice[4..])
+                            .map_err(|err| Error::DeletionVector(err.to_string()))
                     }
+                    NATIVE_ROARING_BITMAP_MAGIC => deserialize_native_bitmap(&byte_slice[4..]),
                     _ => Err(Error::DeletionVector(format!("Invalid magic {magic}"))),
                 }
             }
@@ -137,7 +145,7 @@ impl DeletionVectorDescriptor {
                     .map_err(|err| Error::DeletionVector(err.to_string()))?;
                 let version = u8::from_be_bytes(version_buf);
                 require!(
-                    version == 1,
+                    version == DV_FILE_FORMAT_VERSION,
                     Error::DeletionVector(format!("Invalid version: {version}"))
                 );
 
@@ -153,7 +161,7 @@ impl DeletionVectorDescriptor {
                 );
                 let magic = read_u32(&mut cursor, Endian::Little)?;
                 require!(
-                    magic == 1681511377,
+                    magic == PORTABLE_ROARING_BITMAP_MAGIC,
                     Error::DeletionVector(format!("Invalid magic: {magic}"))
                 );
 
@@ -183,6 +191,143 @@ impl DeletionVectorDescriptor {
     ) -> DeltaResult<Vec<u64>> {
         Ok(self.read(fs_client, parent)?.into_iter().collect())
     }
+
+    /// Create a descriptor for a DV stored inline in the log (`storageType = 'i'`). Inline DVs
+    /// bloat the log, so they should only be used for small bitmaps.
+    pub fn try_new_inline(bitmap: &RoaringTreemap) -> DeltaResult<Self> {
+        let mut bytes = serialize_bitmap(bitmap)?;
+        let size_in_bytes = dv_size(&bytes)?;
+        // z85 encodes 4 bytes at a time, so zero-pad the data. Readers only consume the bitmap
+        // itself, so the padding is ignored.
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+        Ok(DeletionVectorDescriptor {
+            storage_type: "i".to_string(),
+            path_or_inline_dv: z85::encode(bytes),
+            offset: None,
+            size_in_bytes,
+            cardinality: cardinality(bitmap)?,
+        })
+    }
+
+    /// Read this DV and return the union of the rows it already deletes and `deleted_rows`. This
+    /// is used to mark more rows of a file as deleted when it already has a DV.
+    pub fn merge(
+        &self,
+        fs_client: Arc<dyn FileSystemClient>,
+        parent: &Url,
+        deleted_rows: &RoaringTreemap,
+    ) -> DeltaResult<RoaringTreemap> {
+        let mut merged = self.read(fs_client, parent)?;
+        merged |= deleted_rows;
+        Ok(merged)
+    }
+}
+
+/// Writes one or more deletion vectors into a single DV file, following the [Deletion Vector
+/// Format]: a version byte, followed by each DV as its size (big endian), the serialized bitmap and
+/// a CRC-32 checksum of the bitmap (big endian).
+///
+/// Every call to [`DeletionVectorWriter::add`] returns the descriptor for the DV it appended, but
+/// the descriptors must only be committed once the file was written with
+/// [`DeletionVectorWriter::write`].
+///
+/// [Deletion Vector Format]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Deletion-Vector-Format
+#[derive(Debug)]
+pub struct DeletionVectorWriter {
+    storage_type: String,
+    path_or_inline_dv: String,
+    location: Url,
+    buffer: Vec<u8>,
+}
+
+impl DeletionVectorWriter {
+    /// Create a writer for a DV file stored relative to the table root (`storageType = 'u'`). The
+    /// file name is derived from `uuid`, and is placed in the `random_prefix` directory if the
+    /// prefix is not empty.
+    pub fn try_new_relative(
+        table_root: &Url,
+        uuid: Uuid,
+        random_prefix: &str,
+    ) -> DeltaResult<Self> {
+        let path_or_inline_dv = format!("{random_prefix}{}", z85::encode(uuid.as_bytes()));
+        let location = DeletionVectorDescriptor {
+            storage_type: "u".to_string(),
+            path_or_inline_dv: path_or_inline_dv.clone(),
+            offset: None,
+            size_in_bytes: 0,
+            cardinality: 0,
+        }
+        .absolute_path(table_root)?
+        .ok_or_else(|| Error::internal_error("relative DVs must have a path"))?;
+        Ok(Self::new("u", path_or_inline_dv, location))
+    }
+
+    /// Create a writer for a DV file stored at the absolute path `location` (`storageType = 'p'`).
+    pub fn new_absolute(location: Url) -> Self {
+        Self::new("p", location.to_string(), location)
+    }
+
+    fn new(storage_type: &str, path_or_inline_dv: String, location: Url) -> Self {
+        Self {
+            storage_type: storage_type.to_string(),
+            path_or_inline_dv,
+            location,
+            buffer: vec![DV_FILE_FORMAT_VERSION],
+        }
+    }
+
+    /// The location of the DV file this writer produces.
+    pub fn location(&self) -> &Url {
+        &self.location
+    }
+
+    /// Append `bitmap` to the DV file and return the descriptor pointing at it.
+    pub fn add(&mut self, bitmap: &RoaringTreemap) -> DeltaResult<DeletionVectorDescriptor> {
+        let offset = i32::try_from(self.buffer.len())
+            .map_err(|_| Error::deletion_vector("DV file exceeds the maximum size"))?;
+        let bytes = serialize_bitmap(bitmap)?;
+        let size_in_bytes = dv_size(&bytes)?;
+        self.buffer.extend_from_slice(&size_in_bytes.to_be_bytes());
+        self.buffer.extend_from_slice(&bytes);
+        self.buffer
+            .extend_from_slice(&crc32fast::hash(&bytes).to_be_bytes());
+        Ok(DeletionVectorDescriptor {
+            storage_type: self.storage_type.clone(),
+            path_or_inline_dv: self.path_or_inline_dv.clone(),
+            offset: Some(offset),
+            size_in_bytes,
+            cardinality: cardinality(bitmap)?,
+        })
+    }
+
+    /// Consume the writer and return the serialized contents of the DV file.
+    pub fn into_bytes(self) -> Bytes {
+        self.buffer.into()
+    }
+
+    /// Consume the writer and write the DV file. Fails if the file already exists.
+    pub fn write(self, fs_client: &dyn FileSystemClient) -> DeltaResult<()> {
+        let location = self.location.clone();
+        fs_client.write_file(&location, self.into_bytes(), false)
+    }
+}
+
+/// serialize a treemap in the portable `RoaringBitmapArray` format, prefixed by its magic number
+fn serialize_bitmap(bitmap: &RoaringTreemap) -> DeltaResult<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(4 + bitmap.serialized_size());
+    bytes.extend_from_slice(&PORTABLE_ROARING_BITMAP_MAGIC.to_le_bytes());
+    bitmap
+        .serialize_into(&mut bytes)
+        .map_err(|err| Error::DeletionVector(err.to_string()))?;
+    Ok(bytes)
+}
+
+fn dv_size(bytes: &[u8]) -> DeltaResult<i32> {
+    i32::try_from(bytes.len()).map_err(|_| Error::deletion_vector("DV exceeds the maximum size"))
+}
+
+fn cardinality(bitmap: &RoaringTreemap) -> DeltaResult<i64> {
+    i64::try_from(bitmap.len()).map_err(|_| Error::deletion_vector("DV cardinality overflow"))
 }
 
 enum Endian {
@@ -202,6 +347,30 @@ fn read_u32(cursor: &mut Cursor<Bytes>, endian: Endian) -> DeltaResult<u32> {
     }
 }
 
+/// Deserialize a `RoaringBitmapArray` in the "native" format (after its magic): the number of
+/// 32-bit bitmaps, followed by the size and the serialization of each bitmap, all little endian.
+/// The bitmap at index `i` holds the row indexes whose high 32 bits are `i`.
+fn deserialize_native_bitmap(bytes: &[u8]) -> DeltaResult<RoaringTreemap> {
+    let truncated = || Error::deletion_vector("Truncated native deletion vector");
+    let read_u32_at = |position: usize| {
+        let buf = bytes.get(position..position + 4).ok_or_else(truncated)?;
+        slice_to_u32(buf, Endian::Little)
+    };
+    let num_bitmaps = read_u32_at(0)?;
+    let mut position = 4;
+    let mut bitmaps = Vec::with_capacity(num_bitmaps as usize);
+    for high_bits in 0..num_bitmaps {
+        let size = read_u32_at(position)? as usize;
+        position += 4;
+        let bitmap_bytes = bytes.get(position..position + size).ok_or_else(truncated)?;
+        let bitmap = RoaringBitmap::deserialize_from(bitmap_bytes)
+            .map_err(|err| Error::DeletionVector(err.to_string()))?;
+        bitmaps.push((high_bits, bitmap));
+        position += size;
+    }
+    Ok(RoaringTreemap::from_bitmaps(bitmaps))
+}
+
 /// decode a slice into a u32
 fn slice_to_u32(buf: &[u8], endian: Endian) -> DeltaResult<u32> {
     let array = buf
@@ -375,6 +544,48 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_inline_native_read() {
+        // a native RoaringBitmapArray with rows 3 and 7, none with high bits 1, and 2^33 + 5
+        let mut bytes = NATIVE_ROARING_BITMAP_MAGIC.to_le_bytes().to_vec();
+        bytes.extend_from_slice(&3u32.to_le_bytes());
+        for bitmap in [
+            RoaringBitmap::from_iter([3, 7]),
+            RoaringBitmap::new(),
+            RoaringBitmap::from_iter([5]),
+        ] {
+            bytes.extend_from_slice(&(bitmap.serialized_size() as u32).to_le_bytes());
+            bitmap.serialize_into(&mut bytes).unwrap();
+        }
+        // z85 encodes multiples of 4 bytes
+        bytes.resize(bytes.len().next_multiple_of(4), 0);
+        let inline = DeletionVectorDescriptor {
+            storage_type: "i".to_string(),
+            path_or_inline_dv: z85::encode(&bytes),
+            offset: None,
+            size_in_bytes: bytes.len() as i32,
+            cardinality: 3,
+        };
+        let sync_engine = SyncEngine::new();
+        let fs_client = sync_engine.get_file_system_client();
+        let parent = Url::parse("http://not.used").unwrap();
+        let tree_map = inline.read(fs_client.clone(), &parent).unwrap();
+        assert_eq!(
+            tree_map.into_iter().collect::<Vec<_>>(),
+            vec![3, 7, (2 << 32) + 5]
+        );
+
+        // truncated bitmaps are errors, not panics
+        let truncated = DeletionVectorDescriptor {
+            path_or_inline_dv: z85::encode(&bytes[..12]),
+            ..inline
+        };
+        assert!(matches!(
+            truncated.read(fs_client, &parent),
+            Err(Error::DeletionVector(_))
+        ));
+    }
+
     #[test]
     fn test_deletion_vector_read() {
         let path =
@@ -437,6 +648,93 @@ mod tests {
         assert_eq!(bools, expected);
     }
 
+    #[test]
+    fn test_inline_write_roundtrip() {
+        let bitmap = RoaringTreemap::from_iter([3, 4, 7, 11, 18, 29, 4294967297]);
+        let inline = DeletionVectorDescriptor::try_new_inline(&bitmap).unwrap();
+        assert_eq!(inline.storage_type, "i");
+        assert_eq!(inline.offset, None);
+        assert_eq!(inline.cardinality, 7);
+        assert_eq!(inline.size_in_bytes as usize, 4 + bitmap.serialized_size());
+
+        let sync_engine = SyncEngine::new();
+        let fs_client = sync_engine.get_file_system_client();
+        let parent = Url::parse("http://not.used").unwrap();
+        assert_eq!(inline.read(fs_client, &parent).unwrap(), bitmap);
+    }
+
+    #[test]
+    fn test_deletion_vector_write_roundtrip() {
+        let tmp = tempfile::tempdir().unwrap();
+        let parent = Url::from_directory_path(tmp.path()).unwrap();
+        let sync_engine = SyncEngine::new();
+        let fs_client = sync_engine.get_file_system_client();
+
+        let uuid = Uuid::parse_str("d2c639aa-8816-431a-aaf6-d3fe2512ff61").unwrap();
+        let mut writer = DeletionVectorWriter::try_new_relative(&parent, uuid, "ab").unwrap();
+        let first_bitmap = RoaringTreemap::from_iter([0, 9]);
+        let second_bitmap = RoaringTreemap::from_iter(0..1000);
+        let first = writer.add(&first_bitmap).unwrap();
+        let second = writer.add(&second_bitmap).unwrap();
+        let location = writer.location().clone();
+        writer.write(fs_client.as_ref()).unwrap();
+
+        assert_eq!(first.storage_type, "u");
+        assert_eq!(first.path_or_inline_dv, "ab^-aqEH.-t@S}K{vb[*k^");
+        assert_eq!(first.offset, Some(1));
+        assert_eq!(first.cardinality, 2);
+        // size, bitmap and checksum of the first DV precede the second one
+        assert_eq!(second.offset, Some(1 + 4 + first.size_in_bytes + 4));
+        assert_eq!(second.cardinality, 1000);
+        assert_eq!(
+            first.absolute_path(&parent).unwrap(),
+            Some(location.clone())
+        );
+        assert_eq!(
+            second.absolute_path(&parent).unwrap(),
+            Some(location.clone())
+        );
+
+        assert_eq!(
+            first.read(fs_client.clone(), &parent).unwrap(),
+            first_bitmap
+        );
+        assert_eq!(
+            second.read(fs_client.clone(), &parent).unwrap(),
+            second_bitmap
+        );
+
+        // the checksum is the big endian CRC-32 of the serialized bitmap
+        let bytes = std::fs::read(location.to_file_path().unwrap()).unwrap();
+        let start = first.offset.unwrap() as usize + 4;
+        let end = start + first.size_in_bytes as usize;
+        let checksum = u32::from_be_bytes(bytes[end..end + 4].try_into().unwrap());
+        assert_eq!(checksum, crc32fast::hash(&bytes[start..end]));
+
+        // DV files are never overwritten
+        let mut writer = DeletionVectorWriter::try_new_relative(&parent, uuid, "ab").unwrap();
+        writer.add(&first_bitmap).unwrap();
+        assert!(matches!(
+            writer.write(fs_client.as_ref()),
+            Err(Error::FileAlreadyExists(_))
+        ));
+    }
+
+    #[test]
+    fn test_deletion_vector_merge() {
+        let path =
+            std::fs::canonicalize(PathBuf::from("./tests/data/table-with-dv-small/")).unwrap();
+        let parent = url::Url::from_directory_path(path).unwrap();
+        let sync_engine = SyncEngine::new();
+        let fs_client = sync_engine.get_file_system_client();
+
+        let merged = dv_example()
+            .merge(fs_client, &parent, &RoaringTreemap::from_iter([3, 9]))
+            .unwrap();
+        let found = merged.iter().collect::<Vec<_>>();
+        assert_eq!(found, vec![0, 3, 9]);
+    }
+
     #[test]
     fn test_dv_row_indexes() {
         let example = dv_inline();
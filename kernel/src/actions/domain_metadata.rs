@@ -0,0 +1,66 @@
+//! Replay of the [domain metadata] of a table: the latest configuration of each domain, unless the
+//! domain was removed.
+//!
+//! [domain metadata]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#domain-metadata
+
+use std::sync::{Arc, LazyLock};
+
+use crate::actions::visitors::DomainMetadataVisitor;
+use crate::actions::{get_log_schema, DOMAIN_METADATA_NAME};
+use crate::log_segment::LogSegment;
+use crate::{DeltaResult, Engine, Expression as Expr, ExpressionRef, RowVisitor as _};
+
+pub(crate) use crate::actions::visitors::DomainMetadataMap;
+
+/// The prefix of the system-controlled metadata domains, which are managed by the table features
+/// that use them (e.g. `delta.clustering`) and can't be changed directly by users.
+pub(crate) const SYSTEM_DOMAIN_PREFIX: &str = "delta.";
+
+/// Whether `domain` is a system-controlled domain.
+pub(crate) fn is_system_domain(domain: &str) -> bool {
+    domain.starts_with(SYSTEM_DOMAIN_PREFIX)
+}
+
+/// Replay the log segment for the latest domain metadata of each domain, or of `domain` only if
+/// it is given. Removed domains are dropped from the result.
+pub(crate) fn scan_domain_metadatas(
+    log_segment: &LogSegment,
+    domain: Option<&str>,
+    engine: &dyn Engine,
+) -> DeltaResult<DomainMetadataMap> {
+    // Like the transaction identifiers, all the domain metadata actions of a checkpoint end up in a
+    // single checkpoint part, which this meta-predicate lets us find.
+    static META_PREDICATE: LazyLock<Option<ExpressionRef>> = LazyLock::new(|| {
+        Some(Arc::new(
+            Expr::column([DOMAIN_METADATA_NAME, "domain"]).is_not_null(),
+        ))
+    });
+    let schema = get_log_schema().project(&[DOMAIN_METADATA_NAME])?;
+    let mut visitor = DomainMetadataVisitor::new(domain.map(str::to_string));
+    for actions in
+        log_segment.read_actions(engine, schema.clone(), schema, META_PREDICATE.clone())?
+    {
+        let (actions, _) = actions?;
+        visitor.visit_rows_of(actions.as_ref())?;
+        // the first action of the requested domain is its latest one
+        if domain.is_some() && !visitor.domain_metadatas.is_empty() {
+            break;
+        }
+    }
+    let mut domain_metadatas = visitor.domain_metadatas;
+    domain_metadatas.retain(|_, domain_metadata| !domain_metadata.removed);
+    Ok(domain_metadatas)
+}
+
+/// The configuration of `domain` in the log segment, or `None` if the domain doesn't exist or was
+/// removed.
+pub(crate) fn domain_metadata_configuration(
+    log_segment: &LogSegment,
+    domain: &str,
+    engine: &dyn Engine,
+) -> DeltaResult<Option<String>> {
+    let mut domain_metadatas = scan_domain_metadatas(log_segment, Some(domain), engine)?;
+    Ok(domain_metadatas
+        .remove(domain)
+        .map(|domain_metadata| domain_metadata.configuration))
+}
@@ -6,17 +6,20 @@ use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Display};
 use std::hash::Hash;
 use std::str::FromStr;
-use std::sync::LazyLock;
+use std::sync::{Arc, LazyLock};
 
 use self::deletion_vector::DeletionVectorDescriptor;
 use crate::actions::schemas::GetStructField;
-use crate::schema::{SchemaRef, StructType};
+use crate::schema::{DataType, SchemaRef, StructField, StructType};
 use crate::table_features::{
     ReaderFeature, WriterFeature, SUPPORTED_READER_FEATURES, SUPPORTED_WRITER_FEATURES,
 };
 use crate::table_properties::TableProperties;
 use crate::utils::require;
-use crate::{DeltaResult, EngineData, Error, FileMeta, RowVisitor as _};
+use crate::{
+    DeltaResult, Engine, EngineData, Error, ExpressionHandlerExtension as _, FileMeta,
+    RowVisitor as _,
+};
 use url::Url;
 use visitors::{MetadataVisitor, ProtocolVisitor};
 
@@ -24,6 +27,7 @@ use delta_kernel_derive::Schema;
 use serde::{Deserialize, Serialize};
 
 pub mod deletion_vector;
+pub(crate) mod domain_metadata;
 pub mod set_transaction;
 
 pub(crate) mod schemas;
@@ -50,6 +54,8 @@ pub(crate) const CDC_NAME: &str = "cdc";
 pub(crate) const SIDECAR_NAME: &str = "sidecar";
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 pub(crate) const CHECKPOINT_METADATA_NAME: &str = "checkpointMetadata";
+#[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
+pub(crate) const DOMAIN_METADATA_NAME: &str = "domainMetadata";
 
 static LOG_ADD_SCHEMA: LazyLock<SchemaRef> =
     LazyLock::new(|| StructType::new([Option::<Add>::get_struct_field(ADD_NAME)]).into());
@@ -65,8 +71,7 @@ static LOG_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
         Option::<Cdc>::get_struct_field(CDC_NAME),
         Option::<Sidecar>::get_struct_field(SIDECAR_NAME),
         Option::<CheckpointMetadata>::get_struct_field(CHECKPOINT_METADATA_NAME),
-        // We don't support the following actions yet
-        //Option::<DomainMetadata>::get_struct_field(DOMAIN_METADATA_NAME),
+        Option::<DomainMetadata>::get_struct_field(DOMAIN_METADATA_NAME),
     ])
     .into()
 });
@@ -91,9 +96,31 @@ pub(crate) fn get_log_commit_info_schema() -> &'static SchemaRef {
     &LOG_COMMIT_INFO_SCHEMA
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Schema)]
+/// Convert an action into a single-row [`EngineData`] with the log schema projected to the action
+/// `name`, e.g. to write the action to the log. Since kernel can't build engine data with map or
+/// array literals yet, the action is serialized as JSON and parsed by the engine's [`JsonHandler`].
+///
+/// [`JsonHandler`]: crate::JsonHandler
+pub(crate) fn action_to_engine_data(
+    engine: &dyn Engine,
+    name: &str,
+    action: &impl Serialize,
+) -> DeltaResult<Box<dyn EngineData>> {
+    let json = serde_json::json!({ name: action }).to_string();
+    let json_schema = Arc::new(StructType::new([StructField::nullable(
+        "json",
+        DataType::STRING,
+    )]));
+    let json_data = engine
+        .get_expression_handler()
+        .create_one(json_schema, &[json.into()])?;
+    let log_schema = get_log_schema().project(&[name])?;
+    engine.get_json_handler().parse_json(json_data, log_schema)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Schema, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
-#[cfg_attr(test, derive(Serialize), serde(rename_all = "camelCase"))]
 pub(crate) struct Format {
     /// Name of the encoding for files in this table
     pub(crate) provider: String,
@@ -110,8 +137,8 @@ impl Default for Format {
     }
 }
 
-#[derive(Debug, Default, Clone, PartialEq, Eq, Schema)]
-#[cfg_attr(test, derive(Serialize), serde(rename_all = "camelCase"))]
+#[derive(Debug, Default, Clone, PartialEq, Eq, Schema, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 pub(crate) struct Metadata {
     /// Unique identifier for this table
@@ -308,11 +335,11 @@ impl Protocol {
                 ))
             }
             None => {
-                // no features, we currently only support version 1 or 2 in this case
+                // no features, we currently only support versions 1 to 3 in this case
                 require!(
-                    self.min_writer_version == 1 || self.min_writer_version == 2,
+                    (1..=3).contains(&self.min_writer_version),
                     Error::unsupported(
-                        "Currently delta-kernel-rs can only write to tables with protocol.minWriterVersion = 1, 2, or 7"
+                        "Currently delta-kernel-rs can only write to tables with protocol.minWriterVersion = 1, 2, 3, or 7"
                     )
                 );
                 Ok(())
@@ -385,6 +412,10 @@ pub(crate) struct CommitInfo {
     /// A place for the engine to store additional metadata associated with this commit encoded as
     /// a map of strings.
     pub(crate) engine_commit_info: Option<HashMap<String, String>>,
+    /// Whether this commit only added new data files, without reading or removing any existing
+    /// ones. Concurrent transactions use this to decide whether the commit conflicts with them.
+    /// Read: optional (missing means `false`), write: required (that is, kernel always writes).
+    pub(crate) is_blind_append: Option<bool>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Schema)]
@@ -483,6 +514,12 @@ pub(crate) struct Remove {
     #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
     pub(crate) size: Option<i64>,
 
+    /// Contains [statistics] (e.g., count, min/max values for columns) about the data in this logical file encoded as a JSON string.
+    ///
+    /// [statistics]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Per-file-Statistics
+    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    pub(crate) stats: Option<String>,
+
     /// Map containing metadata about this logical file.
     #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
     pub(crate) tags: Option<HashMap<String, String>>,
@@ -534,7 +571,8 @@ pub(crate) struct Cdc {
     pub tags: Option<HashMap<String, String>>,
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Schema)]
+#[derive(Debug, Clone, PartialEq, Eq, Schema, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 pub(crate) struct SetTransaction {
     /// A unique identifier for the application performing the transaction.
@@ -551,7 +589,8 @@ pub(crate) struct SetTransaction {
 /// file actions. This action is only allowed in checkpoints following the V2 spec.
 ///
 /// [More info]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#sidecar-file-information
-#[derive(Schema, Debug, PartialEq)]
+#[derive(Schema, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 pub(crate) struct Sidecar {
     /// A path to a sidecar file that can be either:
@@ -595,7 +634,7 @@ impl Sidecar {
 /// The CheckpointMetadata action describes details about a checkpoint following the V2 specification.
 ///
 /// [More info]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#checkpoint-metadata
-#[derive(Debug, Clone, PartialEq, Eq, Schema)]
+#[derive(Debug, Clone, PartialEq, Eq, Schema, Serialize)]
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 pub(crate) struct CheckpointMetadata {
     /// The version of the V2 spec checkpoint.
@@ -610,6 +649,25 @@ pub(crate) struct CheckpointMetadata {
     pub(crate) tags: Option<HashMap<String, String>>,
 }
 
+/// The domain metadata action sets or removes the configuration of a metadata domain of the table.
+/// Domains whose name starts with `delta.` are system domains, which are controlled by the table
+/// features that define them (e.g. `delta.clustering` for clustered tables).
+///
+/// [More info]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#domain-metadata
+#[derive(Debug, Clone, PartialEq, Eq, Schema, Serialize)]
+#[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
+pub(crate) struct DomainMetadata {
+    /// The name of the metadata domain.
+    pub(crate) domain: String,
+
+    /// The configuration of the domain, as a string (typically JSON) that only the owner of the
+    /// domain interprets.
+    pub(crate) configuration: String,
+
+    /// Whether the domain is removed, in which case its configuration is ignored.
+    pub(crate) removed: bool,
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -723,6 +781,7 @@ mod tests {
                 StructField::nullable("extendedFileMetadata", DataType::BOOLEAN),
                 partition_values_field(),
                 StructField::nullable("size", DataType::LONG),
+                StructField::nullable("stats", DataType::STRING),
                 tags_field(),
                 deletion_vector_field(),
                 StructField::nullable("baseRowId", DataType::LONG),
@@ -802,6 +861,23 @@ mod tests {
         assert_eq!(schema, expected);
     }
 
+    #[test]
+    fn test_domain_metadata_schema() {
+        let schema = get_log_schema()
+            .project(&[DOMAIN_METADATA_NAME])
+            .expect("Couldn't get domainMetadata field");
+
+        let expected = Arc::new(StructType::new([StructField::nullable(
+            "domainMetadata",
+            StructType::new([
+                StructField::not_null("domain", DataType::STRING),
+                StructField::not_null("configuration", DataType::STRING),
+                StructField::not_null("removed", DataType::BOOLEAN),
+            ]),
+        )]));
+        assert_eq!(schema, expected);
+    }
+
     #[test]
     fn test_commit_info_schema() {
         let schema = get_log_schema()
@@ -823,6 +899,7 @@ mod tests {
                     "engineCommitInfo",
                     MapType::new(DataType::STRING, DataType::STRING, false),
                 ),
+                StructField::nullable("isBlindAppend", DataType::BOOLEAN),
             ]),
         )]));
         assert_eq!(schema, expected);
@@ -964,10 +1041,16 @@ mod tests {
             3,
             7,
             Some([ReaderFeature::DeletionVectors]),
-            Some([WriterFeature::RowTracking]),
+            Some([WriterFeature::ChangeDataFeed]),
         )
         .unwrap();
         assert!(protocol.ensure_write_supported().is_err());
+
+        // writer version 3 adds CHECK constraints, and version 4 change data feed
+        let protocol = Protocol::try_new(1, 3, None::<Vec<String>>, None::<Vec<String>>).unwrap();
+        assert!(protocol.ensure_write_supported().is_ok());
+        let protocol = Protocol::try_new(1, 4, None::<Vec<String>>, None::<Vec<String>>).unwrap();
+        assert!(protocol.ensure_write_supported().is_err());
     }
 
     #[test]
@@ -3,20 +3,33 @@ use std::sync::{Arc, LazyLock};
 use crate::actions::visitors::SetTransactionVisitor;
 use crate::actions::{get_log_schema, SetTransaction, SET_TRANSACTION_NAME};
 use crate::snapshot::Snapshot;
+use crate::table_properties::TableProperties;
+use crate::utils::current_time_ms;
 use crate::{
     DeltaResult, Engine, EngineData, Expression as Expr, ExpressionRef, RowVisitor as _, SchemaRef,
 };
 
 pub(crate) use crate::actions::visitors::SetTransactionMap;
 
-#[allow(dead_code)]
-pub(crate) struct SetTransactionScanner {
-    snapshot: Arc<Snapshot>,
+/// The timestamp (in milliseconds since the Unix epoch) at and before which transactions are
+/// expired, if the table has a retention duration for transactions
+/// (`delta.setTransactionRetentionDuration`).
+pub(crate) fn txn_expiration_timestamp(
+    table_properties: &TableProperties,
+) -> DeltaResult<Option<i64>> {
+    let Some(retention) = table_properties.set_transaction_retention_duration else {
+        return Ok(None);
+    };
+    let retention_ms = i64::try_from(retention.as_millis()).unwrap_or(i64::MAX);
+    Ok(Some(current_time_ms()?.saturating_sub(retention_ms)))
 }
 
-#[allow(dead_code)]
-impl SetTransactionScanner {
-    pub(crate) fn new(snapshot: Arc<Snapshot>) -> Self {
+pub(crate) struct SetTransactionScanner<'a> {
+    snapshot: &'a Snapshot,
+}
+
+impl<'a> SetTransactionScanner<'a> {
+    pub(crate) fn new(snapshot: &'a Snapshot) -> Self {
         SetTransactionScanner { snapshot }
     }
 
@@ -39,7 +52,17 @@ impl SetTransactionScanner {
             }
         }
 
-        Ok(visitor.set_transactions)
+        // The latest transaction of an application is expired if it was last updated before the
+        // table's `delta.setTransactionRetentionDuration`, in which case the application is
+        // considered to have no transaction. Transactions without `lastUpdated` never expire.
+        let mut set_transactions = visitor.set_transactions;
+        if let Some(expiration_timestamp) =
+            txn_expiration_timestamp(self.snapshot.table_properties())?
+        {
+            set_transactions
+                .retain(|_, txn| !txn.last_updated.is_some_and(|t| t <= expiration_timestamp));
+        }
+        Ok(set_transactions)
     }
 
     // Factored out to facilitate testing
@@ -79,14 +102,6 @@ impl SetTransactionScanner {
         let mut transactions = self.scan_application_transactions(engine, Some(application_id))?;
         Ok(transactions.remove(application_id))
     }
-
-    /// Scan the Delta Log to obtain the latest transaction for all applications
-    pub(crate) fn application_transactions(
-        &self,
-        engine: &dyn Engine,
-    ) -> DeltaResult<SetTransactionMap> {
-        self.scan_application_transactions(engine, None)
-    }
 }
 
 #[cfg(all(test, feature = "sync-engine"))]
@@ -108,10 +123,12 @@ mod tests {
 
         let table = Table::new(url);
         let snapshot = table.snapshot(&engine, None).unwrap();
-        let txn_scan = SetTransactionScanner::new(snapshot.into());
+        let txn_scan = SetTransactionScanner::new(&snapshot);
 
         (
-            txn_scan.application_transactions(&engine).unwrap(),
+            txn_scan
+                .scan_application_transactions(&engine, None)
+                .unwrap(),
             txn_scan.application_transaction(&engine, app_id).unwrap(),
         )
     }
@@ -159,7 +176,7 @@ mod tests {
 
         let table = Table::new(url);
         let snapshot = table.snapshot(&engine, None).unwrap();
-        let txn = SetTransactionScanner::new(snapshot.into());
+        let txn = SetTransactionScanner::new(&snapshot);
         let txn_schema = SetTransactionScanner::get_txn_schema().unwrap();
 
         // The checkpoint has five parts, each containing one action. There are two app ids.
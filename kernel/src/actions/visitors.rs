@@ -12,8 +12,9 @@ use crate::{DeltaResult, Error};
 use super::deletion_vector::DeletionVectorDescriptor;
 use super::schemas::ToSchema as _;
 use super::{
-    Add, Cdc, Format, Metadata, Protocol, Remove, SetTransaction, Sidecar, ADD_NAME, CDC_NAME,
-    METADATA_NAME, PROTOCOL_NAME, REMOVE_NAME, SET_TRANSACTION_NAME, SIDECAR_NAME,
+    Add, Cdc, DomainMetadata, Format, Metadata, Protocol, Remove, SetTransaction, Sidecar,
+    ADD_NAME, CDC_NAME, DOMAIN_METADATA_NAME, METADATA_NAME, PROTOCOL_NAME, REMOVE_NAME,
+    SET_TRANSACTION_NAME, SIDECAR_NAME,
 };
 
 #[derive(Default)]
@@ -253,7 +254,7 @@ impl RemoveVisitor {
         getters: &[&'a dyn GetData<'a>],
     ) -> DeltaResult<Remove> {
         require!(
-            getters.len() == 14,
+            getters.len() == 15,
             Error::InternalError(format!(
                 "Wrong number of RemoveVisitor getters: {}",
                 getters.len()
@@ -270,13 +271,15 @@ impl RemoveVisitor {
 
         let size: Option<i64> = getters[5].get_opt(row_index, "remove.size")?;
 
-        // TODO(nick) tags are skipped in getters[6]
+        let stats: Option<String> = getters[6].get_opt(row_index, "remove.stats")?;
 
-        let deletion_vector = visit_deletion_vector_at(row_index, &getters[7..])?;
+        // TODO(nick) tags are skipped in getters[7]
+
+        let deletion_vector = visit_deletion_vector_at(row_index, &getters[8..])?;
 
-        let base_row_id: Option<i64> = getters[12].get_opt(row_index, "remove.baseRowId")?;
+        let base_row_id: Option<i64> = getters[13].get_opt(row_index, "remove.baseRowId")?;
         let default_row_commit_version: Option<i64> =
-            getters[13].get_opt(row_index, "remove.defaultRowCommitVersion")?;
+            getters[14].get_opt(row_index, "remove.defaultRowCommitVersion")?;
 
         Ok(Remove {
             path,
@@ -285,6 +288,7 @@ impl RemoveVisitor {
             extended_file_metadata,
             partition_values,
             size,
+            stats,
             tags: None,
             deletion_vector,
             base_row_id,
@@ -438,6 +442,68 @@ impl RowVisitor for SetTransactionVisitor {
     }
 }
 
+pub(crate) type DomainMetadataMap = HashMap<String, DomainMetadata>;
+
+/// Extract the domain metadata actions from the log into a map of the latest action of each
+/// domain, including the actions that remove their domain.
+///
+/// Like [`SetTransactionVisitor`], this visitor keeps the first action of each domain it
+/// encounters, so the log must be visited newest-first. When a specific domain is requested with
+/// `domain`, the actions of the other domains are ignored.
+#[derive(Default, Debug)]
+#[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
+pub(crate) struct DomainMetadataVisitor {
+    pub(crate) domain_metadatas: DomainMetadataMap,
+    pub(crate) domain: Option<String>,
+}
+
+impl DomainMetadataVisitor {
+    /// Create a new visitor. When domain is set then bookkeeping is only for that domain
+    pub(crate) fn new(domain: Option<String>) -> Self {
+        DomainMetadataVisitor {
+            domain_metadatas: HashMap::default(),
+            domain,
+        }
+    }
+}
+
+impl RowVisitor for DomainMetadataVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| DomainMetadata::to_schema().leaves(DOMAIN_METADATA_NAME));
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 3,
+            Error::InternalError(format!(
+                "Wrong number of DomainMetadataVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        for i in 0..row_count {
+            let Some(domain) = getters[0].get_opt(i, "domainMetadata.domain")? else {
+                continue;
+            };
+            let domain: String = domain;
+            let is_requested = !self
+                .domain
+                .as_ref()
+                .is_some_and(|requested| *requested != domain);
+            if is_requested && !self.domain_metadatas.contains_key(&domain) {
+                let domain_metadata = DomainMetadata {
+                    domain: domain.clone(),
+                    configuration: getters[1].get(i, "domainMetadata.configuration")?,
+                    removed: getters[2].get(i, "domainMetadata.removed")?,
+                };
+                self.domain_metadatas.insert(domain, domain_metadata);
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Default)]
 #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
 pub(crate) struct SidecarVisitor {
@@ -650,7 +716,7 @@ mod tests {
         };
         let expected = vec![add1, add2, add3];
         assert_eq!(add_visitor.adds.len(), expected.len());
-        for (add, expected) in add_visitor.adds.into_iter().zip(expected.into_iter()) {
+        for (add, expected) in add_visitor.adds.into_iter().zip(expected) {
             assert_eq!(add, expected);
         }
     }
@@ -721,4 +787,38 @@ mod tests {
             })
         );
     }
+
+    #[test]
+    fn test_parse_domain_metadata() {
+        // the newest action of each domain comes first
+        let json_strings: StringArray = vec![
+            r#"{"commitInfo":{"timestamp":1670892998177,"operation":"WRITE"}}"#,
+            r#"{"domainMetadata":{"domain":"app1","configuration":"{\"watermark\":2}","removed":false}}"#,
+            r#"{"domainMetadata":{"domain":"app2","configuration":"","removed":true}}"#,
+            r#"{"domainMetadata":{"domain":"app1","configuration":"{\"watermark\":1}","removed":false}}"#,
+            r#"{"domainMetadata":{"domain":"app2","configuration":"{}","removed":false}}"#,
+        ]
+        .into();
+        let batch = parse_json_batch(json_strings);
+        let mut visitor = DomainMetadataVisitor::default();
+        visitor.visit_rows_of(batch.as_ref()).unwrap();
+        let domain_metadatas = visitor.domain_metadatas;
+        assert_eq!(domain_metadatas.len(), 2);
+        assert_eq!(
+            domain_metadatas["app1"],
+            DomainMetadata {
+                domain: "app1".to_string(),
+                configuration: r#"{"watermark":2}"#.to_string(),
+                removed: false,
+            }
+        );
+        assert!(domain_metadatas["app2"].removed);
+
+        let mut visitor = DomainMetadataVisitor::new(Some("app2".to_string()));
+        visitor.visit_rows_of(batch.as_ref()).unwrap();
+        assert_eq!(
+            visitor.domain_metadatas.keys().collect::<Vec<_>>(),
+            vec!["app2"]
+        );
+    }
 }
@@ -0,0 +1,177 @@
+//! Cleanup of the expired files of the Delta log.
+//!
+//! The log files of a table expire after its log retention duration (`delta.logRetentionDuration`,
+//! 30 days by default). Expired files are deleted once they are not needed to reconstruct any
+//! retained version, i.e. any version whose commit hasn't expired: these versions are reconstructed
+//! from the newest complete checkpoint that precedes them, so the commits, checkpoints, log
+//! compaction and `.crc` files of the versions before that checkpoint can be deleted, as well as
+//! the sidecar files that no remaining checkpoint references.
+
+use std::collections::{BTreeMap, HashSet};
+use std::sync::LazyLock;
+
+use itertools::Itertools;
+use tracing::debug;
+use url::Url;
+
+use crate::actions::visitors::SidecarVisitor;
+use crate::actions::{get_log_schema, SIDECAR_NAME};
+use crate::log_segment::{group_checkpoint_parts, list_log_files};
+use crate::path::{LogPathFileType, ParsedLogPath};
+use crate::schema::SchemaRef;
+use crate::snapshot::Snapshot;
+use crate::table_properties::DEFAULT_LOG_RETENTION_DURATION;
+use crate::utils::current_time_ms;
+use crate::{DeltaResult, Engine, Error, FileMeta, FileSystemClient, RowVisitor as _, Version};
+
+// The sidecar actions of a checkpoint
+static SIDECAR_READ_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    get_log_schema()
+        .project(&[SIDECAR_NAME])
+        .expect("sidecar action is in the log schema")
+});
+
+/// Delete the expired log files of the table of the snapshot that are not needed to reconstruct
+/// any retained version, through [`FileSystemClient::delete_file`]. Versions after the snapshot's
+/// version are always retained. Nothing is deleted if the engine doesn't support deleting files.
+///
+/// [`FileSystemClient::delete_file`]: crate::FileSystemClient::delete_file
+pub(crate) fn cleanup_expired_logs(snapshot: &Snapshot, engine: &dyn Engine) -> DeltaResult<()> {
+    let log_retention = snapshot
+        .table_properties()
+        .log_retention_duration
+        .unwrap_or(DEFAULT_LOG_RETENTION_DURATION);
+    let log_retention = i64::try_from(log_retention.as_millis())
+        .map_err(|_| Error::generic("log retention duration is too large"))?;
+    let cutoff_timestamp = current_time_ms()?.saturating_sub(log_retention);
+    let is_expired = |file: &FileMeta| file.last_modified < cutoff_timestamp;
+
+    let fs_client = engine.get_file_system_client();
+    let log_root = &snapshot.log_segment().log_root;
+    let log_files: Vec<_> =
+        list_log_files(fs_client.as_ref(), log_root, None, None)?.try_collect()?;
+
+    // the oldest retained version, and the newest complete checkpoint it can be reconstructed from
+    let oldest_retained_version = log_files
+        .iter()
+        .find(|file| file.is_commit() && !is_expired(&file.location))
+        .map_or(snapshot.version(), |file| file.version)
+        .min(snapshot.version());
+    let Some(checkpoint_version) =
+        newest_complete_checkpoint_version(&log_files, oldest_retained_version)
+    else {
+        return Ok(());
+    };
+
+    let deletable_files = log_files.iter().filter(|file| {
+        is_expired(&file.location)
+            && match &file.file_type {
+                LogPathFileType::CompactedCommit { hi } => *hi < checkpoint_version,
+                LogPathFileType::Unknown => {
+                    file.extension == "crc" && file.version < checkpoint_version
+                }
+                _ => file.version < checkpoint_version,
+            }
+    });
+    // delete in version order, so that an interrupted cleanup leaves a reconstructable log
+    for file in deletable_files {
+        debug!("Deleting expired log file {}", file.location.location);
+        if !delete_file(fs_client.as_ref(), &file.location.location)? {
+            return Ok(());
+        }
+    }
+
+    if snapshot.table_configuration().is_v2_checkpoint_supported() {
+        let retained_checkpoints = log_files
+            .iter()
+            .filter(|file| file.is_checkpoint() && file.version >= checkpoint_version);
+        let referenced_sidecars = referenced_sidecars(engine, log_root, retained_checkpoints)?;
+        let sidecar_root = log_root.join("_sidecars/")?;
+        for sidecar in fs_client.list_from(&sidecar_root)? {
+            let sidecar = sidecar?;
+            // listing a missing directory may return the files next to it
+            if sidecar.location.as_str().starts_with(sidecar_root.as_str())
+                && is_expired(&sidecar)
+                && !referenced_sidecars.contains(&sidecar.location)
+            {
+                debug!("Deleting expired sidecar file {}", sidecar.location);
+                if !delete_file(fs_client.as_ref(), &sidecar.location)? {
+                    return Ok(());
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+// Delete the file at `path`, returning false if the engine doesn't support deleting files
+fn delete_file(fs_client: &dyn FileSystemClient, path: &Url) -> DeltaResult<bool> {
+    match fs_client.delete_file(path) {
+        Ok(()) => Ok(true),
+        Err(Error::Unsupported(_)) => {
+            debug!("Skipping log cleanup: the engine does not support deleting files");
+            Ok(false)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+// The version of the newest complete checkpoint at or before `max_version`
+fn newest_complete_checkpoint_version(
+    log_files: &[ParsedLogPath],
+    max_version: Version,
+) -> Option<Version> {
+    let mut checkpoints: BTreeMap<Version, Vec<ParsedLogPath>> = BTreeMap::new();
+    for file in log_files {
+        if file.is_checkpoint() && file.version <= max_version {
+            checkpoints
+                .entry(file.version)
+                .or_default()
+                .push(file.clone());
+        }
+    }
+    checkpoints
+        .into_iter()
+        .rev()
+        .find(|(_, parts)| {
+            group_checkpoint_parts(parts.clone())
+                .iter()
+                // `num_parts` is guaranteed to be non-negative and within `usize` range
+                .any(|(num_parts, part_files)| part_files.len() == *num_parts as usize)
+        })
+        .map(|(version, _)| version)
+}
+
+// The locations of the sidecar files referenced by the given checkpoints. Multi-part checkpoints
+// never reference sidecar files.
+fn referenced_sidecars<'a>(
+    engine: &dyn Engine,
+    log_root: &Url,
+    checkpoints: impl Iterator<Item = &'a ParsedLogPath>,
+) -> DeltaResult<HashSet<Url>> {
+    let mut referenced_sidecars = HashSet::new();
+    for checkpoint in checkpoints {
+        let files = [checkpoint.location.clone()];
+        let actions = match &checkpoint.file_type {
+            LogPathFileType::MultiPartCheckpoint { .. } => continue,
+            _ if checkpoint.extension == "json" => engine.get_json_handler().read_json_files(
+                &files,
+                SIDECAR_READ_SCHEMA.clone(),
+                None,
+            )?,
+            _ => engine.get_parquet_handler().read_parquet_files(
+                &files,
+                SIDECAR_READ_SCHEMA.clone(),
+                None,
+            )?,
+        };
+        for actions in actions {
+            let mut visitor = SidecarVisitor::default();
+            visitor.visit_rows_of(actions?.as_ref())?;
+            for sidecar in visitor.sidecars {
+                referenced_sidecars.insert(sidecar.to_filemeta(log_root)?.location);
+            }
+        }
+    }
+    Ok(referenced_sidecars)
+}
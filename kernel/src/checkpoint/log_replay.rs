@@ -0,0 +1,312 @@
+//! Log replay for checkpoints: select the actions that make up the state of the table at the
+//! snapshot version.
+//!
+//! Actions are visited newest-first, and the following actions are selected:
+//! - the most recent `protocol` and `metaData` actions
+//! - the most recent `txn` action of each application, unless it has expired
+//! - the most recent `domainMetadata` action of each domain, unless it removes the domain (log
+//!   compactions keep these, since they must still override the older actions of the domain)
+//! - the `add` actions of the files that are still in the table
+//! - the `remove` actions (tombstones) of the files that were removed from the table, unless they
+//!   have expired according to the table's `delta.deletedFileRetentionDuration`
+//!
+//! All the other actions (e.g. `commitInfo` and `cdc`) are dropped.
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
+use crate::log_replay::{FileActionDeduplicator, FileActionKey};
+use crate::schema::{column_name, ColumnName, ColumnNamesAndTypes, DataType};
+use crate::DeltaResult;
+
+/// State of the checkpoint log replay which persists across the batches of the log.
+#[derive(Debug, Default)]
+pub(crate) struct CheckpointLogReplayState {
+    /// The files seen so far, to drop the actions of files superseded by newer actions
+    pub(crate) seen_file_keys: HashSet<FileActionKey>,
+    pub(crate) seen_protocol: bool,
+    pub(crate) seen_metadata: bool,
+    /// The application ids of the `txn` actions seen so far
+    pub(crate) seen_txns: HashSet<String>,
+    /// The domains of the `domainMetadata` actions seen so far
+    pub(crate) seen_domains: HashSet<String>,
+}
+
+/// A visitor that selects the actions of a batch that belong in the checkpoint.
+pub(crate) struct CheckpointVisitor<'seen> {
+    deduplicator: FileActionDeduplicator<'seen>,
+    seen_protocol: &'seen mut bool,
+    seen_metadata: &'seen mut bool,
+    seen_txns: &'seen mut HashSet<String>,
+    seen_domains: &'seen mut HashSet<String>,
+    /// Tombstones deleted at or before this timestamp are expired
+    minimum_file_retention_timestamp: i64,
+    /// `txn` actions last updated at or before this timestamp are expired
+    txn_expiration_timestamp: Option<i64>,
+    /// Whether the `domainMetadata` actions that remove their domain are kept
+    keep_removed_domains: bool,
+    pub(crate) selection_vector: Vec<bool>,
+    /// Whether each row is a file action, so that V2 checkpoints can move the selected file
+    /// actions to sidecar files
+    pub(crate) file_actions: Vec<bool>,
+    /// The number of actions selected in the batch
+    pub(crate) actions_count: i64,
+    /// The number of `add` actions selected in the batch
+    pub(crate) add_actions_count: i64,
+}
+
+impl<'seen> CheckpointVisitor<'seen> {
+    // The index position in the row getters for the following columns
+    const ADD_PATH_INDEX: usize = 0;
+    const ADD_DV_START_INDEX: usize = 1;
+    const REMOVE_PATH_INDEX: usize = 4;
+    const REMOVE_DELETION_TIMESTAMP_INDEX: usize = 5;
+    const REMOVE_DV_START_INDEX: usize = 6;
+    const METADATA_ID_INDEX: usize = 9;
+    const PROTOCOL_MIN_READER_VERSION_INDEX: usize = 10;
+    const TXN_APP_ID_INDEX: usize = 11;
+    const TXN_LAST_UPDATED_INDEX: usize = 12;
+    const DOMAIN_METADATA_DOMAIN_INDEX: usize = 13;
+    const DOMAIN_METADATA_REMOVED_INDEX: usize = 14;
+
+    pub(crate) fn new(
+        state: &'seen mut CheckpointLogReplayState,
+        is_log_batch: bool,
+        minimum_file_retention_timestamp: i64,
+        txn_expiration_timestamp: Option<i64>,
+        keep_removed_domains: bool,
+    ) -> Self {
+        CheckpointVisitor {
+            deduplicator: FileActionDeduplicator::new(
+                &mut state.seen_file_keys,
+                is_log_batch,
+                Self::ADD_PATH_INDEX,
+                Self::REMOVE_PATH_INDEX,
+                Self::ADD_DV_START_INDEX,
+                Self::REMOVE_DV_START_INDEX,
+            ),
+            seen_protocol: &mut state.seen_protocol,
+            seen_metadata: &mut state.seen_metadata,
+            seen_txns: &mut state.seen_txns,
+            seen_domains: &mut state.seen_domains,
+            minimum_file_retention_timestamp,
+            txn_expiration_timestamp,
+            keep_removed_domains,
+            selection_vector: vec![],
+            file_actions: vec![],
+            actions_count: 0,
+            add_actions_count: 0,
+        }
+    }
+
+    // Select the add actions of files that have not been seen yet, and the remove actions of files
+    // that have not been seen yet whose tombstones have not expired. Returns `None` if the row is
+    // not a file action.
+    fn is_valid_file_action<'a>(
+        &mut self,
+        i: usize,
+        getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<Option<bool>> {
+        // unlike scans, checkpoints keep the tombstones of checkpoint batches too
+        let Some((file_key, is_add)) = self.deduplicator.extract_file_action(i, getters, false)?
+        else {
+            return Ok(None);
+        };
+        if self.deduplicator.check_and_record_seen(file_key) {
+            return Ok(Some(false));
+        }
+        if is_add {
+            self.add_actions_count += 1;
+            return Ok(Some(true));
+        }
+        let deletion_timestamp: Option<i64> = getters[Self::REMOVE_DELETION_TIMESTAMP_INDEX]
+            .get_opt(i, "remove.deletionTimestamp")?;
+        Ok(Some(
+            deletion_timestamp.unwrap_or(0) > self.minimum_file_retention_timestamp,
+        ))
+    }
+
+    fn is_valid_non_file_action<'a>(
+        &mut self,
+        i: usize,
+        getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<bool> {
+        let metadata_id: Option<String> =
+            getters[Self::METADATA_ID_INDEX].get_opt(i, "metaData.id")?;
+        if metadata_id.is_some() {
+            return Ok(!std::mem::replace(self.seen_metadata, true));
+        }
+        let min_reader_version: Option<i32> = getters[Self::PROTOCOL_MIN_READER_VERSION_INDEX]
+            .get_opt(i, "protocol.minReaderVersion")?;
+        if min_reader_version.is_some() {
+            return Ok(!std::mem::replace(self.seen_protocol, true));
+        }
+        let app_id: Option<String> = getters[Self::TXN_APP_ID_INDEX].get_opt(i, "txn.appId")?;
+        if let Some(app_id) = app_id {
+            // only the most recent txn action of an application counts, even if it has expired
+            if !self.seen_txns.insert(app_id) {
+                return Ok(false);
+            }
+            let last_updated: Option<i64> =
+                getters[Self::TXN_LAST_UPDATED_INDEX].get_opt(i, "txn.lastUpdated")?;
+            let expired = self
+                .txn_expiration_timestamp
+                .zip(last_updated)
+                .is_some_and(|(expiration, last_updated)| last_updated <= expiration);
+            return Ok(!expired);
+        }
+        let domain: Option<String> =
+            getters[Self::DOMAIN_METADATA_DOMAIN_INDEX].get_opt(i, "domainMetadata.domain")?;
+        if let Some(domain) = domain {
+            // only the most recent action of a domain counts, even if it removes the domain
+            if !self.seen_domains.insert(domain) {
+                return Ok(false);
+            }
+            let removed: bool =
+                getters[Self::DOMAIN_METADATA_REMOVED_INDEX].get(i, "domainMetadata.removed")?;
+            return Ok(!removed || self.keep_removed_domains);
+        }
+        Ok(false)
+    }
+}
+
+impl RowVisitor for CheckpointVisitor<'_> {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        // NOTE: The order of the names and types must match the index constants above
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            const STRING: DataType = DataType::STRING;
+            const INTEGER: DataType = DataType::INTEGER;
+            const LONG: DataType = DataType::LONG;
+            let types_and_names = vec![
+                (STRING, column_name!("add.path")),
+                (STRING, column_name!("add.deletionVector.storageType")),
+                (STRING, column_name!("add.deletionVector.pathOrInlineDv")),
+                (INTEGER, column_name!("add.deletionVector.offset")),
+                (STRING, column_name!("remove.path")),
+                (LONG, column_name!("remove.deletionTimestamp")),
+                (STRING, column_name!("remove.deletionVector.storageType")),
+                (STRING, column_name!("remove.deletionVector.pathOrInlineDv")),
+                (INTEGER, column_name!("remove.deletionVector.offset")),
+                (STRING, column_name!("metaData.id")),
+                (INTEGER, column_name!("protocol.minReaderVersion")),
+                (STRING, column_name!("txn.appId")),
+                (LONG, column_name!("txn.lastUpdated")),
+                (STRING, column_name!("domainMetadata.domain")),
+                (DataType::BOOLEAN, column_name!("domainMetadata.removed")),
+            ];
+            let (types, names) = types_and_names.into_iter().unzip();
+            (names, types).into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        self.selection_vector = vec![false; row_count];
+        self.file_actions = vec![false; row_count];
+        for i in 0..row_count {
+            let is_valid = match self.is_valid_file_action(i, getters)? {
+                Some(is_valid) => {
+                    self.file_actions[i] = true;
+                    is_valid
+                }
+                None => self.is_valid_non_file_action(i, getters)?,
+            };
+            self.selection_vector[i] = is_valid;
+            self.actions_count += is_valid as i64;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::arrow::array::StringArray;
+    use crate::utils::test_utils::parse_json_batch;
+
+    fn visit(
+        state: &mut CheckpointLogReplayState,
+        json_strings: Vec<&str>,
+        is_log_batch: bool,
+    ) -> (Vec<bool>, Vec<bool>) {
+        let json_strings: StringArray = json_strings.into();
+        let batch = parse_json_batch(json_strings);
+        let mut visitor = CheckpointVisitor::new(state, is_log_batch, 100, Some(100), false);
+        visitor.visit_rows_of(batch.as_ref()).unwrap();
+        (visitor.selection_vector, visitor.file_actions)
+    }
+
+    #[test]
+    fn test_checkpoint_visitor() {
+        let mut state = CheckpointLogReplayState::default();
+        let (selection_vector, file_actions) = visit(
+            &mut state,
+            vec![
+                r#"{"commitInfo":{"timestamp":1,"operation":"WRITE"}}"#,
+                r#"{"add":{"path":"a","partitionValues":{},"size":1,"modificationTime":1,"dataChange":true}}"#,
+                r#"{"remove":{"path":"b","deletionTimestamp":200,"dataChange":true}}"#,
+                r#"{"remove":{"path":"c","deletionTimestamp":100,"dataChange":true}}"#,
+                r#"{"remove":{"path":"d","dataChange":true}}"#,
+                r#"{"txn":{"appId":"app1","version":2,"lastUpdated":200}}"#,
+                r#"{"txn":{"appId":"app2","version":2,"lastUpdated":100}}"#,
+                r#"{"txn":{"appId":"app3","version":2}}"#,
+                r#"{"metaData":{"id":"id","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[]}","partitionColumns":[],"configuration":{}}}"#,
+                r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":2}}"#,
+            ],
+            true,
+        );
+        // commit info, expired tombstones and expired transactions are dropped
+        assert_eq!(
+            selection_vector,
+            vec![false, true, true, false, false, true, false, true, true, true]
+        );
+        assert_eq!(
+            file_actions,
+            vec![false, true, true, true, true, false, false, false, false, false]
+        );
+
+        // older actions superseded by the newer ones are dropped, even from checkpoint batches
+        let (selection_vector, _) = visit(
+            &mut state,
+            vec![
+                r#"{"add":{"path":"b","partitionValues":{},"size":1,"modificationTime":1,"dataChange":true}}"#,
+                r#"{"add":{"path":"e","partitionValues":{},"size":1,"modificationTime":1,"dataChange":true}}"#,
+                r#"{"remove":{"path":"a","deletionTimestamp":300,"dataChange":true}}"#,
+                r#"{"txn":{"appId":"app1","version":1,"lastUpdated":200}}"#,
+                r#"{"txn":{"appId":"app2","version":1}}"#,
+                r#"{"metaData":{"id":"id","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[]}","partitionColumns":[],"configuration":{}}}"#,
+                r#"{"protocol":{"minReaderVersion":1,"minWriterVersion":1}}"#,
+            ],
+            false,
+        );
+        assert_eq!(
+            selection_vector,
+            vec![false, true, false, false, false, false, false]
+        );
+    }
+
+    #[test]
+    fn test_checkpoint_visitor_domain_metadata() {
+        let actions = vec![
+            r#"{"domainMetadata":{"domain":"a","configuration":"2","removed":false}}"#,
+            r#"{"domainMetadata":{"domain":"b","configuration":"","removed":true}}"#,
+            r#"{"domainMetadata":{"domain":"a","configuration":"1","removed":false}}"#,
+            r#"{"domainMetadata":{"domain":"b","configuration":"1","removed":false}}"#,
+        ];
+        // checkpoints drop the removed domains, along with the older actions of each domain
+        let mut state = CheckpointLogReplayState::default();
+        let (selection_vector, file_actions) = visit(&mut state, actions.clone(), true);
+        assert_eq!(selection_vector, vec![true, false, false, false]);
+        assert_eq!(file_actions, vec![false; 4]);
+
+        // log compactions keep the actions that remove their domain
+        let json_strings: StringArray = actions.into();
+        let batch = parse_json_batch(json_strings);
+        let mut state = CheckpointLogReplayState::default();
+        let mut visitor = CheckpointVisitor::new(&mut state, true, i64::MIN, None, true);
+        visitor.visit_rows_of(batch.as_ref()).unwrap();
+        assert_eq!(visitor.selection_vector, vec![true, true, false, false]);
+    }
+}
@@ -0,0 +1,663 @@
+//! Writing checkpoints of a table.
+//!
+//! A checkpoint contains the complete state of a table at a version, so that readers don't have
+//! to replay the commits up to that version. Kernel writes single-part checkpoints with a
+//! [`CheckpointBuilder`], and updates the `_last_checkpoint` file to point to the new checkpoint.
+//! The kind of checkpoint depends on the table:
+//! - Tables without the `v2Checkpoint` feature get classic checkpoints, named
+//!   `n.checkpoint.parquet` where `n` is the zero-padded version.
+//! - Tables with the `v2Checkpoint` feature get classic-named checkpoints that follow the V2 spec
+//!   (i.e. that contain a `checkpointMetadata` action), unless their `delta.checkpointPolicy` is
+//!   `v2`.
+//! - Tables with `delta.checkpointPolicy = v2` get UUID-named V2 checkpoints, named
+//!   `n.checkpoint.<uuid>.parquet` or `n.checkpoint.<uuid>.json`. Their `add` and `remove` actions
+//!   are written to parquet sidecar files in `_delta_log/_sidecars/`, which are referenced by the
+//!   `sidecar` actions of the checkpoint, so that large checkpoints can be written and read in
+//!   parallel.
+//!
+//! Log compaction files, named `x.y.compacted.json`, are a lighter alternative to checkpoints:
+//! they summarize the commits from version `x` to `y` (inclusive), and readers replay them instead
+//! of these commits. See [`write_log_compaction`].
+//!
+//! Writing a checkpoint also deletes the log files that expired after the log retention duration of
+//! the table (`delta.logRetentionDuration`) and that are no longer needed to reconstruct any
+//! retained version, unless `delta.enableExpiredLogCleanup` is false.
+//!
+//! See the [checkpoint specification] and the [log compaction specification] for details.
+//!
+//! [checkpoint specification]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#checkpoints
+//! [log compaction specification]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#log-compaction-files
+
+use std::num::NonZero;
+use std::sync::{Arc, LazyLock};
+
+use serde::Serialize;
+use tracing::warn;
+use url::Url;
+
+use crate::actions::set_transaction::txn_expiration_timestamp;
+use crate::actions::{
+    action_to_engine_data, get_log_schema, CheckpointMetadata, Sidecar, ADD_NAME,
+    CHECKPOINT_METADATA_NAME, DOMAIN_METADATA_NAME, METADATA_NAME, PROTOCOL_NAME, REMOVE_NAME,
+    SET_TRANSACTION_NAME, SIDECAR_NAME,
+};
+use crate::log_segment::LogSegment;
+use crate::path::ParsedLogPath;
+use crate::schema::SchemaRef;
+use crate::snapshot::Snapshot;
+use crate::snapshot::{read_last_checkpoint, LastCheckpointHint, LAST_CHECKPOINT_FILE_NAME};
+use crate::table_properties::{
+    CheckpointPolicy, TableProperties, DEFAULT_DELETED_FILE_RETENTION_DURATION,
+};
+use crate::utils::current_time_ms;
+use crate::{
+    DeltaResult, Engine, EngineData, Error, Expression, ExpressionEvaluator, FileSystemClient,
+    FilteredEngineData, RowVisitor as _, Version,
+};
+
+use log_cleanup::cleanup_expired_logs;
+use log_replay::{CheckpointLogReplayState, CheckpointVisitor};
+
+mod log_cleanup;
+mod log_replay;
+
+/// The default maximum number of file actions written to each sidecar file of V2 checkpoints
+const DEFAULT_FILE_ACTIONS_PER_SIDECAR: usize = 1_000_000;
+
+// The actions read from the log to write a checkpoint. The sidecar column is only read so that
+// the file actions of V2 checkpoints with sidecars are read too.
+static CHECKPOINT_READ_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    get_log_schema()
+        .project(&[
+            ADD_NAME,
+            REMOVE_NAME,
+            METADATA_NAME,
+            PROTOCOL_NAME,
+            SET_TRANSACTION_NAME,
+            DOMAIN_METADATA_NAME,
+            SIDECAR_NAME,
+        ])
+        .expect("checkpoint actions are in the log schema")
+});
+
+// The actions of a classic checkpoint
+static CHECKPOINT_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    get_log_schema()
+        .project(&[
+            ADD_NAME,
+            REMOVE_NAME,
+            METADATA_NAME,
+            PROTOCOL_NAME,
+            SET_TRANSACTION_NAME,
+            DOMAIN_METADATA_NAME,
+        ])
+        .expect("checkpoint actions are in the log schema")
+});
+
+// The actions of a checkpoint following the V2 spec
+static V2_CHECKPOINT_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    get_log_schema()
+        .project(&[
+            ADD_NAME,
+            REMOVE_NAME,
+            METADATA_NAME,
+            PROTOCOL_NAME,
+            SET_TRANSACTION_NAME,
+            DOMAIN_METADATA_NAME,
+            CHECKPOINT_METADATA_NAME,
+            SIDECAR_NAME,
+        ])
+        .expect("checkpoint actions are in the log schema")
+});
+
+// The actions of a sidecar file
+static SIDECAR_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    get_log_schema()
+        .project(&[ADD_NAME, REMOVE_NAME])
+        .expect("file actions are in the log schema")
+});
+
+/// The format of the top-level file of UUID-named V2 checkpoints. Sidecar files are always
+/// written as parquet.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum V2CheckpointFormat {
+    /// Write the top-level checkpoint file as parquet
+    #[default]
+    Parquet,
+    /// Write the top-level checkpoint file as JSON
+    Json,
+}
+
+impl V2CheckpointFormat {
+    fn extension(&self) -> &'static str {
+        match self {
+            V2CheckpointFormat::Parquet => "parquet",
+            V2CheckpointFormat::Json => "json",
+        }
+    }
+}
+
+/// Builder for writing a checkpoint of the table at the version of a [`Snapshot`]. Create one with
+/// [`Snapshot::checkpoint_builder`], and write the checkpoint with [`CheckpointBuilder::write`].
+#[derive(Debug)]
+pub struct CheckpointBuilder<'a> {
+    snapshot: &'a Snapshot,
+    v2_checkpoint_format: V2CheckpointFormat,
+    file_actions_per_sidecar: NonZero<usize>,
+}
+
+impl<'a> CheckpointBuilder<'a> {
+    pub(crate) fn new(snapshot: &'a Snapshot) -> Self {
+        CheckpointBuilder {
+            snapshot,
+            v2_checkpoint_format: V2CheckpointFormat::default(),
+            file_actions_per_sidecar: NonZero::new(DEFAULT_FILE_ACTIONS_PER_SIDECAR)
+                .expect("default file actions per sidecar is not zero"),
+        }
+    }
+
+    /// Set the format of the top-level file of UUID-named V2 checkpoints (parquet by default).
+    /// Ignored for other kinds of checkpoints, which are always written as parquet.
+    pub fn with_v2_checkpoint_format(mut self, format: V2CheckpointFormat) -> Self {
+        self.v2_checkpoint_format = format;
+        self
+    }
+
+    /// Set the maximum number of `add` and `remove` actions written to each sidecar file of
+    /// UUID-named V2 checkpoints (one million by default).
+    pub fn with_file_actions_per_sidecar(
+        mut self,
+        file_actions_per_sidecar: NonZero<usize>,
+    ) -> Self {
+        self.file_actions_per_sidecar = file_actions_per_sidecar;
+        self
+    }
+
+    /// Write the checkpoint, then update `_last_checkpoint` to point to it (unless it already
+    /// points to a newer checkpoint). Unless `delta.enableExpiredLogCleanup` is false, the log
+    /// files that expired after `delta.logRetentionDuration` and are no longer needed are then
+    /// deleted. Failing to delete them doesn't fail the checkpoint.
+    ///
+    /// Returns an error if kernel doesn't support writing to the table, or if the table has
+    /// `delta.checkpointPolicy = v2` without the `v2Checkpoint` feature.
+    pub fn write(self, engine: &dyn Engine) -> DeltaResult<()> {
+        // the checkpoint must preserve all the actions required by the writer features of the
+        // table, so we can only checkpoint tables kernel can write to
+        let snapshot = self.snapshot;
+        let protocol = snapshot.protocol();
+        protocol.ensure_write_supported()?;
+        let v2_checkpoint_supported = snapshot.table_configuration().is_v2_checkpoint_supported();
+        let checkpoint_policy = snapshot
+            .table_properties()
+            .checkpoint_policy
+            .clone()
+            .unwrap_or_default();
+        let counts = match (v2_checkpoint_supported, checkpoint_policy) {
+            (false, CheckpointPolicy::Classic) => write_classic_checkpoint(snapshot, engine, false)?,
+            (true, CheckpointPolicy::Classic) => write_classic_checkpoint(snapshot, engine, true)?,
+            (true, CheckpointPolicy::V2) => self.write_v2_checkpoint(engine)?,
+            (false, CheckpointPolicy::V2) => {
+                return Err(Error::invalid_protocol(
+                    "delta.checkpointPolicy is v2, but the table doesn't support the v2Checkpoint feature",
+                ))
+            }
+        };
+
+        let version = snapshot.version();
+        let log_root = &snapshot.log_segment().log_root;
+        let fs_client = engine.get_file_system_client();
+        let newer_checkpoint_exists = read_last_checkpoint(fs_client.as_ref(), log_root)?
+            .is_some_and(|last_checkpoint| last_checkpoint.version > version);
+        if !newer_checkpoint_exists {
+            write_last_checkpoint(fs_client.as_ref(), log_root, version, &counts)?;
+        }
+
+        // the new checkpoint may make older log files unnecessary
+        if snapshot
+            .table_properties()
+            .enable_expired_log_cleanup
+            .unwrap_or(true)
+        {
+            if let Err(e) = cleanup_expired_logs(snapshot, engine) {
+                warn!("Failed to clean up expired log files: {e}");
+            }
+        }
+        Ok(())
+    }
+
+    // Write a UUID-named V2 checkpoint, with the file actions in sidecar files
+    fn write_v2_checkpoint(&self, engine: &dyn Engine) -> DeltaResult<ActionCounts> {
+        let snapshot = self.snapshot;
+        let log_root = &snapshot.log_segment().log_root;
+        let file_actions_per_sidecar = self.file_actions_per_sidecar.get();
+        let sidecar_evaluator =
+            actions_evaluator(engine, CHECKPOINT_READ_SCHEMA.clone(), &SIDECAR_SCHEMA);
+        let checkpoint_evaluator = actions_evaluator(
+            engine,
+            CHECKPOINT_READ_SCHEMA.clone(),
+            &V2_CHECKPOINT_SCHEMA,
+        );
+
+        // write the file actions to sidecar files as they are replayed, and keep the other actions
+        // for the top-level checkpoint file, which references the sidecar files
+        let mut counts = ActionCounts::default();
+        let mut checkpoint_actions = vec![];
+        let mut sidecars = vec![];
+        let mut sidecar_actions = vec![];
+        let mut sidecar_actions_count = 0;
+        for batch in checkpoint_batches(snapshot, engine)? {
+            let batch = batch?;
+            counts.actions += batch.actions_count;
+            counts.add_actions += batch.add_actions_count;
+            let (mut file_selection, other_selection): (Vec<_>, Vec<_>) = batch
+                .selection_vector
+                .iter()
+                .zip(&batch.file_actions)
+                .map(|(&selected, &is_file_action)| {
+                    (selected && is_file_action, selected && !is_file_action)
+                })
+                .unzip();
+            if other_selection.contains(&true) {
+                let actions = checkpoint_evaluator.evaluate(batch.actions.as_ref())?;
+                checkpoint_actions.push(FilteredEngineData::try_new(actions, other_selection)?);
+            }
+
+            // split the file actions of the batch across sidecar files as needed
+            let mut remaining = file_selection.iter().filter(|&&selected| selected).count();
+            while remaining > 0 {
+                let take = remaining.min(file_actions_per_sidecar - sidecar_actions_count);
+                let mut selection = vec![false; file_selection.len()];
+                for (i, _) in file_selection
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, &selected)| selected)
+                    .take(take)
+                {
+                    selection[i] = true;
+                }
+                for (selected, taken) in file_selection.iter_mut().zip(&selection) {
+                    *selected &= !taken;
+                }
+                let actions = sidecar_evaluator.evaluate(batch.actions.as_ref())?;
+                sidecar_actions.push(FilteredEngineData::try_new(actions, selection)?);
+                sidecar_actions_count += take;
+                remaining -= take;
+                if sidecar_actions_count == file_actions_per_sidecar {
+                    sidecars.push(write_sidecar(
+                        engine,
+                        log_root,
+                        std::mem::take(&mut sidecar_actions),
+                    )?);
+                    sidecar_actions_count = 0;
+                }
+            }
+        }
+        if !sidecar_actions.is_empty() {
+            sidecars.push(write_sidecar(engine, log_root, sidecar_actions)?);
+        }
+
+        let version = snapshot.version();
+        let checkpoint_metadata = CheckpointMetadata {
+            version: version as i64,
+            tags: None,
+        };
+        checkpoint_actions.push(single_action(
+            engine,
+            CHECKPOINT_METADATA_NAME,
+            &checkpoint_metadata,
+            &V2_CHECKPOINT_SCHEMA,
+        )?);
+        for sidecar in &sidecars {
+            checkpoint_actions.push(single_action(
+                engine,
+                SIDECAR_NAME,
+                sidecar,
+                &V2_CHECKPOINT_SCHEMA,
+            )?);
+        }
+        counts.actions += 1 + sidecars.len() as i64;
+
+        let format = self.v2_checkpoint_format;
+        let checkpoint_path =
+            ParsedLogPath::new_uuid_checkpoint(snapshot.table_root(), version, format.extension())?;
+        let checkpoint_actions = Box::new(checkpoint_actions.into_iter().map(Ok));
+        match format {
+            V2CheckpointFormat::Parquet => {
+                engine.get_parquet_handler().write_parquet_file(
+                    &checkpoint_path.location,
+                    checkpoint_actions,
+                    false,
+                )?;
+            }
+            V2CheckpointFormat::Json => engine.get_json_handler().write_json_file(
+                &checkpoint_path.location,
+                checkpoint_actions,
+                false,
+            )?,
+        }
+        Ok(counts)
+    }
+}
+
+/// Write a log compaction file that summarizes the commits of the table from `start_version` to
+/// `end_version` (inclusive). It contains the actions of these commits that survive reconciliation:
+/// the most recent `protocol`, `metaData` and (per application) `txn` actions, and the most recent
+/// `add` or `remove` action of each file. Tombstones and transaction identifiers never expire in
+/// log compaction files, since they may have to reconcile older actions.
+pub(crate) fn write_log_compaction(
+    snapshot: &Snapshot,
+    engine: &dyn Engine,
+    start_version: Version,
+    end_version: Version,
+) -> DeltaResult<()> {
+    // the log compaction must preserve all the actions required by the writer features of the
+    // table, so we can only compact the log of tables kernel can write to
+    snapshot.protocol().ensure_write_supported()?;
+    if start_version >= end_version || end_version > snapshot.version() {
+        return Err(Error::generic(format!(
+            "Invalid log compaction range [{start_version}, {end_version}] for a snapshot at version {}",
+            snapshot.version()
+        )));
+    }
+
+    let log_segment = LogSegment::for_table_changes(
+        engine.get_file_system_client().as_ref(),
+        snapshot.log_segment().log_root.clone(),
+        start_version,
+        end_version,
+    )?;
+    let evaluator = actions_evaluator(engine, CHECKPOINT_READ_SCHEMA.clone(), &CHECKPOINT_SCHEMA);
+    let actions = replay_batches(&log_segment, engine, i64::MIN, None, true)?.map(|batch| {
+        let batch = batch?;
+        let actions = evaluator.evaluate(batch.actions.as_ref())?;
+        FilteredEngineData::try_new(actions, batch.selection_vector)
+    });
+    let compaction_path =
+        ParsedLogPath::new_log_compaction(snapshot.table_root(), start_version, end_version)?;
+    // the log compaction of a range of commits always has the same content, so it's safe to
+    // overwrite
+    engine
+        .get_json_handler()
+        .write_json_file(&compaction_path.location, Box::new(actions), true)
+}
+
+// The number of actions and add actions written to a checkpoint
+#[derive(Debug, Default)]
+struct ActionCounts {
+    actions: i64,
+    add_actions: i64,
+}
+
+// Update `_last_checkpoint` to point to the checkpoint at `version`
+fn write_last_checkpoint(
+    fs_client: &dyn FileSystemClient,
+    log_root: &Url,
+    version: Version,
+    counts: &ActionCounts,
+) -> DeltaResult<()> {
+    let last_checkpoint = LastCheckpointHint {
+        version,
+        size: counts.actions,
+        parts: None,
+        size_in_bytes: None,
+        num_of_add_files: Some(counts.add_actions),
+        checkpoint_schema: None,
+        checksum: None,
+    };
+    fs_client.write_file(
+        &log_root.join(LAST_CHECKPOINT_FILE_NAME)?,
+        serde_json::to_vec(&last_checkpoint)?.into(),
+        true,
+    )
+}
+
+// Write a classic-named single-part parquet checkpoint. If `v2_spec` is set, the checkpoint follows
+// the V2 spec, i.e. it contains a `checkpointMetadata` action.
+fn write_classic_checkpoint(
+    snapshot: &Snapshot,
+    engine: &dyn Engine,
+    v2_spec: bool,
+) -> DeltaResult<ActionCounts> {
+    let version = snapshot.version();
+    let checkpoint_path =
+        ParsedLogPath::new_classic_parquet_checkpoint(snapshot.table_root(), version)?;
+    let schema = if v2_spec {
+        &V2_CHECKPOINT_SCHEMA
+    } else {
+        &CHECKPOINT_SCHEMA
+    };
+    let evaluator = actions_evaluator(engine, CHECKPOINT_READ_SCHEMA.clone(), schema);
+    let checkpoint_metadata = v2_spec
+        .then(|| {
+            let checkpoint_metadata = CheckpointMetadata {
+                version: version as i64,
+                tags: None,
+            };
+            single_action(
+                engine,
+                CHECKPOINT_METADATA_NAME,
+                &checkpoint_metadata,
+                schema,
+            )
+        })
+        .transpose()?;
+
+    let mut counts = ActionCounts {
+        actions: v2_spec as i64,
+        add_actions: 0,
+    };
+    let actions = checkpoint_batches(snapshot, engine)?
+        .map(|batch| {
+            let batch = batch?;
+            counts.actions += batch.actions_count;
+            counts.add_actions += batch.add_actions_count;
+            let actions = evaluator.evaluate(batch.actions.as_ref())?;
+            FilteredEngineData::try_new(actions, batch.selection_vector)
+        })
+        .chain(checkpoint_metadata.map(Ok));
+    // the checkpoint of a version always has the same content, so it's safe to overwrite
+    engine.get_parquet_handler().write_parquet_file(
+        &checkpoint_path.location,
+        Box::new(actions),
+        true,
+    )?;
+    Ok(counts)
+}
+
+// Write the file actions to a new UUID-named parquet sidecar file, and return the sidecar action
+// that references it
+fn write_sidecar(
+    engine: &dyn Engine,
+    log_root: &Url,
+    file_actions: Vec<FilteredEngineData>,
+) -> DeltaResult<Sidecar> {
+    let file_name = format!("{}.parquet", uuid::Uuid::new_v4());
+    let location = log_root.join("_sidecars/")?.join(&file_name)?;
+    let file_meta = engine.get_parquet_handler().write_parquet_file(
+        &location,
+        Box::new(file_actions.into_iter().map(Ok)),
+        false,
+    )?;
+    Ok(Sidecar {
+        path: file_name,
+        size_in_bytes: file_meta
+            .size
+            .try_into()
+            .map_err(|_| Error::generic("sidecar file size is too large"))?,
+        modification_time: file_meta.last_modified,
+        tags: None,
+    })
+}
+
+// Create an evaluator that converts actions in `input_schema` to `output_schema`, with null columns
+// for the actions of `output_schema` that are not in `input_schema`
+fn actions_evaluator(
+    engine: &dyn Engine,
+    input_schema: SchemaRef,
+    output_schema: &SchemaRef,
+) -> Arc<dyn ExpressionEvaluator> {
+    let columns = output_schema.fields().map(|field| {
+        if input_schema.field(field.name()).is_some() {
+            Expression::column([field.name()])
+        } else {
+            Expression::null_literal(field.data_type().clone())
+        }
+    });
+    let expression = Expression::struct_from(columns);
+    engine.get_expression_handler().get_evaluator(
+        input_schema,
+        expression,
+        output_schema.clone().into(),
+    )
+}
+
+// Build a single-row batch of actions in `output_schema` that contains the action `name`
+fn single_action(
+    engine: &dyn Engine,
+    name: &str,
+    action: &impl Serialize,
+    output_schema: &SchemaRef,
+) -> DeltaResult<FilteredEngineData> {
+    let action = action_to_engine_data(engine, name, action)?;
+    let evaluator = actions_evaluator(engine, get_log_schema().project(&[name])?, output_schema);
+    FilteredEngineData::try_new(evaluator.evaluate(action.as_ref())?, vec![true])
+}
+
+// A batch of actions read from the log, with the actions that belong in the checkpoint selected
+pub(crate) struct CheckpointBatch {
+    // the actions, in CHECKPOINT_READ_SCHEMA
+    pub(crate) actions: Box<dyn EngineData>,
+    pub(crate) selection_vector: Vec<bool>,
+    // whether each row is a file action
+    file_actions: Vec<bool>,
+    actions_count: i64,
+    add_actions_count: i64,
+}
+
+// Replay the log of the snapshot, and return the batches of actions with the actions to write in
+// the checkpoint selected.
+fn checkpoint_batches(
+    snapshot: &Snapshot,
+    engine: &dyn Engine,
+) -> DeltaResult<impl Iterator<Item = DeltaResult<CheckpointBatch>> + Send> {
+    let table_properties = snapshot.table_properties();
+    let minimum_file_retention_timestamp = minimum_file_retention_timestamp(table_properties)?;
+    let txn_expiration_timestamp = txn_expiration_timestamp(table_properties)?;
+    replay_batches(
+        snapshot.log_segment(),
+        engine,
+        minimum_file_retention_timestamp,
+        txn_expiration_timestamp,
+        false,
+    )
+}
+
+// The timestamp before which removed files expire, i.e. the current time minus the
+// `delta.deletedFileRetentionDuration` of the table (one week by default)
+pub(crate) fn minimum_file_retention_timestamp(
+    table_properties: &TableProperties,
+) -> DeltaResult<i64> {
+    let deleted_file_retention = table_properties
+        .deleted_file_retention_duration
+        .unwrap_or(DEFAULT_DELETED_FILE_RETENTION_DURATION);
+    let deleted_file_retention = i64::try_from(deleted_file_retention.as_millis())
+        .map_err(|_| Error::generic("deleted file retention duration is too large"))?;
+    Ok(current_time_ms()?.saturating_sub(deleted_file_retention))
+}
+
+// Replay the log segment, and return the batches of actions with the surviving actions selected.
+// Tombstones deleted at or before `minimum_file_retention_timestamp` and `txn` actions last
+// updated at or before `txn_expiration_timestamp` don't survive, nor do the `domainMetadata`
+// actions that remove their domain unless `keep_removed_domains` is set.
+pub(crate) fn replay_batches(
+    log_segment: &LogSegment,
+    engine: &dyn Engine,
+    minimum_file_retention_timestamp: i64,
+    txn_expiration_timestamp: Option<i64>,
+    keep_removed_domains: bool,
+) -> DeltaResult<impl Iterator<Item = DeltaResult<CheckpointBatch>> + Send> {
+    let mut state = CheckpointLogReplayState::default();
+    let actions = log_segment.read_actions(
+        engine,
+        CHECKPOINT_READ_SCHEMA.clone(),
+        CHECKPOINT_READ_SCHEMA.clone(),
+        None,
+    )?;
+    Ok(actions.map(move |actions| -> DeltaResult<_> {
+        let (actions, is_log_batch) = actions?;
+        let mut visitor = CheckpointVisitor::new(
+            &mut state,
+            is_log_batch,
+            minimum_file_retention_timestamp,
+            txn_expiration_timestamp,
+            keep_removed_domains,
+        );
+        visitor.visit_rows_of(actions.as_ref())?;
+        Ok(CheckpointBatch {
+            actions,
+            selection_vector: visitor.selection_vector,
+            file_actions: visitor.file_actions,
+            actions_count: visitor.actions_count,
+            add_actions_count: visitor.add_actions_count,
+        })
+    }))
+}
+
+#[cfg(all(test, feature = "sync-engine"))]
+mod tests {
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::engine::sync::SyncEngine;
+    use crate::Table;
+
+    fn copy_dir(from: &Path, to: &Path) {
+        std::fs::create_dir_all(to).unwrap();
+        for entry in std::fs::read_dir(from).unwrap() {
+            let entry = entry.unwrap();
+            let target = to.join(entry.file_name());
+            if entry.file_type().unwrap().is_dir() {
+                copy_dir(&entry.path(), &target);
+            } else {
+                std::fs::copy(entry.path(), target).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_write_checkpoint() {
+        let dir = tempfile::tempdir().unwrap();
+        copy_dir(
+            &PathBuf::from("./tests/data/app-txn-no-checkpoint/"),
+            dir.path(),
+        );
+        let url = url::Url::from_directory_path(dir.path()).unwrap();
+        let engine = SyncEngine::new();
+        let table = Table::new(url);
+
+        let snapshot = table.snapshot(&engine, None).unwrap();
+        assert!(snapshot.log_segment().checkpoint_parts.is_empty());
+        snapshot.checkpoint(&engine).unwrap();
+
+        // the new snapshot is loaded from the checkpoint, with the same state
+        let checkpointed = table.snapshot(&engine, None).unwrap();
+        let log_segment = checkpointed.log_segment();
+        assert_eq!(log_segment.checkpoint_parts.len(), 1);
+        assert_eq!(log_segment.checkpoint_parts[0].version, 1);
+        assert!(log_segment.ascending_commit_files.is_empty());
+        assert_eq!(checkpointed.metadata(), snapshot.metadata());
+        assert_eq!(checkpointed.protocol(), snapshot.protocol());
+        for app_id in ["my-app", "my-app2"] {
+            assert_eq!(
+                checkpointed.get_app_id_version(app_id, &engine).unwrap(),
+                snapshot.get_app_id_version(app_id, &engine).unwrap()
+            );
+        }
+        let scan_files = |snapshot: Snapshot| {
+            let scan = snapshot.into_scan_builder().build().unwrap();
+            scan.execute(Arc::new(SyncEngine::new())).unwrap().count()
+        };
+        assert_eq!(scan_files(checkpointed), scan_files(snapshot));
+    }
+}
@@ -0,0 +1,303 @@
+//! Clustering of arrow data along the space-filling curves of [`Clustering`]s.
+//!
+//! The rows are sorted by a key computed from the values of the clustering columns: each value is
+//! replaced by its rank among the values of its column, so that columns of any type and
+//! distribution contribute evenly to the key, and the bits of the ranks are combined along the
+//! curve. For the Z-order curve, the bits are interleaved; for the Hilbert curve, the ranks are
+//! first transformed with [Skilling's algorithm], so that the interleaved bits are the Hilbert
+//! index of the ranks.
+//!
+//! [Skilling's algorithm]: https://doi.org/10.1063/1.1751381
+
+use std::sync::Arc;
+
+use itertools::Itertools;
+
+use crate::arrow::array::{ArrayRef, BinaryArray, BinaryBuilder, RecordBatch};
+use crate::arrow::compute::{cast, concat_batches, rank, sort_to_indices, take_record_batch};
+use crate::arrow::datatypes::DataType as ArrowDataType;
+use crate::engine::arrow_data::ArrowEngineData;
+use crate::engine::arrow_expression::ArrowExpressionHandler;
+use crate::optimize::{Clustering, SpaceFillingCurve};
+use crate::schema::{ColumnName, DataType, SchemaRef, StructType};
+use crate::{DeltaResult, Error, Expression, ExpressionHandler};
+
+/// Sort the rows of the batches along the curve of the clustering, and split them into (at most)
+/// `num_files` batches of about the same number of rows, each to be written to its own file.
+/// The batches must all have the same schema, which contains the clustering columns.
+///
+/// This is typically applied to the data of a clustering [`RewriteTask`], after the rows removed
+/// by deletion vectors are filtered out, with the task's [`RewriteTask::num_output_files`].
+///
+/// [`RewriteTask`]: crate::optimize::RewriteTask
+/// [`RewriteTask::num_output_files`]: crate::optimize::RewriteTask::num_output_files
+pub fn cluster_record_batches(
+    batches: &[RecordBatch],
+    clustering: &Clustering,
+    num_files: usize,
+) -> DeltaResult<Vec<RecordBatch>> {
+    let Some(first) = batches.first() else {
+        return Ok(vec![]);
+    };
+    let batch = concat_batches(&first.schema(), batches)?;
+    let num_rows = batch.num_rows();
+    if num_rows == 0 {
+        return Ok(vec![]);
+    }
+    let keys = clustering_keys(&batch, clustering)?;
+    let indices = sort_to_indices(&keys, None, None)?;
+    let batch = take_record_batch(&batch, &indices)?;
+    let rows_per_file = num_rows.div_ceil(num_files.clamp(1, num_rows));
+    Ok((0..num_rows)
+        .step_by(rows_per_file)
+        .map(|offset| batch.slice(offset, rows_per_file.min(num_rows - offset)))
+        .collect())
+}
+
+// The keys of the rows of the batch along the curve of the clustering. Each key holds 32 bits of
+// the rank of each clustering column.
+fn clustering_keys(batch: &RecordBatch, clustering: &Clustering) -> DeltaResult<BinaryArray> {
+    let schema: SchemaRef = Arc::new(batch.schema().try_into()?);
+    let data = ArrowEngineData::new(batch.clone());
+    let ranks: Vec<_> = clustering
+        .columns()
+        .iter()
+        .map(|column| {
+            let data_type = column_type(&schema, column)?;
+            let evaluator = ArrowExpressionHandler.get_evaluator(
+                schema.clone(),
+                Expression::Column(column.clone()),
+                data_type,
+            );
+            let values = ArrowEngineData::try_from_engine_data(evaluator.evaluate(&data)?)?;
+            scaled_ranks(values.record_batch().column(0))
+        })
+        .try_collect()?;
+
+    let num_rows = batch.num_rows();
+    let mut keys = BinaryBuilder::with_capacity(num_rows, num_rows * ranks.len() * 4);
+    let mut point = vec![0; ranks.len()];
+    for row in 0..num_rows {
+        for (coordinate, ranks) in point.iter_mut().zip(&ranks) {
+            *coordinate = ranks[row];
+        }
+        if clustering.curve() == SpaceFillingCurve::Hilbert {
+            hilbert_transpose(&mut point);
+        }
+        keys.append_value(interleave_bits(&point));
+    }
+    Ok(keys.finish())
+}
+
+// The type of a (possibly nested) column of the schema
+fn column_type(schema: &StructType, column: &ColumnName) -> DeltaResult<DataType> {
+    let mut struct_type = schema;
+    let mut data_type: Option<&DataType> = None;
+    for name in column.path() {
+        if let Some(DataType::Struct(child)) = data_type {
+            struct_type = child.as_ref();
+        } else if data_type.is_some() {
+            return Err(Error::missing_column(column));
+        }
+        let field = struct_type
+            .field(name)
+            .ok_or_else(|| Error::missing_column(column))?;
+        data_type = Some(field.data_type());
+    }
+    data_type
+        .cloned()
+        .ok_or_else(|| Error::missing_column(column))
+}
+
+// The ranks of the values (nulls first), scaled so that the highest rank uses all 32 bits
+fn scaled_ranks(values: &ArrayRef) -> DeltaResult<Vec<u32>> {
+    let values = match values.data_type() {
+        ArrowDataType::Boolean => cast(values, &ArrowDataType::Int8)?,
+        _ => values.clone(),
+    };
+    // ranks start at 1, and equal values share the highest of their ranks
+    let ranks = rank(&values, None)?;
+    let max_rank = ranks.iter().max().map_or(0, |max_rank| max_rank - 1);
+    let shift = max_rank.leading_zeros();
+    Ok(ranks
+        .into_iter()
+        .map(|rank| (rank - 1).checked_shl(shift).unwrap_or(0))
+        .collect())
+}
+
+// Interleave the bits of the coordinates, from the most significant bit of the first coordinate
+fn interleave_bits(point: &[u32]) -> Vec<u8> {
+    let mut key = vec![0; point.len() * 4];
+    let bits = (0..32)
+        .rev()
+        .flat_map(|bit| point.iter().map(move |x| (x >> bit) & 1));
+    for (i, bit) in bits.enumerate() {
+        if bit == 1 {
+            key[i / 8] |= 0x80 >> (i % 8);
+        }
+    }
+    key
+}
+
+// Transform the coordinates of a point into the "transposed" Hilbert index, whose interleaved bits
+// are the index of the point along the Hilbert curve (Skilling's AxesToTranspose)
+fn hilbert_transpose(x: &mut [u32]) {
+    let n = x.len();
+    // inverse undo
+    let mut q = 1 << 31;
+    while q > 1 {
+        let p = q - 1;
+        for i in 0..n {
+            if x[i] & q != 0 {
+                x[0] ^= p;
+            } else {
+                let t = (x[0] ^ x[i]) & p;
+                x[0] ^= t;
+                x[i] ^= t;
+            }
+        }
+        q >>= 1;
+    }
+    // Gray encode
+    for i in 1..n {
+        x[i] ^= x[i - 1];
+    }
+    let mut t = 0;
+    q = 1 << 31;
+    while q > 1 {
+        if x[n - 1] & q != 0 {
+            t ^= q - 1;
+        }
+        q >>= 1;
+    }
+    for xi in x.iter_mut() {
+        *xi ^= t;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arrow::array::{AsArray, Int32Array, StringArray};
+    use crate::arrow::datatypes::{Field, Int32Type, Schema};
+    use crate::schema::column_name;
+
+    // The points of a 4x4 grid, with a string column that sorts in reverse of `y`
+    fn grid() -> RecordBatch {
+        let (x, y): (Vec<i32>, Vec<i32>) = (0..4).cartesian_product(0..4).unzip();
+        let s: Vec<String> = y.iter().map(|y| format!("{}", 9 - y)).collect();
+        let schema = Schema::new(vec![
+            Field::new("x", ArrowDataType::Int32, false),
+            Field::new("y", ArrowDataType::Int32, false),
+            Field::new("s", ArrowDataType::Utf8, false),
+        ]);
+        RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(x)),
+                Arc::new(Int32Array::from(y)),
+                Arc::new(StringArray::from(s)),
+            ],
+        )
+        .unwrap()
+    }
+
+    fn points(batch: &RecordBatch) -> Vec<(i32, i32)> {
+        let x = batch.column(0).as_primitive::<Int32Type>();
+        let y = batch.column(1).as_primitive::<Int32Type>();
+        x.values()
+            .iter()
+            .copied()
+            .zip(y.values().iter().copied())
+            .collect()
+    }
+
+    #[test]
+    fn test_z_order() {
+        let batch = grid();
+        let clustering = Clustering::new(
+            SpaceFillingCurve::ZOrder,
+            vec![column_name!("x"), column_name!("y")],
+        );
+        let clustered =
+            cluster_record_batches(std::slice::from_ref(&batch), &clustering, 1).unwrap();
+        let expected = [
+            (0, 0),
+            (0, 1),
+            (1, 0),
+            (1, 1),
+            (0, 2),
+            (0, 3),
+            (1, 2),
+            (1, 3),
+            (2, 0),
+            (2, 1),
+            (3, 0),
+            (3, 1),
+            (2, 2),
+            (2, 3),
+            (3, 2),
+            (3, 3),
+        ];
+        assert_eq!(points(&clustered[0]), expected);
+
+        // the ranks of the values order the rows, whatever their type
+        let clustering = Clustering::new(
+            SpaceFillingCurve::ZOrder,
+            vec![column_name!("x"), column_name!("s")],
+        );
+        let clustered = cluster_record_batches(&[batch], &clustering, 1).unwrap();
+        assert_eq!(points(&clustered[0])[..4], [(0, 3), (0, 2), (1, 3), (1, 2)]);
+    }
+
+    #[test]
+    fn test_hilbert() {
+        let clustering = Clustering::new(
+            SpaceFillingCurve::Hilbert,
+            vec![column_name!("x"), column_name!("y")],
+        );
+        let clustered = cluster_record_batches(&[grid()], &clustering, 1).unwrap();
+        let points = points(&clustered[0]);
+        assert_eq!(points.len(), 16);
+        assert_eq!(points[0], (0, 0));
+        // consecutive points of the Hilbert curve are neighbors
+        for (a, b) in points.iter().tuple_windows() {
+            assert_eq!((a.0 - b.0).abs() + (a.1 - b.1).abs(), 1, "{a:?} -> {b:?}");
+        }
+    }
+
+    #[test]
+    fn test_cluster_record_batches() {
+        let batch = grid();
+        let (first, second) = (batch.slice(0, 10), batch.slice(10, 6));
+        for curve in [SpaceFillingCurve::ZOrder, SpaceFillingCurve::Hilbert] {
+            let clustering = Clustering::new(curve, vec![column_name!("x"), column_name!("y")]);
+            let clustered =
+                cluster_record_batches(&[first.clone(), second.clone()], &clustering, 4).unwrap();
+            // each file holds a quadrant of the grid
+            assert_eq!(clustered.len(), 4);
+            for file in &clustered {
+                let points = points(file);
+                assert_eq!(points.len(), 4);
+                assert!(points.iter().all(|p| p.0 / 2 == points[0].0 / 2));
+                assert!(points.iter().all(|p| p.1 / 2 == points[0].1 / 2));
+            }
+        }
+
+        let clustering = Clustering::new(SpaceFillingCurve::ZOrder, vec![column_name!("z")]);
+        assert!(cluster_record_batches(&[batch], &clustering, 1).is_err());
+        assert!(cluster_record_batches(&[], &clustering, 1)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_scaled_ranks() {
+        let values: ArrayRef = Arc::new(Int32Array::from(vec![Some(5), None, Some(1), Some(5)]));
+        let ranks = scaled_ranks(&values).unwrap();
+        // ranks 4, 1, 2, 4 (nulls first) use the two most significant bits
+        assert_eq!(ranks, [3 << 30, 0, 1 << 30, 3 << 30]);
+        let values: ArrayRef = Arc::new(Int32Array::from(vec![7]));
+        assert_eq!(scaled_ranks(&values).unwrap(), [0]);
+    }
+}
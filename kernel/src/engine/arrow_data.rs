@@ -1,16 +1,24 @@
 use crate::engine_data::{EngineData, EngineList, EngineMap, GetData, RowVisitor};
-use crate::schema::{ColumnName, DataType};
+use crate::expressions::ArrayData;
+use crate::schema::{ColumnName, DataType, SchemaRef};
+use crate::utils::require;
 use crate::{DeltaResult, Error};
 
 use crate::arrow::array::cast::AsArray;
 use crate::arrow::array::types::{Int32Type, Int64Type};
 use crate::arrow::array::{
-    Array, ArrayRef, GenericListArray, MapArray, OffsetSizeTrait, RecordBatch, StructArray,
+    new_empty_array, Array, ArrayRef, GenericListArray, MapArray, OffsetSizeTrait, RecordBatch,
+    StructArray,
 };
-use crate::arrow::datatypes::{DataType as ArrowDataType, FieldRef};
+use crate::arrow::compute::concat;
+use crate::arrow::datatypes::{
+    DataType as ArrowDataType, Field as ArrowField, FieldRef, Schema as ArrowSchema,
+};
+use itertools::Itertools;
 use tracing::debug;
 
 use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 
 pub use crate::engine::arrow_utils::fix_nested_null_masks;
 
@@ -152,6 +160,49 @@ impl EngineData for ArrowEngineData {
         self.data.num_rows()
     }
 
+    fn append_columns(
+        &self,
+        schema: SchemaRef,
+        columns: Vec<ArrayData>,
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        require!(
+            schema.fields_len() == columns.len(),
+            Error::generic(format!(
+                "Expected {} columns to append, but got {}",
+                schema.fields_len(),
+                columns.len()
+            ))
+        );
+        let mut fields = self.data.schema().fields().to_vec();
+        let mut arrays = self.data.columns().to_vec();
+        for (field, column) in schema.fields().zip(columns) {
+            #[allow(deprecated)]
+            let elements = column.array_elements();
+            require!(
+                elements.len() == self.len(),
+                Error::generic(format!(
+                    "Column {} has {} elements, but the data has {} rows",
+                    field.name(),
+                    elements.len(),
+                    self.len()
+                ))
+            );
+            let field = ArrowField::try_from(field)?;
+            let array = if elements.is_empty() {
+                new_empty_array(field.data_type())
+            } else {
+                let element_arrays: Vec<_> =
+                    elements.iter().map(|e| e.to_array(1)).try_collect()?;
+                let element_arrays: Vec<_> = element_arrays.iter().map(AsRef::as_ref).collect();
+                concat(&element_arrays)?
+            };
+            fields.push(Arc::new(field));
+            arrays.push(array);
+        }
+        let schema = Arc::new(ArrowSchema::new(fields));
+        Ok(Box::new(Self::new(RecordBatch::try_new(schema, arrays)?)))
+    }
+
     fn visit_rows(
         &self,
         leaf_columns: &[ColumnName],
@@ -294,7 +345,15 @@ impl ArrowEngineData {
 
 #[cfg(test)]
 mod tests {
-    use crate::arrow::array::StringArray;
+    use std::sync::Arc;
+
+    use crate::arrow::array::cast::AsArray;
+    use crate::arrow::array::types::{Int32Type, Int64Type};
+    use crate::arrow::array::{Array, StringArray};
+
+    use crate::engine::arrow_data::ArrowEngineData;
+    use crate::expressions::{ArrayData, Scalar, StructData};
+    use crate::schema::{ArrayType, DataType, StructField, StructType};
 
     use crate::utils::test_utils::string_array_to_engine_data;
     use crate::{
@@ -344,4 +403,48 @@ mod tests {
         );
         Ok(())
     }
+
+    #[test]
+    fn test_append_columns() -> DeltaResult<()> {
+        let data = string_array_to_engine_data(vec!["a", "b", "c"].into());
+        let x_field = StructField::nullable("x", DataType::INTEGER);
+        let nested_type = DataType::struct_type([x_field.clone()]);
+        let schema = Arc::new(StructType::new([
+            StructField::nullable("id", DataType::LONG),
+            StructField::nullable("nested", nested_type.clone()),
+        ]));
+        let nested = |x: i32| {
+            Scalar::Struct(StructData::try_new(vec![x_field.clone()], vec![x.into()]).unwrap())
+        };
+        let columns = vec![
+            ArrayData::new(ArrayType::new(DataType::LONG, true), [1i64, 2, 3]),
+            ArrayData::new(
+                ArrayType::new(nested_type.clone(), true),
+                [nested(1), Scalar::Null(nested_type.clone()), nested(3)],
+            ),
+        ];
+        let appended = data.append_columns(schema.clone(), columns)?;
+        let batch = ArrowEngineData::try_from_engine_data(appended)?
+            .record_batch()
+            .clone();
+        assert_eq!(batch.num_columns(), 3);
+        assert_eq!(
+            batch.column(1).as_primitive::<Int64Type>().values(),
+            &[1, 2, 3]
+        );
+        let nested_column = batch.column(2).as_struct();
+        assert!(nested_column.is_null(1));
+        assert_eq!(
+            nested_column.column(0).as_primitive::<Int32Type>().value(2),
+            3
+        );
+
+        // every column must have one element per row
+        let columns = vec![
+            ArrayData::new(ArrayType::new(DataType::LONG, true), [1i64]),
+            ArrayData::new(ArrayType::new(nested_type, true), [nested(1)]),
+        ];
+        assert!(data.append_columns(schema, columns).is_err());
+        Ok(())
+    }
 }
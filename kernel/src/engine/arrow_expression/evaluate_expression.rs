@@ -1,12 +1,13 @@
 //! Expression handling based on arrow-rs compute kernels.
 use crate::arrow::array::types::*;
 use crate::arrow::array::{
-    Array, ArrayRef, AsArray, BooleanArray, Datum, RecordBatch, StructArray,
+    Array, ArrayRef, AsArray, BooleanArray, Datum, Int64Array, RecordBatch, StructArray,
 };
 use crate::arrow::compute::kernels::cmp::{distinct, eq, gt, gt_eq, lt, lt_eq, neq};
 use crate::arrow::compute::kernels::comparison::in_list_utf8;
 use crate::arrow::compute::kernels::numeric::{add, div, mul, sub};
-use crate::arrow::compute::{and_kleene, is_null, not, or_kleene};
+use crate::arrow::compute::kernels::zip::zip;
+use crate::arrow::compute::{and_kleene, cast, is_not_null, is_null, not, or_kleene};
 use crate::arrow::datatypes::{
     DataType as ArrowDataType, Field as ArrowField, IntervalUnit, TimeUnit,
 };
@@ -14,8 +15,8 @@ use crate::arrow::error::ArrowError;
 use crate::engine::arrow_utils::prim_array_cmp;
 use crate::error::{DeltaResult, Error};
 use crate::expressions::{
-    BinaryExpression, BinaryOperator, Expression, Scalar, UnaryExpression, UnaryOperator,
-    VariadicExpression, VariadicOperator,
+    BinaryExpression, BinaryOperator, CastExpression, Expression, Scalar, UnaryExpression,
+    UnaryOperator, VariadicExpression, VariadicOperator,
 };
 use crate::schema::DataType;
 use itertools::Itertools;
@@ -92,6 +93,9 @@ pub(crate) fn evaluate_expression(
     match (expression, result_type) {
         (Literal(scalar), _) => Ok(scalar.to_array(batch.num_rows())?),
         (Column(name), _) => extract_column(batch, name),
+        (RowIndex, _) => Ok(Arc::new(Int64Array::from_iter_values(
+            0..batch.num_rows() as i64,
+        ))),
         (Struct(fields), Some(DataType::Struct(output_schema))) => {
             let columns = fields
                 .iter()
@@ -102,10 +106,12 @@ pub(crate) fn evaluate_expression(
                 .iter()
                 .zip(output_schema.fields())
                 .map(|(output_col, output_field)| -> DeltaResult<_> {
+                    // arrow rejects arrays with a null buffer in non-nullable fields, even if
+                    // the buffer has no nulls, as those of some boolean kernels may
                     Ok(ArrowField::new(
                         output_field.name(),
                         output_col.data_type().clone(),
-                        output_col.is_nullable(),
+                        output_col.logical_nulls().is_some(),
                     ))
                 })
                 .try_collect()?;
@@ -122,6 +128,18 @@ pub(crate) fn evaluate_expression(
                 UnaryOperator::IsNull => Arc::new(is_null(&arr)?),
             })
         }
+        (Cast(CastExpression { expr, data_type }), _) => {
+            let mut arr = evaluate_expression(expr.as_ref(), batch, None)?;
+            let to_type = ArrowDataType::try_from(data_type)?;
+            // arrow only casts dates to timestamps without a time zone, whose values are then
+            // taken to be in the time zone of the timestamps they are cast to
+            if let (ArrowDataType::Date32, ArrowDataType::Timestamp(unit, Some(_))) =
+                (arr.data_type(), &to_type)
+            {
+                arr = cast(&arr, &ArrowDataType::Timestamp(*unit, None))?;
+            }
+            Ok(cast(&arr, &to_type)?)
+        }
         (
             Binary(BinaryExpression {
                 op: In,
@@ -214,6 +232,7 @@ pub(crate) fn evaluate_expression(
                 Equal => |l, r| eq(l, r).map(wrap_comparison_result),
                 NotEqual => |l, r| neq(l, r).map(wrap_comparison_result),
                 Distinct => |l, r| distinct(l, r).map(wrap_comparison_result),
+                Coalesce => |l, r| zip(&is_not_null(l.get().0)?, l, r),
                 // NOTE: [Not]In was already covered above
                 In | NotIn => return Err(Error::generic("Invalid expression given")),
             };
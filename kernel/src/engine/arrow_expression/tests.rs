@@ -1,7 +1,8 @@
 use std::ops::{Add, Div, Mul, Sub};
 
 use crate::arrow::array::{
-    create_array, ArrayRef, BooleanArray, GenericStringArray, Int32Array, ListArray, StructArray,
+    create_array, ArrayRef, AsArray, BooleanArray, Date32Array, GenericStringArray, Int32Array,
+    Int64Array, ListArray, StructArray, TimestampMicrosecondArray,
 };
 use crate::arrow::buffer::{OffsetBuffer, ScalarBuffer};
 use crate::arrow::datatypes::{DataType, Field, Fields, Schema};
@@ -219,6 +220,96 @@ fn test_binary_op() {
     assert_eq!(results.as_ref(), expected.as_ref());
 }
 
+#[test]
+fn test_coalesce() {
+    let schema = Schema::new(vec![
+        Field::new("a", DataType::Int32, true),
+        Field::new("b", DataType::Int32, false),
+    ]);
+    let batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![
+            Arc::new(Int32Array::from(vec![Some(1), None, Some(3)])),
+            Arc::new(Int32Array::from(vec![4, 5, 6])),
+        ],
+    )
+    .unwrap();
+
+    let expression = column_expr!("a").coalesce(column_expr!("b"));
+    let results = evaluate_expression(&expression, &batch, None).unwrap();
+    let expected = Arc::new(Int32Array::from(vec![1, 5, 3]));
+    assert_eq!(results.as_ref(), expected.as_ref());
+
+    let expression = column_expr!("a").coalesce(Expression::literal(0));
+    let results = evaluate_expression(&expression, &batch, None).unwrap();
+    let expected = Arc::new(Int32Array::from(vec![1, 0, 3]));
+    assert_eq!(results.as_ref(), expected.as_ref());
+}
+
+#[test]
+fn test_row_index() {
+    let schema = Schema::new(vec![Field::new("a", DataType::Int64, true)]);
+    let batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![Arc::new(Int64Array::from(vec![Some(7), None, None]))],
+    )
+    .unwrap();
+
+    let results = evaluate_expression(&Expression::row_index(), &batch, None).unwrap();
+    let expected = Arc::new(Int64Array::from(vec![0, 1, 2]));
+    assert_eq!(results.as_ref(), expected.as_ref());
+
+    let generated =
+        Expression::literal(10i64) + Expression::literal(5i64) * Expression::row_index();
+    let expression = column_expr!("a").coalesce(generated);
+    let results = evaluate_expression(&expression, &batch, None).unwrap();
+    let expected = Arc::new(Int64Array::from(vec![7, 15, 20]));
+    assert_eq!(results.as_ref(), expected.as_ref());
+}
+
+#[test]
+fn test_struct_of_array_without_nulls_with_null_buffer() {
+    let schema = Schema::new(vec![Field::new("a", DataType::Int32, true)]);
+    let batch = RecordBatch::try_new(
+        Arc::new(schema),
+        vec![Arc::new(Int32Array::from(vec![Some(1), None]))],
+    )
+    .unwrap();
+
+    // OR keeps a null buffer, even when it has no nulls
+    let predicate = column_expr!("a")
+        .gt(Expression::literal(0))
+        .or(column_expr!("a").is_null());
+    let output_type =
+        DeltaDataTypes::struct_type([StructField::nullable("holds", DeltaDataTypes::BOOLEAN)]);
+    let expression = Expression::struct_from([predicate]);
+    let results = evaluate_expression(&expression, &batch, Some(&output_type)).unwrap();
+    let expected = BooleanArray::from(vec![true, true]);
+    assert_eq!(results.as_struct().column(0).as_boolean(), &expected);
+}
+
+#[test]
+fn test_cast() {
+    let timestamps = TimestampMicrosecondArray::from(vec![Some(-1), Some(86_400_000_000), None])
+        .with_timezone("UTC");
+    let schema = Schema::new(vec![Field::new("ts", timestamps.data_type().clone(), true)]);
+    let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(timestamps)]).unwrap();
+
+    // timestamps are cast to their dates in UTC
+    let expression = column_expr!("ts").cast(DeltaDataTypes::DATE);
+    let results = evaluate_expression(&expression, &batch, None).unwrap();
+    let expected = Arc::new(Date32Array::from(vec![Some(-1), Some(1), None]));
+    assert_eq!(results.as_ref(), expected.as_ref());
+
+    let expression = expression.cast(DeltaDataTypes::TIMESTAMP);
+    let results = evaluate_expression(&expression, &batch, None).unwrap();
+    let expected = Arc::new(
+        TimestampMicrosecondArray::from(vec![Some(-86_400_000_000), Some(86_400_000_000), None])
+            .with_timezone("UTC"),
+    );
+    assert_eq!(results.as_ref(), expected.as_ref());
+}
+
 #[test]
 fn test_binary_cmp() {
     let schema = Schema::new(vec![Field::new("a", DataType::Int32, false)]);
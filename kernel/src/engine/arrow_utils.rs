@@ -2,27 +2,30 @@
 
 use std::collections::HashSet;
 use std::io::{BufRead, BufReader};
+use std::ops::Range;
 use std::sync::Arc;
 
 use crate::engine::ensure_data_types::DataTypeCompat;
 use crate::{
     engine::arrow_data::ArrowEngineData,
-    schema::{DataType, Schema, SchemaRef, StructField, StructType},
+    schema::{DataType, MetadataColumnSpec, Schema, SchemaRef, StructField, StructType},
     utils::require,
-    DeltaResult, EngineData, Error,
+    DeltaResult, EngineData, Error, FilteredEngineData,
 };
 
 use crate::arrow::array::{
-    cast::AsArray, make_array, new_null_array, Array as ArrowArray, GenericListArray,
+    cast::AsArray, make_array, new_null_array, Array as ArrowArray, GenericListArray, Int64Array,
     OffsetSizeTrait, RecordBatch, StringArray, StructArray,
 };
 use crate::arrow::buffer::NullBuffer;
-use crate::arrow::compute::concat_batches;
+use crate::arrow::compute::{concat_batches, filter_record_batch};
 use crate::arrow::datatypes::{
     DataType as ArrowDataType, Field as ArrowField, FieldRef as ArrowFieldRef, Fields,
     SchemaRef as ArrowSchemaRef,
 };
 use crate::arrow::json::{LineDelimitedWriter, ReaderBuilder};
+use crate::parquet::arrow::arrow_writer::ArrowWriter;
+use crate::parquet::file::metadata::RowGroupMetaData;
 use crate::parquet::{arrow::ProjectionMask, schema::types::SchemaDescriptor};
 use itertools::Itertools;
 use tracing::debug;
@@ -69,14 +72,18 @@ pub(crate) fn make_arrow_error(s: impl Into<String>) -> Error {
 /// Applies post-processing to data read from parquet files. This includes `reorder_struct_array` to
 /// ensure schema compatibility, as well as `fix_nested_null_masks` to ensure that leaf columns have
 /// accurate null masks that row visitors rely on for correctness.
+///
+/// If the requested schema has a row index metadata column, `row_indexes` must produce the row
+/// indexes of the rows of the file that are read, in order (see [`RowIndexBuilder`]).
 pub(crate) fn fixup_parquet_read<T>(
     batch: RecordBatch,
     requested_ordering: &[ReorderIndex],
+    row_indexes: Option<&mut RowIndexIterator>,
 ) -> DeltaResult<T>
 where
     StructArray: Into<T>,
 {
-    let data = reorder_struct_array(batch.into(), requested_ordering)?;
+    let data = reorder_struct_array(batch.into(), requested_ordering, row_indexes)?;
     let data = fix_nested_null_masks(data);
     Ok(data.into())
 }
@@ -201,6 +208,8 @@ pub(crate) enum ReorderIndexTransform {
     Identity,
     /// Data is missing, fill in with a null column
     Missing(ArrowFieldRef),
+    /// Row index metadata column, fill in with the row indexes of the rows in the file
+    RowIndex(ArrowFieldRef),
 }
 
 impl ReorderIndex {
@@ -224,12 +233,18 @@ impl ReorderIndex {
         ReorderIndex::new(index, ReorderIndexTransform::Missing(field))
     }
 
+    fn row_index(index: usize, field: ArrowFieldRef) -> Self {
+        ReorderIndex::new(index, ReorderIndexTransform::RowIndex(field))
+    }
+
     /// Check if this reordering requires a transformation anywhere. See comment below on
     /// [`ordering_needs_transform`] to understand why this is needed.
     fn needs_transform(&self) -> bool {
         match self.transform {
-            // if we're casting or inserting null, we need to transform
-            ReorderIndexTransform::Cast(_) | ReorderIndexTransform::Missing(_) => true,
+            // if we're casting or inserting null or row indexes, we need to transform
+            ReorderIndexTransform::Cast(_)
+            | ReorderIndexTransform::Missing(_)
+            | ReorderIndexTransform::RowIndex(_) => true,
             // if our nested ordering needs a transform, we need a transform
             ReorderIndexTransform::Nested(ref children) => ordering_needs_transform(children),
             // no transform needed
@@ -273,7 +288,12 @@ fn get_indices(
     // field, and info about where it appears in the requested_schema, or None if the field is not
     // requested
     let all_field_info = fields.iter().enumerate().map(|(parquet_index, field)| {
-        let field_info = requested_schema.fields.get_full(field.name());
+        // metadata columns are never read from the parquet file, even if it has a column of the
+        // same name
+        let field_info = requested_schema
+            .fields
+            .get_full(field.name())
+            .filter(|(_, _, requested_field)| !requested_field.is_metadata_column());
         (parquet_index, field, field_info)
     });
     for (parquet_index, field, field_info) in all_field_info {
@@ -413,7 +433,13 @@ fn get_indices(
         // some fields are missing, but they might be nullable, need to insert them into the reorder_indices
         for (requested_position, field) in requested_schema.fields().enumerate() {
             if !found_fields.contains(field.name()) {
-                if field.nullable {
+                if let Some(MetadataColumnSpec::RowIndex) = field.get_metadata_column_spec() {
+                    debug!("Inserting row index field: {}", field.name());
+                    reorder_indices.push(ReorderIndex::row_index(
+                        requested_position,
+                        Arc::new(field.try_into()?),
+                    ));
+                } else if field.nullable {
                     debug!("Inserting missing and nullable field: {}", field.name());
                     reorder_indices.push(ReorderIndex::missing(
                         requested_position,
@@ -455,6 +481,67 @@ pub(crate) fn get_requested_indices(
     Ok((mask_indices, reorder_indexes))
 }
 
+/// The row indexes of the rows read from a parquet file, in order.
+pub(crate) type RowIndexIterator = std::iter::Flatten<std::vec::IntoIter<Range<i64>>>;
+
+/// Computes the row indexes of the rows read from a parquet file, to fill in the row index metadata
+/// columns ([`MetadataColumnSpec::RowIndex`]). The row index of a row is its position in the file,
+/// regardless of the row groups that are skipped.
+pub(crate) struct RowIndexBuilder {
+    row_group_row_index_ranges: Vec<Range<i64>>,
+    row_group_ordinals: Option<Vec<usize>>,
+}
+
+impl RowIndexBuilder {
+    /// A builder for the row groups of a parquet file, if `requested_schema` has a row index column.
+    pub(crate) fn try_for_schema(
+        requested_schema: &Schema,
+        row_groups: &[RowGroupMetaData],
+    ) -> Option<Self> {
+        requested_schema
+            .fields()
+            .any(|field| field.get_metadata_column_spec() == Some(MetadataColumnSpec::RowIndex))
+            .then(|| Self::new(row_groups))
+    }
+
+    pub(crate) fn new(row_groups: &[RowGroupMetaData]) -> Self {
+        let mut row_group_row_index_ranges = Vec::with_capacity(row_groups.len());
+        let mut offset = 0;
+        for row_group in row_groups {
+            let num_rows = row_group.num_rows();
+            row_group_row_index_ranges.push(offset..offset + num_rows);
+            offset += num_rows;
+        }
+        Self {
+            row_group_row_index_ranges,
+            row_group_ordinals: None,
+        }
+    }
+
+    /// Only the rows of the given row groups (by ordinal, in order) are read.
+    pub(crate) fn select_row_groups(&mut self, ordinals: &[usize]) {
+        self.row_group_ordinals = Some(ordinals.to_vec());
+    }
+
+    pub(crate) fn build(self) -> DeltaResult<RowIndexIterator> {
+        let ranges = match self.row_group_ordinals {
+            Some(ordinals) => ordinals
+                .iter()
+                .map(|&ordinal| {
+                    self.row_group_row_index_ranges
+                        .get(ordinal)
+                        .cloned()
+                        .ok_or_else(|| {
+                            Error::generic(format!("Row group ordinal {ordinal} is out of bounds"))
+                        })
+                })
+                .try_collect()?,
+            None => self.row_group_row_index_ranges,
+        };
+        Ok(ranges.into_iter().flatten())
+    }
+}
+
 /// Create a mask that will only select the specified indices from the parquet. `indices` can be
 /// computed from a [`Schema`] using [`get_requested_indices`]
 pub(crate) fn generate_mask(
@@ -496,10 +583,12 @@ fn ordering_needs_transform(requested_ordering: &[ReorderIndex]) -> bool {
 type FieldArrayOpt = Option<(Arc<ArrowField>, Arc<dyn ArrowArray>)>;
 
 /// Reorder a RecordBatch to match `requested_ordering`. For each non-zero value in
-/// `requested_ordering`, the column at that index will be added in order to returned batch
+/// `requested_ordering`, the column at that index will be added in order to returned batch. Row
+/// index columns (only supported at the top level) take their values from `row_indexes`.
 pub(crate) fn reorder_struct_array(
     input_data: StructArray,
     requested_ordering: &[ReorderIndex],
+    mut row_indexes: Option<&mut RowIndexIterator>,
 ) -> DeltaResult<StructArray> {
     debug!("Reordering {input_data:?} with ordering: {requested_ordering:?}");
     if !ordering_needs_transform(requested_ordering) {
@@ -533,7 +622,7 @@ pub(crate) fn reorder_struct_array(
                         ArrowDataType::Struct(_) => {
                             let struct_array = input_cols[parquet_position].as_struct().clone();
                             let result_array =
-                                Arc::new(reorder_struct_array(struct_array, children)?);
+                                Arc::new(reorder_struct_array(struct_array, children, None)?);
                             // create the new field specifying the correct order for the struct
                             let new_field = Arc::new(ArrowField::new_struct(
                                 input_fields[parquet_position].name(),
@@ -578,6 +667,19 @@ pub(crate) fn reorder_struct_array(
                     let field = field.clone(); // cheap Arc clone
                     final_fields_cols[reorder_index.index] = Some((field, null_array));
                 }
+                ReorderIndexTransform::RowIndex(field) => {
+                    let row_indexes = row_indexes.as_deref_mut().ok_or_else(|| {
+                        Error::internal_error("Row indexes are required to read a row index column")
+                    })?;
+                    let row_index_array: Int64Array = row_indexes.take(num_rows).collect();
+                    require!(
+                        row_index_array.len() == num_rows,
+                        Error::internal_error("Ran out of row indexes for the rows read")
+                    );
+                    let field = field.clone(); // cheap Arc clone
+                    final_fields_cols[reorder_index.index] =
+                        Some((field, Arc::new(row_index_array)));
+                }
             }
         }
         let num_cols = final_fields_cols.len();
@@ -603,7 +705,7 @@ fn reorder_list<O: OffsetSizeTrait>(
     let (list_field, offset_buffer, maybe_sa, null_buf) = list_array.into_parts();
     if let Some(struct_array) = maybe_sa.as_struct_opt() {
         let struct_array = struct_array.clone();
-        let result_array = Arc::new(reorder_struct_array(struct_array, children)?);
+        let result_array = Arc::new(reorder_struct_array(struct_array, children, None)?);
         let new_list_field = Arc::new(ArrowField::new_struct(
             list_field.name(),
             result_array.fields().clone(),
@@ -726,21 +828,56 @@ fn parse_json_impl(json_strings: &StringArray, schema: ArrowSchemaRef) -> DeltaR
     Ok(concat_batches(&schema, output.iter())?)
 }
 
-/// serialize an arrow RecordBatch to a JSON string by appending to a buffer.
+/// serialize an arrow RecordBatch to a JSON string by appending to a buffer. Only the rows selected
+/// by each chunk's selection vector are written.
 // TODO (zach): this should stream data to the JSON writer and output an iterator.
 pub(crate) fn to_json_bytes(
-    data: impl Iterator<Item = DeltaResult<Box<dyn EngineData>>> + Send,
+    data: impl Iterator<Item = DeltaResult<FilteredEngineData>> + Send,
 ) -> DeltaResult<Vec<u8>> {
     let mut writer = LineDelimitedWriter::new(Vec::new());
     for chunk in data.into_iter() {
-        let arrow_data = ArrowEngineData::try_from_engine_data(chunk?)?;
-        let record_batch = arrow_data.record_batch();
-        writer.write(record_batch)?;
+        writer.write(&selected_record_batch(chunk?)?)?;
     }
     writer.finish()?;
     Ok(writer.into_inner())
 }
 
+/// Encode the selected rows of `data` as a parquet file. The schema of the file is the schema of
+/// the first batch, and every other batch must have the same schema. Since the schema can't be
+/// known without data, `data` must not be empty.
+pub(crate) fn to_parquet_bytes(
+    data: impl Iterator<Item = DeltaResult<FilteredEngineData>> + Send,
+) -> DeltaResult<Vec<u8>> {
+    let mut writer: Option<ArrowWriter<Vec<u8>>> = None;
+    for chunk in data {
+        let record_batch = selected_record_batch(chunk?)?;
+        let writer = match &mut writer {
+            Some(writer) => writer,
+            None => writer.insert(ArrowWriter::try_new(vec![], record_batch.schema(), None)?),
+        };
+        writer.write(&record_batch)?;
+    }
+    let Some(writer) = writer else {
+        return Err(Error::generic("Cannot write a parquet file without data"));
+    };
+    // the writer must be closed to write the footer
+    Ok(writer.into_inner()?)
+}
+
+// the record batch of the rows selected by the selection vector of `chunk`
+fn selected_record_batch(chunk: FilteredEngineData) -> DeltaResult<RecordBatch> {
+    let all_rows_selected = chunk.has_all_rows_selected();
+    let (data, mut selection_vector) = chunk.into_parts();
+    let arrow_data = ArrowEngineData::try_from_engine_data(data)?;
+    let record_batch = arrow_data.record_batch();
+    if all_rows_selected {
+        return Ok(record_batch.clone());
+    }
+    // rows beyond the end of the selection vector are implicitly selected
+    selection_vector.resize(record_batch.num_rows(), true);
+    Ok(filter_record_batch(record_batch, &selection_vector.into())?)
+}
+
 #[cfg(test)]
 mod tests {
     use std::sync::Arc;
@@ -755,7 +892,10 @@ mod tests {
     use crate::arrow::{
         array::AsArray,
         buffer::{OffsetBuffer, ScalarBuffer},
+        datatypes::Int64Type,
     };
+    use crate::parquet::file::metadata::ColumnChunkMetaData;
+    use crate::parquet::schema::parser::parse_message_type;
 
     use crate::schema::{ArrayType, DataType, MapType, StructField, StructType};
 
@@ -1336,10 +1476,92 @@ mod tests {
     fn simple_reorder_struct() {
         let arry = make_struct_array();
         let reorder = vec![ReorderIndex::identity(1), ReorderIndex::identity(0)];
-        let ordered = reorder_struct_array(arry, &reorder).unwrap();
+        let ordered = reorder_struct_array(arry, &reorder, None).unwrap();
         assert_eq!(ordered.column_names(), vec!["c", "b"]);
     }
 
+    #[test]
+    fn row_index_indices() {
+        let requested_schema = Arc::new(StructType::new([
+            StructField::not_null("i", DataType::INTEGER),
+            StructField::create_metadata_column("row_index", MetadataColumnSpec::RowIndex),
+        ]));
+        let parquet_schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "i",
+            ArrowDataType::Int32,
+            false,
+        )]));
+        let (mask_indices, reorder_indices) =
+            get_requested_indices(&requested_schema, &parquet_schema).unwrap();
+        let row_index_field = ArrowField::new("row_index", ArrowDataType::Int64, false)
+            .with_metadata([("delta.metadataSpec".into(), "row_index".into())].into());
+        let row_index_field = Arc::new(row_index_field);
+        let expect_reorder = vec![
+            ReorderIndex::identity(0),
+            ReorderIndex::row_index(1, row_index_field),
+        ];
+        assert_eq!(mask_indices, vec![0]);
+        assert_eq!(reorder_indices, expect_reorder);
+
+        let ordered = reorder_struct_array(
+            make_struct_array(),
+            &expect_reorder,
+            Some(&mut vec![5..7, 7..9].into_iter().flatten()),
+        );
+        let ordered = ordered.unwrap();
+        assert_eq!(ordered.column_names(), vec!["b", "row_index"]);
+        let row_indexes: Vec<_> = ordered
+            .column(1)
+            .as_primitive::<Int64Type>()
+            .values()
+            .to_vec();
+        assert_eq!(row_indexes, vec![5, 6, 7, 8]);
+
+        // the row indexes must cover every row
+        let mut row_indexes = vec![0..1, 1..2].into_iter().flatten();
+        assert!(
+            reorder_struct_array(make_struct_array(), &expect_reorder, Some(&mut row_indexes))
+                .is_err()
+        );
+        assert!(reorder_struct_array(make_struct_array(), &expect_reorder, None).is_err());
+    }
+
+    #[test]
+    fn row_index_builder() {
+        let schema = parse_message_type("message schema { required int32 i; }").unwrap();
+        let schema = Arc::new(SchemaDescriptor::new(Arc::new(schema)));
+        let row_groups: Vec<_> = [3, 2, 4]
+            .into_iter()
+            .map(|num_rows| {
+                let column = ColumnChunkMetaData::builder(schema.column(0))
+                    .build()
+                    .unwrap();
+                RowGroupMetaData::builder(schema.clone())
+                    .set_num_rows(num_rows)
+                    .set_column_metadata(vec![column])
+                    .build()
+                    .unwrap()
+            })
+            .collect();
+
+        let requested_schema = StructType::new([StructField::nullable("i", DataType::INTEGER)]);
+        assert!(RowIndexBuilder::try_for_schema(&requested_schema, &row_groups).is_none());
+
+        let builder = RowIndexBuilder::new(&row_groups);
+        let row_indexes: Vec<_> = builder.build().unwrap().collect();
+        assert_eq!(row_indexes, (0..9).collect::<Vec<_>>());
+
+        // skipped row groups don't shift the row indexes of the following ones
+        let mut builder = RowIndexBuilder::new(&row_groups);
+        builder.select_row_groups(&[0, 2]);
+        let row_indexes: Vec<_> = builder.build().unwrap().collect();
+        assert_eq!(row_indexes, vec![0, 1, 2, 5, 6, 7, 8]);
+
+        let mut builder = RowIndexBuilder::new(&row_groups);
+        builder.select_row_groups(&[3]);
+        assert!(builder.build().is_err());
+    }
+
     #[test]
     fn nested_reorder_struct() {
         let arry1 = Arc::new(make_struct_array());
@@ -1384,7 +1606,7 @@ mod tests {
                 ],
             ),
         ];
-        let ordered = reorder_struct_array(nested, &reorder).unwrap();
+        let ordered = reorder_struct_array(nested, &reorder, None).unwrap();
         assert_eq!(ordered.column_names(), vec!["struct2", "struct1"]);
         let ordered_s2 = ordered.column(0).as_struct();
         assert_eq!(ordered_s2.column_names(), vec!["b", "c", "s"]);
@@ -1431,7 +1653,7 @@ mod tests {
             0,
             vec![ReorderIndex::identity(1), ReorderIndex::identity(0)],
         )];
-        let ordered = reorder_struct_array(struct_array, &reorder).unwrap();
+        let ordered = reorder_struct_array(struct_array, &reorder, None).unwrap();
         let ordered_list_col = ordered.column(0).as_list::<i32>();
         for i in 0..ordered_list_col.len() {
             let array_item = ordered_list_col.value(i);
@@ -1491,7 +1713,7 @@ mod tests {
             vec![Arc::new(StringArray::from(vec!["string1", "string2"]))],
         )?;
         let data: Box<dyn EngineData> = Box::new(ArrowEngineData::new(data));
-        let json = to_json_bytes(Box::new(std::iter::once(Ok(data))))?;
+        let json = to_json_bytes(Box::new(std::iter::once(Ok(data.into()))))?;
         assert_eq!(
             json,
             "{\"string\":\"string1\"}\n{\"string\":\"string2\"}\n".as_bytes()
@@ -1499,6 +1721,30 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_write_json_filtered() -> DeltaResult<()> {
+        let schema = Arc::new(ArrowSchema::new(vec![ArrowField::new(
+            "string",
+            ArrowDataType::Utf8,
+            true,
+        )]));
+        let data = RecordBatch::try_new(
+            schema.clone(),
+            vec![Arc::new(StringArray::from(vec![
+                "string1", "string2", "string3",
+            ]))],
+        )?;
+        let data: Box<dyn EngineData> = Box::new(ArrowEngineData::new(data));
+        // the last row is not covered by the selection vector, so it is selected
+        let data = FilteredEngineData::try_new(data, vec![false, true])?;
+        let json = to_json_bytes(Box::new(std::iter::once(Ok(data))))?;
+        assert_eq!(
+            json,
+            "{\"string\":\"string2\"}\n{\"string\":\"string3\"}\n".as_bytes()
+        );
+        Ok(())
+    }
+
     #[test]
     fn test_arrow_broken_nested_null_masks() {
         use crate::arrow::datatypes::{DataType, Field, Fields, Schema};
@@ -155,6 +155,36 @@ impl<E: TaskExecutor> FileSystemClient for ObjectStoreFileSystemClient<E> {
 
         Ok(Box::new(receiver.into_iter()))
     }
+
+    fn write_file(&self, path: &Url, data: Bytes, overwrite: bool) -> DeltaResult<()> {
+        let store = self.inner.clone();
+        let path = Path::from_url_path(path.path())?;
+        let path_str = path.to_string();
+        let mode = if overwrite {
+            object_store::PutMode::Overwrite
+        } else {
+            object_store::PutMode::Create
+        };
+        self.task_executor
+            .block_on(async move { store.put_opts(&path, data.into(), mode.into()).await })
+            .map_err(|e| match e {
+                object_store::Error::AlreadyExists { .. } => Error::FileAlreadyExists(path_str),
+                e => e.into(),
+            })?;
+        Ok(())
+    }
+
+    fn delete_file(&self, path: &Url) -> DeltaResult<()> {
+        let store = self.inner.clone();
+        let path = Path::from_url_path(path.path())?;
+        match self
+            .task_executor
+            .block_on(async move { store.delete(&path).await })
+        {
+            Ok(()) | Err(object_store::Error::NotFound { .. }) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -23,7 +23,7 @@ use crate::engine::arrow_utils::to_json_bytes;
 use crate::schema::SchemaRef;
 use crate::{
     DeltaResult, EngineData, Error, ExpressionRef, FileDataReadResultIterator, FileMeta,
-    JsonHandler,
+    FilteredEngineData, JsonHandler,
 };
 
 const DEFAULT_BUFFER_SIZE: usize = 1000;
@@ -136,7 +136,7 @@ impl<E: TaskExecutor> JsonHandler for DefaultJsonHandler<E> {
     fn write_json_file(
         &self,
         path: &Url,
-        data: Box<dyn Iterator<Item = DeltaResult<Box<dyn EngineData>>> + Send + '_>,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_>,
         _overwrite: bool,
     ) -> DeltaResult<()> {
         let buffer = to_json_bytes(data)?;
@@ -193,15 +193,19 @@ impl JsonOpener {
 
         let path = Path::from_url_path(file_meta.location.path())?;
         match store.get(&path).await?.payload {
+            // writers store some values of string maps (e.g. commitInfo.operationParameters) as
+            // JSON booleans or numbers, so these are read as strings
             GetResultPayload::File(file, _) => {
                 let reader = ReaderBuilder::new(schema)
                     .with_batch_size(batch_size)
+                    .with_coerce_primitive(true)
                     .build(BufReader::new(file))?;
                 Ok(futures::stream::iter(reader).map_err(Error::from).boxed())
             }
             GetResultPayload::Stream(s) => {
                 let mut decoder = ReaderBuilder::new(schema)
                     .with_batch_size(batch_size)
+                    .with_coerce_primitive(true)
                     .build_decoder()?;
 
                 let mut input = s.map_err(Error::from);
@@ -19,7 +19,7 @@ use self::json::DefaultJsonHandler;
 use self::parquet::DefaultParquetHandler;
 use super::arrow_data::ArrowEngineData;
 use super::arrow_expression::ArrowExpressionHandler;
-use crate::schema::Schema;
+use crate::schema::SchemaRef;
 use crate::transaction::WriteContext;
 use crate::{
     DeltaResult, Engine, EngineData, ExpressionHandler, FileSystemClient, JsonHandler,
@@ -31,6 +31,7 @@ pub mod file_stream;
 pub mod filesystem;
 pub mod json;
 pub mod parquet;
+mod stats;
 pub mod storage;
 
 #[derive(Debug)]
@@ -120,21 +121,35 @@ impl<E: TaskExecutor> DefaultEngine<E> {
         partition_values: HashMap<String, String>,
         data_change: bool,
     ) -> DeltaResult<Box<dyn EngineData>> {
-        let transform = write_context.logical_to_physical();
-        let input_schema: Schema = data.record_batch().schema().try_into()?;
+        let input_schema: SchemaRef = Arc::new(data.record_batch().schema().try_into()?);
+        write_context.validate(self, data, &input_schema, &partition_values)?;
+        let transform =
+            write_context.logical_to_physical(data, &input_schema, &partition_values)?;
         let output_schema = write_context.schema();
         let logical_to_physical_expr = self.get_expression_handler().get_evaluator(
-            input_schema.into(),
-            transform.clone(),
+            input_schema,
+            transform,
             output_schema.clone().into(),
         );
         let physical_data = logical_to_physical_expr.evaluate(data)?;
+        if !write_context.collect_stats() {
+            return self
+                .parquet
+                .write_parquet_file(
+                    write_context.target_dir(),
+                    physical_data,
+                    partition_values,
+                    data_change,
+                )
+                .await;
+        }
         self.parquet
-            .write_parquet_file(
+            .write_parquet_file_with_stats(
                 write_context.target_dir(),
                 physical_data,
                 partition_values,
                 data_change,
+                write_context.stats_columns(),
             )
             .await
     }
@@ -13,19 +13,22 @@ use crate::parquet::arrow::arrow_writer::ArrowWriter;
 use crate::parquet::arrow::async_reader::{ParquetObjectReader, ParquetRecordBatchStreamBuilder};
 use futures::StreamExt;
 use object_store::path::Path;
-use object_store::DynObjectStore;
+use object_store::{DynObjectStore, PutMode};
 use uuid::Uuid;
 
 use super::file_stream::{FileOpenFuture, FileOpener, FileStream};
+use super::stats::collect_stats;
 use super::UrlExt;
 use crate::engine::arrow_data::ArrowEngineData;
-use crate::engine::arrow_utils::{fixup_parquet_read, generate_mask, get_requested_indices};
+use crate::engine::arrow_utils::{
+    fixup_parquet_read, generate_mask, get_requested_indices, to_parquet_bytes, RowIndexBuilder,
+};
 use crate::engine::default::executor::TaskExecutor;
 use crate::engine::parquet_row_group_skipping::ParquetRowGroupSkipping;
-use crate::schema::SchemaRef;
+use crate::schema::{ColumnName, SchemaRef};
 use crate::{
     DeltaResult, EngineData, Error, ExpressionRef, FileDataReadResultIterator, FileMeta,
-    ParquetHandler,
+    FilteredEngineData, ParquetHandler,
 };
 
 #[derive(Debug)]
@@ -35,16 +38,28 @@ pub struct DefaultParquetHandler<E: TaskExecutor> {
     readahead: usize,
 }
 
-/// Metadata of a data file (typically a parquet file), currently just includes the file metadata
-/// but will expand to include file statistics and other metadata in the future.
+/// Metadata of a data file (typically a parquet file): its file metadata, and the statistics of
+/// its data if they were collected.
 #[derive(Debug)]
 pub struct DataFileMetadata {
     file_meta: FileMeta,
+    stats: Option<String>,
 }
 
 impl DataFileMetadata {
     pub fn new(file_meta: FileMeta) -> Self {
-        Self { file_meta }
+        Self {
+            file_meta,
+            stats: None,
+        }
+    }
+
+    /// Set the [statistics] of the data of the file, as a JSON string.
+    ///
+    /// [statistics]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Per-file-Statistics
+    pub fn with_stats(mut self, stats: Option<String>) -> Self {
+        self.stats = stats;
+        self
     }
 
     // convert DataFileMetadata into a record batch which matches the 'write_metadata' schema
@@ -60,6 +75,7 @@ impl DataFileMetadata {
                     last_modified,
                     size,
                 },
+            stats,
         } = self;
         let write_metadata_schema = crate::transaction::get_write_metadata_schema();
 
@@ -86,9 +102,17 @@ impl DataFileMetadata {
         let size = Arc::new(Int64Array::from(vec![size]));
         let data_change = Arc::new(BooleanArray::from(vec![data_change]));
         let modification_time = Arc::new(Int64Array::from(vec![*last_modified]));
+        let stats = Arc::new(StringArray::from(vec![stats.clone()]));
         Ok(Box::new(ArrowEngineData::new(RecordBatch::try_new(
             Arc::new(write_metadata_schema.as_ref().try_into()?),
-            vec![path, partitions, size, modification_time, data_change],
+            vec![
+                path,
+                partitions,
+                size,
+                modification_time,
+                data_change,
+                stats,
+            ],
         )?)))
     }
 }
@@ -111,7 +135,8 @@ impl<E: TaskExecutor> DefaultParquetHandler<E> {
     }
 
     // Write `data` to `{path}/<uuid>.parquet` as parquet using ArrowWriter and return the parquet
-    // metadata (where `<uuid>` is a generated UUIDv4).
+    // metadata (where `<uuid>` is a generated UUIDv4), with the statistics of the given columns if
+    // any are given.
     //
     // Note: after encoding the data as parquet, this issues a PUT followed by a HEAD to storage in
     // order to obtain metadata about the object just written.
@@ -119,9 +144,13 @@ impl<E: TaskExecutor> DefaultParquetHandler<E> {
         &self,
         path: &url::Url,
         data: Box<dyn EngineData>,
+        stats_columns: Option<&[ColumnName]>,
     ) -> DeltaResult<DataFileMetadata> {
         let batch: Box<_> = ArrowEngineData::try_from_engine_data(data)?;
         let record_batch = batch.record_batch();
+        let stats = stats_columns
+            .map(|stats_columns| collect_stats(record_batch, stats_columns))
+            .transpose()?;
 
         let mut buffer = vec![];
         let mut writer = ArrowWriter::try_new(&mut buffer, record_batch.schema(), None)?;
@@ -153,7 +182,7 @@ impl<E: TaskExecutor> DefaultParquetHandler<E> {
         }
 
         let file_meta = FileMeta::new(path, modification_time, size);
-        Ok(DataFileMetadata::new(file_meta))
+        Ok(DataFileMetadata::new(file_meta).with_stats(stats))
     }
 
     /// Write `data` to `{path}/<uuid>.parquet` as parquet using ArrowWriter and return the parquet
@@ -168,7 +197,21 @@ impl<E: TaskExecutor> DefaultParquetHandler<E> {
         partition_values: HashMap<String, String>,
         data_change: bool,
     ) -> DeltaResult<Box<dyn EngineData>> {
-        let parquet_metadata = self.write_parquet(path, data).await?;
+        let parquet_metadata = self.write_parquet(path, data, None).await?;
+        parquet_metadata.as_record_batch(&partition_values, data_change)
+    }
+
+    /// Like [`Self::write_parquet_file`], and the write metadata includes the statistics of
+    /// `data`: its number of records, and the statistics of the given (physical) columns.
+    pub async fn write_parquet_file_with_stats(
+        &self,
+        path: &url::Url,
+        data: Box<dyn EngineData>,
+        partition_values: HashMap<String, String>,
+        data_change: bool,
+        stats_columns: &[ColumnName],
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        let parquet_metadata = self.write_parquet(path, data, Some(stats_columns)).await?;
         parquet_metadata.as_record_batch(&partition_values, data_change)
     }
 }
@@ -214,6 +257,37 @@ impl<E: TaskExecutor> ParquetHandler for DefaultParquetHandler<E> {
             self.readahead,
         )
     }
+
+    fn write_parquet_file(
+        &self,
+        location: &url::Url,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_>,
+        overwrite: bool,
+    ) -> DeltaResult<FileMeta> {
+        let buffer = to_parquet_bytes(data)?;
+        let size = buffer.len();
+        let store = self.store.clone(); // cheap Arc
+        let path = Path::from_url_path(location.path())?;
+        let path_str = path.to_string();
+        let mode = if overwrite {
+            PutMode::Overwrite
+        } else {
+            PutMode::Create
+        };
+        // issue a HEAD after the PUT to obtain the modification time of the file
+        let metadata = self
+            .task_executor
+            .block_on(async move {
+                store.put_opts(&path, buffer.into(), mode.into()).await?;
+                store.head(&path).await
+            })
+            .map_err(|e| match e {
+                object_store::Error::AlreadyExists { .. } => Error::FileAlreadyExists(path_str),
+                e => e.into(),
+            })?;
+        let modification_time = metadata.last_modified.timestamp_millis();
+        Ok(FileMeta::new(location.clone(), modification_time, size))
+    }
 }
 
 /// Implements [`FileOpener`] for a parquet file
@@ -274,16 +348,21 @@ impl FileOpener for ParquetOpener {
                 builder = builder.with_projection(mask)
             }
 
+            let mut row_indexes =
+                RowIndexBuilder::try_for_schema(&table_schema, builder.metadata().row_groups());
             if let Some(ref predicate) = predicate {
-                builder = builder.with_row_group_filter(predicate);
+                builder = builder.with_row_group_filter(predicate, row_indexes.as_mut());
             }
+            let mut row_indexes = row_indexes.map(RowIndexBuilder::build).transpose()?;
             if let Some(limit) = limit {
                 builder = builder.with_limit(limit)
             }
 
             let stream = builder.with_batch_size(batch_size).build()?;
 
-            let stream = stream.map(move |rbr| fixup_parquet_read(rbr?, &requested_ordering));
+            let stream = stream.map(move |rbr| {
+                fixup_parquet_read(rbr?, &requested_ordering, row_indexes.as_mut())
+            });
             Ok(stream.boxed())
         }))
     }
@@ -342,9 +421,12 @@ impl FileOpener for PresignedUrlOpener {
                 builder = builder.with_projection(mask)
             }
 
+            let mut row_indexes =
+                RowIndexBuilder::try_for_schema(&table_schema, builder.metadata().row_groups());
             if let Some(ref predicate) = predicate {
-                builder = builder.with_row_group_filter(predicate);
+                builder = builder.with_row_group_filter(predicate, row_indexes.as_mut());
             }
+            let mut row_indexes = row_indexes.map(RowIndexBuilder::build).transpose()?;
             if let Some(limit) = limit {
                 builder = builder.with_limit(limit)
             }
@@ -352,7 +434,9 @@ impl FileOpener for PresignedUrlOpener {
             let reader = builder.with_batch_size(batch_size).build()?;
 
             let stream = futures::stream::iter(reader);
-            let stream = stream.map(move |rbr| fixup_parquet_read(rbr?, &requested_ordering));
+            let stream = stream.map(move |rbr| {
+                fixup_parquet_read(rbr?, &requested_ordering, row_indexes.as_mut())
+            });
             Ok(stream.boxed())
         }))
     }
@@ -369,6 +453,7 @@ mod tests {
 
     use crate::engine::arrow_data::ArrowEngineData;
     use crate::engine::default::executor::tokio::TokioBackgroundExecutor;
+    use crate::schema::column_name;
     use crate::EngineData;
 
     use itertools::Itertools;
@@ -462,6 +547,7 @@ mod tests {
                 Arc::new(Int64Array::from(vec![size])),
                 Arc::new(Int64Array::from(vec![last_modified])),
                 Arc::new(BooleanArray::from(vec![data_change])),
+                Arc::new(StringArray::from(vec![None::<String>])),
             ],
         )
         .unwrap();
@@ -484,7 +570,11 @@ mod tests {
         ));
 
         let write_metadata = parquet_handler
-            .write_parquet(&Url::parse("memory:///data/").unwrap(), data)
+            .write_parquet(
+                &Url::parse("memory:///data/").unwrap(),
+                data,
+                Some(&[column_name!("a")]),
+            )
             .await
             .unwrap();
 
@@ -495,6 +585,7 @@ mod tests {
                     last_modified,
                     size,
                 },
+            ref stats,
         } = write_metadata;
         let expected_location = Url::parse("memory:///data/").unwrap();
 
@@ -514,6 +605,10 @@ mod tests {
         assert_eq!(&expected_location.join(filename).unwrap(), location);
         assert_eq!(expected_size, size);
         assert!(now - last_modified < 10_000);
+        assert_eq!(
+            stats.as_deref(),
+            Some(r#"{"maxValues":{"a":3},"minValues":{"a":1},"nullCount":{"a":0},"numRecords":3}"#)
+        );
 
         // check we can read back
         let path = Path::from(location.path());
@@ -527,7 +622,7 @@ mod tests {
 
         let data: Vec<RecordBatch> = parquet_handler
             .read_parquet_files(
-                &[parquet_file.clone()],
+                std::slice::from_ref(parquet_file),
                 Arc::new(physical_schema.try_into().unwrap()),
                 None,
             )
@@ -555,7 +650,7 @@ mod tests {
         ));
 
         assert!(parquet_handler
-            .write_parquet(&Url::parse("memory:///data").unwrap(), data)
+            .write_parquet(&Url::parse("memory:///data").unwrap(), data, None)
             .await
             .is_err());
     }
@@ -0,0 +1,346 @@
+//! Collection of the [statistics] of the data files written by the default engine.
+//!
+//! [statistics]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Per-file-Statistics
+
+use chrono::DateTime;
+use serde_json::{json, Map, Number, Value};
+
+use crate::arrow::array::{make_array, Array, ArrayRef, AsArray, RecordBatch, StructArray};
+use crate::arrow::buffer::NullBuffer;
+use crate::arrow::compute::{max, max_boolean, max_string, min, min_boolean, min_string};
+use crate::arrow::datatypes::{
+    ArrowNumericType, DataType as ArrowDataType, Date32Type, Decimal128Type, DecimalType as _,
+    Float32Type, Float64Type, Int16Type, Int32Type, Int64Type, Int8Type, TimeUnit,
+    TimestampMicrosecondType,
+};
+use crate::schema::ColumnName;
+use crate::{DeltaResult, Error};
+
+/// The number of characters that the min and max values of string columns are truncated to, so
+/// that long values don't bloat the log.
+const STRING_PREFIX_LENGTH: usize = 32;
+
+/// Collect the statistics of `batch` for the given (physical) columns, as the JSON string of the
+/// `stats` field of an `add` action: the number of records, and the null count and min and max
+/// values of each column. The min and max values are only collected for the columns of numeric,
+/// decimal, boolean, string, date and timestamp types, and are omitted when the exact bound can't
+/// be represented (e.g. a NaN maximum, a maximum string longer than the truncation length, or a
+/// decimal that a JSON number can't hold exactly). Without columns, the statistics only have the
+/// number of records.
+pub(crate) fn collect_stats(batch: &RecordBatch, columns: &[ColumnName]) -> DeltaResult<String> {
+    let mut min_values = Map::new();
+    let mut max_values = Map::new();
+    let mut null_count = Map::new();
+    for column in columns {
+        let array = column_array(batch, column)?;
+        insert_at(&mut null_count, column.path(), json!(array.null_count()));
+        if array.null_count() == array.len() {
+            continue;
+        }
+        let (min_value, max_value) = bounds(array.as_ref())?;
+        if let Some(min_value) = min_value {
+            insert_at(&mut min_values, column.path(), min_value);
+        }
+        if let Some(max_value) = max_value {
+            insert_at(&mut max_values, column.path(), max_value);
+        }
+    }
+    let stats = json!({
+        "numRecords": batch.num_rows(),
+        "minValues": min_values,
+        "maxValues": max_values,
+        "nullCount": null_count,
+    });
+    Ok(stats.to_string())
+}
+
+// The array of a (possibly nested) column, whose nulls include those of its parent structs
+fn column_array(batch: &RecordBatch, column: &ColumnName) -> DeltaResult<ArrayRef> {
+    let missing_column = || Error::missing_column(format!("No stats column {column} in data"));
+    let mut struct_array = StructArray::from(batch.clone());
+    let mut nulls: Option<NullBuffer> = None;
+    let (last, parents) = column.path().split_last().ok_or_else(missing_column)?;
+    for name in parents {
+        let child = struct_array
+            .column_by_name(name)
+            .ok_or_else(missing_column)?
+            .as_struct_opt()
+            .ok_or_else(missing_column)?
+            .clone();
+        nulls = NullBuffer::union(nulls.as_ref(), child.nulls());
+        struct_array = child;
+    }
+    let array = struct_array
+        .column_by_name(last)
+        .ok_or_else(missing_column)?
+        .clone();
+    let nulls = NullBuffer::union(nulls.as_ref(), array.nulls());
+    let data = array.to_data().into_builder().nulls(nulls).build()?;
+    Ok(make_array(data))
+}
+
+// Insert `value` in the nested JSON objects of `map` at `path`
+fn insert_at(map: &mut Map<String, Value>, path: &[String], value: Value) {
+    match path {
+        [] => {}
+        [name] => {
+            map.insert(name.clone(), value);
+        }
+        [name, rest @ ..] => {
+            let child = map
+                .entry(name.clone())
+                .or_insert_with(|| Value::Object(Map::new()));
+            if let Value::Object(child) = child {
+                insert_at(child, rest, value);
+            }
+        }
+    }
+}
+
+// The min and max values of the array, if they can be represented in the stats. The array must
+// have a non-null value.
+fn bounds(array: &dyn Array) -> DeltaResult<(Option<Value>, Option<Value>)> {
+    fn numeric<T: ArrowNumericType>(array: &dyn Array) -> (Option<T::Native>, Option<T::Native>) {
+        let array = array.as_primitive::<T>();
+        (min(array), max(array))
+    }
+    fn number(value: Option<impl Into<Value>>) -> Option<Value> {
+        value.map(Into::into)
+    }
+    fn float(value: Option<f64>) -> Option<Value> {
+        value.and_then(Number::from_f64).map(Value::Number)
+    }
+
+    let bounds = match array.data_type() {
+        ArrowDataType::Int8 => {
+            let (min, max) = numeric::<Int8Type>(array);
+            (number(min), number(max))
+        }
+        ArrowDataType::Int16 => {
+            let (min, max) = numeric::<Int16Type>(array);
+            (number(min), number(max))
+        }
+        ArrowDataType::Int32 => {
+            let (min, max) = numeric::<Int32Type>(array);
+            (number(min), number(max))
+        }
+        ArrowDataType::Int64 => {
+            let (min, max) = numeric::<Int64Type>(array);
+            (number(min), number(max))
+        }
+        ArrowDataType::Float32 => {
+            let (min, max) = numeric::<Float32Type>(array);
+            (float(min.map(f64::from)), float(max.map(f64::from)))
+        }
+        ArrowDataType::Float64 => {
+            let (min, max) = numeric::<Float64Type>(array);
+            (float(min), float(max))
+        }
+        ArrowDataType::Decimal128(precision, scale) => {
+            let (min, max) = numeric::<Decimal128Type>(array);
+            (
+                min.and_then(|min| decimal(min, *precision, *scale)),
+                max.and_then(|max| decimal(max, *precision, *scale)),
+            )
+        }
+        ArrowDataType::Boolean => {
+            let array = array.as_boolean();
+            (
+                min_boolean(array).map(Value::Bool),
+                max_boolean(array).map(Value::Bool),
+            )
+        }
+        ArrowDataType::Utf8 => {
+            let array = array.as_string::<i32>();
+            // a prefix of the min value is still a lower bound, but a prefix of the max value isn't
+            // an upper bound
+            let min = min_string(array).map(|min| min.chars().take(STRING_PREFIX_LENGTH).collect());
+            let max = max_string(array).filter(|max| max.chars().count() <= STRING_PREFIX_LENGTH);
+            (
+                min.map(Value::String),
+                max.map(|max| Value::String(max.to_string())),
+            )
+        }
+        ArrowDataType::Date32 => {
+            let (min, max) = numeric::<Date32Type>(array);
+            (
+                min.map(format_date).transpose()?,
+                max.map(format_date).transpose()?,
+            )
+        }
+        ArrowDataType::Timestamp(TimeUnit::Microsecond, timezone) => {
+            // timestamps are written with millisecond precision, so the bounds are rounded down
+            // and up to the millisecond
+            let (min, max) = numeric::<TimestampMicrosecondType>(array);
+            let is_utc = timezone.is_some();
+            let min = min.map(|min| min.div_euclid(1000) * 1000);
+            let max = max.and_then(|max| Some(max.checked_add(999)?.div_euclid(1000) * 1000));
+            (
+                min.map(|min| format_timestamp(min, is_utc)).transpose()?,
+                max.map(|max| format_timestamp(max, is_utc)).transpose()?,
+            )
+        }
+        _ => (None, None),
+    };
+    Ok(bounds)
+}
+
+// The decimal with the given unscaled value as a JSON number, if the number is exactly the decimal
+// (JSON numbers are written as 64-bit integers or floats)
+fn decimal(value: i128, precision: u8, scale: i8) -> Option<Value> {
+    if scale == 0 {
+        return i64::try_from(value).ok().map(Value::from);
+    }
+    let decimal = Decimal128Type::format_decimal(value, precision, scale);
+    let number = Number::from_f64(decimal.parse().ok()?)?;
+    // compare the digits, without the trailing zeros of either representation
+    let trim = |digits: &str| -> String {
+        if digits.contains('.') {
+            digits
+                .trim_end_matches('0')
+                .trim_end_matches('.')
+                .to_string()
+        } else {
+            digits.to_string()
+        }
+    };
+    (trim(&number.to_string()) == trim(&decimal)).then_some(Value::Number(number))
+}
+
+fn format_date(days: i32) -> DeltaResult<Value> {
+    let date = DateTime::UNIX_EPOCH
+        .date_naive()
+        .checked_add_signed(chrono::Duration::days(days.into()))
+        .ok_or_else(|| Error::generic(format!("Date out of range: {days}")))?;
+    Ok(Value::String(date.format("%Y-%m-%d").to_string()))
+}
+
+fn format_timestamp(micros: i64, is_utc: bool) -> DeltaResult<Value> {
+    let timestamp = DateTime::from_timestamp_micros(micros)
+        .ok_or_else(|| Error::generic(format!("Timestamp out of range: {micros}")))?;
+    let format = if is_utc {
+        "%Y-%m-%dT%H:%M:%S%.3fZ"
+    } else {
+        "%Y-%m-%dT%H:%M:%S%.3f"
+    };
+    Ok(Value::String(timestamp.format(format).to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::arrow::array::{
+        BooleanArray, Decimal128Array, Float64Array, Int32Array, StringArray,
+        TimestampMicrosecondArray,
+    };
+    use crate::arrow::datatypes::{Field, Fields, Schema};
+    use crate::schema::column_name;
+
+    #[test]
+    fn test_collect_stats() {
+        let long_string = "a".repeat(40);
+        let nested_fields = Fields::from(vec![Field::new("s", ArrowDataType::Utf8, true)]);
+        let nested = StructArray::new(
+            nested_fields.clone(),
+            vec![Arc::new(StringArray::from(vec![
+                Some("b"),
+                Some(long_string.as_str()),
+                Some("c"),
+            ]))],
+            Some(NullBuffer::from(vec![true, true, false])),
+        );
+        let schema = Schema::new(vec![
+            Field::new("i", ArrowDataType::Int32, true),
+            Field::new("f", ArrowDataType::Float64, true),
+            Field::new(
+                "t",
+                ArrowDataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())),
+                true,
+            ),
+            Field::new("n", ArrowDataType::Struct(nested_fields), true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema),
+            vec![
+                Arc::new(Int32Array::from(vec![Some(3), None, Some(-1)])),
+                Arc::new(Float64Array::from(vec![1.5, f64::NAN, 0.5])),
+                Arc::new(
+                    TimestampMicrosecondArray::from(vec![1_500, 2_000, 2_001]).with_timezone("UTC"),
+                ),
+                Arc::new(nested),
+            ],
+        )
+        .unwrap();
+
+        let columns = [
+            column_name!("i"),
+            column_name!("f"),
+            column_name!("t"),
+            column_name!("n.s"),
+        ];
+        let stats = collect_stats(&batch, &columns).unwrap();
+        let stats: Value = serde_json::from_str(&stats).unwrap();
+        let expected = json!({
+            "numRecords": 3,
+            "minValues": {
+                "i": -1,
+                "f": 0.5,
+                "t": "1970-01-01T00:00:00.001Z",
+                "n": { "s": "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa" },
+            },
+            "maxValues": {
+                "i": 3,
+                "t": "1970-01-01T00:00:00.003Z",
+                "n": { "s": "b" },
+            },
+            "nullCount": { "i": 1, "f": 0, "t": 0, "n": { "s": 1 } },
+        });
+        assert_eq!(stats, expected);
+
+        assert_eq!(
+            collect_stats(&batch, &[]).unwrap(),
+            r#"{"maxValues":{},"minValues":{},"nullCount":{},"numRecords":3}"#
+        );
+        assert!(collect_stats(&batch, &[column_name!("x")]).is_err());
+    }
+
+    #[test]
+    fn test_decimal_and_boolean_stats() {
+        let decimals = Decimal128Array::from(vec![12345, -50, 100])
+            .with_precision_and_scale(10, 2)
+            .unwrap();
+        // decimals that a JSON number can't hold exactly have no bounds
+        let wide_decimals = Decimal128Array::from(vec![12_345_678_901_234_567_891, 1])
+            .with_precision_and_scale(38, 0)
+            .unwrap();
+        let schema = Schema::new(vec![
+            Field::new("d", decimals.data_type().clone(), true),
+            Field::new("b", ArrowDataType::Boolean, true),
+        ]);
+        let batch = RecordBatch::try_new(
+            Arc::new(schema.clone()),
+            vec![
+                Arc::new(decimals),
+                Arc::new(BooleanArray::from(vec![Some(true), None, Some(true)])),
+            ],
+        )
+        .unwrap();
+        let stats = collect_stats(&batch, &[column_name!("d"), column_name!("b")]).unwrap();
+        let stats: Value = serde_json::from_str(&stats).unwrap();
+        assert_eq!(stats["minValues"], json!({ "d": -0.5, "b": true }));
+        assert_eq!(stats["maxValues"], json!({ "d": 123.45, "b": true }));
+
+        let schema = Schema::new(vec![Field::new(
+            "d",
+            wide_decimals.data_type().clone(),
+            true,
+        )]);
+        let batch = RecordBatch::try_new(Arc::new(schema), vec![Arc::new(wide_decimals)]).unwrap();
+        let stats = collect_stats(&batch, &[column_name!("d")]).unwrap();
+        let stats: Value = serde_json::from_str(&stats).unwrap();
+        assert_eq!(stats["minValues"], json!({ "d": 1 }));
+        assert_eq!(stats["maxValues"], json!({}));
+    }
+}
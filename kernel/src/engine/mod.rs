@@ -5,6 +5,11 @@
 #[cfg(feature = "arrow-conversion")]
 pub(crate) mod arrow_conversion;
 
+#[cfg(all(
+    feature = "arrow-expression",
+    any(feature = "default-engine-base", feature = "sync-engine")
+))]
+pub mod arrow_clustering;
 #[cfg(all(
     feature = "arrow-expression",
     any(feature = "default-engine-base", feature = "sync-engine")
@@ -48,7 +53,7 @@ mod tests {
         engine_data: impl Fn() -> Box<dyn EngineData>,
     ) {
         let json = engine.get_json_handler();
-        let get_data = || Box::new(std::iter::once(Ok(engine_data())));
+        let get_data = || Box::new(std::iter::once(Ok(engine_data().into())));
 
         let expected_names: Vec<Path> = (1..4)
             .map(|i| delta_path_for_version(i, "json"))
@@ -1,3 +1,7 @@
+use std::fs::DirEntry;
+use std::io::Write;
+use std::path::Path;
+
 use bytes::Bytes;
 use itertools::Itertools;
 use url::Url;
@@ -8,7 +12,9 @@ pub(crate) struct SyncFilesystemClient;
 
 impl FileSystemClient for SyncFilesystemClient {
     /// List the paths in the same directory that are lexicographically greater or equal to
-    /// (UTF-8 sorting) the given `path`. The result is sorted by the file name.
+    /// (UTF-8 sorting) the given `path`. The result is sorted by the file name. If `path` is an
+    /// existing directory, the files in it and its subdirectories are listed, like object stores
+    /// list a prefix.
     fn list_from(
         &self,
         url_path: &Url,
@@ -36,14 +42,15 @@ impl FileSystemClient for SyncFilesystemClient {
                 (parent, Some(file_name))
             };
 
-            let all_ents: Vec<_> = std::fs::read_dir(path_to_read)?
-                .filter(|ent_res| {
-                    match (ent_res, min_file_name) {
-                        (Ok(ent), Some(min_file_name)) => ent.file_name() > *min_file_name,
-                        _ => true, // Keep unfiltered and/or error entries
-                    }
-                })
-                .try_collect()?;
+            let all_ents: Vec<_> = match min_file_name {
+                Some(min_file_name) => std::fs::read_dir(path_to_read)?
+                    .filter(|ent_res| match ent_res {
+                        Ok(ent) => ent.file_name() > *min_file_name,
+                        Err(_) => true, // Keep error entries
+                    })
+                    .try_collect()?,
+                None => read_dir_recursive(&path_to_read)?,
+            };
             let it = all_ents
                 .into_iter()
                 .sorted_by_key(|ent| ent.path())
@@ -72,6 +79,59 @@ impl FileSystemClient for SyncFilesystemClient {
         });
         Ok(Box::new(iter))
     }
+
+    fn write_file(&self, url: &Url, data: Bytes, overwrite: bool) -> DeltaResult<()> {
+        if url.scheme() != "file" {
+            return Err(Error::generic("Can only write local filesystem"));
+        }
+        let path = url
+            .to_file_path()
+            .map_err(|_| Error::generic(format!("Invalid path for write_file: {url:?}")))?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .create_new(!overwrite)
+            .open(&path)
+            .map_err(|e| match e.kind() {
+                std::io::ErrorKind::AlreadyExists => {
+                    Error::FileAlreadyExists(path.to_string_lossy().to_string())
+                }
+                _ => e.into(),
+            })?;
+        file.write_all(&data)?;
+        Ok(())
+    }
+
+    fn delete_file(&self, url: &Url) -> DeltaResult<()> {
+        if url.scheme() != "file" {
+            return Err(Error::generic("Can only delete from local filesystem"));
+        }
+        let path = url
+            .to_file_path()
+            .map_err(|_| Error::generic(format!("Invalid path for delete_file: {url:?}")))?;
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+// the entries of the files in `dir` and all of its subdirectories
+fn read_dir_recursive(dir: &Path) -> DeltaResult<Vec<DirEntry>> {
+    let mut files = vec![];
+    for ent in std::fs::read_dir(dir)? {
+        let ent = ent?;
+        if ent.file_type()?.is_dir() {
+            files.extend(read_dir_recursive(&ent.path())?);
+        } else {
+            files.push(ent);
+        }
+    }
+    Ok(files)
 }
 
 #[cfg(test)]
@@ -149,6 +209,19 @@ mod tests {
         file_count = list.count();
         assert_eq!(file_count, 3);
 
+        // listing a directory includes the files in its subdirectories
+        let sub_dir = tmp_dir.path().join("sub");
+        std::fs::create_dir(&sub_dir)?;
+        File::create(sub_dir.join(get_json_filename(0)))?;
+        let url = Url::from_directory_path(tmp_dir.path()).unwrap();
+        let files: Vec<_> = client.list_from(&url)?.try_collect()?;
+        assert_eq!(files.len(), 4);
+        assert!(files[3]
+            .location
+            .path()
+            .ends_with("/sub/00000000000000000000.json"));
+        std::fs::remove_dir_all(&sub_dir)?;
+
         let url_path = tmp_dir.path().join(format!("{:020}", 1));
         let url = Url::from_file_path(url_path).unwrap();
         let list = client.list_from(&url)?;
@@ -179,4 +252,18 @@ mod tests {
         assert_eq!(file_count, 1);
         Ok(())
     }
+
+    #[test]
+    fn test_delete_file() -> Result<(), Box<dyn std::error::Error>> {
+        let client = SyncFilesystemClient;
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let path = tmp_dir.path().join(get_json_filename(1));
+        File::create(path.clone())?;
+        let url = Url::from_file_path(&path).unwrap();
+        client.delete_file(&url)?;
+        assert!(!path.exists());
+        // deleting a missing file succeeds
+        client.delete_file(&url)?;
+        Ok(())
+    }
 }
@@ -12,7 +12,7 @@ use crate::engine::arrow_utils::to_json_bytes;
 use crate::schema::SchemaRef;
 use crate::{
     DeltaResult, EngineData, Error, ExpressionRef, FileDataReadResultIterator, FileMeta,
-    JsonHandler,
+    FilteredEngineData, JsonHandler,
 };
 
 pub(crate) struct SyncJsonHandler;
@@ -23,7 +23,10 @@ fn try_create_from_json(
     arrow_schema: ArrowSchemaRef,
     _predicate: Option<ExpressionRef>,
 ) -> DeltaResult<impl Iterator<Item = DeltaResult<ArrowEngineData>>> {
+    // writers store some values of string maps (e.g. commitInfo.operationParameters) as JSON
+    // booleans or numbers, so these are read as strings
     let json = ReaderBuilder::new(arrow_schema)
+        .with_coerce_primitive(true)
         .build(BufReader::new(file))?
         .map(|data| Ok(ArrowEngineData::new(data?)));
     Ok(json)
@@ -53,7 +56,7 @@ impl JsonHandler for SyncJsonHandler {
     fn write_json_file(
         &self,
         path: &Url,
-        data: Box<dyn Iterator<Item = DeltaResult<Box<dyn EngineData>>> + Send + '_>,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_>,
         _overwrite: bool,
     ) -> DeltaResult<()> {
         let path = path
@@ -123,10 +126,10 @@ mod tests {
 
         let url = Url::from_file_path(path.clone()).unwrap();
         handler
-            .write_json_file(&url, Box::new(std::iter::once(Ok(data))), false)
+            .write_json_file(&url, Box::new(std::iter::once(Ok(data.into()))), false)
             .expect("write json file");
         assert!(matches!(
-            handler.write_json_file(&url, Box::new(std::iter::once(Ok(empty))), false),
+            handler.write_json_file(&url, Box::new(std::iter::once(Ok(empty.into()))), false),
             Err(Error::FileAlreadyExists(_))
         ));
 
@@ -1,14 +1,24 @@
 use std::fs::File;
+use std::io::Write as _;
+use std::time::UNIX_EPOCH;
+
+use tempfile::NamedTempFile;
+use url::Url;
 
 use crate::arrow::datatypes::SchemaRef as ArrowSchemaRef;
 use crate::parquet::arrow::arrow_reader::{ArrowReaderMetadata, ParquetRecordBatchReaderBuilder};
 
 use super::read_files;
 use crate::engine::arrow_data::ArrowEngineData;
-use crate::engine::arrow_utils::{fixup_parquet_read, generate_mask, get_requested_indices};
+use crate::engine::arrow_utils::{
+    fixup_parquet_read, generate_mask, get_requested_indices, to_parquet_bytes, RowIndexBuilder,
+};
 use crate::engine::parquet_row_group_skipping::ParquetRowGroupSkipping;
 use crate::schema::SchemaRef;
-use crate::{DeltaResult, ExpressionRef, FileDataReadResultIterator, FileMeta, ParquetHandler};
+use crate::{
+    DeltaResult, Error, ExpressionRef, FileDataReadResultIterator, FileMeta, FilteredEngineData,
+    ParquetHandler,
+};
 
 pub(crate) struct SyncParquetHandler;
 
@@ -25,11 +35,13 @@ fn try_create_from_parquet(
     if let Some(mask) = generate_mask(&schema, parquet_schema, builder.parquet_schema(), &indices) {
         builder = builder.with_projection(mask);
     }
+    let mut row_indexes = RowIndexBuilder::try_for_schema(&schema, builder.metadata().row_groups());
     if let Some(predicate) = predicate {
-        builder = builder.with_row_group_filter(predicate.as_ref());
+        builder = builder.with_row_group_filter(predicate.as_ref(), row_indexes.as_mut());
     }
+    let mut row_indexes = row_indexes.map(RowIndexBuilder::build).transpose()?;
     let stream = builder.build()?;
-    Ok(stream.map(move |rbr| fixup_parquet_read(rbr?, &requested_ordering)))
+    Ok(stream.map(move |rbr| fixup_parquet_read(rbr?, &requested_ordering, row_indexes.as_mut())))
 }
 
 impl ParquetHandler for SyncParquetHandler {
@@ -41,4 +53,53 @@ impl ParquetHandler for SyncParquetHandler {
     ) -> DeltaResult<FileDataReadResultIterator> {
         read_files(files, schema, predicate, try_create_from_parquet)
     }
+
+    fn write_parquet_file(
+        &self,
+        location: &Url,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_>,
+        overwrite: bool,
+    ) -> DeltaResult<FileMeta> {
+        let path = location
+            .to_file_path()
+            .map_err(|_| Error::generic("sync client can only write local files"))?;
+        let Some(parent) = path.parent() else {
+            return Err(Error::generic(format!("no parent found for {:?}", path)));
+        };
+        if !parent.exists() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        // write data to tmp file, then atomically rename it to the final path
+        let mut tmp_file = NamedTempFile::new_in(parent)?;
+        tmp_file.write_all(&to_parquet_bytes(data)?)?;
+        tmp_file.flush()?;
+        let file = if overwrite {
+            tmp_file
+                .persist(&path)
+                .map_err(|e| Error::IOError(e.into()))?
+        } else {
+            tmp_file.persist_noclobber(&path).map_err(|e| match e {
+                tempfile::PersistError { error, .. }
+                    if error.kind() == std::io::ErrorKind::AlreadyExists =>
+                {
+                    Error::FileAlreadyExists(path.to_string_lossy().to_string())
+                }
+                e => Error::IOError(e.into()),
+            })?
+        };
+        let metadata = file.metadata()?;
+        let last_modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_err(|_| Error::generic("Failed to convert file timestamp to milliseconds"))?
+            .as_millis()
+            .try_into()
+            .map_err(|_| Error::generic("Failed to convert file modification time into i64"))?;
+        Ok(FileMeta::new(
+            location.clone(),
+            last_modified,
+            metadata.len() as usize,
+        ))
+    }
 }
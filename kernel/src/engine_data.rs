@@ -1,6 +1,7 @@
 //! Traits that engines need to implement in order to pass data between themselves and kernel.
 
-use crate::schema::{ColumnName, DataType};
+use crate::expressions::ArrayData;
+use crate::schema::{ColumnName, DataType, SchemaRef};
 use crate::{AsAny, DeltaResult, Error};
 
 use tracing::debug;
@@ -257,4 +258,97 @@ pub trait EngineData: AsAny {
     fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Return new data with the given `columns` appended to the columns of this data. The appended
+    /// columns are described by the fields of `schema`, and each holds one element per row of this
+    /// data.
+    ///
+    /// Kernel uses this to add per-row values that it computes itself to data it got from the
+    /// engine, e.g. the new deletion vectors of the files updated by a transaction. The default
+    /// implementation returns [`Error::Unsupported`], in which case kernel can't commit such
+    /// changes.
+    fn append_columns(
+        &self,
+        _schema: SchemaRef,
+        _columns: Vec<ArrayData>,
+    ) -> DeltaResult<Box<dyn EngineData>> {
+        Err(Error::unsupported(
+            "Appending columns is not supported by this EngineData",
+        ))
+    }
+}
+
+/// [`EngineData`] paired with a selection vector that indicates which rows of the data are
+/// "selected". If a row is at index `i` and the selection vector is `false` at index `i`, then that
+/// row should *not* be processed. If the selection vector is *shorter* than the number of rows in
+/// the data, missing elements are considered `true` (i.e. selected).
+///
+/// This is the unit of data that is handed to engines when only a subset of the rows of some
+/// [`EngineData`] should be written, e.g. by [`JsonHandler::write_json_file`].
+///
+/// [`JsonHandler::write_json_file`]: crate::JsonHandler::write_json_file
+pub struct FilteredEngineData {
+    data: Box<dyn EngineData>,
+    selection_vector: Vec<bool>,
+}
+
+impl FilteredEngineData {
+    /// Create a new [`FilteredEngineData`]. Fails if the selection vector is longer than the number
+    /// of rows in `data`.
+    pub fn try_new(data: Box<dyn EngineData>, selection_vector: Vec<bool>) -> DeltaResult<Self> {
+        if selection_vector.len() > data.len() {
+            return Err(Error::generic(format!(
+                "Selection vector is larger than data length: {} > {}",
+                selection_vector.len(),
+                data.len()
+            )));
+        }
+        Ok(Self {
+            data,
+            selection_vector,
+        })
+    }
+
+    /// Create a new [`FilteredEngineData`] in which all rows of `data` are selected.
+    pub fn with_all_rows_selected(data: Box<dyn EngineData>) -> Self {
+        Self {
+            data,
+            selection_vector: vec![],
+        }
+    }
+
+    /// The underlying (unfiltered) data.
+    pub fn data(&self) -> &dyn EngineData {
+        self.data.as_ref()
+    }
+
+    /// The selection vector for the data. See [`FilteredEngineData`] for its semantics.
+    pub fn selection_vector(&self) -> &[bool] {
+        &self.selection_vector
+    }
+
+    /// Returns true if every row of the data is selected.
+    pub fn has_all_rows_selected(&self) -> bool {
+        self.selection_vector.iter().all(|selected| *selected)
+    }
+
+    /// Consume this [`FilteredEngineData`], returning the data and the selection vector.
+    pub fn into_parts(self) -> (Box<dyn EngineData>, Vec<bool>) {
+        (self.data, self.selection_vector)
+    }
+}
+
+impl From<Box<dyn EngineData>> for FilteredEngineData {
+    fn from(data: Box<dyn EngineData>) -> Self {
+        Self::with_all_rows_selected(data)
+    }
+}
+
+impl std::fmt::Debug for FilteredEngineData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FilteredEngineData")
+            .field("len", &self.data.len())
+            .field("selection_vector", &self.selection_vector)
+            .finish()
+    }
 }
@@ -199,11 +199,49 @@ pub enum Error {
     #[error("Invalid Checkpoint: {0}")]
     InvalidCheckpoint(String),
 
+    /// The transaction contains changes that are not valid for the table
+    #[error("Invalid transaction: {0}")]
+    InvalidTransaction(String),
+
+    /// No commit of the table matches a timestamp
+    #[error("Timestamp out of range: {0}")]
+    TimestampOutOfRange(String),
+
     /// Error while transforming a schema + leaves into an Expression of literals
     #[error(transparent)]
     LiteralExpressionTransformError(
         #[from] crate::expressions::literal_expression_transform::Error,
     ),
+
+    /// The transaction conflicts with a transaction that was committed concurrently
+    #[error(transparent)]
+    TransactionConflict(#[from] crate::transaction::ConflictError),
+
+    /// A table already exists at the location where a table is being created
+    #[error("Table already exists: {0}")]
+    TableAlreadyExists(String),
+
+    /// The partition columns of a table are not valid for its schema
+    #[error("Invalid partition column: {0}")]
+    InvalidPartitionColumn(String),
+
+    /// The clustering columns of a table are not valid for its schema
+    #[error("Invalid clustering column: {0}")]
+    InvalidClusteringColumn(String),
+
+    /// An identity column of a table is not valid, or the data written to it is not allowed
+    #[error("Invalid identity column: {0}")]
+    InvalidIdentityColumn(String),
+
+    /// A generated column of a table is not valid, or the data written to it doesn't match its
+    /// generation expression
+    #[error("Invalid generated column: {0}")]
+    InvalidGeneratedColumn(String),
+
+    /// A CHECK constraint or column invariant of a table is not valid, or the data written to the
+    /// table violates it
+    #[error("Invalid constraint: {0}")]
+    InvalidConstraint(String),
 }
 
 // Convenience constructors for Error types that take a String argument
@@ -281,6 +319,34 @@ impl Error {
         Self::InvalidCheckpoint(msg.to_string())
     }
 
+    pub fn invalid_transaction(msg: impl ToString) -> Self {
+        Self::InvalidTransaction(msg.to_string())
+    }
+
+    pub fn timestamp_out_of_range(msg: impl ToString) -> Self {
+        Self::TimestampOutOfRange(msg.to_string())
+    }
+
+    pub fn invalid_partition_column(msg: impl ToString) -> Self {
+        Self::InvalidPartitionColumn(msg.to_string())
+    }
+
+    pub fn invalid_clustering_column(msg: impl ToString) -> Self {
+        Self::InvalidClusteringColumn(msg.to_string())
+    }
+
+    pub fn invalid_identity_column(msg: impl ToString) -> Self {
+        Self::InvalidIdentityColumn(msg.to_string())
+    }
+
+    pub fn invalid_generated_column(msg: impl ToString) -> Self {
+        Self::InvalidGeneratedColumn(msg.to_string())
+    }
+
+    pub fn invalid_constraint(msg: impl ToString) -> Self {
+        Self::InvalidConstraint(msg.to_string())
+    }
+
     // Capture a backtrace when the error is constructed.
     #[must_use]
     pub fn with_backtrace(self) -> Self {
@@ -16,6 +16,7 @@ mod column_names;
 mod scalars;
 
 pub(crate) mod literal_expression_transform;
+pub(crate) mod sql;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 /// A binary operator.
@@ -46,6 +47,8 @@ pub enum BinaryOperator {
     In,
     /// NOT IN
     NotIn,
+    /// Coalesce: the left value, or the right value if the left one is NULL
+    Coalesce,
 }
 
 impl BinaryOperator {
@@ -53,7 +56,7 @@ impl BinaryOperator {
     pub(crate) fn is_null_intolerant_comparison(&self) -> bool {
         use BinaryOperator::*;
         match self {
-            Plus | Minus | Multiply | Divide => false, // not a comparison
+            Plus | Minus | Multiply | Divide | Coalesce => false, // not a comparison
             LessThan | LessThanOrEqual | GreaterThan | GreaterThanOrEqual => true,
             Equal | NotEqual => true,
             Distinct | In | NotIn => false, // tolerates NULL input
@@ -69,7 +72,7 @@ impl BinaryOperator {
             LessThan => Some(GreaterThan),
             LessThanOrEqual => Some(GreaterThanOrEqual),
             Equal | NotEqual | Distinct | Plus | Multiply => Some(*self),
-            In | NotIn | Minus | Divide => None, // not commutative
+            In | NotIn | Minus | Divide | Coalesce => None, // not commutative
         }
     }
 }
@@ -109,6 +112,7 @@ impl Display for BinaryOperator {
             Self::Distinct => write!(f, "DISTINCT"),
             Self::In => write!(f, "IN"),
             Self::NotIn => write!(f, "NOT IN"),
+            Self::Coalesce => write!(f, "COALESCE"),
         }
     }
 }
@@ -155,6 +159,20 @@ impl BinaryExpression {
     }
 }
 
+#[derive(Clone, Debug, PartialEq)]
+pub struct CastExpression {
+    /// The expression whose values are cast.
+    pub expr: Box<Expression>,
+    /// The type the values are cast to.
+    pub data_type: DataType,
+}
+impl CastExpression {
+    fn new(expr: impl Into<Expression>, data_type: DataType) -> Self {
+        let expr = Box::new(expr.into());
+        Self { expr, data_type }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct VariadicExpression {
     /// The operator.
@@ -187,6 +205,11 @@ pub enum Expression {
     Binary(BinaryExpression),
     /// A variadic operation.
     Variadic(VariadicExpression),
+    /// A conversion of the values of an expression to another type.
+    Cast(CastExpression),
+    /// The index of each row in the data the expression is evaluated on, starting at 0, as a
+    /// `long`.
+    RowIndex,
     // TODO: support more expressions, such as IS IN, LIKE, etc.
 }
 
@@ -217,6 +240,11 @@ impl Display for Expression {
                 left,
                 right,
             }) => write!(f, "DISTINCT({left}, {right})"),
+            Self::Binary(BinaryExpression {
+                op: BinaryOperator::Coalesce,
+                left,
+                right,
+            }) => write!(f, "COALESCE({left}, {right})"),
             Self::Binary(BinaryExpression { op, left, right }) => write!(f, "{left} {op} {right}"),
             Self::Unary(UnaryExpression { op, expr }) => match op {
                 UnaryOperator::Not => write!(f, "NOT {expr}"),
@@ -230,6 +258,10 @@ impl Display for Expression {
                 };
                 write!(f, "{op}({exprs})")
             }
+            Self::Cast(CastExpression { expr, data_type }) => {
+                write!(f, "CAST({expr} AS {data_type})")
+            }
+            Self::RowIndex => write!(f, "ROW_INDEX()"),
         }
     }
 }
@@ -259,6 +291,11 @@ impl Expression {
         Self::Literal(Scalar::Null(data_type))
     }
 
+    /// Create a new expression for the index of each row in the evaluated data
+    pub const fn row_index() -> Self {
+        Self::RowIndex
+    }
+
     /// Create a new struct expression
     pub fn struct_from(exprs: impl IntoIterator<Item = Self>) -> Self {
         Self::Struct(exprs.into_iter().collect())
@@ -365,6 +402,16 @@ impl Expression {
     pub fn distinct(self, other: impl Into<Self>) -> Self {
         Self::binary(BinaryOperator::Distinct, self, other)
     }
+
+    /// Create a new expression `COALESCE(self, other)`
+    pub fn coalesce(self, other: impl Into<Self>) -> Self {
+        Self::binary(BinaryOperator::Coalesce, self, other)
+    }
+
+    /// Create a new expression `CAST(self AS data_type)`
+    pub fn cast(self, data_type: impl Into<DataType>) -> Self {
+        Self::Cast(CastExpression::new(self, data_type.into()))
+    }
 }
 
 /// Generic framework for recursive bottom-up expression transforms. Transformations return
@@ -429,6 +476,12 @@ pub trait ExpressionTransform<'a> {
         self.recurse_into_variadic(expr)
     }
 
+    /// Called for each [`CastExpression`] encountered during the traversal. Implementations can
+    /// call [`Self::recurse_into_cast`] if they wish to recursively transform the child.
+    fn transform_cast(&mut self, expr: &'a CastExpression) -> Option<Cow<'a, CastExpression>> {
+        self.recurse_into_cast(expr)
+    }
+
     /// General entry point for transforming an expression. This method will dispatch to the
     /// specific transform for each expression variant. Also invoked internally in order to recurse
     /// on the child(ren) of non-leaf variants.
@@ -459,6 +512,11 @@ pub trait ExpressionTransform<'a> {
                 Owned(v) => Owned(Expression::Variadic(v)),
                 Borrowed(_) => Borrowed(expr),
             },
+            Expression::Cast(c) => match self.transform_cast(c)? {
+                Owned(c) => Owned(Expression::Cast(c)),
+                Borrowed(_) => Borrowed(expr),
+            },
+            Expression::RowIndex => Borrowed(expr),
         };
         Some(expr)
     }
@@ -538,6 +596,17 @@ pub trait ExpressionTransform<'a> {
         };
         Some(v)
     }
+
+    /// Recursively transforms a cast expression's child. Returns `None` if the child was removed,
+    /// `Some(Cow::Owned)` if the child was changed, and `Some(Cow::Borrowed)` otherwise.
+    fn recurse_into_cast(&mut self, c: &'a CastExpression) -> Option<Cow<'a, CastExpression>> {
+        use Cow::*;
+        let c = match self.transform(&c.expr)? {
+            Owned(expr) => Owned(CastExpression::new(expr, c.data_type.clone())),
+            Borrowed(_) => Borrowed(c),
+        };
+        Some(c)
+    }
 }
 
 impl std::ops::Not for Expression {
@@ -0,0 +1,869 @@
+//! A parser for the SQL expressions that tables store in their metadata, such as the generation
+//! expressions of generated columns.
+//!
+//! Only a subset of Spark SQL is supported: column references, literals (including `DATE '...'`
+//! and `TIMESTAMP '...'`), arithmetic, comparisons, `AND`, `OR`, `NOT`, `IS [NOT] NULL`,
+//! `[NOT] IN`, `[NOT] BETWEEN`, `COALESCE` and `CAST` to `DATE` or `TIMESTAMP`. Column references
+//! are resolved against the schema of the table, case-insensitively like Spark, and operands are
+//! converted to a common type like Spark converts them: numbers are widened, division is of doubles
+//! and literals are converted to the type of the values they are compared with. Expressions whose
+//! types Spark derives differently, such as decimal arithmetic, aren't supported. Timestamps are
+//! cast to dates in UTC, like Spark with a UTC session time zone.
+
+use crate::expressions::{
+    BinaryExpression, BinaryOperator, CastExpression, ColumnName, Expression, Scalar,
+    UnaryExpression, VariadicExpression,
+};
+use crate::schema::{DataType, PrimitiveType, StructType};
+use crate::utils::require;
+use crate::{DeltaResult, Error};
+
+/// Parse `sql` into an expression over the columns of `schema` whose values are of type
+/// `data_type`. Returns [`Error::Unsupported`] if the expression uses SQL that the parser doesn't
+/// support.
+pub(crate) fn parse_sql_expression(
+    sql: &str,
+    schema: &StructType,
+    data_type: &DataType,
+) -> DeltaResult<Expression> {
+    let mut parser = Parser {
+        sql,
+        tokens: tokenize(sql)?,
+        position: 0,
+    };
+    let expression = parser.parse_or()?;
+    if let Some(token) = parser.peek() {
+        return Err(parser.error(format!("unexpected {token:?}")));
+    }
+    let (expression, expression_type) = resolve(expression, schema)?;
+    let expression = coerce(expression, data_type)?;
+    if !matches!(expression, Expression::Literal(_)) {
+        if let Some(expression_type) = expression_type {
+            require!(
+                &expression_type == data_type,
+                parser.error(format!("is of type {expression_type}, not {data_type}"))
+            );
+        }
+    }
+    Ok(expression)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    // an unquoted identifier or keyword
+    Word(String),
+    // an identifier quoted with backticks
+    QuotedIdentifier(String),
+    Number(String),
+    String(String),
+    Symbol(&'static str),
+}
+
+// the symbols of the supported operators, longest first
+const SYMBOLS: [&str; 17] = [
+    "<=>", "==", "!=", "<>", "<=", ">=", "(", ")", ",", ".", "+", "-", "*", "/", "=", "<", ">",
+];
+
+fn tokenize(sql: &str) -> DeltaResult<Vec<Token>> {
+    let error = |msg: &str| Error::generic(format!("Invalid SQL expression {sql}: {msg}"));
+    let mut tokens = vec![];
+    let mut rest = sql;
+    while let Some(c) = rest.chars().next() {
+        if c.is_whitespace() {
+            rest = &rest[c.len_utf8()..];
+            continue;
+        }
+        let (token, len) = if c.is_ascii_alphabetic() || c == '_' {
+            let len = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+            (Token::Word(rest[..len].to_string()), len)
+        } else if c.is_ascii_digit()
+            || c == '.' && rest[1..].starts_with(|c: char| c.is_ascii_digit())
+        {
+            // digits, a fraction, an exponent (possibly signed) and a type suffix
+            let mut len = 0;
+            for (i, c) in rest.char_indices() {
+                let signed_exponent = (c == '+' || c == '-') && rest[..i].ends_with(['e', 'E']);
+                if !c.is_ascii_alphanumeric() && c != '.' && !signed_exponent {
+                    break;
+                }
+                len = i + 1;
+            }
+            (Token::Number(rest[..len].to_string()), len)
+        } else if c == '\'' || c == '"' {
+            let mut value = String::new();
+            let mut chars = rest.char_indices().skip(1);
+            let len = loop {
+                match chars.next() {
+                    Some((i, end)) if end == c => break i + 1,
+                    Some((_, '\\')) => match chars.next() {
+                        Some((_, 'n')) => value.push('\n'),
+                        Some((_, 't')) => value.push('\t'),
+                        Some((_, 'r')) => value.push('\r'),
+                        Some((_, '0')) => value.push('\0'),
+                        Some((_, escaped)) => value.push(escaped),
+                        None => return Err(error("unterminated string")),
+                    },
+                    Some((_, c)) => value.push(c),
+                    None => return Err(error("unterminated string")),
+                }
+            };
+            (Token::String(value), len)
+        } else if c == '`' {
+            // backticks are escaped by doubling them
+            let mut name = String::new();
+            let mut chars = rest.char_indices().skip(1).peekable();
+            let len = loop {
+                match chars.next() {
+                    Some((_, '`')) if chars.peek().is_some_and(|(_, c)| *c == '`') => {
+                        chars.next();
+                        name.push('`');
+                    }
+                    Some((i, '`')) => break i + 1,
+                    Some((_, c)) => name.push(c),
+                    None => return Err(error("unterminated quoted identifier")),
+                }
+            };
+            (Token::QuotedIdentifier(name), len)
+        } else {
+            let symbol = SYMBOLS
+                .into_iter()
+                .find(|symbol| rest.starts_with(symbol))
+                .ok_or_else(|| error(&format!("unexpected character {c}")))?;
+            (Token::Symbol(symbol), symbol.len())
+        };
+        tokens.push(token);
+        rest = &rest[len..];
+    }
+    Ok(tokens)
+}
+
+// A recursive descent parser of SQL expressions, from the lowest precedence (OR) to the highest
+// (literals, column references, function calls and parenthesized expressions)
+struct Parser<'a> {
+    sql: &'a str,
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, msg: impl std::fmt::Display) -> Error {
+        Error::generic(format!("Invalid SQL expression {}: {msg}", self.sql))
+    }
+
+    fn unsupported(&self, msg: impl std::fmt::Display) -> Error {
+        Error::unsupported(format!("Unsupported SQL expression {}: {msg}", self.sql))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> DeltaResult<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .cloned()
+            .ok_or_else(|| self.error("unexpected end of expression"))?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn next_is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn consume_keyword(&mut self, keyword: &str) -> bool {
+        let found = matches!(self.peek(), Some(Token::Word(w)) if w.eq_ignore_ascii_case(keyword));
+        self.position += found as usize;
+        found
+    }
+
+    fn consume_symbol(&mut self, symbol: &str) -> bool {
+        let found = self.next_is_symbol(symbol);
+        self.position += found as usize;
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> DeltaResult<()> {
+        require!(
+            self.consume_keyword(keyword),
+            self.error(format!("expected {keyword}"))
+        );
+        Ok(())
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> DeltaResult<()> {
+        require!(
+            self.consume_symbol(symbol),
+            self.error(format!("expected {symbol}"))
+        );
+        Ok(())
+    }
+
+    // a comma-separated list of expressions in parentheses
+    fn parse_list(&mut self) -> DeltaResult<Vec<Expression>> {
+        self.expect_symbol("(")?;
+        let mut exprs = vec![self.parse_or()?];
+        while self.consume_symbol(",") {
+            exprs.push(self.parse_or()?);
+        }
+        self.expect_symbol(")")?;
+        Ok(exprs)
+    }
+
+    fn parse_or(&mut self) -> DeltaResult<Expression> {
+        let mut exprs = vec![self.parse_and()?];
+        while self.consume_keyword("OR") {
+            exprs.push(self.parse_and()?);
+        }
+        Ok(match exprs.len() {
+            1 => exprs.remove(0),
+            _ => Expression::or_from(exprs),
+        })
+    }
+
+    fn parse_and(&mut self) -> DeltaResult<Expression> {
+        let mut exprs = vec![self.parse_not()?];
+        while self.consume_keyword("AND") {
+            exprs.push(self.parse_not()?);
+        }
+        Ok(match exprs.len() {
+            1 => exprs.remove(0),
+            _ => Expression::and_from(exprs),
+        })
+    }
+
+    fn parse_not(&mut self) -> DeltaResult<Expression> {
+        if self.consume_keyword("NOT") {
+            return Ok(!self.parse_not()?);
+        }
+        self.parse_predicate()
+    }
+
+    fn parse_predicate(&mut self) -> DeltaResult<Expression> {
+        let left = self.parse_additive()?;
+        if self.consume_keyword("IS") {
+            let negated = self.consume_keyword("NOT");
+            self.expect_keyword("NULL")?;
+            return Ok(if negated {
+                left.is_not_null()
+            } else {
+                left.is_null()
+            });
+        }
+        let negated = self.consume_keyword("NOT");
+        let predicate = if self.consume_keyword("IN") {
+            // `x IN (a, b)` is defined as `x = a OR x = b`, including for nulls
+            let values = self.parse_list()?;
+            Expression::or_from(values.into_iter().map(|value| left.clone().eq(value)))
+        } else if self.consume_keyword("BETWEEN") {
+            let low = self.parse_additive()?;
+            self.expect_keyword("AND")?;
+            let high = self.parse_additive()?;
+            Expression::and_from([left.clone().ge(low), left.le(high)])
+        } else if negated {
+            return Err(self.error("expected IN or BETWEEN after NOT"));
+        } else {
+            let op = match self.peek() {
+                Some(Token::Symbol("=" | "==")) => BinaryOperator::Equal,
+                Some(Token::Symbol("!=" | "<>")) => BinaryOperator::NotEqual,
+                Some(Token::Symbol("<")) => BinaryOperator::LessThan,
+                Some(Token::Symbol("<=")) => BinaryOperator::LessThanOrEqual,
+                Some(Token::Symbol(">")) => BinaryOperator::GreaterThan,
+                Some(Token::Symbol(">=")) => BinaryOperator::GreaterThanOrEqual,
+                // null-safe equality
+                Some(Token::Symbol("<=>")) => BinaryOperator::Distinct,
+                _ => return Ok(left),
+            };
+            self.position += 1;
+            let right = self.parse_additive()?;
+            let comparison = Expression::binary(op, left, right);
+            return Ok(match op {
+                BinaryOperator::Distinct => !comparison,
+                _ => comparison,
+            });
+        };
+        Ok(if negated { !predicate } else { predicate })
+    }
+
+    fn parse_additive(&mut self) -> DeltaResult<Expression> {
+        let mut expr = self.parse_multiplicative()?;
+        loop {
+            expr = if self.consume_symbol("+") {
+                expr + self.parse_multiplicative()?
+            } else if self.consume_symbol("-") {
+                expr - self.parse_multiplicative()?
+            } else {
+                return Ok(expr);
+            };
+        }
+    }
+
+    fn parse_multiplicative(&mut self) -> DeltaResult<Expression> {
+        let mut expr = self.parse_unary()?;
+        loop {
+            expr = if self.consume_symbol("*") {
+                expr * self.parse_unary()?
+            } else if self.consume_symbol("/") {
+                expr / self.parse_unary()?
+            } else {
+                return Ok(expr);
+            };
+        }
+    }
+
+    fn parse_unary(&mut self) -> DeltaResult<Expression> {
+        if self.consume_symbol("+") {
+            return self.parse_unary();
+        }
+        if !self.consume_symbol("-") {
+            return self.parse_primary();
+        }
+        // the negation of the minimum value of an integral type only fits in the next wider type
+        Ok(match self.parse_unary()? {
+            Expression::Literal(Scalar::Byte(value)) => match value.checked_neg() {
+                Some(value) => Expression::literal(value),
+                None => Expression::literal(-i16::from(value)),
+            },
+            Expression::Literal(Scalar::Short(value)) => match value.checked_neg() {
+                Some(value) => Expression::literal(value),
+                None => Expression::literal(-i32::from(value)),
+            },
+            Expression::Literal(Scalar::Integer(value)) => match value.checked_neg() {
+                Some(value) => Expression::literal(value),
+                None => Expression::literal(-i64::from(value)),
+            },
+            // the negation of a long literal may be an integer, e.g. -2147483648
+            Expression::Literal(Scalar::Long(value)) => {
+                let value = value
+                    .checked_neg()
+                    .ok_or_else(|| self.error(format!("-({value}) is out of range")))?;
+                match i32::try_from(value) {
+                    Ok(value) => Expression::literal(value),
+                    Err(_) => Expression::literal(value),
+                }
+            }
+            Expression::Literal(Scalar::Float(value)) => Expression::literal(-value),
+            Expression::Literal(Scalar::Double(value)) => Expression::literal(-value),
+            // a byte zero keeps the type of the negated values
+            expr => Expression::literal(0i8) - expr,
+        })
+    }
+
+    fn parse_primary(&mut self) -> DeltaResult<Expression> {
+        match self.next()? {
+            Token::Number(text) => self.parse_number(&text),
+            Token::String(value) => Ok(Expression::literal(value)),
+            Token::Symbol("(") => {
+                let expr = self.parse_or()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            Token::QuotedIdentifier(name) => self.parse_column(name),
+            Token::Word(word) => {
+                let keyword = word.to_ascii_uppercase();
+                match (keyword.as_str(), self.peek()) {
+                    ("TRUE", _) => Ok(Expression::literal(true)),
+                    ("FALSE", _) => Ok(Expression::literal(false)),
+                    // the type of null literals is set by `resolve`
+                    ("NULL", _) => Ok(Expression::null_literal(DataType::STRING)),
+                    ("DATE" | "TIMESTAMP", Some(Token::String(value))) => {
+                        let data_type = match keyword.as_str() {
+                            "DATE" => PrimitiveType::Date,
+                            _ => PrimitiveType::Timestamp,
+                        };
+                        let value = data_type.parse_scalar(value)?;
+                        self.position += 1;
+                        Ok(Expression::literal(value))
+                    }
+                    (_, Some(Token::Symbol("("))) => self.parse_function(&word),
+                    _ => self.parse_column(word),
+                }
+            }
+            token => Err(self.error(format!("unexpected {token:?}"))),
+        }
+    }
+
+    // Integers are ints if they fit, or else longs, and other numbers are doubles. A suffix sets
+    // the type of the number: L for long, S for short, Y for byte, D for double and F for float.
+    fn parse_number(&self, text: &str) -> DeltaResult<Expression> {
+        let invalid = || self.error(format!("invalid number {text}"));
+        let (digits, suffix) = match text.char_indices().last() {
+            Some((i, c)) if c.is_ascii_alphabetic() && !text[..i].ends_with(['e', 'E']) => {
+                (&text[..i], Some(c.to_ascii_uppercase()))
+            }
+            _ => (text, None),
+        };
+        let scalar = match suffix {
+            Some('L') => digits.parse::<i64>().map(Scalar::from).ok(),
+            Some('S') => digits.parse::<i16>().map(Scalar::from).ok(),
+            Some('Y') => digits.parse::<i8>().map(Scalar::from).ok(),
+            Some('D') => digits.parse::<f64>().map(Scalar::from).ok(),
+            Some('F') => digits.parse::<f32>().map(Scalar::from).ok(),
+            Some(_) => None,
+            None if digits.contains(['.', 'e', 'E']) => {
+                digits.parse::<f64>().map(Scalar::from).ok()
+            }
+            None => digits
+                .parse::<i32>()
+                .map(Scalar::from)
+                .or_else(|_| digits.parse::<i64>().map(Scalar::from))
+                .ok(),
+        };
+        scalar.map(Expression::literal).ok_or_else(invalid)
+    }
+
+    // a column reference, whose nested fields are separated by dots
+    fn parse_column(&mut self, name: String) -> DeltaResult<Expression> {
+        let mut path = vec![name];
+        while self.consume_symbol(".") {
+            match self.next()? {
+                Token::Word(name) | Token::QuotedIdentifier(name) => path.push(name),
+                token => return Err(self.error(format!("unexpected {token:?}"))),
+            }
+        }
+        Ok(Expression::column(path))
+    }
+
+    fn parse_function(&mut self, name: &str) -> DeltaResult<Expression> {
+        if name.eq_ignore_ascii_case("cast") {
+            return self.parse_cast();
+        }
+        if !name.eq_ignore_ascii_case("coalesce") {
+            return Err(self.unsupported(format!("unsupported function {name}")));
+        }
+        let args = self.parse_list()?;
+        Ok(args
+            .into_iter()
+            .rev()
+            .reduce(|rest, arg| arg.coalesce(rest))
+            .expect("parse_list returns at least one expression"))
+    }
+
+    // `CAST(expr AS type)`, whose types are checked by `resolve`
+    fn parse_cast(&mut self) -> DeltaResult<Expression> {
+        self.expect_symbol("(")?;
+        let expr = self.parse_or()?;
+        self.expect_keyword("AS")?;
+        let data_type = match self.next()? {
+            Token::Word(word) if word.eq_ignore_ascii_case("DATE") => DataType::DATE,
+            Token::Word(word) if word.eq_ignore_ascii_case("TIMESTAMP") => DataType::TIMESTAMP,
+            Token::Word(word) => {
+                return Err(self.unsupported(format!("unsupported cast to {word}")))
+            }
+            token => return Err(self.error(format!("unexpected {token:?}"))),
+        };
+        self.expect_symbol(")")?;
+        Ok(expr.cast(data_type))
+    }
+}
+
+// Resolve the column references of `expr` against `schema`, and convert the literals that are
+// combined with values of another type to that type. Returns the resolved expression and its
+// type, unless it is a null literal of unknown type.
+fn resolve(expr: Expression, schema: &StructType) -> DeltaResult<(Expression, Option<DataType>)> {
+    use BinaryOperator::*;
+    match expr {
+        Expression::Literal(Scalar::Null(_)) => Ok((expr, None)),
+        Expression::Literal(ref scalar) => {
+            let data_type = scalar.data_type();
+            Ok((expr, Some(data_type)))
+        }
+        Expression::Column(name) => {
+            let (name, data_type) = resolve_column(&name, schema)?;
+            Ok((Expression::Column(name), Some(data_type)))
+        }
+        Expression::Unary(UnaryExpression { op, expr }) => {
+            let (expr, _) = resolve(*expr, schema)?;
+            Ok((Expression::unary(op, expr), Some(DataType::BOOLEAN)))
+        }
+        Expression::Variadic(VariadicExpression { op, exprs }) => {
+            let exprs: Vec<_> = exprs
+                .into_iter()
+                .map(|expr| Ok(resolve(expr, schema)?.0))
+                .collect::<DeltaResult<_>>()?;
+            Ok((Expression::variadic(op, exprs), Some(DataType::BOOLEAN)))
+        }
+        Expression::Binary(BinaryExpression { op, left, right }) => {
+            let (left, right, operand_type) =
+                resolve_operands(op, resolve(*left, schema)?, resolve(*right, schema)?)?;
+            let data_type = match op {
+                Plus | Minus | Multiply | Divide | Coalesce => operand_type,
+                LessThan | LessThanOrEqual | GreaterThan | GreaterThanOrEqual | Equal
+                | NotEqual | Distinct | In | NotIn => Some(DataType::BOOLEAN),
+            };
+            Ok((Expression::binary(op, left, right), data_type))
+        }
+        Expression::Cast(CastExpression { expr, data_type }) => {
+            let (expr, expr_type) = resolve(*expr, schema)?;
+            let expr = match (expr, expr_type) {
+                // like `DATE '...'`, null and string literals are converted when parsing
+                (expr @ Expression::Literal(Scalar::Null(_) | Scalar::String(_)), _) => {
+                    coerce(expr, &data_type)?
+                }
+                (expr, Some(expr_type)) if expr_type == data_type => expr,
+                (expr, Some(expr_type)) if can_cast(&expr_type, &data_type) => {
+                    expr.cast(data_type.clone())
+                }
+                (expr, _) => {
+                    return Err(Error::unsupported(format!(
+                        "Unsupported SQL expression: cannot cast {expr} to {data_type}"
+                    )))
+                }
+            };
+            Ok((expr, Some(data_type)))
+        }
+        Expression::Struct(_) | Expression::RowIndex => Err(Error::internal_error(format!(
+            "Unexpected expression in parsed SQL: {expr}"
+        ))),
+    }
+}
+
+// Convert the operands of `op` to a common type the way Spark does, and return them with that
+// type. Numbers are widened to the wider of their types, except that division is always of
+// doubles, and the literals that are combined with values of another type are converted to that
+// type. Returns [`Error::Unsupported`] for the operands whose conversions aren't implemented, such
+// as decimal arithmetic, whose result types Spark derives from the precisions of the operands.
+fn resolve_operands(
+    op: BinaryOperator,
+    (left, left_type): (Expression, Option<DataType>),
+    (right, right_type): (Expression, Option<DataType>),
+) -> DeltaResult<(Expression, Expression, Option<DataType>)> {
+    use BinaryOperator::*;
+    let unsupported = || {
+        let type_name = |data_type: &Option<DataType>| match data_type {
+            Some(data_type) => data_type.to_string(),
+            None => "null".to_string(),
+        };
+        Error::unsupported(format!(
+            "Unsupported SQL expression: {op} of {} and {}",
+            type_name(&left_type),
+            type_name(&right_type)
+        ))
+    };
+    let is_literal = |expr: &Expression, of: fn(&Scalar) -> bool| matches!(expr, Expression::Literal(scalar) if of(scalar));
+    let is_string = |scalar: &Scalar| matches!(scalar, Scalar::String(_));
+    let is_integer = |scalar: &Scalar| {
+        matches!(
+            scalar,
+            Scalar::Byte(_) | Scalar::Short(_) | Scalar::Integer(_) | Scalar::Long(_)
+        )
+    };
+    let is_comparison = !matches!(op, Plus | Minus | Multiply | Divide | Coalesce);
+    let data_type = match (&left_type, &right_type) {
+        (None, None) => None,
+        (Some(data_type), None) | (None, Some(data_type)) => Some(data_type.clone()),
+        (Some(left_type), Some(right_type)) if left_type == right_type => Some(left_type.clone()),
+        (Some(left_type), Some(right_type)) => {
+            match (numeric_rank(left_type), numeric_rank(right_type)) {
+                (Some(left_rank), Some(right_rank)) if left_rank < right_rank => {
+                    Some(right_type.clone())
+                }
+                (Some(_), Some(_)) => Some(left_type.clone()),
+                _ if !is_comparison => return Err(unsupported()),
+                // like `DATE '...'`, string literals are converted to the type of the values
+                // they are compared with
+                _ if is_literal(&right, is_string) => Some(left_type.clone()),
+                _ if is_literal(&left, is_string) => Some(right_type.clone()),
+                // integers are compared exactly with decimals, whatever their precision
+                (None, Some(_)) if is_decimal(left_type) && is_literal(&right, is_integer) => {
+                    Some(left_type.clone())
+                }
+                (Some(_), None) if is_decimal(right_type) && is_literal(&left, is_integer) => {
+                    Some(right_type.clone())
+                }
+                _ => return Err(unsupported()),
+            }
+        }
+    };
+    let Some(mut data_type) = data_type else {
+        return Ok((left, right, None));
+    };
+    match op {
+        Plus | Minus | Multiply if numeric_rank(&data_type).is_none() => return Err(unsupported()),
+        Divide if numeric_rank(&data_type).is_none() => return Err(unsupported()),
+        Divide => data_type = DataType::DOUBLE,
+        _ => {}
+    }
+    let left = convert(left, left_type, &data_type)?;
+    let right = convert(right, right_type, &data_type)?;
+    Ok((left, right, Some(data_type)))
+}
+
+// The position of a numeric type among the types that numbers are widened to, narrowest first.
+// Decimals aren't widened, like in Spark, whose decimal types depend on the values they hold.
+fn numeric_rank(data_type: &DataType) -> Option<usize> {
+    [
+        DataType::BYTE,
+        DataType::SHORT,
+        DataType::INTEGER,
+        DataType::LONG,
+        DataType::FLOAT,
+        DataType::DOUBLE,
+    ]
+    .iter()
+    .position(|numeric_type| numeric_type == data_type)
+}
+
+fn is_decimal(data_type: &DataType) -> bool {
+    matches!(data_type, DataType::Primitive(PrimitiveType::Decimal(..)))
+}
+
+// Convert `expr`, whose type is `expr_type`, to `data_type`: literals are converted when parsing
+// and other expressions are cast
+fn convert(
+    expr: Expression,
+    expr_type: Option<DataType>,
+    data_type: &DataType,
+) -> DeltaResult<Expression> {
+    match expr {
+        Expression::Literal(_) => coerce(expr, data_type),
+        expr if expr_type.as_ref() == Some(data_type) => Ok(expr),
+        expr => Ok(expr.cast(data_type.clone())),
+    }
+}
+
+// Whether values of type `from` can be cast to `to` the way Spark casts them
+fn can_cast(from: &DataType, to: &DataType) -> bool {
+    use PrimitiveType::*;
+    matches!(
+        (from, to),
+        (
+            DataType::Primitive(Date | Timestamp | TimestampNtz),
+            DataType::Primitive(Date)
+        ) | (
+            DataType::Primitive(Date | Timestamp),
+            DataType::Primitive(Timestamp)
+        )
+    )
+}
+
+// The column of `schema` that `name` refers to, and its type. Names match the fields of the schema
+// exactly or, failing that, case-insensitively.
+fn resolve_column(name: &ColumnName, schema: &StructType) -> DeltaResult<(ColumnName, DataType)> {
+    let mut path = vec![];
+    let mut struct_type = schema;
+    let mut data_type: Option<&DataType> = None;
+    for part in name.iter() {
+        if let Some(DataType::Struct(nested)) = data_type {
+            struct_type = nested.as_ref();
+        } else if data_type.is_some() {
+            return Err(Error::MissingColumn(format!(
+                "{name} is not a column: {} is not a struct",
+                ColumnName::new(&path)
+            )));
+        }
+        let field = struct_type
+            .field(part)
+            .or_else(|| {
+                struct_type
+                    .fields()
+                    .find(|field| field.name().eq_ignore_ascii_case(part))
+            })
+            .ok_or_else(|| Error::MissingColumn(format!("{name} is not a column")))?;
+        path.push(field.name().clone());
+        data_type = Some(field.data_type());
+    }
+    let data_type =
+        data_type.ok_or_else(|| Error::MissingColumn("Empty column name".to_string()))?;
+    Ok((ColumnName::new(path), data_type.clone()))
+}
+
+// Convert a literal to `data_type`, if it isn't of that type already. Other expressions are
+// returned as is.
+fn coerce(expr: Expression, data_type: &DataType) -> DeltaResult<Expression> {
+    use Scalar::*;
+    let Expression::Literal(scalar) = expr else {
+        return Ok(expr);
+    };
+    if &scalar.data_type() == data_type {
+        return Ok(Expression::Literal(scalar));
+    }
+    let converted = match (&scalar, data_type) {
+        (Null(_), _) => Null(data_type.clone()),
+        (String(value), DataType::Primitive(primitive)) => primitive.parse_scalar(value)?,
+        (
+            Byte(_) | Short(_) | Integer(_) | Long(_),
+            &DataType::Primitive(PrimitiveType::Decimal(precision, scale)),
+        ) => {
+            let value = scalar
+                .to_string()
+                .parse::<i128>()?
+                .checked_mul(10i128.pow(scale.into()))
+                .filter(|value| value.unsigned_abs() < 10u128.pow(precision.into()))
+                .ok_or_else(|| Error::generic(format!("{scalar} doesn't fit in {data_type}")))?;
+            Decimal(value, precision, scale)
+        }
+        (
+            Byte(_) | Short(_) | Integer(_) | Long(_) | Float(_) | Double(_),
+            DataType::Primitive(primitive),
+        ) => primitive.parse_scalar(&scalar.to_string())?,
+        _ => {
+            return Err(Error::generic(format!(
+                "Cannot convert the literal {scalar} to {data_type}"
+            )))
+        }
+    };
+    Ok(Expression::Literal(converted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expressions::column_expr;
+    use crate::schema::StructField;
+
+    fn schema() -> StructType {
+        StructType::new([
+            StructField::nullable("a", DataType::INTEGER),
+            StructField::nullable("B", DataType::LONG),
+            StructField::nullable("c d", DataType::STRING),
+            StructField::nullable("day", DataType::DATE),
+            StructField::nullable("ts", DataType::TIMESTAMP),
+            StructField::nullable("price", DataType::decimal(10, 2).unwrap()),
+            StructField::nullable(
+                "s",
+                StructType::new([StructField::nullable("x", DataType::DOUBLE)]),
+            ),
+        ])
+    }
+
+    #[test]
+    fn test_parse_sql_expression() {
+        let schema = schema();
+        let parse = |sql, data_type| parse_sql_expression(sql, &schema, data_type).unwrap();
+        assert_eq!(
+            parse("a + 1", &DataType::INTEGER),
+            column_expr!("a") + Expression::literal(1)
+        );
+        // literals take the type of the values they are combined with
+        assert_eq!(
+            parse("b * -2 - 1", &DataType::LONG),
+            column_expr!("B") * Expression::literal(-2i64) - Expression::literal(1i64)
+        );
+        assert_eq!(
+            parse("s.X / 2", &DataType::DOUBLE),
+            column_expr!("s.x") / Expression::literal(2.0)
+        );
+        assert_eq!(
+            parse("coalesce(`c d`, 'none')", &DataType::STRING),
+            Expression::column(["c d"]).coalesce(Expression::literal("none"))
+        );
+        assert_eq!(parse("42", &DataType::LONG), Expression::literal(42i64));
+        // negating the minimum value of a type widens it instead of overflowing
+        assert_eq!(
+            parse("-(-2147483648)", &DataType::LONG),
+            Expression::literal(2147483648i64)
+        );
+        assert_eq!(
+            parse("- -2147483648", &DataType::LONG),
+            Expression::literal(2147483648i64)
+        );
+        assert!(matches!(
+            parse("NULL", &DataType::DATE),
+            Expression::Literal(Scalar::Null(DataType::DATE))
+        ));
+        assert_eq!(
+            parse(
+                "a > 0 AND NOT (day = '2024-01-01' OR day IS NULL)",
+                &DataType::BOOLEAN
+            ),
+            Expression::and_from([
+                column_expr!("a").gt(Expression::literal(0)),
+                !Expression::or_from([
+                    column_expr!("day").eq(Expression::literal(Scalar::Date(19723))),
+                    column_expr!("day").is_null(),
+                ]),
+            ])
+        );
+        assert_eq!(
+            parse("a NOT IN (1, 2) or B between 1 and 10L", &DataType::BOOLEAN),
+            Expression::or_from([
+                !Expression::or_from([
+                    column_expr!("a").eq(Expression::literal(1)),
+                    column_expr!("a").eq(Expression::literal(2)),
+                ]),
+                Expression::and_from([
+                    column_expr!("B").ge(Expression::literal(1i64)),
+                    column_expr!("B").le(Expression::literal(10i64)),
+                ]),
+            ])
+        );
+        assert_eq!(
+            parse("day <=> DATE '1970-01-02'", &DataType::BOOLEAN),
+            !column_expr!("day").distinct(Expression::literal(Scalar::Date(1)))
+        );
+        assert_eq!(
+            parse(r#"`c d` != "it\'s""#, &DataType::BOOLEAN),
+            Expression::column(["c d"]).ne(Expression::literal("it's"))
+        );
+        assert_eq!(
+            parse("CAST(ts AS DATE)", &DataType::DATE),
+            column_expr!("ts").cast(DataType::DATE)
+        );
+        assert_eq!(
+            parse("cast(day as timestamp) < ts", &DataType::BOOLEAN),
+            column_expr!("day")
+                .cast(DataType::TIMESTAMP)
+                .lt(column_expr!("ts"))
+        );
+        assert_eq!(
+            parse("CAST('2024-01-01' AS DATE)", &DataType::DATE),
+            Expression::literal(Scalar::Date(19723))
+        );
+        // numbers are widened and divided as doubles, like in Spark
+        assert_eq!(
+            parse("a + b", &DataType::LONG),
+            column_expr!("a").cast(DataType::LONG) + column_expr!("B")
+        );
+        assert_eq!(
+            parse("a / 2", &DataType::DOUBLE),
+            column_expr!("a").cast(DataType::DOUBLE) / Expression::literal(2.0)
+        );
+        assert_eq!(
+            parse("-a < 3000000000", &DataType::BOOLEAN),
+            (Expression::literal(0) - column_expr!("a"))
+                .cast(DataType::LONG)
+                .lt(Expression::literal(3000000000i64))
+        );
+        assert_eq!(
+            parse("price >= 0", &DataType::BOOLEAN),
+            column_expr!("price").ge(Expression::literal(Scalar::Decimal(0, 10, 2)))
+        );
+    }
+
+    #[test]
+    fn test_parse_invalid_sql_expression() {
+        let schema = schema();
+        let parse = |sql| parse_sql_expression(sql, &schema, &DataType::INTEGER);
+        for sql in ["a +", "(a", "a b", "'a", "1.2.3", "a NOT 1", "a = = 1"] {
+            assert!(matches!(parse(sql), Err(Error::Generic(_))), "{sql}");
+        }
+        assert!(matches!(parse("abs(a)"), Err(Error::Unsupported(_))));
+        assert!(matches!(
+            parse("CAST(a AS INT)"),
+            Err(Error::Unsupported(_))
+        ));
+        assert!(matches!(
+            parse("CAST(a AS DATE)"),
+            Err(Error::Unsupported(_))
+        ));
+        for sql in [
+            "price + 1",
+            "price * price",
+            "a + 'x'",
+            "`c d` / 2",
+            "day = 1",
+        ] {
+            assert!(matches!(parse(sql), Err(Error::Unsupported(_))), "{sql}");
+        }
+        assert!(matches!(parse("e"), Err(Error::MissingColumn(_))));
+        assert!(matches!(parse("a.x"), Err(Error::MissingColumn(_))));
+        assert!(matches!(parse("a = 'x'"), Err(Error::ParseError(..))));
+        // the expression must be of the requested type
+        assert!(matches!(parse("b"), Err(Error::Generic(_))));
+        assert!(matches!(parse("a > 1"), Err(Error::Generic(_))));
+    }
+}
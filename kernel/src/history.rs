@@ -0,0 +1,874 @@
+//! The commit history of tables, and the resolution of table versions from timestamps for
+//! timestamp-based time travel.
+//!
+//! The timestamp of a commit is its in-commit timestamp if [in-commit timestamps] were enabled when
+//! it was made, and the modification time of its file otherwise. File modification times are not
+//! guaranteed to increase with versions, so they are adjusted to be monotonic: a commit is never
+//! considered older than the commit before it.
+//!
+//! [in-commit timestamps]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#in-commit-timestamps
+
+use std::collections::HashMap;
+use std::ops::{Bound, RangeBounds};
+use std::sync::{Arc, LazyLock};
+
+use itertools::Itertools;
+use url::Url;
+
+use crate::actions::COMMIT_INFO_NAME;
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
+use crate::log_segment::list_log_files;
+use crate::path::ParsedLogPath;
+use crate::schema::{
+    column_name, ColumnName, ColumnNamesAndTypes, DataType, MapType, SchemaRef, StructField,
+    StructType,
+};
+use crate::snapshot::Snapshot;
+use crate::utils::require;
+use crate::{DeltaResult, Engine, Error, FileMeta, Version};
+
+/// How to resolve a timestamp that doesn't match the timestamp of a commit exactly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampSearchMode {
+    /// Resolve to the latest commit made at or before the timestamp.
+    AtOrBefore,
+    /// Resolve to the earliest commit made at or after the timestamp.
+    AtOrAfter,
+}
+
+/// Resolve `timestamp` (in milliseconds since the Unix epoch) to the version of a commit of the
+/// table, among the commits up to the version of `snapshot`. The in-commit timestamp settings of
+/// `snapshot` determine which commits have in-commit timestamps.
+pub(crate) fn timestamp_to_version(
+    snapshot: &Snapshot,
+    engine: &dyn Engine,
+    timestamp: i64,
+    mode: TimestampSearchMode,
+) -> DeltaResult<Version> {
+    CommitTimestamps::try_new(snapshot, engine)?.find_version(timestamp, mode)
+}
+
+/// The timestamps of the commits of a table. The timestamps of commits without in-commit
+/// timestamps are computed upfront from the file listing, while in-commit timestamps are only read
+/// when needed.
+///
+/// The log is first listed from the latest checkpoint of the snapshot, and earlier commits are only
+/// listed when a timestamp precedes the listed commits, doubling the number of listed commits
+/// every time. The file modification times are adjusted to be monotonic from the earliest listed
+/// commit.
+pub(crate) struct CommitTimestamps<'a> {
+    engine: &'a dyn Engine,
+    log_root: Url,
+    /// The version from which the commits have in-commit timestamps, if they are enabled
+    in_commit_timestamps_version: Option<Version>,
+    /// The listed commit files, in ascending order of version
+    commits: Vec<ParsedLogPath>,
+    /// Whether the earliest listed commit is the earliest commit retained in the log
+    listed_earliest_commit: bool,
+    /// The monotonic file modification times of the commits without in-commit timestamps, which
+    /// precede the commits with in-commit timestamps
+    file_timestamps: Vec<i64>,
+}
+
+impl<'a> CommitTimestamps<'a> {
+    /// List the commits of the table from the latest checkpoint up to the version of `snapshot`.
+    pub(crate) fn try_new(snapshot: &Snapshot, engine: &'a dyn Engine) -> DeltaResult<Self> {
+        let fs_client = engine.get_file_system_client();
+        let log_segment = snapshot.log_segment();
+        let start_version = log_segment
+            .checkpoint_parts
+            .first()
+            .map_or(0, |checkpoint| checkpoint.version);
+        let commits: Vec<_> = list_log_files(
+            fs_client.as_ref(),
+            &log_segment.log_root,
+            start_version,
+            snapshot.version(),
+        )?
+        .filter_ok(|path| path.is_commit())
+        .try_collect()?;
+        if commits.is_empty() {
+            return Err(Error::generic("No commits found in the table log"));
+        }
+
+        let table_configuration = snapshot.table_configuration();
+        let in_commit_timestamps_version = table_configuration
+            .is_in_commit_timestamps_enabled()
+            .then(|| {
+                table_configuration
+                    .table_properties()
+                    .in_commit_timestamp_enablement_version
+                    .unwrap_or(0)
+            });
+        let mut commit_timestamps = CommitTimestamps {
+            engine,
+            log_root: log_segment.log_root.clone(),
+            in_commit_timestamps_version,
+            commits,
+            listed_earliest_commit: start_version == 0,
+            file_timestamps: vec![],
+        };
+        commit_timestamps.compute_file_timestamps();
+        Ok(commit_timestamps)
+    }
+
+    // List the commits that precede the listed ones, as many as there are listed versions. The
+    // commits before a gap in the log are no longer available, so the listing stops there.
+    fn list_earlier_commits(&mut self) -> DeltaResult<()> {
+        let first_version = self.commits[0].version;
+        let last_version = self.commits[self.commits.len() - 1].version;
+        let start_version = first_version.saturating_sub(last_version - first_version + 1);
+        let fs_client = self.engine.get_file_system_client();
+        let mut earlier_commits: Vec<_> = list_log_files(
+            fs_client.as_ref(),
+            &self.log_root,
+            start_version,
+            first_version - 1,
+        )?
+        .filter_ok(|path| path.is_commit())
+        .try_collect()?;
+        let mut next_version = first_version;
+        let retained = earlier_commits
+            .iter()
+            .rev()
+            .take_while(|commit| {
+                let is_contiguous = commit.version + 1 == next_version;
+                if is_contiguous {
+                    next_version = commit.version;
+                }
+                is_contiguous
+            })
+            .count();
+        self.listed_earliest_commit = start_version == 0 || next_version > start_version;
+        let gap = earlier_commits.len() - retained;
+        self.commits.splice(0..0, earlier_commits.drain(gap..));
+        self.compute_file_timestamps();
+        Ok(())
+    }
+
+    fn compute_file_timestamps(&mut self) {
+        self.file_timestamps.clear();
+        for commit in &self.commits {
+            if self
+                .in_commit_timestamps_version
+                .is_some_and(|version| commit.version >= version)
+            {
+                break;
+            }
+            let timestamp = match self.file_timestamps.last() {
+                Some(&previous) if commit.location.last_modified <= previous => previous + 1,
+                _ => commit.location.last_modified,
+            };
+            self.file_timestamps.push(timestamp);
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        self.commits.len()
+    }
+
+    pub(crate) fn version(&self, index: usize) -> Version {
+        self.commits[index].version
+    }
+
+    /// The timestamp of the commit at `index`
+    pub(crate) fn timestamp(&self, index: usize) -> DeltaResult<i64> {
+        match self.file_timestamps.get(index) {
+            Some(&timestamp) => Ok(timestamp),
+            None => {
+                let commit = &self.commits[index];
+                read_in_commit_timestamp(self.engine, &commit.location)?.ok_or_else(|| {
+                    Error::generic(format!(
+                        "In-commit timestamp missing from commit {}",
+                        commit.version
+                    ))
+                })
+            }
+        }
+    }
+
+    /// Find the version of the commit that matches `timestamp` according to `mode`. Returns a
+    /// [`Error::TimestampOutOfRange`] if the timestamp is before the earliest commit (when
+    /// searching at or before it) or after the latest commit (when searching at or after it).
+    pub(crate) fn find_version(
+        &mut self,
+        timestamp: i64,
+        mode: TimestampSearchMode,
+    ) -> DeltaResult<Version> {
+        // the matching commit is listed once the earliest listed commit is before the timestamp
+        // (or at it, when searching at or before it)
+        while !self.listed_earliest_commit {
+            let earliest_timestamp = self.timestamp(0)?;
+            let is_listed = match mode {
+                TimestampSearchMode::AtOrBefore => earliest_timestamp <= timestamp,
+                TimestampSearchMode::AtOrAfter => earliest_timestamp < timestamp,
+            };
+            if is_listed {
+                break;
+            }
+            self.list_earlier_commits()?;
+        }
+        match mode {
+            TimestampSearchMode::AtOrBefore => {
+                // the latest commit at or before the timestamp precedes the first one after it
+                let after =
+                    self.partition_point(|commit_timestamp| commit_timestamp <= timestamp)?;
+                match after.checked_sub(1) {
+                    Some(index) => Ok(self.version(index)),
+                    None => Err(Error::timestamp_out_of_range(format!(
+                        "{timestamp} is before the earliest available commit (version {} at {})",
+                        self.version(0),
+                        self.timestamp(0)?
+                    ))),
+                }
+            }
+            TimestampSearchMode::AtOrAfter => {
+                let index =
+                    self.partition_point(|commit_timestamp| commit_timestamp < timestamp)?;
+                if index < self.len() {
+                    return Ok(self.version(index));
+                }
+                let last = self.len() - 1;
+                Err(Error::timestamp_out_of_range(format!(
+                    "{timestamp} is after the latest commit (version {} at {})",
+                    self.version(last),
+                    self.timestamp(last)?
+                )))
+            }
+        }
+    }
+
+    // The index of the first commit whose timestamp doesn't satisfy `pred`, assuming that the
+    // timestamps of all the commits before it do
+    fn partition_point(&self, pred: impl Fn(i64) -> bool) -> DeltaResult<usize> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if pred(self.timestamp(mid)?) {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+        Ok(low)
+    }
+}
+
+/// A commit in the history of a table, described by its `commitInfo` action. The fields that the
+/// writer of the commit didn't record are `None` (or empty).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistoryEntry {
+    /// The version of the commit.
+    pub version: Version,
+    /// The timestamp of the commit, in milliseconds since the Unix epoch. This is its in-commit
+    /// timestamp if it has one, and the modification time of its file otherwise, adjusted so that
+    /// a commit is never older than the commit before it.
+    pub timestamp: i64,
+    /// The in-commit timestamp of the commit, if it was made with in-commit timestamps enabled.
+    pub in_commit_timestamp: Option<i64>,
+    /// The operation performed by the commit, e.g. `WRITE` or `DELETE`.
+    pub operation: Option<String>,
+    /// The parameters of the operation, e.g. the predicate of a `DELETE`.
+    pub operation_parameters: HashMap<String, String>,
+    /// The metrics of the operation, e.g. the number of files it added.
+    pub operation_metrics: HashMap<String, String>,
+    /// The engine that made the commit.
+    pub engine_info: Option<String>,
+    /// Whether the commit only added new data files, without reading or removing existing ones.
+    pub is_blind_append: Option<bool>,
+}
+
+/// Read the history of the table at `table_root`, for the commits with versions in `versions`.
+/// The entries are ordered from the latest commit to the earliest one. Commits that are no longer
+/// in the log (or that precede a gap in it) are not part of the history, so the history stops at
+/// the earliest retained commit.
+pub(crate) fn read_history(
+    engine: &dyn Engine,
+    table_root: &Url,
+    versions: impl RangeBounds<Version>,
+) -> DeltaResult<Vec<HistoryEntry>> {
+    let start_version = match versions.start_bound() {
+        Bound::Included(&version) => version,
+        Bound::Excluded(&version) => version + 1,
+        Bound::Unbounded => 0,
+    };
+    let end_version = match versions.end_bound() {
+        Bound::Included(&version) => Some(version),
+        Bound::Excluded(&0) => return Ok(vec![]),
+        Bound::Excluded(&version) => Some(version - 1),
+        Bound::Unbounded => None,
+    };
+    let fs_client = engine.get_file_system_client();
+    let log_root = table_root.join("_delta_log/")?;
+    let commits: Vec<_> =
+        list_log_files(fs_client.as_ref(), &log_root, start_version, end_version)?
+            .filter_ok(|path| path.is_commit())
+            .try_collect()?;
+    let retained = commits
+        .windows(2)
+        .rposition(|pair| pair[1].version != pair[0].version + 1)
+        .map_or(0, |gap| gap + 1);
+
+    let commits = &commits[retained..];
+    let commit_infos = read_commit_infos(engine, commits)?;
+
+    let mut entries: Vec<HistoryEntry> = Vec::with_capacity(commits.len());
+    for (commit, commit_info) in commits.iter().zip(commit_infos) {
+        let timestamp = match (commit_info.in_commit_timestamp, entries.last()) {
+            (Some(in_commit_timestamp), _) => in_commit_timestamp,
+            (None, Some(HistoryEntry { timestamp, .. }))
+                if commit.location.last_modified <= *timestamp =>
+            {
+                timestamp + 1
+            }
+            (None, _) => commit.location.last_modified,
+        };
+        entries.push(HistoryEntry {
+            version: commit.version,
+            timestamp,
+            in_commit_timestamp: commit_info.in_commit_timestamp,
+            operation: commit_info.operation,
+            operation_parameters: commit_info.operation_parameters,
+            operation_metrics: commit_info.operation_metrics,
+            engine_info: commit_info.engine_info,
+            is_blind_append: commit_info.is_blind_append,
+        });
+    }
+    entries.reverse();
+    Ok(entries)
+}
+
+// Read the first commitInfo action of every commit. The commits are read together, and their
+// commitInfo actions are matched with them in order if there is one per commit, as writers
+// record them. Otherwise, or if the read fails, e.g. because a writer recorded operation
+// parameters that aren't strings, every commit is read on its own, without its operation
+// parameters and metrics if they can't be read.
+fn read_commit_infos(
+    engine: &dyn Engine,
+    commits: &[ParsedLogPath],
+) -> DeltaResult<Vec<CommitInfoFields>> {
+    let files: Vec<_> = commits
+        .iter()
+        .map(|commit| commit.location.clone())
+        .collect();
+    if let Ok(commit_infos) = read_all_commit_infos(engine, &files, true) {
+        if commit_infos.len() == commits.len() {
+            return Ok(commit_infos);
+        }
+    }
+    files
+        .iter()
+        .map(|file| {
+            let file = std::slice::from_ref(file);
+            let commit_infos = read_all_commit_infos(engine, file, true)
+                .or_else(|_| read_all_commit_infos(engine, file, false))?;
+            Ok(commit_infos.into_iter().next().unwrap_or_default())
+        })
+        .collect()
+}
+
+// Read the commitInfo actions of the files, with their operation parameters and metrics if
+// `read_operation_details` is true
+fn read_all_commit_infos(
+    engine: &dyn Engine,
+    files: &[FileMeta],
+    read_operation_details: bool,
+) -> DeltaResult<Vec<CommitInfoFields>> {
+    let schema = if read_operation_details {
+        HISTORY_READ_SCHEMA.clone()
+    } else {
+        HISTORY_READ_SCHEMA_WITHOUT_OPERATION_DETAILS.clone()
+    };
+    let batches = engine
+        .get_json_handler()
+        .read_json_files(files, schema, None)?;
+    let mut visitor = HistoryVisitor {
+        read_operation_details,
+        commit_infos: vec![],
+    };
+    for batch in batches {
+        visitor.visit_rows_of(batch?.as_ref())?;
+    }
+    Ok(visitor.commit_infos)
+}
+
+// The commitInfo fields read for the history of a table. Kernel's `CommitInfo` doesn't have all of
+// them, since kernel doesn't write them. The operation parameters and metrics come last, so that
+// they can be left out.
+fn history_read_schema(read_operation_details: bool) -> SchemaRef {
+    let string_map = MapType::new(DataType::STRING, DataType::STRING, true);
+    let operation_details = [
+        StructField::nullable("operationParameters", string_map.clone()),
+        StructField::nullable("operationMetrics", string_map),
+    ];
+    let fields = [
+        StructField::nullable("timestamp", DataType::LONG),
+        StructField::nullable("inCommitTimestamp", DataType::LONG),
+        StructField::nullable("operation", DataType::STRING),
+        StructField::nullable("engineInfo", DataType::STRING),
+        StructField::nullable("isBlindAppend", DataType::BOOLEAN),
+    ]
+    .into_iter()
+    .chain(
+        operation_details
+            .into_iter()
+            .filter(|_| read_operation_details),
+    );
+    Arc::new(StructType::new([StructField::nullable(
+        COMMIT_INFO_NAME,
+        StructType::new(fields),
+    )]))
+}
+
+static HISTORY_READ_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| history_read_schema(true));
+static HISTORY_READ_SCHEMA_WITHOUT_OPERATION_DETAILS: LazyLock<SchemaRef> =
+    LazyLock::new(|| history_read_schema(false));
+
+// The fields of a commitInfo action
+#[derive(Debug, Default)]
+struct CommitInfoFields {
+    in_commit_timestamp: Option<i64>,
+    operation: Option<String>,
+    operation_parameters: HashMap<String, String>,
+    operation_metrics: HashMap<String, String>,
+    engine_info: Option<String>,
+    is_blind_append: Option<bool>,
+}
+
+// Extracts the fields of the commitInfo actions, with the operation parameters and metrics if
+// `read_operation_details` is true
+#[derive(Debug)]
+struct HistoryVisitor {
+    read_operation_details: bool,
+    commit_infos: Vec<CommitInfoFields>,
+}
+
+impl RowVisitor for HistoryVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        // NOTE: The order of the names and types is based on [`history_read_schema`]
+        fn names_and_types(read_operation_details: bool) -> ColumnNamesAndTypes {
+            let string_map: DataType =
+                MapType::new(DataType::STRING, DataType::STRING, true).into();
+            let operation_details = [
+                (
+                    string_map.clone(),
+                    column_name!("commitInfo.operationParameters"),
+                ),
+                (string_map, column_name!("commitInfo.operationMetrics")),
+            ];
+            let types_and_names = vec![
+                (DataType::LONG, column_name!("commitInfo.timestamp")),
+                (DataType::LONG, column_name!("commitInfo.inCommitTimestamp")),
+                (DataType::STRING, column_name!("commitInfo.operation")),
+                (DataType::STRING, column_name!("commitInfo.engineInfo")),
+                (DataType::BOOLEAN, column_name!("commitInfo.isBlindAppend")),
+            ];
+            let (types, names): (Vec<_>, Vec<_>) = types_and_names
+                .into_iter()
+                .chain(
+                    operation_details
+                        .into_iter()
+                        .filter(|_| read_operation_details),
+                )
+                .unzip();
+            (names, types).into()
+        }
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| names_and_types(true));
+        static NAMES_AND_TYPES_WITHOUT_OPERATION_DETAILS: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| names_and_types(false));
+        if self.read_operation_details {
+            NAMES_AND_TYPES.as_ref()
+        } else {
+            NAMES_AND_TYPES_WITHOUT_OPERATION_DETAILS.as_ref()
+        }
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        let expected_getters = if self.read_operation_details { 7 } else { 5 };
+        require!(
+            getters.len() == expected_getters,
+            Error::InternalError(format!(
+                "Wrong number of HistoryVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        for i in 0..row_count {
+            // every writer records the timestamp or the operation of its commits
+            let timestamp: Option<i64> = getters[0].get_opt(i, "commitInfo.timestamp")?;
+            let operation = getters[2].get_opt(i, "commitInfo.operation")?;
+            if timestamp.is_none() && operation.is_none() {
+                continue;
+            }
+            let mut commit_info = CommitInfoFields {
+                in_commit_timestamp: getters[1].get_opt(i, "commitInfo.inCommitTimestamp")?,
+                operation,
+                engine_info: getters[3].get_opt(i, "commitInfo.engineInfo")?,
+                is_blind_append: getters[4].get_opt(i, "commitInfo.isBlindAppend")?,
+                ..Default::default()
+            };
+            if self.read_operation_details {
+                commit_info.operation_parameters = getters[5]
+                    .get_opt(i, "commitInfo.operationParameters")?
+                    .unwrap_or_default();
+                commit_info.operation_metrics = getters[6]
+                    .get_opt(i, "commitInfo.operationMetrics")?
+                    .unwrap_or_default();
+            }
+            self.commit_infos.push(commit_info);
+        }
+        Ok(())
+    }
+}
+
+/// The `commitInfo` field of the log schema, with only the in-commit timestamp. Engines may write
+/// other `commitInfo` fields with types that don't match kernel's [`CommitInfo`], so reading the
+/// in-commit timestamp must not read them.
+///
+/// [`CommitInfo`]: crate::actions::CommitInfo
+pub(crate) fn in_commit_timestamp_field() -> StructField {
+    StructField::nullable(
+        COMMIT_INFO_NAME,
+        StructType::new([StructField::nullable("inCommitTimestamp", DataType::LONG)]),
+    )
+}
+
+/// Read the in-commit timestamp of a commit, from its first `commitInfo` action.
+pub(crate) fn read_in_commit_timestamp(
+    engine: &dyn Engine,
+    commit: &FileMeta,
+) -> DeltaResult<Option<i64>> {
+    let batches = engine.get_json_handler().read_json_files(
+        std::slice::from_ref(commit),
+        Arc::new(StructType::new([in_commit_timestamp_field()])),
+        None,
+    )?;
+    let mut visitor = InCommitTimestampVisitor::default();
+    for batch in batches {
+        visitor.visit_rows_of(batch?.as_ref())?;
+        if visitor.in_commit_timestamp.is_some() {
+            break;
+        }
+    }
+    Ok(visitor.in_commit_timestamp)
+}
+
+#[derive(Debug, Default)]
+struct InCommitTimestampVisitor {
+    in_commit_timestamp: Option<i64>,
+}
+
+impl RowVisitor for InCommitTimestampVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            (
+                vec![column_name!("commitInfo.inCommitTimestamp")],
+                vec![DataType::LONG],
+            )
+                .into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for i in 0..row_count {
+            if self.in_commit_timestamp.is_some() {
+                break;
+            }
+            self.in_commit_timestamp = getters[0].get_opt(i, "commitInfo.inCommitTimestamp")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs::File;
+    use std::path::PathBuf;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::engine::sync::SyncEngine;
+    use crate::Table;
+
+    // Write commits to a local table. Each commit is given by the modification time of its file and
+    // has a commitInfo action with the given in-commit timestamp (if any), and the first commit
+    // also has the protocol and metadata.
+    fn setup_table(
+        configuration: serde_json::Value,
+        commits: &[(i64, Option<i64>)],
+    ) -> (TempDir, Table, SyncEngine) {
+        let dir = tempfile::tempdir().unwrap();
+        let log_dir = dir.path().join("_delta_log");
+        std::fs::create_dir(&log_dir).unwrap();
+        for (version, (modification_time, in_commit_timestamp)) in commits.iter().enumerate() {
+            let mut actions = vec![json!({
+                "commitInfo": {"timestamp": 0, "inCommitTimestamp": in_commit_timestamp}
+            })];
+            if version == 0 {
+                actions.push(json!({
+                    "protocol": {
+                        "minReaderVersion": 1,
+                        "minWriterVersion": 7,
+                        "writerFeatures": ["inCommitTimestamp"]
+                    }
+                }));
+                actions.push(json!({
+                    "metaData": {
+                        "id": "id",
+                        "format": {"provider": "parquet", "options": {}},
+                        "schemaString": "{\"type\":\"struct\",\"fields\":[]}",
+                        "partitionColumns": [],
+                        "configuration": configuration,
+                    }
+                }));
+            }
+            let commit = actions.iter().map(|action| action.to_string()).join("\n");
+            let path = log_dir.join(format!("{version:020}.json"));
+            std::fs::write(&path, commit).unwrap();
+            let modification_time = UNIX_EPOCH + Duration::from_millis(*modification_time as u64);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modification_time)
+                .unwrap();
+        }
+        let table = Table::new(Url::from_directory_path(dir.path()).unwrap());
+        (dir, table, SyncEngine::new())
+    }
+
+    fn resolve(
+        table: &Table,
+        engine: &dyn Engine,
+        timestamp: i64,
+        mode: TimestampSearchMode,
+    ) -> Option<Version> {
+        let snapshot = table.snapshot_at_timestamp(engine, timestamp, mode).ok()?;
+        Some(snapshot.version())
+    }
+
+    #[test]
+    fn test_timestamp_to_version_with_in_commit_timestamps() {
+        let (_dir, table, engine) = setup_table(
+            json!({"delta.enableInCommitTimestamps": "true"}),
+            &[(1000, Some(1000)), (2000, Some(2000)), (3000, Some(3000))],
+        );
+        use TimestampSearchMode::*;
+        let cases = [
+            (500, AtOrBefore, None),
+            (500, AtOrAfter, Some(0)),
+            (1000, AtOrBefore, Some(0)),
+            (1500, AtOrBefore, Some(0)),
+            (1500, AtOrAfter, Some(1)),
+            (2000, AtOrAfter, Some(1)),
+            (3000, AtOrBefore, Some(2)),
+            (3500, AtOrBefore, Some(2)),
+            (3500, AtOrAfter, None),
+        ];
+        for (timestamp, mode, expected) in cases {
+            assert_eq!(
+                resolve(&table, &engine, timestamp, mode),
+                expected,
+                "{timestamp} {mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_table_changes_by_timestamp() {
+        let (_dir, table, engine) = setup_table(
+            json!({
+                "delta.enableInCommitTimestamps": "true",
+                "delta.enableChangeDataFeed": "true",
+            }),
+            &[(1000, Some(1000)), (2000, Some(2000)), (3000, Some(3000))],
+        );
+        let version_range = |start_timestamp, end_timestamp| {
+            table
+                .table_changes_by_timestamp(&engine, start_timestamp, end_timestamp)
+                .map(|changes| (changes.start_version(), changes.end_version()))
+        };
+        assert_eq!(version_range(500, None).unwrap(), (0, 2));
+        assert_eq!(version_range(1500, Some(2500)).unwrap(), (1, 1));
+        assert_eq!(version_range(2000, Some(3000)).unwrap(), (1, 2));
+        for (start_timestamp, end_timestamp) in [(3500, None), (500, Some(900)), (1200, Some(1800))]
+        {
+            assert!(matches!(
+                version_range(start_timestamp, end_timestamp),
+                Err(Error::TimestampOutOfRange(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_history() {
+        let path =
+            std::fs::canonicalize(PathBuf::from("./tests/data/table-with-dv-small/")).unwrap();
+        let table = Table::new(Url::from_directory_path(path).unwrap());
+        let engine = SyncEngine::new();
+
+        let history = table.history(&engine, ..).unwrap();
+        assert_eq!(
+            history.iter().map(|entry| entry.version).collect_vec(),
+            [1, 0]
+        );
+        let delete = &history[0];
+        assert_eq!(delete.operation.as_deref(), Some("DELETE"));
+        assert_eq!(
+            delete.operation_parameters["predicate"],
+            "[\"(spark_catalog.delta.`/tmp/table-with-dv-small`.value IN (0, 9))\"]"
+        );
+        assert_eq!(delete.operation_metrics["numDeletedRows"], "2");
+        assert_eq!(
+            delete.engine_info.as_deref(),
+            Some("Databricks-Runtime/<unknown>")
+        );
+        assert_eq!(delete.is_blind_append, Some(false));
+        assert_eq!(delete.in_commit_timestamp, None);
+        assert!(delete.timestamp > history[1].timestamp);
+
+        let versions = |range: (Bound<Version>, Bound<Version>)| {
+            let history = table.history(&engine, range).unwrap();
+            history.iter().map(|entry| entry.version).collect_vec()
+        };
+        use Bound::*;
+        assert_eq!(versions((Included(1), Unbounded)), [1]);
+        assert_eq!(versions((Unbounded, Excluded(1))), [0]);
+        assert!(versions((Unbounded, Excluded(0))).is_empty());
+        assert_eq!(versions((Included(0), Included(5))), [1, 0]);
+        assert!(versions((Included(2), Unbounded)).is_empty());
+    }
+
+    #[test]
+    fn test_history_with_in_commit_timestamps() {
+        let (_dir, table, engine) = setup_table(
+            json!({"delta.enableInCommitTimestamps": "true"}),
+            &[(1000, Some(1000)), (2000, Some(2000)), (3000, Some(3000))],
+        );
+        let history = table.history(&engine, 1..).unwrap();
+        let timestamps = history
+            .iter()
+            .map(|entry| (entry.version, entry.timestamp, entry.in_commit_timestamp))
+            .collect_vec();
+        assert_eq!(timestamps, [(2, 3000, Some(3000)), (1, 2000, Some(2000))]);
+    }
+
+    #[test]
+    fn test_history_with_unexpected_commit_infos() {
+        let (dir, table, engine) =
+            setup_table(json!({}), &[(1000, None), (2000, None), (3000, None)]);
+        // the operation parameters of a commit aren't all strings, and a commit has no commitInfo
+        let commits = [
+            json!({
+                "commitInfo": {
+                    "timestamp": 0,
+                    "operation": "WRITE",
+                    "operationParameters": {"mode": "Append", "partitionBy": ["part"]},
+                    "engineInfo": "engine"
+                }
+            }),
+            json!({"txn": {"appId": "app", "version": 1}}),
+        ];
+        for (version, commit) in [1, 2].into_iter().zip(commits) {
+            let path = dir.path().join(format!("_delta_log/{version:020}.json"));
+            std::fs::write(path, commit.to_string()).unwrap();
+        }
+
+        let history = table.history(&engine, ..).unwrap();
+        let entries = history
+            .iter()
+            .map(|entry| {
+                let operation = entry.operation.as_deref();
+                (entry.version, operation, entry.operation_parameters.len())
+            })
+            .collect_vec();
+        assert_eq!(entries, [(2, None, 0), (1, Some("WRITE"), 0), (0, None, 0)]);
+        assert_eq!(history[1].engine_info.as_deref(), Some("engine"));
+    }
+
+    #[test]
+    fn test_timestamp_to_version_with_file_timestamps() {
+        // in-commit timestamps are only used since the enablement version, and the file
+        // modification times are adjusted to increase with versions
+        let (_dir, table, engine) = setup_table(
+            json!({
+                "delta.enableInCommitTimestamps": "true",
+                "delta.inCommitTimestampEnablementVersion": "2",
+            }),
+            &[
+                (1000, None),
+                (900, None),
+                (3000, Some(i64::MAX - 1)),
+                (4000, Some(i64::MAX)),
+            ],
+        );
+        let snapshot = table.snapshot(&engine, None).unwrap();
+        let commits = CommitTimestamps::try_new(&snapshot, &engine).unwrap();
+        assert_eq!(commits.file_timestamps, [1000, 1001]);
+
+        use TimestampSearchMode::*;
+        let cases = [
+            (999, AtOrBefore, None),
+            (999, AtOrAfter, Some(0)),
+            (1000, AtOrBefore, Some(0)),
+            (1001, AtOrAfter, Some(1)),
+            (1002, AtOrBefore, Some(1)),
+            (1002, AtOrAfter, Some(2)),
+            (i64::MAX, AtOrBefore, Some(3)),
+        ];
+        for (timestamp, mode, expected) in cases {
+            assert_eq!(
+                resolve(&table, &engine, timestamp, mode),
+                expected,
+                "{timestamp} {mode:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_timestamp_to_version_lists_from_checkpoint() {
+        // the commits have expired, so the checkpoint must not clean them up
+        let (dir, table, engine) = setup_table(
+            json!({"delta.enableExpiredLogCleanup": "false"}),
+            &[
+                (1000, None),
+                (2000, None),
+                (3000, None),
+                (4000, None),
+                (5000, None),
+                (6000, None),
+            ],
+        );
+        table
+            .snapshot(&engine, Some(4))
+            .unwrap()
+            .checkpoint(&engine)
+            .unwrap();
+        let snapshot = table.snapshot(&engine, None).unwrap();
+
+        // only the commits since the checkpoint are listed, until an earlier commit is needed
+        use TimestampSearchMode::*;
+        let mut commits = CommitTimestamps::try_new(&snapshot, &engine).unwrap();
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits.find_version(5500, AtOrBefore).unwrap(), 4);
+        assert_eq!(commits.len(), 2);
+        assert_eq!(commits.find_version(3500, AtOrAfter).unwrap(), 3);
+        assert_eq!(commits.len(), 4);
+        assert_eq!(commits.find_version(500, AtOrAfter).unwrap(), 0);
+        assert_eq!(commits.len(), 6);
+        assert!(matches!(
+            commits.find_version(500, AtOrBefore),
+            Err(Error::TimestampOutOfRange(_))
+        ));
+
+        // the commits before a gap in the log are no longer available
+        std::fs::remove_file(dir.path().join("_delta_log/00000000000000000001.json")).unwrap();
+        let mut commits = CommitTimestamps::try_new(&snapshot, &engine).unwrap();
+        assert_eq!(commits.find_version(500, AtOrAfter).unwrap(), 2);
+        assert_eq!(commits.len(), 4);
+    }
+}
@@ -2,11 +2,11 @@
 //!
 //! Delta-kernel-rs is an experimental [Delta](https://github.com/delta-io/delta/) implementation
 //! focused on interoperability with a wide range of query engines. It supports reads and
-//! (experimental) writes (only blind appends in the write path currently). This library defines a
-//! number of traits which must be implemented to provide a working delta implementation. They are
-//! detailed below. There is a provided "default engine" that implements all these traits and can
-//! be used to ease integration work. See [`DefaultEngine`](engine/default/index.html) for more
-//! information.
+//! (experimental) writes (only appends and file removals in the write path currently). This
+//! library defines a number of traits which must be implemented to provide a working delta
+//! implementation. They are detailed below. There is a provided "default engine" that implements
+//! all these traits and can be used to ease integration work. See
+//! [`DefaultEngine`](engine/default/index.html) for more information.
 //!
 //! A full `rust` example for reading table data using the default engine can be found in the
 //! [read-table-single-threaded] example (and for a more complex multi-threaded reader see the
@@ -74,10 +74,13 @@ use url::Url;
 use self::schema::{DataType, SchemaRef};
 
 pub mod actions;
+pub mod checkpoint;
 pub mod engine_data;
 pub mod error;
 pub mod expressions;
+pub mod history;
 pub mod log_replay;
+pub mod optimize;
 pub mod scan;
 pub mod schema;
 pub mod snapshot;
@@ -87,6 +90,7 @@ pub mod table_configuration;
 pub mod table_features;
 pub mod table_properties;
 pub mod transaction;
+pub mod vacuum;
 
 pub mod arrow;
 pub mod parquet;
@@ -104,7 +108,7 @@ pub mod log_segment;
 pub(crate) mod log_segment;
 
 pub use delta_kernel_derive;
-pub use engine_data::{EngineData, RowVisitor};
+pub use engine_data::{EngineData, FilteredEngineData, RowVisitor};
 pub use error::{DeltaResult, Error};
 pub use expressions::{Expression, ExpressionRef};
 pub use table::Table;
@@ -381,7 +385,7 @@ trait ExpressionHandlerExtension: ExpressionHandler {
 }
 
 // Auto-implement the extension trait for all ExpressionHandlers
-impl<T: ExpressionHandler> ExpressionHandlerExtension for T {}
+impl<T: ExpressionHandler + ?Sized> ExpressionHandlerExtension for T {}
 
 /// Provides file system related functionalities to Delta Kernel.
 ///
@@ -393,7 +397,10 @@ pub trait FileSystemClient: AsAny {
     /// (UTF-8 sorting) the given `path`. The result should also be sorted by the file name.
     ///
     /// If the path is directory-like (ends with '/'), the result should contain
-    /// all the files in the directory.
+    /// all the files in the directory and, recursively, in its subdirectories, like
+    /// the listing of a prefix in an object store. Directories themselves are not
+    /// listed. Kernel relies on this to find all the files of a table when planning
+    /// a VACUUM.
     fn list_from(&self, path: &Url)
         -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<FileMeta>>>>;
 
@@ -402,6 +409,32 @@ pub trait FileSystemClient: AsAny {
         &self,
         files: Vec<FileSlice>,
     ) -> DeltaResult<Box<dyn Iterator<Item = DeltaResult<Bytes>>>>;
+
+    /// Write `data` as the full contents of the file at `path`, creating any missing parent
+    /// directories.
+    ///
+    /// If `overwrite` is false and the file already exists, the call must fail with
+    /// [`Error::FileAlreadyExists`] and leave the existing file untouched.
+    ///
+    /// Kernel only writes files for optional operations (like checkpoints or deletion vectors),
+    /// which fail with [`Error::Unsupported`] when the engine doesn't implement this method.
+    fn write_file(&self, path: &Url, data: Bytes, overwrite: bool) -> DeltaResult<()> {
+        let _ = (data, overwrite);
+        Err(Error::unsupported(format!(
+            "This engine does not support writing files: {path}"
+        )))
+    }
+
+    /// Delete the file at `path`. Deleting a file that doesn't exist is not an error, so that
+    /// interrupted cleanups can be retried.
+    ///
+    /// Engines that don't implement this method return [`Error::Unsupported`], in which case
+    /// kernel skips the cleanup of expired log files.
+    fn delete_file(&self, path: &Url) -> DeltaResult<()> {
+        Err(Error::unsupported(format!(
+            "This engine does not support deleting files: {path}"
+        )))
+    }
 }
 
 /// Provides JSON handling functionality to Delta Kernel.
@@ -459,18 +492,21 @@ pub trait JsonHandler: AsAny {
     /// { "a": "..." }. Note that including nulls is technically valid JSON, but would bloat the
     /// log, therefore we recommend omitting them.
     ///
+    /// NOTE: Only the rows selected by each [`FilteredEngineData`]'s selection vector may be
+    /// written. Rows that are not selected must be skipped entirely.
+    ///
     /// # Parameters
     ///
     /// - `path` - URL specifying the location to write the JSON file
-    /// - `data` - Iterator of EngineData to write to the JSON file. Each row should be written as
-    ///   a new JSON object appended to the file. (that is, the file is newline-delimited JSON, and
-    ///   each row is a JSON object on a single line)
+    /// - `data` - Iterator of [`FilteredEngineData`] to write to the JSON file. Each selected row
+    ///   should be written as a new JSON object appended to the file. (that is, the file is
+    ///   newline-delimited JSON, and each row is a JSON object on a single line)
     /// - `overwrite` - If true, overwrite the file if it exists. If false, the call must fail if
     ///   the file exists.
     fn write_json_file(
         &self,
         path: &Url,
-        data: Box<dyn Iterator<Item = DeltaResult<Box<dyn EngineData>>> + Send + '_>,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_>,
         overwrite: bool,
     ) -> DeltaResult<()>;
 }
@@ -484,6 +520,11 @@ pub trait ParquetHandler: AsAny {
     /// the columns requested by physical schema . The ParquetHandler _must_ return exactly the
     /// columns specified in `physical_schema`, and they _must_ be in schema order.
     ///
+    /// Top-level metadata columns of `physical_schema` (see [`schema::MetadataColumnSpec`]) are
+    /// not read from the files: for a [`schema::MetadataColumnSpec::RowIndex`] column, the
+    /// ParquetHandler _must_ fill in the index of each row in its file (starting at 0, and
+    /// counting the rows of any skipped row groups).
+    ///
     /// # Parameters
     ///
     /// - `files` - File metadata for files to be read.
@@ -495,6 +536,35 @@ pub trait ParquetHandler: AsAny {
         physical_schema: SchemaRef,
         predicate: Option<ExpressionRef>,
     ) -> DeltaResult<FileDataReadResultIterator>;
+
+    /// Write a single Parquet file containing the selected rows of `data`, and return the
+    /// [`FileMeta`] of the written file. Every batch has the same schema, which becomes the schema
+    /// of the file. This is used by kernel to write log files such as checkpoints (data files are
+    /// written by the engine).
+    ///
+    /// NOTE: Only the rows selected by each [`FilteredEngineData`]'s selection vector may be
+    /// written. Rows that are not selected must be skipped entirely.
+    ///
+    /// # Parameters
+    ///
+    /// - `location` - URL specifying the location to write the Parquet file
+    /// - `data` - Iterator of [`FilteredEngineData`] to write to the Parquet file
+    /// - `overwrite` - If true, overwrite the file if it exists. If false, the call must fail with
+    ///   [`Error::FileAlreadyExists`] if the file exists.
+    ///
+    /// Kernel only writes Parquet files for optional operations (like checkpoints), which fail with
+    /// [`Error::Unsupported`] when the engine doesn't implement this method.
+    fn write_parquet_file(
+        &self,
+        location: &Url,
+        data: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_>,
+        overwrite: bool,
+    ) -> DeltaResult<FileMeta> {
+        let _ = (data, overwrite);
+        Err(Error::unsupported(format!(
+            "This engine does not support writing Parquet files: {location}"
+        )))
+    }
 }
 
 /// The `Engine` trait encapsulates all the functionality an engine or connector needs to provide
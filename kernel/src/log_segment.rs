@@ -32,6 +32,7 @@ mod tests;
 ///        version and the first commit version.
 ///     3. All checkpoint_parts must belong to the same checkpoint version, and must form a complete
 ///        version. Multi-part checkpoints must have all their parts.
+///     4. Log compaction files only cover commits of the log segment.
 ///
 /// [`LogSegment`] is used in [`Snapshot`] when built with [`LogSegment::for_snapshot`], and
 /// and in `TableChanges` when built with [`LogSegment::for_table_changes`].
@@ -46,6 +47,9 @@ pub(crate) struct LogSegment {
     pub ascending_commit_files: Vec<ParsedLogPath>,
     /// Checkpoint files in the log segment.
     pub checkpoint_parts: Vec<ParsedLogPath>,
+    /// Log compaction files in the log segment, sorted by start version (ascending). Each of them
+    /// summarizes a contiguous range of the commit files, and can be replayed instead of them.
+    pub ascending_compaction_files: Vec<ParsedLogPath>,
 }
 
 impl LogSegment {
@@ -100,13 +104,33 @@ impl LogSegment {
             log_root,
             ascending_commit_files,
             checkpoint_parts,
+            ascending_compaction_files: vec![],
         })
     }
 
+    // Keep the log compaction files that only cover commits of this log segment
+    fn with_compaction_files(mut self, mut ascending_compaction_files: Vec<ParsedLogPath>) -> Self {
+        let (Some(first_commit), Some(last_commit)) = (
+            self.ascending_commit_files.first(),
+            self.ascending_commit_files.last(),
+        ) else {
+            return self;
+        };
+        ascending_compaction_files.retain(|file| match file.file_type {
+            LogPathFileType::CompactedCommit { hi } => {
+                first_commit.version <= file.version && hi <= last_commit.version
+            }
+            _ => false,
+        });
+        self.ascending_compaction_files = ascending_compaction_files;
+        self
+    }
+
     /// Constructs a [`LogSegment`] to be used for [`Snapshot`]. For a `Snapshot` at version `n`:
     /// Its LogSegment is made of zero or one checkpoint, and all commits between the checkpoint up
     /// to and including the end version `n`. Note that a checkpoint may be made of multiple
-    /// parts. All these parts will have the same checkpoint version.
+    /// parts. All these parts will have the same checkpoint version. The log compaction files that
+    /// cover some of these commits are included too, and are replayed instead of the commits.
     ///
     /// The options for constructing a LogSegment for Snapshot are as follows:
     /// - `checkpoint_hint`: a `LastCheckpointHint` to start the log segment from (e.g. from reading the `last_checkpoint` file).
@@ -122,7 +146,7 @@ impl LogSegment {
     ) -> DeltaResult<Self> {
         let time_travel_version = time_travel_version.into();
 
-        let (mut ascending_commit_files, checkpoint_parts) =
+        let (mut ascending_commit_files, mut ascending_compaction_files, checkpoint_parts) =
             match (checkpoint_hint.into(), time_travel_version) {
                 (Some(cp), None) => {
                     list_log_files_with_checkpoint(&cp, fs_client, &log_root, None)?
@@ -136,20 +160,26 @@ impl LogSegment {
         // Commit file versions must be greater than the most recent checkpoint version if it exists
         if let Some(checkpoint_file) = checkpoint_parts.first() {
             ascending_commit_files.retain(|log_path| checkpoint_file.version < log_path.version);
+            ascending_compaction_files
+                .retain(|log_path| checkpoint_file.version < log_path.version);
         }
 
-        LogSegment::try_new(
+        let log_segment = LogSegment::try_new(
             ascending_commit_files,
             checkpoint_parts,
             log_root,
             time_travel_version,
-        )
+        )?;
+        Ok(log_segment.with_compaction_files(ascending_compaction_files))
     }
 
     /// Constructs a [`LogSegment`] to be used for `TableChanges`. For a TableChanges between versions
     /// `start_version` and `end_version`: Its LogSegment is made of zero checkpoints and all commits
     /// between versions `start_version` (inclusive) and `end_version` (inclusive). If no `end_version`
-    /// is specified it will be the most recent version by default.
+    /// is specified it will be the most recent version by default. The log compaction files that
+    /// only cover commits of this range are included too, so that replays of the whole range (like
+    /// [`LogSegment::read_actions`]) read them instead of the commits. Change data feed itself
+    /// reads `ascending_commit_files`, since table changes are reported for each commit separately.
     #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
     pub(crate) fn for_table_changes(
         fs_client: &dyn FileSystemClient,
@@ -166,10 +196,10 @@ impl LogSegment {
             }
         }
 
-        let ascending_commit_files: Vec<_> =
+        let (ascending_commit_files, ascending_compaction_files): (Vec<_>, Vec<_>) =
             list_log_files(fs_client, &log_root, start_version, end_version)?
-                .filter_ok(|x| x.is_commit())
-                .try_collect()?;
+                .filter_ok(|x| x.is_commit() || x.is_compaction())
+                .process_results(|files| files.partition(|x| x.is_commit()))?;
 
         // - Here check that the start version is correct.
         // - [`LogSegment::try_new`] will verify that the `end_version` is correct if present.
@@ -184,7 +214,9 @@ impl LogSegment {
                 start_version
             ))
         );
-        LogSegment::try_new(ascending_commit_files, vec![], log_root, end_version)
+        let log_segment =
+            LogSegment::try_new(ascending_commit_files, vec![], log_root, end_version)?;
+        Ok(log_segment.with_compaction_files(ascending_compaction_files))
     }
 
     /// Read a stream of actions from this log segment. This returns an iterator of (EngineData,
@@ -207,12 +239,10 @@ impl LogSegment {
         checkpoint_read_schema: SchemaRef,
         meta_predicate: Option<ExpressionRef>,
     ) -> DeltaResult<impl Iterator<Item = DeltaResult<(Box<dyn EngineData>, bool)>> + Send> {
-        // `replay` expects commit files to be sorted in descending order, so we reverse the sorted
-        // commit files
+        // `replay` expects commit files to be sorted in descending order
         let commit_files: Vec<_> = self
-            .ascending_commit_files
-            .iter()
-            .rev()
+            .descending_replay_files()
+            .into_iter()
             .map(|f| f.location.clone())
             .collect();
         let commit_stream = engine
@@ -226,6 +256,32 @@ impl LogSegment {
         Ok(commit_stream.chain(checkpoint_stream))
     }
 
+    /// Returns the commit and log compaction files to replay, most recent first. A log compaction
+    /// file replaces the commits it covers. When several log compaction files end at the same
+    /// version, the one that covers the most commits is used.
+    fn descending_replay_files(&self) -> Vec<&ParsedLogPath> {
+        let mut files = vec![];
+        let mut commits = self.ascending_commit_files.iter().rev().peekable();
+        while let Some(commit) = commits.next() {
+            let compaction = self
+                .ascending_compaction_files
+                .iter()
+                .find(|file| matches!(file.file_type, LogPathFileType::CompactedCommit { hi } if hi == commit.version));
+            match compaction {
+                Some(compaction) => {
+                    files.push(compaction);
+                    // skip the other commits covered by the compaction
+                    while commits
+                        .next_if(|c| compaction.version <= c.version)
+                        .is_some()
+                    {}
+                }
+                None => files.push(commit),
+            }
+        }
+        files
+    }
+
     /// Returns an iterator over checkpoint data, processing sidecar files when necessary.
     ///
     /// By default, `create_checkpoint_stream` checks for the presence of sidecar files, and
@@ -411,7 +467,7 @@ impl LogSegment {
 /// the most recent version will be included.
 ///
 /// Note: this calls [`FileSystemClient::list_from`] to get the list of log files.
-fn list_log_files(
+pub(crate) fn list_log_files(
     fs_client: &dyn FileSystemClient,
     log_root: &Url,
     start_version: impl Into<Option<Version>>,
@@ -432,17 +488,18 @@ fn list_log_files(
             Err(_) => true,
         }))
 }
-/// List all commit and checkpoint files with versions above the provided `start_version` (inclusive).
-/// If successful, this returns a tuple `(ascending_commit_files, checkpoint_parts)` of type
-/// `(Vec<ParsedLogPath>, Vec<ParsedLogPath>)`. The commit files are guaranteed to be sorted in
-/// ascending order by version. The elements of `checkpoint_parts` are all the parts of the same
-/// checkpoint. Checkpoint parts share the same version.
+/// List all commit, log compaction and checkpoint files with versions above the provided
+/// `start_version` (inclusive). If successful, this returns a tuple `(ascending_commit_files,
+/// ascending_compaction_files, checkpoint_parts)` of type `(Vec<ParsedLogPath>, Vec<ParsedLogPath>,
+/// Vec<ParsedLogPath>)`. The commit and log compaction files are guaranteed to be sorted in
+/// ascending order by (start) version. The elements of `checkpoint_parts` are all the parts of the
+/// same checkpoint. Checkpoint parts share the same version.
 fn list_log_files_with_version(
     fs_client: &dyn FileSystemClient,
     log_root: &Url,
     start_version: Option<Version>,
     end_version: Option<Version>,
-) -> DeltaResult<(Vec<ParsedLogPath>, Vec<ParsedLogPath>)> {
+) -> DeltaResult<(Vec<ParsedLogPath>, Vec<ParsedLogPath>, Vec<ParsedLogPath>)> {
     // We expect 10 commit files per checkpoint, so start with that size. We could adjust this based
     // on config at some point
 
@@ -450,6 +507,7 @@ fn list_log_files_with_version(
 
     log_files.process_results(|iter| {
         let mut commit_files = Vec::with_capacity(10);
+        let mut compaction_files = vec![];
         let mut checkpoint_parts = vec![];
 
         // Group log files by version
@@ -460,6 +518,8 @@ fn list_log_files_with_version(
             for file in files {
                 if file.is_commit() {
                     commit_files.push(file);
+                } else if file.is_compaction() {
+                    compaction_files.push(file);
                 } else if file.is_checkpoint() {
                     new_checkpoint_parts.push(file);
                 } else {
@@ -478,10 +538,12 @@ fn list_log_files_with_version(
                 .find(|(num_parts, part_files)| part_files.len() == *num_parts as usize)
             {
                 checkpoint_parts = complete_checkpoint;
-                commit_files.clear(); // Log replay only uses commits after a complete checkpoint
+                // Log replay only uses commits (and compactions) after a complete checkpoint
+                commit_files.clear();
+                compaction_files.clear();
             }
         }
-        (commit_files, checkpoint_parts)
+        (commit_files, compaction_files, checkpoint_parts)
     })
 }
 
@@ -489,7 +551,9 @@ fn list_log_files_with_version(
 ///
 /// NOTE: There could be a single-part and/or any number of uuid-based checkpoints. They
 /// are all equivalent, and this routine keeps only one of them (arbitrarily chosen).
-fn group_checkpoint_parts(parts: Vec<ParsedLogPath>) -> HashMap<u32, Vec<ParsedLogPath>> {
+pub(crate) fn group_checkpoint_parts(
+    parts: Vec<ParsedLogPath>,
+) -> HashMap<u32, Vec<ParsedLogPath>> {
     let mut checkpoints: HashMap<u32, Vec<ParsedLogPath>> = HashMap::new();
     for part_file in parts {
         use LogPathFileType::*;
@@ -539,8 +603,8 @@ fn list_log_files_with_checkpoint(
     fs_client: &dyn FileSystemClient,
     log_root: &Url,
     end_version: Option<Version>,
-) -> DeltaResult<(Vec<ParsedLogPath>, Vec<ParsedLogPath>)> {
-    let (commit_files, checkpoint_parts) = list_log_files_with_version(
+) -> DeltaResult<(Vec<ParsedLogPath>, Vec<ParsedLogPath>, Vec<ParsedLogPath>)> {
+    let (commit_files, compaction_files, checkpoint_parts) = list_log_files_with_version(
         fs_client,
         log_root,
         Some(checkpoint_metadata.version),
@@ -566,5 +630,5 @@ fn list_log_files_with_checkpoint(
             checkpoint_parts.len()
         )));
     }
-    Ok((commit_files, checkpoint_parts))
+    Ok((commit_files, compaction_files, checkpoint_parts))
 }
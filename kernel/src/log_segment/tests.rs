@@ -80,6 +80,12 @@ fn delta_path_for_multipart_checkpoint(version: u64, part_num: u32, num_parts: u
     Path::from(path.as_str())
 }
 
+// get an ObjectStore path for a log compaction file covering the commits `lo` to `hi` (inclusive)
+fn delta_path_for_compaction(lo: u64, hi: u64) -> Path {
+    let path = format!("_delta_log/{lo:020}.{hi:020}.compacted.json");
+    Path::from(path.as_str())
+}
+
 // Utility method to build a log using a list of log paths and an optional checkpoint hint. The
 // LastCheckpointHint is written to `_delta_log/_last_checkpoint`.
 fn build_log_with_paths_and_checkpoint(
@@ -696,6 +702,117 @@ fn build_snapshot_with_start_checkpoint_and_time_travel_version() {
     assert_eq!(log_segment.ascending_commit_files.len(), 1);
     assert_eq!(log_segment.ascending_commit_files[0].version, 4);
 }
+#[test]
+fn build_snapshot_with_compaction_files() {
+    let (client, log_root) = build_log_with_paths_and_checkpoint(
+        &[
+            delta_path_for_version(0, "json"),
+            delta_path_for_version(1, "json"),
+            delta_path_for_compaction(1, 3),
+            delta_path_for_version(2, "json"),
+            delta_path_for_version(2, "checkpoint.parquet"),
+            delta_path_for_version(3, "json"),
+            delta_path_for_compaction(3, 4),
+            delta_path_for_compaction(3, 5),
+            delta_path_for_version(4, "json"),
+            delta_path_for_compaction(4, 6),
+            delta_path_for_version(5, "json"),
+            delta_path_for_version(6, "json"),
+            delta_path_for_compaction(6, 8),
+            delta_path_for_version(7, "json"),
+        ],
+        None,
+    );
+    let replay_files = |log_segment: &LogSegment| {
+        log_segment
+            .descending_replay_files()
+            .into_iter()
+            .map(|file| file.filename.clone())
+            .collect_vec()
+    };
+
+    // compaction files that cover the checkpoint or versions after the end version are ignored
+    let log_segment =
+        LogSegment::for_snapshot(client.as_ref(), log_root.clone(), None, None).unwrap();
+    let versions = log_segment
+        .ascending_commit_files
+        .iter()
+        .map(|x| x.version)
+        .collect_vec();
+    assert_eq!(versions, vec![3, 4, 5, 6, 7]);
+    let compaction_files = log_segment
+        .ascending_compaction_files
+        .iter()
+        .map(|x| x.filename.as_str())
+        .collect_vec();
+    assert_eq!(
+        compaction_files,
+        vec![
+            "00000000000000000003.00000000000000000004.compacted.json",
+            "00000000000000000003.00000000000000000005.compacted.json",
+            "00000000000000000004.00000000000000000006.compacted.json",
+        ]
+    );
+    assert_eq!(
+        replay_files(&log_segment),
+        vec![
+            "00000000000000000007.json",
+            "00000000000000000004.00000000000000000006.compacted.json",
+            "00000000000000000003.json",
+        ]
+    );
+
+    let log_segment = LogSegment::for_snapshot(client.as_ref(), log_root, None, Some(5)).unwrap();
+    assert_eq!(log_segment.ascending_compaction_files.len(), 2);
+    assert_eq!(
+        replay_files(&log_segment),
+        vec!["00000000000000000003.00000000000000000005.compacted.json"]
+    );
+}
+
+#[test]
+fn test_read_actions_with_compaction_file() -> DeltaResult<()> {
+    let (store, log_root) = new_in_memory_store();
+    let engine = DefaultEngine::new(store.clone(), Arc::new(TokioBackgroundExecutor::new()));
+    let add = |path: &str| {
+        Action::Add(Add {
+            path: path.into(),
+            data_change: true,
+            ..Default::default()
+        })
+    };
+    write_json_to_store(&store, vec![add("a")], "00000000000000000000.json")?;
+    write_json_to_store(&store, vec![add("b")], "00000000000000000001.json")?;
+    write_json_to_store(&store, vec![add("c")], "00000000000000000002.json")?;
+    write_json_to_store(
+        &store,
+        vec![add("b"), add("c")],
+        "00000000000000000001.00000000000000000002.compacted.json",
+    )?;
+
+    let client = engine.get_file_system_client();
+    let log_segment = LogSegment::for_snapshot(client.as_ref(), log_root, None, None)?;
+    assert_eq!(log_segment.ascending_commit_files.len(), 3);
+    assert_eq!(log_segment.ascending_compaction_files.len(), 1);
+
+    // the compaction file is read instead of commits 1 and 2
+    let schema = get_log_schema().project(&[ADD_NAME, SIDECAR_NAME])?;
+    let batches: Vec<_> = log_segment
+        .read_actions(&engine, schema.clone(), schema, None)?
+        .try_collect()?;
+    let paths = batches
+        .iter()
+        .map(|(batch, is_log_batch)| {
+            assert!(is_log_batch);
+            let mut visitor = AddVisitor::default();
+            visitor.visit_rows_of(batch.as_ref()).unwrap();
+            visitor.adds.into_iter().map(|add| add.path).collect_vec()
+        })
+        .collect_vec();
+    assert_eq!(paths, vec![vec!["b", "c"], vec!["a"]]);
+    Ok(())
+}
+
 #[test]
 fn build_table_changes_with_commit_versions() {
     let (client, log_root) = build_log_with_paths_and_checkpoint(
@@ -757,6 +874,61 @@ fn build_table_changes_with_commit_versions() {
     assert_eq!(versions, expected_versions);
 }
 
+#[test]
+fn build_table_changes_with_compaction_files() {
+    let (client, log_root) = build_log_with_paths_and_checkpoint(
+        &[
+            delta_path_for_version(0, "json"),
+            delta_path_for_compaction(0, 2),
+            delta_path_for_version(1, "json"),
+            delta_path_for_version(2, "json"),
+            delta_path_for_compaction(2, 3),
+            delta_path_for_version(3, "json"),
+            delta_path_for_compaction(3, 5),
+            delta_path_for_version(4, "json"),
+            delta_path_for_version(5, "json"),
+        ],
+        None,
+    );
+
+    // compaction files that start before the start version or end after the end version are
+    // ignored
+    let log_segment =
+        LogSegment::for_table_changes(client.as_ref(), log_root.clone(), 1, 4).unwrap();
+    let versions = log_segment
+        .ascending_commit_files
+        .iter()
+        .map(|x| x.version)
+        .collect_vec();
+    assert_eq!(versions, vec![1, 2, 3, 4]);
+    let compaction_files = log_segment
+        .ascending_compaction_files
+        .iter()
+        .map(|x| x.filename.as_str())
+        .collect_vec();
+    assert_eq!(
+        compaction_files,
+        vec!["00000000000000000002.00000000000000000003.compacted.json"]
+    );
+    let replay_files = log_segment
+        .descending_replay_files()
+        .into_iter()
+        .map(|file| file.filename.as_str())
+        .collect_vec();
+    assert_eq!(
+        replay_files,
+        vec![
+            "00000000000000000004.json",
+            "00000000000000000002.00000000000000000003.compacted.json",
+            "00000000000000000001.json",
+        ]
+    );
+
+    let log_segment = LogSegment::for_table_changes(client.as_ref(), log_root, 0, None).unwrap();
+    assert_eq!(log_segment.ascending_commit_files.len(), 6);
+    assert_eq!(log_segment.ascending_compaction_files.len(), 3);
+}
+
 #[test]
 fn test_non_contiguous_log() {
     // Commit with version 1 is missing
@@ -0,0 +1,543 @@
+//! Planning and committing of OPTIMIZE operations, which compact the small files of a table into
+//! fewer, larger files.
+//!
+//! An [`OptimizeBuilder`] plans the compaction of the active files of a table: the files of each
+//! partition that are smaller than the target file size are bin-packed into [`RewriteTask`]s of
+//! about the target size. Files with deletion vectors are skipped unless requested, in which case
+//! they are rewritten regardless of their size to purge their deleted rows. For each task of the
+//! resulting [`OptimizePlan`], the engine reads the data of the task's files with
+//! [`OptimizePlan::execute_task`] and writes it to new files in the task's partition. It then adds
+//! the metadata of the new files to the transaction returned by [`OptimizePlan::into_transaction`]
+//! and commits it, which atomically removes the rewritten files and adds the new ones, all with
+//! `dataChange` set to `false` since the data of the table doesn't change.
+//!
+//! # Clustering
+//!
+//! With [`OptimizeBuilder::with_clustering`], the rows of the table are instead laid out along a
+//! space-filling curve ([Z-order] or [Hilbert]) over a few columns, so that the data files cover
+//! narrow ranges of values of every clustering column and queries filtering on these columns can
+//! skip most files. All the files of a partition are then rewritten by a single task, whose rows
+//! the engine sorts along the curve before splitting them into [`RewriteTask::num_output_files`]
+//! files of about the target size. Engines based on arrow can do both with
+//! [`cluster_record_batches`]. The [`OptimizePlan::write_context`] of a clustering requests the
+//! statistics of the clustering columns for the written files, which data skipping relies on.
+//!
+//! Since a clustering rewrites all the files of every partition with more than one file (or with
+//! a file larger than the target size, which is split), it reads and writes the whole table every
+//! time, even the files that are already clustered. OPTIMIZE therefore only clusters a table when
+//! a clustering is explicitly requested: clustered tables (see [`Snapshot::clustering_columns`])
+//! are otherwise compacted like other tables, and the compacted files keep the statistics of the
+//! clustering columns. To recluster a clustered table, e.g. after its clustering columns changed,
+//! request a clustering over its clustering columns.
+//!
+//! # Row tracking
+//!
+//! The rewritten rows of tables with row tracking keep their row IDs and row commit versions: the
+//! data read by [`OptimizePlan::execute_task`] then has their `_metadata` column (see
+//! [`ScanBuilder::with_row_tracking_columns`]), which the [`OptimizePlan::write_context`] writes
+//! to the materialized row tracking columns of the new files.
+//!
+//! [Z-order]: https://en.wikipedia.org/wiki/Z-order_curve
+//! [Hilbert]: https://en.wikipedia.org/wiki/Hilbert_curve
+//! [`cluster_record_batches`]: crate::engine::arrow_clustering::cluster_record_batches
+//! [`ScanBuilder::with_row_tracking_columns`]: crate::scan::ScanBuilder::with_row_tracking_columns
+//!
+//! # Examples
+//!
+//! ```rust,ignore
+//! let plan = snapshot.optimize_builder().build(&engine)?;
+//! let mut write_metadata = vec![];
+//! for task in plan.tasks() {
+//!     // read the data of the task, and filter out the rows masked by deletion vectors
+//!     let data = read_task_data(plan.execute_task(engine.clone(), task)?)?;
+//!     let partition_values = task.partition_values().clone();
+//!     let metadata = engine
+//!         .write_parquet(&data, &plan.write_context(), partition_values, false)
+//!         .await?;
+//!     write_metadata.push(metadata);
+//! }
+//! let mut txn = plan.into_transaction().with_commit_info(commit_info);
+//! for metadata in write_metadata {
+//!     txn.add_write_metadata(metadata);
+//! }
+//! txn.commit(&engine)?;
+//! ```
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::num::NonZero;
+use std::sync::Arc;
+
+use crate::engine_data::RowVisitor as _;
+use crate::scan::state::{visit_scan_files, DvInfo, Stats};
+use crate::scan::{Scan, ScanFile, ScanResult};
+use crate::schema::ColumnName;
+use crate::snapshot::Snapshot;
+use crate::table_features::clustering::physical_clustering_column;
+use crate::transaction::{ScanRowPathVisitor, Transaction, WriteContext};
+use crate::{DeltaResult, Engine, EngineData, Error, ExpressionRef, FilteredEngineData};
+
+/// The target file size of compactions when the table doesn't set `delta.targetFileSize`: 1 GiB.
+const DEFAULT_TARGET_FILE_SIZE: u64 = 1024 * 1024 * 1024;
+
+/// The target file size of compactions of tables with `delta.tuneFileSizesForRewrites` enabled
+/// and without `delta.targetFileSize`: 256 MiB. Such tables are frequently rewritten (e.g. by
+/// MERGE), which is cheaper with smaller files.
+const TUNED_TARGET_FILE_SIZE: u64 = 256 * 1024 * 1024;
+
+const OPTIMIZE_OPERATION: &str = "OPTIMIZE";
+
+/// The space-filling curve that a [`Clustering`] lays out the rows of the table along.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpaceFillingCurve {
+    /// The Z-order curve, which interleaves the bits of the values of the clustering columns.
+    ZOrder,
+    /// The Hilbert curve, which keeps the rows of consecutive files closer together than the
+    /// Z-order curve, at a slightly higher cost.
+    Hilbert,
+}
+
+/// The multi-dimensional clustering of the rows of a table over some of its columns. See the
+/// [module documentation] for details.
+///
+/// [module documentation]: crate::optimize#clustering
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clustering {
+    curve: SpaceFillingCurve,
+    columns: Vec<ColumnName>,
+}
+
+impl Clustering {
+    /// Create a clustering along the given curve over the given (logical) columns, which must be
+    /// non-partition columns of primitive types.
+    pub fn new(curve: SpaceFillingCurve, columns: Vec<ColumnName>) -> Self {
+        Clustering { curve, columns }
+    }
+
+    /// The space-filling curve of the clustering.
+    pub fn curve(&self) -> SpaceFillingCurve {
+        self.curve
+    }
+
+    /// The logical columns of the clustering.
+    pub fn columns(&self) -> &[ColumnName] {
+        &self.columns
+    }
+}
+
+/// Builder for an [`OptimizePlan`]. Create one with [`Snapshot::optimize_builder`].
+#[derive(Debug)]
+pub struct OptimizeBuilder {
+    snapshot: Arc<Snapshot>,
+    target_file_size: Option<NonZero<u64>>,
+    rewrite_deletion_vectors: bool,
+    clustering: Option<Clustering>,
+}
+
+impl OptimizeBuilder {
+    pub(crate) fn new(snapshot: Arc<Snapshot>) -> Self {
+        OptimizeBuilder {
+            snapshot,
+            target_file_size: None,
+            rewrite_deletion_vectors: false,
+            clustering: None,
+        }
+    }
+
+    /// Set the size in bytes of the files written by the compaction. By default, this is the
+    /// `delta.targetFileSize` of the table, or 256 MiB if the table sets
+    /// `delta.tuneFileSizesForRewrites`, or 1 GiB otherwise.
+    pub fn with_target_file_size(mut self, target_file_size: NonZero<u64>) -> Self {
+        self.target_file_size = Some(target_file_size);
+        self
+    }
+
+    /// Set whether the files with deletion vectors are rewritten, regardless of their size, so
+    /// that their deleted rows are purged (false by default).
+    pub fn with_rewrite_deletion_vectors(mut self, rewrite_deletion_vectors: bool) -> Self {
+        self.rewrite_deletion_vectors = rewrite_deletion_vectors;
+        self
+    }
+
+    /// Cluster the rows of the table instead of compacting its small files: every partition with
+    /// more than one file is rewritten by a single task, whose rows are sorted along the curve of
+    /// the clustering. Since the files that are already clustered are rewritten too, this reads
+    /// and writes the whole table, which is why clustered tables are only compacted otherwise.
+    pub fn with_clustering(mut self, clustering: Clustering) -> Self {
+        self.clustering = Some(clustering);
+        self
+    }
+
+    /// Plan the compaction of the table, by bin-packing the files of each partition into rewrite
+    /// tasks, or its clustering.
+    ///
+    /// Returns an error if kernel doesn't support writing to the table, or if the clustering
+    /// columns aren't non-partition columns of primitive types of the table.
+    pub fn build(self, engine: &dyn Engine) -> DeltaResult<OptimizePlan> {
+        let mut transaction = Transaction::try_new(self.snapshot.clone(), engine)?
+            .with_operation(OPTIMIZE_OPERATION.to_string())
+            .with_data_change(false);
+        let table_properties = self.snapshot.table_properties();
+        let target_file_size = match (
+            self.target_file_size.or(table_properties.target_file_size),
+            table_properties.tune_file_sizes_for_rewrites,
+        ) {
+            (Some(target_file_size), _) => target_file_size.get(),
+            (None, Some(true)) => TUNED_TARGET_FILE_SIZE,
+            (None, _) => DEFAULT_TARGET_FILE_SIZE,
+        };
+        let clustering = self.clustering;
+        let schema = self.snapshot.schema();
+        let partition_columns = self.snapshot.metadata().partition_columns();
+        let stats_columns = match &clustering {
+            Some(clustering) if clustering.columns.is_empty() => {
+                return Err(Error::generic("Clustering requires at least one column"));
+            }
+            Some(clustering) => clustering
+                .columns
+                .iter()
+                .map(|column| physical_clustering_column(&schema, partition_columns, column))
+                .collect::<DeltaResult<_>>()?,
+            None => vec![],
+        };
+        let is_clustering = clustering.is_some();
+
+        // collect the candidate files of each partition (all of them when clustering), keeping the
+        // scan rows to remove the rewritten files
+        // the rewritten rows of tables with row tracking keep their row IDs and row commit versions,
+        // which are read with the data and materialized in the new files
+        let row_tracking = self
+            .snapshot
+            .table_configuration()
+            .is_row_tracking_enabled();
+        let scan = if row_tracking {
+            transaction = transaction.with_materialized_row_tracking();
+            self.snapshot
+                .scan_builder()
+                .with_row_tracking_columns()
+                .build()?
+        } else {
+            self.snapshot.scan_builder().build()?
+        };
+        let mut scan_rows = vec![];
+        let mut partitions: BTreeMap<BTreeMap<String, String>, Partition> = BTreeMap::new();
+        for scan_data in scan.scan_data(engine)? {
+            let (data, selection_vector, transforms) = scan_data?;
+            let files = visit_scan_files(
+                data.as_ref(),
+                &selection_vector,
+                &transforms,
+                vec![],
+                collect_file,
+            )?;
+            for (file, partition_values) in files {
+                let has_deletion_vector = file.dv_info.deletion_vector.is_some();
+                let is_candidate = if is_clustering {
+                    true
+                } else if has_deletion_vector {
+                    self.rewrite_deletion_vectors
+                } else {
+                    (file.size as u64) < target_file_size
+                };
+                if is_candidate {
+                    let key = partition_values.clone().into_iter().collect();
+                    partitions
+                        .entry(key)
+                        .or_insert_with(|| Partition {
+                            partition_values,
+                            files: vec![],
+                        })
+                        .files
+                        .push(file);
+                }
+            }
+            scan_rows.push((data, selection_vector));
+        }
+
+        let tasks: Vec<_> = partitions
+            .into_values()
+            .flat_map(|partition| {
+                let bins = if is_clustering {
+                    cluster_bin(partition.files, target_file_size)
+                        .into_iter()
+                        .collect()
+                } else {
+                    bin_pack(partition.files, target_file_size)
+                };
+                bins.into_iter().map(move |files| {
+                    let num_output_files = if is_clustering {
+                        let size: u64 = files.iter().map(|file| file.size as u64).sum();
+                        size.div_ceil(target_file_size).max(1) as usize
+                    } else {
+                        1
+                    };
+                    RewriteTask {
+                        partition_values: partition.partition_values.clone(),
+                        files,
+                        num_output_files,
+                    }
+                })
+            })
+            .collect();
+        let rewritten_paths: HashSet<_> = tasks
+            .iter()
+            .flat_map(|task| task.files.iter().map(|file| file.path.as_str()))
+            .collect();
+        for (data, selection_vector) in scan_rows {
+            let remove_selection_vector =
+                rewritten_rows(data.as_ref(), &selection_vector, &rewritten_paths)?;
+            if remove_selection_vector.contains(&true) {
+                transaction
+                    .remove_files(FilteredEngineData::try_new(data, remove_selection_vector)?);
+            }
+        }
+        Ok(OptimizePlan {
+            scan,
+            tasks,
+            transaction,
+            clustering,
+            stats_columns,
+        })
+    }
+}
+
+/// A planned compaction of a table. See the [module documentation] for how to execute it.
+///
+/// [module documentation]: crate::optimize
+#[derive(Debug)]
+pub struct OptimizePlan {
+    scan: Scan,
+    tasks: Vec<RewriteTask>,
+    transaction: Transaction,
+    clustering: Option<Clustering>,
+    // the physical names of the clustering columns
+    stats_columns: Vec<ColumnName>,
+}
+
+impl OptimizePlan {
+    /// The rewrite tasks of the compaction. The compaction has nothing to do if there are none.
+    pub fn tasks(&self) -> &[RewriteTask] {
+        &self.tasks
+    }
+
+    /// Read the data of the files of a rewrite task, in the logical schema of the table. Like
+    /// [`Scan::execute`], the rows removed by deletion vectors must be filtered out with the mask
+    /// of each [`ScanResult`] before the data is written.
+    pub fn execute_task(
+        &self,
+        engine: Arc<dyn Engine>,
+        task: &RewriteTask,
+    ) -> DeltaResult<impl Iterator<Item = DeltaResult<ScanResult>>> {
+        self.scan
+            .execute_scan_files(engine, task.files.clone().into_iter().map(Ok))
+    }
+
+    /// The clustering of the rows of the table, if the plan clusters the table rather than
+    /// compacting it. The rows of each task must then be sorted along its curve before they are
+    /// written.
+    pub fn clustering(&self) -> Option<&Clustering> {
+        self.clustering.as_ref()
+    }
+
+    /// The write context to write the data of the rewrite tasks with. When clustering, it
+    /// requests the statistics of the clustering columns for the written files.
+    pub fn write_context(&self) -> WriteContext {
+        let write_context = self.transaction.get_write_context();
+        // compactions keep the statistics columns of the transaction, i.e. the clustering columns
+        // of clustered tables
+        match self.clustering {
+            Some(_) => write_context.with_stats_columns(self.stats_columns.clone()),
+            None => write_context,
+        }
+    }
+
+    /// The transaction that commits the compaction. It removes the files of all the rewrite tasks,
+    /// so the metadata of the files written for every task must be added to it with
+    /// [`Transaction::add_write_metadata`], along with the commit info, before committing it.
+    pub fn into_transaction(self) -> Transaction {
+        self.transaction
+    }
+}
+
+/// A set of files of a partition that are rewritten together into new files.
+#[derive(Debug, Clone)]
+pub struct RewriteTask {
+    partition_values: HashMap<String, String>,
+    files: Vec<ScanFile>,
+    num_output_files: usize,
+}
+
+impl RewriteTask {
+    /// The partition values of the files, which the rewritten files must be written with.
+    pub fn partition_values(&self) -> &HashMap<String, String> {
+        &self.partition_values
+    }
+
+    /// The paths of the files to rewrite, relative to the table root.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.files.iter().map(|file| file.path.as_str())
+    }
+
+    /// The total size of the files to rewrite, in bytes.
+    pub fn size_in_bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.size as u64).sum()
+    }
+
+    /// The number of files to write the rows of the task to, so that they are of about the target
+    /// file size. This is always one when compacting, since the rewritten files are packed up to
+    /// the target size.
+    pub fn num_output_files(&self) -> usize {
+        self.num_output_files
+    }
+}
+
+// The candidate files of a partition
+struct Partition {
+    partition_values: HashMap<String, String>,
+    files: Vec<ScanFile>,
+}
+
+fn collect_file(
+    files: &mut Vec<(ScanFile, HashMap<String, String>)>,
+    path: &str,
+    size: i64,
+    _: Option<Stats>,
+    dv_info: DvInfo,
+    transform: Option<ExpressionRef>,
+    partition_values: HashMap<String, String>,
+) {
+    let file = ScanFile {
+        path: path.to_string(),
+        size,
+        dv_info,
+        transform,
+    };
+    files.push((file, partition_values));
+}
+
+// Pack the files into bins of at most `target_file_size` bytes (unless a single file is larger),
+// smallest files first. Bins with a single file are dropped since rewriting the file wouldn't
+// compact anything, unless the file has a deletion vector to purge.
+fn bin_pack(mut files: Vec<ScanFile>, target_file_size: u64) -> Vec<Vec<ScanFile>> {
+    files.sort_by_key(|file| file.size);
+    let mut bins = vec![];
+    let mut bin: Vec<ScanFile> = vec![];
+    let mut bin_size = 0;
+    for file in files {
+        let size = file.size as u64;
+        if !bin.is_empty() && bin_size + size > target_file_size {
+            bins.push(std::mem::take(&mut bin));
+            bin_size = 0;
+        }
+        bin_size += size;
+        bin.push(file);
+    }
+    bins.push(bin);
+    bins.retain(|bin| is_worth_rewriting(bin));
+    bins
+}
+
+// The bin of all the files of a partition to cluster, unless clustering them wouldn't change
+// their layout. Unlike compactions, clusterings split the files larger than `target_file_size`.
+fn cluster_bin(files: Vec<ScanFile>, target_file_size: u64) -> Option<Vec<ScanFile>> {
+    let is_split = matches!(&files[..], [file] if file.size as u64 > target_file_size);
+    (is_split || is_worth_rewriting(&files)).then_some(files)
+}
+
+// Whether rewriting the files changes their layout: a single file is only rewritten to purge its
+// deletion vector
+fn is_worth_rewriting(files: &[ScanFile]) -> bool {
+    match files {
+        [] => false,
+        [file] => file.dv_info.deletion_vector.is_some(),
+        _ => true,
+    }
+}
+
+// The selection vector of the selected scan rows of the given paths
+fn rewritten_rows(
+    data: &dyn EngineData,
+    selection_vector: &[bool],
+    paths: &HashSet<&str>,
+) -> DeltaResult<Vec<bool>> {
+    let mut visitor = ScanRowPathVisitor {
+        selection_vector,
+        paths: vec![],
+    };
+    visitor.visit_rows_of(data)?;
+    let mut rewritten_rows = vec![false; data.len()];
+    for (row, path) in visitor.paths {
+        if paths.contains(path.as_str()) {
+            rewritten_rows[row] = true;
+        }
+    }
+    Ok(rewritten_rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::deletion_vector::DeletionVectorDescriptor;
+
+    fn file(path: &str, size: i64, has_deletion_vector: bool) -> ScanFile {
+        let deletion_vector = has_deletion_vector.then(|| {
+            DeletionVectorDescriptor::try_new_inline(&Default::default())
+                .expect("empty inline deletion vector")
+        });
+        ScanFile {
+            path: path.to_string(),
+            size,
+            dv_info: DvInfo { deletion_vector },
+            transform: None,
+        }
+    }
+
+    fn paths(bins: &[Vec<ScanFile>]) -> Vec<Vec<&str>> {
+        bins.iter()
+            .map(|bin| bin.iter().map(|file| file.path.as_str()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_bin_pack() {
+        let files = vec![
+            file("a", 40, false),
+            file("b", 10, false),
+            file("c", 30, false),
+            file("d", 70, false),
+            file("e", 20, false),
+        ];
+        // the smallest files fill a bin, and the largest one is left alone
+        let bins = bin_pack(files, 100);
+        assert_eq!(paths(&bins), [vec!["b", "e", "c", "a"]]);
+
+        let files = vec![
+            file("a", 40, false),
+            file("b", 10, false),
+            file("c", 30, false),
+            file("d", 70, false),
+        ];
+        let bins = bin_pack(files, 50);
+        assert_eq!(paths(&bins), [vec!["b", "c"]]);
+
+        // single files are only rewritten to purge their deletion vectors
+        let bins = bin_pack(vec![file("a", 40, true)], 50);
+        assert_eq!(paths(&bins), [vec!["a"]]);
+        let bins = bin_pack(vec![file("a", 40, false)], 50);
+        assert!(bins.is_empty());
+    }
+
+    #[test]
+    fn test_cluster_bin() {
+        // all the files of a partition are clustered together
+        let bin = cluster_bin(vec![file("a", 40, false), file("b", 70, false)], 50);
+        assert_eq!(paths(&Vec::from_iter(bin)), [vec!["a", "b"]]);
+
+        // single files are clustered to purge their deletion vectors or to be split
+        let bin = cluster_bin(vec![file("a", 40, true)], 50);
+        assert_eq!(paths(&Vec::from_iter(bin)), [vec!["a"]]);
+        let bin = cluster_bin(vec![file("a", 70, false)], 50);
+        assert_eq!(paths(&Vec::from_iter(bin)), [vec!["a"]]);
+        assert!(cluster_bin(vec![file("a", 40, false)], 50).is_none());
+        assert!(cluster_bin(vec![], 50).is_none());
+    }
+}
@@ -30,7 +30,6 @@ enum LogPathFileType {
         part_num: u32,
         num_parts: u32,
     },
-    #[allow(unused)]
     CompactedCommit {
         hi: Version,
     },
@@ -160,6 +159,12 @@ impl<Location: AsUrl> ParsedLogPath<Location> {
         matches!(self.file_type, LogPathFileType::Commit)
     }
 
+    #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
+    #[cfg_attr(not(feature = "developer-visibility"), visibility::make(pub(crate)))]
+    fn is_compaction(&self) -> bool {
+        matches!(self.file_type, LogPathFileType::CompactedCommit { .. })
+    }
+
     #[cfg_attr(feature = "developer-visibility", visibility::make(pub))]
     #[cfg_attr(not(feature = "developer-visibility"), visibility::make(pub(crate)))]
     fn is_checkpoint(&self) -> bool {
@@ -196,6 +201,68 @@ impl ParsedLogPath<Url> {
         }
         Ok(path)
     }
+
+    /// Create a new ParsedCommitPath<Url> for a new classic single-part parquet checkpoint file at
+    /// the specified version
+    pub(crate) fn new_classic_parquet_checkpoint(
+        table_root: &Url,
+        version: Version,
+    ) -> DeltaResult<ParsedLogPath<Url>> {
+        let filename = format!("{:020}.checkpoint.parquet", version);
+        let location = table_root.join("_delta_log/")?.join(&filename)?;
+        let path = Self::try_from(location)?
+            .ok_or_else(|| Error::internal_error("attempted to create invalid checkpoint path"))?;
+        if !matches!(path.file_type, LogPathFileType::SinglePartCheckpoint) {
+            return Err(Error::internal_error(
+                "ParsedLogPath::new_classic_parquet_checkpoint created a non-checkpoint path",
+            ));
+        }
+        Ok(path)
+    }
+
+    /// Create a new ParsedCommitPath<Url> for a new log compaction file that covers the commits
+    /// from `start_version` to `end_version` (inclusive)
+    pub(crate) fn new_log_compaction(
+        table_root: &Url,
+        start_version: Version,
+        end_version: Version,
+    ) -> DeltaResult<ParsedLogPath<Url>> {
+        let filename = format!("{:020}.{:020}.compacted.json", start_version, end_version);
+        let location = table_root.join("_delta_log/")?.join(&filename)?;
+        let path = Self::try_from(location)?.ok_or_else(|| {
+            Error::internal_error("attempted to create invalid log compaction path")
+        })?;
+        if !path.is_compaction() {
+            return Err(Error::internal_error(
+                "ParsedLogPath::new_log_compaction created a non-compaction path",
+            ));
+        }
+        Ok(path)
+    }
+
+    /// Create a new ParsedCommitPath<Url> for a new UUID-named V2 checkpoint file at the specified
+    /// version, with the given `extension` (`json` or `parquet`)
+    pub(crate) fn new_uuid_checkpoint(
+        table_root: &Url,
+        version: Version,
+        extension: &str,
+    ) -> DeltaResult<ParsedLogPath<Url>> {
+        let filename = format!(
+            "{:020}.checkpoint.{}.{}",
+            version,
+            uuid::Uuid::new_v4(),
+            extension
+        );
+        let location = table_root.join("_delta_log/")?.join(&filename)?;
+        let path = Self::try_from(location)?
+            .ok_or_else(|| Error::internal_error("attempted to create invalid checkpoint path"))?;
+        if !matches!(path.file_type, LogPathFileType::UuidCheckpoint(_)) {
+            return Err(Error::internal_error(
+                "ParsedLogPath::new_uuid_checkpoint created a non-checkpoint path",
+            ));
+        }
+        Ok(path)
+    }
 }
 
 #[cfg(test)]
@@ -566,4 +633,40 @@ mod tests {
         assert!(matches!(log_path.file_type, LogPathFileType::Commit));
         assert_eq!(log_path.filename, "00000000000000000010.json");
     }
+
+    #[test]
+    fn test_new_log_compaction() {
+        let table_log_dir = table_log_dir_url();
+        let log_path = ParsedLogPath::new_log_compaction(&table_log_dir, 3, 10).unwrap();
+        assert_eq!(log_path.version, 3);
+        assert!(log_path.is_compaction());
+        assert_eq!(log_path.extension, "json");
+        assert!(matches!(
+            log_path.file_type,
+            LogPathFileType::CompactedCommit { hi: 10 }
+        ));
+        assert_eq!(
+            log_path.filename,
+            "00000000000000000003.00000000000000000010.compacted.json"
+        );
+    }
+
+    #[test]
+    fn test_new_uuid_checkpoint() {
+        let table_log_dir = table_log_dir_url();
+        for extension in ["json", "parquet"] {
+            let log_path =
+                ParsedLogPath::new_uuid_checkpoint(&table_log_dir, 10, extension).unwrap();
+            assert_eq!(log_path.version, 10);
+            assert!(log_path.is_checkpoint());
+            assert_eq!(log_path.extension, extension);
+            assert!(matches!(
+                log_path.file_type,
+                LogPathFileType::UuidCheckpoint(_)
+            ));
+            assert!(log_path
+                .filename
+                .starts_with("00000000000000000010.checkpoint."));
+        }
+    }
 }
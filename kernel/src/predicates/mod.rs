@@ -193,7 +193,7 @@ pub(crate) trait PredicateEvaluator {
             }
         };
         match op {
-            Plus | Minus | Multiply | Divide => None, // Unsupported - not boolean output
+            Plus | Minus | Multiply | Divide | Coalesce => None, // Unsupported - not boolean output
             LessThan => self.eval_lt(col, val, inverted),
             GreaterThanOrEqual => self.eval_lt(col, val, !inverted),
             LessThanOrEqual => self.eval_le(col, val, inverted),
@@ -219,13 +219,14 @@ pub(crate) trait PredicateEvaluator {
 
     /// Dispatches an expression to the specific implementation for each expression variant.
     ///
-    /// NOTE: [`Expression::Struct`] is not supported and always evaluates to `None`.
+    /// NOTE: [`Expression::Struct`], [`Expression::Cast`] and [`Expression::RowIndex`] are not
+    /// supported and always evaluate to `None`.
     fn eval_expr(&self, expr: &Expr, inverted: bool) -> Option<Self::Output> {
         use Expr::*;
         match expr {
             Literal(val) => self.eval_scalar(val, inverted),
             Column(col) => self.eval_column(col, inverted),
-            Struct(_) => None, // not supported
+            Struct(_) | Cast(_) | RowIndex => None, // not supported
             Unary(UnaryExpression { op, expr }) => self.eval_unary(*op, expr, inverted),
             Binary(BinaryExpression { op, left, right }) => {
                 self.eval_binary(*op, left, right, inverted)
@@ -44,8 +44,10 @@ impl AddRemoveDedupVisitor<'_> {
     const ADD_PATH_INDEX: usize = 0;
     const ADD_PARTITION_VALUES_INDEX: usize = 1;
     const ADD_DV_START_INDEX: usize = 2;
-    const REMOVE_PATH_INDEX: usize = 5;
-    const REMOVE_DV_START_INDEX: usize = 6;
+    const ADD_BASE_ROW_ID_INDEX: usize = 5;
+    const ADD_DEFAULT_ROW_COMMIT_VERSION_INDEX: usize = 6;
+    const REMOVE_PATH_INDEX: usize = 7;
+    const REMOVE_DV_START_INDEX: usize = 8;
 
     fn new(
         seen: &mut HashSet<FileActionKey>,
@@ -100,16 +102,18 @@ impl AddRemoveDedupVisitor<'_> {
                 TransformExpr::Partition(field_idx) => {
                     Some(self.parse_partition_value(*field_idx, partition_values))
                 }
-                TransformExpr::Static(_) => None,
+                TransformExpr::Static(_) | TransformExpr::RowTracking(_) => None,
             })
             .try_collect()
     }
 
-    /// Compute an expression that will transform from physical to logical for a given Add file action
+    /// Compute an expression that will transform from physical to logical for a given Add file
+    /// action, with the row tracking values of the file (`baseRowId` and `defaultRowCommitVersion`)
     fn get_transform_expr(
         &self,
         transform: &Transform,
         mut partition_values: HashMap<usize, (String, Scalar)>,
+        row_tracking_values: (Option<i64>, Option<i64>),
     ) -> DeltaResult<ExpressionRef> {
         let transforms = transform
             .iter()
@@ -123,6 +127,16 @@ impl AddRemoveDedupVisitor<'_> {
                     Ok(partition_value.into())
                 }
                 TransformExpr::Static(field_expr) => Ok(field_expr.clone()),
+                TransformExpr::RowTracking(materialized) => {
+                    let (Some(base_row_id), Some(default_row_commit_version)) =
+                        row_tracking_values
+                    else {
+                        return Err(Error::generic(
+                            "Row tracking is enabled but a file is missing its baseRowId or defaultRowCommitVersion",
+                        ));
+                    };
+                    Ok(materialized.row_tracking_expression(base_row_id, default_row_commit_version))
+                }
             })
             .try_collect()?;
         Ok(Arc::new(Expression::Struct(transforms)))
@@ -151,7 +165,7 @@ impl AddRemoveDedupVisitor<'_> {
     fn is_valid_add<'a>(&mut self, i: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<bool> {
         // When processing file actions, we extract path and deletion vector information based on action type:
         // - For Add actions: path is at index 0, followed by DV fields at indexes 2-4
-        // - For Remove actions (in log batches only): path is at index 5, followed by DV fields at indexes 6-8
+        // - For Remove actions (in log batches only): path is at index 7, followed by DV fields at indexes 8-10
         // The file extraction logic selects the appropriate indexes based on whether we found a valid path.
         // Remove getters are not included when visiting a non-log batch (checkpoint batch), so do
         // not try to extract remove actions in that case.
@@ -190,7 +204,14 @@ impl AddRemoveDedupVisitor<'_> {
         let transform = self
             .transform
             .as_ref()
-            .map(|transform| self.get_transform_expr(transform, partition_values))
+            .map(|transform| {
+                let row_tracking_values = (
+                    getters[Self::ADD_BASE_ROW_ID_INDEX].get_opt(i, "add.baseRowId")?,
+                    getters[Self::ADD_DEFAULT_ROW_COMMIT_VERSION_INDEX]
+                        .get_opt(i, "add.defaultRowCommitVersion")?,
+                );
+                self.get_transform_expr(transform, partition_values, row_tracking_values)
+            })
             .transpose()?;
         if transform.is_some() {
             // fill in any needed `None`s for previous rows
@@ -207,6 +228,7 @@ impl RowVisitor for AddRemoveDedupVisitor<'_> {
         static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
             const STRING: DataType = DataType::STRING;
             const INTEGER: DataType = DataType::INTEGER;
+            const LONG: DataType = DataType::LONG;
             let ss_map: DataType = MapType::new(STRING, STRING, true).into();
             let types_and_names = vec![
                 (STRING, column_name!("add.path")),
@@ -214,6 +236,8 @@ impl RowVisitor for AddRemoveDedupVisitor<'_> {
                 (STRING, column_name!("add.deletionVector.storageType")),
                 (STRING, column_name!("add.deletionVector.pathOrInlineDv")),
                 (INTEGER, column_name!("add.deletionVector.offset")),
+                (LONG, column_name!("add.baseRowId")),
+                (LONG, column_name!("add.defaultRowCommitVersion")),
                 (STRING, column_name!("remove.path")),
                 (STRING, column_name!("remove.deletionVector.storageType")),
                 (STRING, column_name!("remove.deletionVector.pathOrInlineDv")),
@@ -228,13 +252,13 @@ impl RowVisitor for AddRemoveDedupVisitor<'_> {
         } else {
             // All checkpoint actions are already reconciled and Remove actions in checkpoint files
             // only serve as tombstones for vacuum jobs. So we only need to examine the adds here.
-            (&names[..5], &types[..5])
+            (&names[..7], &types[..7])
         }
     }
 
     fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
         let is_log_batch = self.deduplicator.is_log_batch();
-        let expected_getters = if is_log_batch { 9 } else { 5 };
+        let expected_getters = if is_log_batch { 11 } else { 7 };
         require!(
             getters.len() == expected_getters,
             Error::InternalError(format!(
@@ -258,8 +282,11 @@ impl RowVisitor for AddRemoveDedupVisitor<'_> {
 pub(crate) static SCAN_ROW_SCHEMA: LazyLock<Arc<StructType>> = LazyLock::new(|| {
     // Note that fields projected out of a nullable struct must be nullable
     let partition_values = MapType::new(DataType::STRING, DataType::STRING, true);
-    let file_constant_values =
-        StructType::new([StructField::nullable("partitionValues", partition_values)]);
+    let file_constant_values = StructType::new([
+        StructField::nullable("partitionValues", partition_values),
+        StructField::nullable("baseRowId", DataType::LONG),
+        StructField::nullable("defaultRowCommitVersion", DataType::LONG),
+    ]);
     let deletion_vector = StructType::new([
         StructField::nullable("storageType", DataType::STRING),
         StructField::nullable("pathOrInlineDv", DataType::STRING),
@@ -287,7 +314,11 @@ fn get_add_transform_expr() -> Expression {
         column_expr!("add.modificationTime"),
         column_expr!("add.stats"),
         column_expr!("add.deletionVector"),
-        Expression::Struct(vec![column_expr!("add.partitionValues")]),
+        Expression::Struct(vec![
+            column_expr!("add.partitionValues"),
+            column_expr!("add.baseRowId"),
+            column_expr!("add.defaultRowCommitVersion"),
+        ]),
     ])
 }
 
@@ -459,8 +490,11 @@ mod tests {
             StructField::new("date", DataType::DATE, true),
         ]));
         let partition_cols = ["date".to_string()];
-        let state_info = get_state_info(schema.as_ref(), &partition_cols).unwrap();
-        let static_transform = Some(Arc::new(Scan::get_static_transform(&state_info.all_fields)));
+        let state_info = get_state_info(schema.as_ref(), &partition_cols, None).unwrap();
+        let static_transform = Some(Arc::new(Scan::get_static_transform(
+            &state_info.all_fields,
+            None,
+        )));
         let batch = vec![add_batch_with_partition_col()];
         let iter = scan_action_iter(
             &SyncEngine::new(),
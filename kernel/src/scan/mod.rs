@@ -20,7 +20,11 @@ use crate::schema::{
     StructType,
 };
 use crate::snapshot::Snapshot;
+use crate::table_features::row_tracking::{
+    metadata_column_field, row_index_field, MaterializedRowTrackingColumns, METADATA_COLUMN_NAME,
+};
 use crate::table_features::ColumnMappingMode;
+use crate::utils::require;
 use crate::{DeltaResult, Engine, EngineData, Error, FileMeta};
 
 use self::log_replay::scan_action_iter;
@@ -35,6 +39,7 @@ pub struct ScanBuilder {
     snapshot: Arc<Snapshot>,
     schema: Option<SchemaRef>,
     predicate: Option<ExpressionRef>,
+    row_tracking_columns: bool,
 }
 
 impl std::fmt::Debug for ScanBuilder {
@@ -42,6 +47,7 @@ impl std::fmt::Debug for ScanBuilder {
         f.debug_struct("ScanBuilder")
             .field("schema", &self.schema)
             .field("predicate", &self.predicate)
+            .field("row_tracking_columns", &self.row_tracking_columns)
             .finish()
     }
 }
@@ -53,6 +59,7 @@ impl ScanBuilder {
             snapshot: snapshot.into(),
             schema: None,
             predicate: None,
+            row_tracking_columns: false,
         }
     }
 
@@ -88,6 +95,18 @@ impl ScanBuilder {
         self
     }
 
+    /// Add the row tracking columns of the table to the scan, as a non-nullable `_metadata` struct
+    /// column appended to the schema of the scan, with the `row_id` and `row_commit_version` (both
+    /// `long`) of every row. Row IDs are stable across the rewrites of the files that materialize
+    /// them (e.g. OPTIMIZE).
+    ///
+    /// Building the scan fails with [`Error::Unsupported`] if row tracking is not enabled on the
+    /// table (`delta.enableRowTracking`), or if the schema of the scan has a `_metadata` column.
+    pub fn with_row_tracking_columns(mut self) -> Self {
+        self.row_tracking_columns = true;
+        self
+    }
+
     /// Build the [`Scan`].
     ///
     /// This does not scan the table at this point, but does do some work to ensure that the
@@ -96,10 +115,31 @@ impl ScanBuilder {
     /// perform actual data reads.
     pub fn build(self) -> DeltaResult<Scan> {
         // if no schema is provided, use snapshot's entire schema (e.g. SELECT *)
-        let logical_schema = self.schema.unwrap_or_else(|| self.snapshot.schema());
+        let mut logical_schema = self.schema.unwrap_or_else(|| self.snapshot.schema());
+        let row_tracking = if self.row_tracking_columns {
+            let table_configuration = self.snapshot.table_configuration();
+            require!(
+                table_configuration.is_row_tracking_enabled(),
+                Error::unsupported("Row tracking is not enabled on the table")
+            );
+            require!(
+                logical_schema.field(METADATA_COLUMN_NAME).is_none(),
+                Error::unsupported(format!(
+                    "Cannot add the row tracking columns to a schema with a {METADATA_COLUMN_NAME} column"
+                ))
+            );
+            let fields = logical_schema.fields().cloned();
+            logical_schema = Arc::new(StructType::new(fields.chain([metadata_column_field()])));
+            Some(Arc::new(MaterializedRowTrackingColumns::new(
+                table_configuration,
+            )))
+        } else {
+            None
+        };
         let state_info = get_state_info(
             logical_schema.as_ref(),
             &self.snapshot.metadata().partition_columns,
+            row_tracking.as_deref(),
         )?;
 
         let physical_predicate = match self.predicate {
@@ -114,6 +154,7 @@ impl ScanBuilder {
             physical_predicate,
             all_fields: Arc::new(state_info.all_fields),
             have_partition_cols: state_info.have_partition_cols,
+            row_tracking,
         })
     }
 }
@@ -297,6 +338,9 @@ pub enum ColumnType {
     Selected(String),
     // A partition column that needs to be added back in
     Partition(usize),
+    // The row tracking metadata column, computed from the row tracking columns that are read and
+    // the row tracking values of the file
+    RowTracking,
 }
 
 /// A transform is ultimately a `Struct` expr. This holds the set of expressions that make that struct expr up
@@ -318,6 +362,7 @@ pub fn get_transform_for_row(
 pub(crate) enum TransformExpr {
     Static(Expression),
     Partition(usize),
+    RowTracking(Arc<MaterializedRowTrackingColumns>),
 }
 
 // TODO(nick): Make this a struct in a follow-on PR
@@ -333,6 +378,7 @@ pub struct Scan {
     physical_predicate: PhysicalPredicate,
     all_fields: Arc<Vec<ColumnType>>,
     have_partition_cols: bool,
+    row_tracking: Option<Arc<MaterializedRowTrackingColumns>>,
 }
 
 impl std::fmt::Debug for Scan {
@@ -364,7 +410,10 @@ impl Scan {
     /// Convert the parts of the transform that can be computed statically into `Expression`s. For
     /// parts that cannot be computed statically, include enough metadata so lower levels of
     /// processing can create and fill in an expression.
-    fn get_static_transform(all_fields: &[ColumnType]) -> Transform {
+    fn get_static_transform(
+        all_fields: &[ColumnType],
+        row_tracking: Option<&Arc<MaterializedRowTrackingColumns>>,
+    ) -> Transform {
         all_fields
             .iter()
             .map(|field| match field {
@@ -372,6 +421,9 @@ impl Scan {
                     TransformExpr::Static(ColumnName::new([col_name]).into())
                 }
                 ColumnType::Partition(idx) => TransformExpr::Partition(*idx),
+                ColumnType::RowTracking => {
+                    TransformExpr::RowTracking(row_tracking.cloned().unwrap_or_default())
+                }
             })
             .collect()
     }
@@ -400,11 +452,17 @@ impl Scan {
         engine: &dyn Engine,
     ) -> DeltaResult<impl Iterator<Item = DeltaResult<ScanData>>> {
         // Compute the static part of the transformation. This is `None` if no transformation is
-        // needed (currently just means no partition cols AND no column mapping but will be extended
-        // for other transforms as we support them)
+        // needed (currently just means no partition cols, no column mapping AND no row tracking
+        // columns but will be extended for other transforms as we support them)
         let static_transform = (self.have_partition_cols
-            || self.snapshot.column_mapping_mode() != ColumnMappingMode::None)
-            .then(|| Arc::new(Scan::get_static_transform(&self.all_fields)));
+            || self.snapshot.column_mapping_mode() != ColumnMappingMode::None
+            || self.row_tracking.is_some())
+        .then(|| {
+            Arc::new(Scan::get_static_transform(
+                &self.all_fields,
+                self.row_tracking.as_ref(),
+            ))
+        });
         let physical_predicate = match self.physical_predicate.clone() {
             PhysicalPredicate::StaticSkipAll => return Ok(None.into_iter().flatten()),
             PhysicalPredicate::Some(predicate, schema) => Some((predicate, schema)),
@@ -461,12 +519,6 @@ impl Scan {
         &self,
         engine: Arc<dyn Engine>,
     ) -> DeltaResult<impl Iterator<Item = DeltaResult<ScanResult>>> {
-        struct ScanFile {
-            path: String,
-            size: i64,
-            dv_info: DvInfo,
-            transform: Option<ExpressionRef>,
-        }
         fn scan_data_callback(
             batches: &mut Vec<ScanFile>,
             path: &str,
@@ -489,10 +541,6 @@ impl Scan {
             self.logical_schema, self.physical_schema
         );
 
-        let global_state = Arc::new(self.global_scan_state());
-        let table_root = self.snapshot.table_root().clone();
-        let physical_predicate = self.physical_predicate();
-
         let scan_data = self.scan_data(engine.as_ref())?;
         let scan_files_iter = scan_data
             .map(|res| {
@@ -508,8 +556,20 @@ impl Scan {
             })
             // Iterator<DeltaResult<Vec<ScanFile>>> to Iterator<DeltaResult<ScanFile>>
             .flatten_ok();
+        self.execute_scan_files(engine, scan_files_iter)
+    }
 
-        let result = scan_files_iter
+    // Read the data of the given scan files, with the masks of their deletion vectors
+    pub(crate) fn execute_scan_files(
+        &self,
+        engine: Arc<dyn Engine>,
+        scan_files: impl Iterator<Item = DeltaResult<ScanFile>>,
+    ) -> DeltaResult<impl Iterator<Item = DeltaResult<ScanResult>>> {
+        let global_state = Arc::new(self.global_scan_state());
+        let table_root = self.snapshot.table_root().clone();
+        let physical_predicate = self.physical_predicate();
+
+        let result = scan_files
             .map(move |scan_file| -> DeltaResult<_> {
                 let scan_file = scan_file?;
                 let file_path = table_root.join(&scan_file.path)?;
@@ -568,6 +628,15 @@ impl Scan {
     }
 }
 
+// A data file to read, as visited from the scan rows
+#[derive(Debug, Clone)]
+pub(crate) struct ScanFile {
+    pub(crate) path: String,
+    pub(crate) size: i64,
+    pub(crate) dv_info: DvInfo,
+    pub(crate) transform: Option<ExpressionRef>,
+}
+
 /// Get the schema that scan rows (from [`Scan::scan_data`]) will be returned with.
 ///
 /// It is:
@@ -585,7 +654,9 @@ impl Scan {
 ///      cardinality: long,
 ///    },
 ///    fileConstantValues: {
-///      partitionValues: map<string, string>
+///      partitionValues: map<string, string>,
+///      baseRowId: long,
+///      defaultRowCommitVersion: long,
 ///    }
 /// }
 /// ```
@@ -616,8 +687,14 @@ struct StateInfo {
     have_partition_cols: bool,
 }
 
-/// Get the state needed to process a scan, see [`StateInfo`] for details.
-fn get_state_info(logical_schema: &Schema, partition_columns: &[String]) -> DeltaResult<StateInfo> {
+/// Get the state needed to process a scan, see [`StateInfo`] for details. If `row_tracking` is given,
+/// the `_metadata` column of the schema is the row tracking column, which reads the row index and
+/// the materialized row tracking columns.
+fn get_state_info(
+    logical_schema: &Schema,
+    partition_columns: &[String],
+    row_tracking: Option<&MaterializedRowTrackingColumns>,
+) -> DeltaResult<StateInfo> {
     let mut have_partition_cols = false;
     let mut read_fields = Vec::with_capacity(logical_schema.fields.len());
     // Loop over all selected fields and note if they are columns that will be read from the
@@ -633,6 +710,12 @@ fn get_state_info(logical_schema: &Schema, partition_columns: &[String]) -> Delt
                 // data type, which we need to properly materialize the column.
                 have_partition_cols = true;
                 Ok(ColumnType::Partition(index))
+            } else if let Some(row_tracking) =
+                row_tracking.filter(|_| logical_field.name() == METADATA_COLUMN_NAME)
+            {
+                read_fields.push(row_index_field());
+                read_fields.extend(row_tracking.read_fields());
+                Ok(ColumnType::RowTracking)
             } else {
                 // Add to read schema, store field so we can build a `Column` expression later
                 // if needed (i.e. if we have partition columns)
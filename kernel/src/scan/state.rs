@@ -83,6 +83,24 @@ impl DvInfo {
         Ok(dv_treemap.map(deletion_treemap_to_bools))
     }
 
+    /// Returns the rows deleted from this file once `deleted_rows` are deleted as well, i.e. the
+    /// union of `deleted_rows` and the rows already deleted by this file's deletion vector. This is
+    /// the bitmap to write as the file's new deletion vector.
+    pub fn merge_deleted_rows(
+        &self,
+        engine: &dyn Engine,
+        table_root: &url::Url,
+        deleted_rows: RoaringTreemap,
+    ) -> DeltaResult<RoaringTreemap> {
+        match self.deletion_vector {
+            Some(ref dv_descriptor) => {
+                let fs_client = engine.get_file_system_client();
+                dv_descriptor.merge(fs_client, table_root, &deleted_rows)
+            }
+            None => Ok(deleted_rows),
+        }
+    }
+
     /// Returns a vector of row indexes that should be *removed* from the result set
     pub fn get_row_indexes(
         &self,
@@ -194,7 +212,7 @@ impl<T> RowVisitor for ScanFileVisitor<'_, T> {
     }
     fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
         require!(
-            getters.len() == 10,
+            getters.len() == 12,
             Error::InternalError(format!(
                 "Wrong number of ScanFileVisitor getters: {}",
                 getters.len()
@@ -1,5 +1,7 @@
 //! Provides utilities to perform comparisons between a [`Schema`]s. The api used to check schema
-//! compatibility is [`can_read_as`] that is exposed through the [`SchemaComparison`] trait.
+//! compatibility is [`can_read_as`] that is exposed through the [`SchemaComparison`] trait. When
+//! the data was written with narrower types that have since been widened (see the `typeWidening`
+//! table feature), [`can_read_as_widened`] can be used instead.
 //!
 //! # Examples
 //!  ```rust, ignore
@@ -20,11 +22,13 @@
 //!  ````
 //!
 //! [`Schema`]: crate::schema::Schema
+//! [`can_read_as`]: SchemaComparison::can_read_as
+//! [`can_read_as_widened`]: SchemaComparison::can_read_as_widened
 use std::collections::{HashMap, HashSet};
 
 use crate::utils::require;
 
-use super::{DataType, StructField, StructType};
+use super::{DataType, PrimitiveType, StructField, StructType};
 
 /// The nullability flag of a schema's field. This can be compared with a read schema field's
 /// nullability flag using [`Nullable::can_read_as`].
@@ -59,13 +63,25 @@ pub(crate) type SchemaComparisonResult = Result<(), Error>;
 /// TODO (Oussama): Remove the `allow(unused)` once this is used in CDF.
 #[allow(unused)]
 pub(crate) trait SchemaComparison {
-    fn can_read_as(&self, read_type: &Self) -> SchemaComparisonResult;
+    /// Check whether `self` can be read as `read_type`. When `allow_type_widening` is set,
+    /// primitive types may be widened in `read_type` (see [`can_widen_to`]).
+    fn check_read_as(&self, read_type: &Self, allow_type_widening: bool) -> SchemaComparisonResult;
+
+    /// Check whether `self` can be read as `read_type`, with primitive types matching exactly.
+    fn can_read_as(&self, read_type: &Self) -> SchemaComparisonResult {
+        self.check_read_as(read_type, false)
+    }
+
+    /// Check whether `self` can be read as `read_type`, where primitive types may be widened.
+    fn can_read_as_widened(&self, read_type: &Self) -> SchemaComparisonResult {
+        self.check_read_as(read_type, true)
+    }
 }
 
 impl SchemaComparison for Nullable {
     /// Represents a nullability comparison between two schemas' fields. Returns true if the
     /// read nullability is the same or wider than the nullability of self.
-    fn can_read_as(&self, read_nullable: &Nullable) -> SchemaComparisonResult {
+    fn check_read_as(&self, read_nullable: &Nullable, _: bool) -> SchemaComparisonResult {
         // The case to avoid is when the column is nullable, but the read schema specifies the
         // column as non-nullable. So we avoid the case where !read_nullable && nullable
         // Hence we check that !(!read_nullable && existing_nullable)
@@ -81,10 +97,15 @@ impl SchemaComparison for StructField {
     ///     1. The read schema field mustn't be non-nullable if this [`StructField`] is nullable.
     ///     2. The both this field and `read_field` must have the same name.
     ///     3. You can read this data type as the `read_field`'s data type.
-    fn can_read_as(&self, read_field: &Self) -> SchemaComparisonResult {
+    fn check_read_as(
+        &self,
+        read_field: &Self,
+        allow_type_widening: bool,
+    ) -> SchemaComparisonResult {
         Nullable(self.nullable).can_read_as(&Nullable(read_field.nullable))?;
         require!(self.name() == read_field.name(), Error::FieldNameMismatch);
-        self.data_type().can_read_as(read_field.data_type())?;
+        self.data_type()
+            .check_read_as(read_field.data_type(), allow_type_widening)?;
         Ok(())
     }
 }
@@ -97,7 +118,7 @@ impl SchemaComparison for StructType {
     ///     4. Both [`StructTypes`] must be valid schemas. No two fields of a structs may share a
     ///        name that only differs by case. TODO: This check should be moved into the constructor
     ///        for [`StructType`].
-    fn can_read_as(&self, read_type: &Self) -> SchemaComparisonResult {
+    fn check_read_as(&self, read_type: &Self, allow_type_widening: bool) -> SchemaComparisonResult {
         let lowercase_field_map: HashMap<String, &StructField> = self
             .fields
             .iter()
@@ -124,7 +145,9 @@ impl SchemaComparison for StructType {
         }
         for read_field in read_type.fields() {
             match lowercase_field_map.get(&read_field.name().to_lowercase()) {
-                Some(existing_field) => existing_field.can_read_as(read_field)?,
+                Some(existing_field) => {
+                    existing_field.check_read_as(read_field, allow_type_widening)?
+                }
                 None => {
                     // Note: Delta spark does not perform the following check. Hence it ignores
                     // non-null fields that exist in the read schema that aren't in this schema.
@@ -138,34 +161,37 @@ impl SchemaComparison for StructType {
 
 impl SchemaComparison for DataType {
     /// Returns `Ok` if this [`DataType`] can be read as `read_type`. This is the case when:
-    ///     1. The data types are the same. Note: This condition will be relaxed to include
-    ///        compatible data types with type widening. See issue [`#623`]
+    ///     1. The data types are the same, or if type widening is allowed, this primitive type can
+    ///        be widened to the `read_type` (see [`can_widen_to`]).
     ///     2. For complex data types, the nested types must be compatible as defined by [`SchemaComparison`]
     ///     3. For array data types, the nullability may not be tightened in the `read_type`. See
     ///        [`Nullable::can_read_as`]
-    ///
-    /// [`#623`]: <https://github.com/delta-io/delta-kernel-rs/issues/623>
-    fn can_read_as(&self, read_type: &Self) -> SchemaComparisonResult {
+    fn check_read_as(&self, read_type: &Self, allow_type_widening: bool) -> SchemaComparisonResult {
         match (self, read_type) {
             (Self::Array(self_array), Self::Array(read_array)) => {
                 Nullable(self_array.contains_null())
                     .can_read_as(&Nullable(read_array.contains_null()))?;
                 self_array
                     .element_type()
-                    .can_read_as(read_array.element_type())?;
+                    .check_read_as(read_array.element_type(), allow_type_widening)?;
             }
             (Self::Struct(self_struct), Self::Struct(read_struct)) => {
-                self_struct.can_read_as(read_struct)?
+                self_struct.check_read_as(read_struct, allow_type_widening)?
             }
             (Self::Map(self_map), Self::Map(read_map)) => {
                 Nullable(self_map.value_contains_null())
                     .can_read_as(&Nullable(read_map.value_contains_null()))?;
-                self_map.key_type().can_read_as(read_map.key_type())?;
-                self_map.value_type().can_read_as(read_map.value_type())?;
+                self_map
+                    .key_type()
+                    .check_read_as(read_map.key_type(), allow_type_widening)?;
+                self_map
+                    .value_type()
+                    .check_read_as(read_map.value_type(), allow_type_widening)?;
+            }
+            (Self::Primitive(a), Self::Primitive(b)) if allow_type_widening => {
+                require!(a == b || can_widen_to(a, b), Error::TypeMismatch);
             }
             (a, b) => {
-                // TODO: In the future, we will change this to support type widening.
-                // See: #623
                 require!(a == b, Error::TypeMismatch);
             }
         };
@@ -173,6 +199,33 @@ impl SchemaComparison for DataType {
     }
 }
 
+/// Returns `true` if values of type `from` can be read as the wider type `to`, following the type
+/// changes supported by the [type widening] table feature.
+///
+/// [type widening]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#type-widening
+pub(crate) fn can_widen_to(from: &PrimitiveType, to: &PrimitiveType) -> bool {
+    use PrimitiveType::*;
+    // the number of integer digits a decimal needs for an integral type to be widened to it: the
+    // protocol requires 10 for all of byte, short and int
+    let integral_digits = |t: &PrimitiveType| match t {
+        Byte | Short | Integer => Some(10),
+        Long => Some(20),
+        _ => None,
+    };
+    match (from, to) {
+        (Byte, Short | Integer | Long | Double) => true,
+        (Short, Integer | Long | Double) => true,
+        (Integer, Long | Double) => true,
+        (Float, Double) => true,
+        (Date, TimestampNtz) => true,
+        (Decimal(p, s), Decimal(to_p, to_s)) => {
+            to_p >= p && to_s >= s && to_p - to_s >= p - s && (p, s) != (to_p, to_s)
+        }
+        (from, Decimal(p, s)) => integral_digits(from).is_some_and(|digits| p - s >= digits),
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::schema::compare::{Error, SchemaComparison};
@@ -398,4 +451,73 @@ mod tests {
             Err(Error::InvalidSchema)
         ));
     }
+
+    #[test]
+    fn widen_types() {
+        let existing_schema = StructType::new([
+            StructField::new("id", DataType::INTEGER, false),
+            StructField::new("scores", ArrayType::new(DataType::FLOAT, true), true),
+            StructField::new(
+                "nested",
+                StructType::new([StructField::new("date", DataType::DATE, true)]),
+                true,
+            ),
+        ]);
+        let read_schema = StructType::new([
+            StructField::new("id", DataType::LONG, false),
+            StructField::new("scores", ArrayType::new(DataType::DOUBLE, true), true),
+            StructField::new(
+                "nested",
+                StructType::new([StructField::new("date", DataType::TIMESTAMP_NTZ, true)]),
+                true,
+            ),
+        ]);
+        assert!(matches!(
+            existing_schema.can_read_as(&read_schema),
+            Err(Error::TypeMismatch)
+        ));
+        assert!(existing_schema.can_read_as_widened(&read_schema).is_ok());
+        // types can't be narrowed
+        assert!(matches!(
+            read_schema.can_read_as_widened(&existing_schema),
+            Err(Error::TypeMismatch)
+        ));
+    }
+
+    #[test]
+    fn widen_primitive_types() {
+        use crate::schema::compare::can_widen_to;
+        use crate::schema::PrimitiveType::*;
+
+        let widenings = [
+            (Byte, Short),
+            (Short, Long),
+            (Integer, Double),
+            (Float, Double),
+            (Date, TimestampNtz),
+            (Decimal(10, 2), Decimal(12, 4)),
+            (Integer, Decimal(10, 0)),
+            (Byte, Decimal(12, 2)),
+            (Long, Decimal(22, 2)),
+        ];
+        for (from, to) in widenings {
+            assert!(can_widen_to(&from, &to), "{from} -> {to}");
+        }
+        let non_widenings = [
+            (Long, Integer),
+            (Long, Double),
+            (Integer, Float),
+            (String, Binary),
+            (Timestamp, TimestampNtz),
+            (Decimal(10, 2), Decimal(10, 2)),
+            (Decimal(10, 2), Decimal(11, 4)),
+            (Decimal(10, 2), Decimal(10, 1)),
+            (Integer, Decimal(10, 2)),
+            (Byte, Decimal(3, 0)),
+            (Short, Decimal(9, 0)),
+        ];
+        for (from, to) in non_widenings {
+            assert!(!can_widen_to(&from, &to), "{from} -> {to}");
+        }
+    }
 }
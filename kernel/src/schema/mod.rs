@@ -3,6 +3,7 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 use std::sync::Arc;
 
 use indexmap::IndexMap;
@@ -83,6 +84,8 @@ pub enum ColumnMetadataKey {
     IdentityHighWaterMark,
     IdentityAllowExplicitInsert,
     Invariants,
+    TypeChanges,
+    MetadataSpec,
 }
 
 impl AsRef<str> for ColumnMetadataKey {
@@ -96,6 +99,43 @@ impl AsRef<str> for ColumnMetadataKey {
             Self::IdentityStart => "delta.identity.start",
             Self::IdentityStep => "delta.identity.step",
             Self::Invariants => "delta.invariants",
+            Self::TypeChanges => "delta.typeChanges",
+            Self::MetadataSpec => "delta.metadataSpec",
+        }
+    }
+}
+
+/// The metadata columns that an engine can be asked to produce when reading parquet files. Unlike
+/// data columns, metadata columns are not read from the files: the reader generates their values.
+/// A metadata column is marked with the [`ColumnMetadataKey::MetadataSpec`] key of its field, see
+/// [`StructField::create_metadata_column`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataColumnSpec {
+    /// The (LONG) index of each row within its parquet file, starting at 0.
+    RowIndex,
+}
+
+impl MetadataColumnSpec {
+    fn text_value(&self) -> &'static str {
+        match self {
+            Self::RowIndex => "row_index",
+        }
+    }
+
+    fn data_type(&self) -> DataType {
+        match self {
+            Self::RowIndex => DataType::LONG,
+        }
+    }
+}
+
+impl FromStr for MetadataColumnSpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "row_index" => Ok(Self::RowIndex),
+            _ => Err(Error::generic(format!("Unknown metadata column spec: {s}"))),
         }
     }
 }
@@ -134,6 +174,13 @@ impl StructField {
         Self::new(name, data_type, false)
     }
 
+    /// Creates a new (non-nullable) metadata column of the given spec. The reader generates the values
+    /// of the column instead of reading them from the file.
+    pub fn create_metadata_column(name: impl Into<String>, spec: MetadataColumnSpec) -> Self {
+        Self::not_null(name, spec.data_type())
+            .with_metadata([(ColumnMetadataKey::MetadataSpec.as_ref(), spec.text_value())])
+    }
+
     pub fn with_metadata(
         mut self,
         metadata: impl IntoIterator<Item = (impl Into<String>, impl Into<MetadataValue>)>,
@@ -149,6 +196,19 @@ impl StructField {
         self.metadata.get(key.as_ref())
     }
 
+    /// The spec of this field if it is a metadata column, see [`Self::create_metadata_column`].
+    pub fn get_metadata_column_spec(&self) -> Option<MetadataColumnSpec> {
+        match self.get_config_value(&ColumnMetadataKey::MetadataSpec) {
+            Some(MetadataValue::String(spec)) => spec.parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Whether this field is a metadata column, see [`Self::create_metadata_column`].
+    pub fn is_metadata_column(&self) -> bool {
+        self.get_metadata_column_spec().is_some()
+    }
+
     /// Get the physical name for this field as it should be read from parquet.
     ///
     /// NOTE: Caller affirms that the schema was already validated by
@@ -2,20 +2,32 @@
 //! has schema etc.)
 
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 use tracing::{debug, warn};
 use url::Url;
 
-use crate::actions::{Metadata, Protocol};
-use crate::log_segment::LogSegment;
+use crate::actions::domain_metadata::{domain_metadata_configuration, scan_domain_metadatas};
+use crate::actions::set_transaction::SetTransactionScanner;
+use crate::actions::{get_log_schema, Metadata, Protocol, ADD_NAME, REMOVE_NAME, SIDECAR_NAME};
+use crate::checkpoint::{self, CheckpointBuilder};
+use crate::history::read_in_commit_timestamp;
+use crate::log_segment::{list_log_files, LogSegment};
+use crate::optimize::OptimizeBuilder;
+use crate::scan::log_replay::scan_action_iter;
+use crate::scan::state::{visit_scan_files, DvInfo, Stats};
 use crate::scan::ScanBuilder;
-use crate::schema::{Schema, SchemaRef};
+use crate::schema::{ColumnName, Schema, SchemaRef};
 use crate::table_configuration::TableConfiguration;
+use crate::table_features::clustering::{
+    logical_clustering_column, parse_clustering_columns, CLUSTERING_DOMAIN_NAME,
+};
 use crate::table_features::ColumnMappingMode;
 use crate::table_properties::TableProperties;
-use crate::{DeltaResult, Engine, Error, FileSystemClient, Version};
+use crate::vacuum::{self, VacuumPlan};
+use crate::{DeltaResult, Engine, Error, ExpressionRef, FileSystemClient, Version};
 
-const LAST_CHECKPOINT_FILE_NAME: &str = "_last_checkpoint";
+pub(crate) const LAST_CHECKPOINT_FILE_NAME: &str = "_last_checkpoint";
 // TODO expose methods for accessing the files of a table (with file pruning).
 /// In-memory representation of a specific snapshot of a Delta table. While a `DeltaTable` exists
 /// throughout time, `Snapshot`s represent a view of a table at a specific point in time; they
@@ -131,17 +143,278 @@ impl Snapshot {
         self.table_configuration.column_mapping_mode()
     }
 
+    /// Get the latest transaction version committed by the application `application_id` (see
+    /// [`Transaction::with_transaction_id`]), or `None` if the application has not committed a
+    /// transaction to the table. Transactions that have expired according to the table's
+    /// `delta.setTransactionRetentionDuration` are ignored.
+    ///
+    /// [`Transaction::with_transaction_id`]: crate::transaction::Transaction::with_transaction_id
+    pub fn get_app_id_version(
+        &self,
+        application_id: &str,
+        engine: &dyn Engine,
+    ) -> DeltaResult<Option<i64>> {
+        let txn =
+            SetTransactionScanner::new(self).application_transaction(engine, application_id)?;
+        Ok(txn.map(|txn| txn.version))
+    }
+
+    /// Get the configuration of the metadata domain `domain`, or `None` if the domain doesn't exist
+    /// in the table or was removed.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#domain-metadata>
+    pub fn get_domain_metadata(
+        &self,
+        domain: &str,
+        engine: &dyn Engine,
+    ) -> DeltaResult<Option<String>> {
+        domain_metadata_configuration(&self.log_segment, domain, engine)
+    }
+
+    /// Get the configurations of all the metadata domains of the table by domain, including the
+    /// system-controlled `delta.*` domains. Removed domains are not included.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#domain-metadata>
+    pub fn domain_metadata(&self, engine: &dyn Engine) -> DeltaResult<HashMap<String, String>> {
+        let domain_metadatas = scan_domain_metadatas(&self.log_segment, None, engine)?;
+        Ok(domain_metadatas
+            .into_iter()
+            .map(|(domain, domain_metadata)| (domain, domain_metadata.configuration))
+            .collect())
+    }
+
+    /// Get the (logical) clustering columns of the table, or `None` if the table is not clustered,
+    /// i.e. if it doesn't support the `clustering` writer feature. A clustered table without
+    /// clustering columns has none.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#clustered-table>
+    pub fn clustering_columns(&self, engine: &dyn Engine) -> DeltaResult<Option<Vec<ColumnName>>> {
+        if !self.table_configuration.is_clustering_supported() {
+            return Ok(None);
+        }
+        let Some(configuration) = self.get_domain_metadata(CLUSTERING_DOMAIN_NAME, engine)? else {
+            return Ok(Some(vec![]));
+        };
+        let schema = self.schema();
+        let columns = parse_clustering_columns(&configuration)?
+            .iter()
+            .map(|column| logical_clustering_column(&schema, column))
+            .collect::<DeltaResult<_>>()?;
+        Ok(Some(columns))
+    }
+
+    /// The timestamp (in milliseconds since the Unix epoch) of the commit at the version of this
+    /// snapshot. This is the commit's in-commit timestamp if [in-commit timestamps] are enabled,
+    /// and the modification time of the commit file otherwise.
+    ///
+    /// Returns an error if the commit file has been removed from the log, or if in-commit
+    /// timestamps are enabled but the commit has none.
+    ///
+    /// [in-commit timestamps]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#in-commit-timestamps
+    pub fn commit_timestamp(&self, engine: &dyn Engine) -> DeltaResult<i64> {
+        let version = self.version();
+        let commit = match self.log_segment.ascending_commit_files.last() {
+            Some(commit) if commit.version == version => commit.location.clone(),
+            // the commit is not part of the log segment when the snapshot is read from a
+            // checkpoint at its version
+            _ => {
+                let fs_client = engine.get_file_system_client();
+                let mut files = list_log_files(
+                    fs_client.as_ref(),
+                    &self.log_segment.log_root,
+                    version,
+                    version,
+                )?;
+                files
+                    .find_map(|file| match file {
+                        Ok(file) if !file.is_commit() => None,
+                        file => Some(file.map(|file| file.location)),
+                    })
+                    .transpose()?
+                    .ok_or_else(|| {
+                        Error::generic(format!("Commit file for version {version} not found"))
+                    })?
+            }
+        };
+        if !self.table_configuration.is_in_commit_timestamps_enabled() {
+            return Ok(commit.last_modified);
+        }
+        read_in_commit_timestamp(engine, &commit)?.ok_or_else(|| {
+            Error::generic(format!("In-commit timestamp missing from commit {version}"))
+        })
+    }
+
+    /// Compute summary statistics of the data files in this snapshot, such as their number and
+    /// total size, in a single replay of the log. See [`SnapshotDetails`] for the statistics.
+    pub fn details(&self, engine: &dyn Engine) -> DeltaResult<SnapshotDetails> {
+        let commit_read_schema = get_log_schema().project(&[ADD_NAME, REMOVE_NAME])?;
+        let checkpoint_read_schema = get_log_schema().project(&[ADD_NAME, SIDECAR_NAME])?;
+        let actions = self.log_segment.read_actions(
+            engine,
+            commit_read_schema,
+            checkpoint_read_schema,
+            None,
+        )?;
+        let mut accumulator = DetailsAccumulator {
+            details: SnapshotDetails::default(),
+            num_records: Some(0),
+            partitions: HashSet::new(),
+        };
+        for scan_data in scan_action_iter(engine, actions, self.schema(), None, None) {
+            let (data, selection_vector, transforms) = scan_data?;
+            accumulator = visit_scan_files(
+                data.as_ref(),
+                &selection_vector,
+                &transforms,
+                accumulator,
+                DetailsAccumulator::add_file,
+            )?;
+        }
+        let mut details = accumulator.details;
+        details.num_records = accumulator.num_records;
+        if !self.metadata().partition_columns.is_empty() {
+            details.num_partitions = accumulator.partitions.len() as u64;
+        }
+        Ok(details)
+    }
+
+    /// Write a checkpoint of the table at the version of this snapshot, and update the
+    /// `_last_checkpoint` file to point to it. The checkpoint is written with the engine's
+    /// [`ParquetHandler`], and contains the protocol, metadata, unexpired transaction identifiers
+    /// (see `delta.setTransactionRetentionDuration`), the files in the table and the tombstones of
+    /// removed files that have not expired yet (see `delta.deletedFileRetentionDuration`). Tables
+    /// with `delta.checkpointPolicy = v2` get UUID-named V2 checkpoints, with the files and
+    /// tombstones in sidecar files.
+    ///
+    /// Engines typically write a checkpoint every `delta.checkpointInterval` commits (see
+    /// [`TableProperties::checkpoint_interval`]), so that loading snapshots doesn't require reading
+    /// more and more commits as the table grows.
+    ///
+    /// This is a shorthand for writing the checkpoint of [`Snapshot::checkpoint_builder`] with the
+    /// default options. Returns an error if kernel doesn't support writing to the table. If the
+    /// engine doesn't support writing Parquet files (see [`ParquetHandler::write_parquet_file`]),
+    /// checkpoints with Parquet files fail with [`Error::Unsupported`] before any file is written.
+    ///
+    /// [`ParquetHandler`]: crate::ParquetHandler
+    /// [`ParquetHandler::write_parquet_file`]: crate::ParquetHandler::write_parquet_file
+    pub fn checkpoint(&self, engine: &dyn Engine) -> DeltaResult<()> {
+        self.checkpoint_builder().write(engine)
+    }
+
+    /// Create a [`CheckpointBuilder`] to write a checkpoint of the table at the version of this
+    /// snapshot, e.g. to choose the format of V2 checkpoints or the size of their sidecar files.
+    pub fn checkpoint_builder(&self) -> CheckpointBuilder<'_> {
+        CheckpointBuilder::new(self)
+    }
+
+    /// Plan a VACUUM of the table, i.e. compute the files in the table directory that are no longer
+    /// referenced by the table, nor by the versions readers may still time travel to, and that
+    /// are older than the deleted file retention duration of the table. Nothing is deleted: the
+    /// engine deletes the files of the returned [`VacuumPlan`] itself, or only reports them for a
+    /// dry run. See the [`vacuum`] module for details.
+    ///
+    /// Returns an error if this snapshot is not the latest version of the table, or if the table
+    /// has the `vacuumProtocolCheck` feature and kernel doesn't support writing to it.
+    ///
+    /// [`vacuum`]: crate::vacuum
+    pub fn plan_vacuum(&self, engine: &dyn Engine) -> DeltaResult<VacuumPlan> {
+        vacuum::plan_vacuum(self, engine)
+    }
+
+    /// Write a log compaction file that summarizes the commits from `start_version` to
+    /// `end_version` (inclusive), so that readers can replay it instead of these commits. Unlike a
+    /// checkpoint, a log compaction file only contains the actions that survive in the given range
+    /// of commits, which makes it cheap to write between checkpoints.
+    ///
+    /// Returns an error if `start_version` is not less than `end_version`, if `end_version` is
+    /// after the version of this snapshot, if any of the commits is missing, or if kernel doesn't
+    /// support writing to the table.
+    pub fn compact_log(
+        &self,
+        engine: &dyn Engine,
+        start_version: Version,
+        end_version: Version,
+    ) -> DeltaResult<()> {
+        checkpoint::write_log_compaction(self, engine, start_version, end_version)
+    }
+
     /// Create a [`ScanBuilder`] for an `Arc<Snapshot>`.
     pub fn scan_builder(self: Arc<Self>) -> ScanBuilder {
         ScanBuilder::new(self)
     }
 
+    /// Create an [`OptimizeBuilder`] to plan the compaction of the small files of the table. See
+    /// the [`optimize`] module for details.
+    ///
+    /// [`optimize`]: crate::optimize
+    pub fn optimize_builder(self: Arc<Self>) -> OptimizeBuilder {
+        OptimizeBuilder::new(self)
+    }
+
     /// Consume this `Snapshot` to create a [`ScanBuilder`]
     pub fn into_scan_builder(self) -> ScanBuilder {
         ScanBuilder::new(self)
     }
 }
 
+/// Summary statistics of the data files in a [`Snapshot`], computed by [`Snapshot::details`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SnapshotDetails {
+    /// The number of data files in the snapshot.
+    pub num_files: u64,
+    /// The total size of the data files, in bytes.
+    pub size_in_bytes: u64,
+    /// The total number of records in the data files (from their `numRecords` statistic),
+    /// including the records deleted by deletion vectors. `None` if some files have no statistics.
+    pub num_records: Option<u64>,
+    /// The number of distinct partitions with data files, or 0 if the table is not partitioned.
+    pub num_partitions: u64,
+    /// The size of the smallest data file in bytes, or `None` if there are no data files.
+    pub min_file_size: Option<u64>,
+    /// The size of the largest data file in bytes, or `None` if there are no data files.
+    pub max_file_size: Option<u64>,
+    /// The number of data files with a deletion vector.
+    pub num_files_with_deletion_vectors: u64,
+    /// The total number of records deleted by deletion vectors.
+    pub num_deleted_rows: u64,
+}
+
+// The state of computing the details of a snapshot over its scan files
+struct DetailsAccumulator {
+    details: SnapshotDetails,
+    num_records: Option<u64>,
+    partitions: HashSet<BTreeMap<String, String>>,
+}
+
+impl DetailsAccumulator {
+    fn add_file(
+        &mut self,
+        _path: &str,
+        size: i64,
+        stats: Option<Stats>,
+        dv_info: DvInfo,
+        _transform: Option<ExpressionRef>,
+        partition_values: HashMap<String, String>,
+    ) {
+        let size = size.max(0) as u64;
+        let details = &mut self.details;
+        details.num_files += 1;
+        details.size_in_bytes += size;
+        details.min_file_size = Some(details.min_file_size.map_or(size, |min| min.min(size)));
+        details.max_file_size = Some(details.max_file_size.map_or(size, |max| max.max(size)));
+        if let Some(deletion_vector) = dv_info.deletion_vector {
+            details.num_files_with_deletion_vectors += 1;
+            details.num_deleted_rows += deletion_vector.cardinality.max(0) as u64;
+        }
+        self.num_records = self
+            .num_records
+            .zip(stats)
+            .map(|(num_records, stats)| num_records + stats.num_records);
+        self.partitions
+            .insert(partition_values.into_iter().collect());
+    }
+}
+
 // Note: Schema can not be derived because the checkpoint schema is only known at runtime.
 #[derive(Debug, Deserialize, Serialize)]
 #[serde(rename_all = "camelCase")]
@@ -154,14 +427,19 @@ struct LastCheckpointHint {
     /// The number of actions that are stored in the checkpoint.
     pub(crate) size: i64,
     /// The number of fragments if the last checkpoint was written in multiple parts.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) parts: Option<usize>,
     /// The number of bytes of the checkpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) size_in_bytes: Option<i64>,
     /// The number of AddFile actions in the checkpoint.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) num_of_add_files: Option<i64>,
     /// The schema of the checkpoint file.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) checkpoint_schema: Option<Schema>,
     /// The checksum of the last checkpoint JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub(crate) checksum: Option<String>,
 }
 
@@ -173,7 +451,7 @@ struct LastCheckpointHint {
 /// cause failure.
 ///
 /// TODO: java kernel retries three times before failing, should we do the same?
-fn read_last_checkpoint(
+pub(crate) fn read_last_checkpoint(
     fs_client: &dyn FileSystemClient,
     log_root: &Url,
 ) -> DeltaResult<Option<LastCheckpointHint>> {
@@ -225,6 +503,47 @@ mod tests {
         assert_eq!(snapshot.schema(), expected);
     }
 
+    #[test]
+    fn test_snapshot_details() {
+        let engine = SyncEngine::new();
+        let snapshot = |table: &str| {
+            let path = std::fs::canonicalize(PathBuf::from(table)).unwrap();
+            let url = url::Url::from_directory_path(path).unwrap();
+            Snapshot::try_new(url, &engine, None).unwrap()
+        };
+
+        // the null partition value is a partition of its own
+        let details = snapshot("./tests/data/basic_partitioned/")
+            .details(&engine)
+            .unwrap();
+        let expected = SnapshotDetails {
+            num_files: 6,
+            size_in_bytes: 5 * 751 + 750,
+            num_records: Some(6),
+            num_partitions: 5,
+            min_file_size: Some(750),
+            max_file_size: Some(751),
+            num_files_with_deletion_vectors: 0,
+            num_deleted_rows: 0,
+        };
+        assert_eq!(details, expected);
+
+        let details = snapshot("./tests/data/table-with-dv-small/")
+            .details(&engine)
+            .unwrap();
+        let expected = SnapshotDetails {
+            num_files: 1,
+            size_in_bytes: 635,
+            num_records: Some(10),
+            num_partitions: 0,
+            min_file_size: Some(635),
+            max_file_size: Some(635),
+            num_files_with_deletion_vectors: 1,
+            num_deleted_rows: 2,
+        };
+        assert_eq!(details, expected);
+    }
+
     #[test]
     fn test_new_snapshot() {
         let path =
@@ -2,14 +2,18 @@
 //! the different versions
 
 use std::borrow::Cow;
-use std::ops::Deref;
+use std::ops::{Deref, RangeBounds};
 use std::path::PathBuf;
 
 use url::Url;
 
+use crate::history::{
+    read_history, timestamp_to_version, CommitTimestamps, HistoryEntry, TimestampSearchMode,
+};
+use crate::schema::SchemaRef;
 use crate::snapshot::Snapshot;
 use crate::table_changes::TableChanges;
-use crate::transaction::Transaction;
+use crate::transaction::{CreateTableBuilder, Transaction};
 use crate::{DeltaResult, Engine, Error, Version};
 
 /// In-memory representation of a Delta table, which acts as an immutable root entity for reading
@@ -81,6 +85,45 @@ impl Table {
         Snapshot::try_new(self.location.clone(), engine, version)
     }
 
+    /// Create a [`Snapshot`] of the table as of `timestamp` (in milliseconds since the Unix
+    /// epoch). Depending on `mode`, the snapshot is at the version of the latest commit made at or
+    /// before the timestamp, or of the earliest commit made at or after it.
+    ///
+    /// The timestamp of a commit is its in-commit timestamp if in-commit timestamps were enabled
+    /// when it was made (see [`TableProperties::in_commit_timestamp_enablement_version`]), and the
+    /// modification time of its file otherwise. Returns an error if no commit matches.
+    ///
+    /// [`TableProperties::in_commit_timestamp_enablement_version`]: crate::table_properties::TableProperties::in_commit_timestamp_enablement_version
+    pub fn snapshot_at_timestamp(
+        &self,
+        engine: &dyn Engine,
+        timestamp: i64,
+        mode: TimestampSearchMode,
+    ) -> DeltaResult<Snapshot> {
+        let latest = self.snapshot(engine, None)?;
+        let version = timestamp_to_version(&latest, engine, timestamp, mode)?;
+        if version == latest.version() {
+            return Ok(latest);
+        }
+        self.snapshot(engine, Some(version))
+    }
+
+    /// Read the commit history of the table for the versions in `versions` (e.g. `..` for the
+    /// whole history, or `5..=10`), from the latest commit to the earliest one. Each
+    /// [`HistoryEntry`] describes a commit with the information its writer recorded in the commit's
+    /// `commitInfo` action, such as the operation and its parameters and metrics; the data files
+    /// of the table are not read.
+    ///
+    /// The history stops at the earliest commit still retained in the log, so it may be shorter
+    /// than requested. Versions after the latest version of the table are ignored.
+    pub fn history(
+        &self,
+        engine: &dyn Engine,
+        versions: impl RangeBounds<Version>,
+    ) -> DeltaResult<Vec<HistoryEntry>> {
+        read_history(engine, &self.location, versions)
+    }
+
     /// Create a [`TableChanges`] to get a change data feed for the table between `start_version`,
     /// and `end_version`. If no `end_version` is supplied, the latest version will be used as the
     /// `end_version`.
@@ -98,9 +141,51 @@ impl Table {
         )
     }
 
+    /// Create a [`TableChanges`] to get a change data feed for the table between `start_timestamp`
+    /// and `end_timestamp` (in milliseconds since the Unix epoch, inclusive). The change data feed
+    /// starts at the earliest commit made at or after `start_timestamp`, and ends at the latest
+    /// commit made at or before `end_timestamp`, or at the latest version if no `end_timestamp` is
+    /// supplied. Commit timestamps are resolved as in [`Table::snapshot_at_timestamp`].
+    ///
+    /// Returns a [`Error::TimestampOutOfRange`] if `start_timestamp` is after the latest commit,
+    /// if `end_timestamp` is before the earliest available commit, or if no commit was made
+    /// between the two timestamps.
+    pub fn table_changes_by_timestamp(
+        &self,
+        engine: &dyn Engine,
+        start_timestamp: i64,
+        end_timestamp: impl Into<Option<i64>>,
+    ) -> DeltaResult<TableChanges> {
+        let latest = self.snapshot(engine, None)?;
+        let mut commits = CommitTimestamps::try_new(&latest, engine)?;
+        let start_version =
+            commits.find_version(start_timestamp, TimestampSearchMode::AtOrAfter)?;
+        let end_version = match end_timestamp.into() {
+            Some(end_timestamp) => {
+                let end_version =
+                    commits.find_version(end_timestamp, TimestampSearchMode::AtOrBefore)?;
+                if start_version > end_version {
+                    return Err(Error::timestamp_out_of_range(format!(
+                        "no commit found between {start_timestamp} and {end_timestamp}"
+                    )));
+                }
+                end_version
+            }
+            None => latest.version(),
+        };
+        self.table_changes(engine, start_version, end_version)
+    }
+
     /// Create a new write transaction for this table.
     pub fn new_transaction(&self, engine: &dyn Engine) -> DeltaResult<Transaction> {
-        Transaction::try_new(self.snapshot(engine, None)?)
+        Transaction::try_new(self.snapshot(engine, None)?, engine)
+    }
+
+    /// Create a new table at `location` with the given schema. The returned
+    /// [`CreateTableBuilder`] can be used to configure the table (e.g. its partition columns and
+    /// table properties) before committing version 0 of the table.
+    pub fn create(location: Url, schema: impl Into<SchemaRef>) -> CreateTableBuilder {
+        CreateTableBuilder::new(location, schema.into())
     }
 }
 
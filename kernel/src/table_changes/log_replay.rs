@@ -12,6 +12,7 @@ use crate::actions::{
 };
 use crate::engine_data::{GetData, TypedGetData};
 use crate::expressions::{column_name, ColumnName};
+use crate::history::in_commit_timestamp_field;
 use crate::path::ParsedLogPath;
 use crate::scan::data_skipping::DataSkippingFilter;
 use crate::scan::state::DvInfo;
@@ -82,6 +83,9 @@ pub(crate) fn table_changes_action_iter(
 ///     - Ensure that any schema update is compatible with the provided `schema`. Currently, schema
 ///       compatibility is checked through schema equality. This will be expanded in the future to
 ///       allow limited schema evolution.
+///     - Find the in-commit timestamp of the commit in its `commitInfo` action, if it has one. This
+///       must be done in the first phase because the second phase lazily transforms engine data
+///       with an extra timestamp column. Thus, the timestamp must be known ahead of time.
 ///
 /// Note: We check the protocol, change data feed enablement, and schema compatibility in phase 1
 /// in order to detect errors and fail early.
@@ -93,12 +97,6 @@ pub(crate) fn table_changes_action_iter(
 ///
 /// See https://github.com/delta-io/delta/blob/master/PROTOCOL.md#deletion-vectors
 ///
-/// TODO: When the kernel supports in-commit timestamps, we will also have to inspect CommitInfo
-/// actions to find the timestamp. These are generated when incommit timestamps is enabled.
-/// This must be done in the first phase because the second phase lazily transforms engine data with
-/// an extra timestamp column. Thus, the timestamp must be known ahead of time.
-/// See https://github.com/delta-io/delta-kernel-rs/issues/559
-///
 /// 2. Scan file generation phase [`LogReplayScanner::into_scan_batches`]: This iterates over every
 ///    action in the commit, and generates [`TableChangesScanData`]. It does so by transforming the
 ///    actions using [`add_transform_expr`], and generating selection vectors with the following rules:
@@ -117,15 +115,9 @@ struct LogReplayScanner {
     remove_dvs: HashMap<String, DvInfo>,
     // The commit file that this replay scanner will operate on.
     commit_file: ParsedLogPath,
-    // The timestamp associated with this commit. This is the file modification time
-    // from the commit's [`FileMeta`].
-    //
-    //
-    // TODO when incommit timestamps are supported: If there is a [`CommitInfo`] with a timestamp
-    // generated by in-commit timestamps, that timestamp will be used instead.
-    //
-    // Note: This will be used once an expression is introduced to transform the engine data in
-    // [`TableChangesScanData`]
+    // The timestamp associated with this commit. This is the in-commit timestamp from the commit's
+    // [`CommitInfo`] if it has one, and the file modification time from the commit's
+    // [`FileMeta`] otherwise.
     timestamp: i64,
 }
 
@@ -136,6 +128,7 @@ impl LogReplayScanner {
     /// 2. Construct a map from path to deletion vector of remove actions that share the same path
     ///    as an add action.
     /// 3. Perform validation on each protocol and metadata action in the commit.
+    /// 4. Find the in-commit timestamp of the commit, if any.
     ///
     /// For more details, see the documentation for [`LogReplayScanner`].
     fn try_new(
@@ -155,7 +148,7 @@ impl LogReplayScanner {
         // all of the rows will be filtered by the predicate. Instead, we wait until deletion
         // vectors are resolved so that we can skip both actions in the pair.
         let action_iter = engine.get_json_handler().read_json_files(
-            &[commit_file.location.clone()],
+            std::slice::from_ref(&commit_file.location),
             visitor_schema,
             None, // not safe to apply data skipping yet
         )?;
@@ -163,6 +156,7 @@ impl LogReplayScanner {
         let mut remove_dvs = HashMap::default();
         let mut add_paths = HashSet::default();
         let mut has_cdc_action = false;
+        let mut in_commit_timestamp = None;
         for actions in action_iter {
             let actions = actions?;
 
@@ -172,9 +166,15 @@ impl LogReplayScanner {
                 has_cdc_action: &mut has_cdc_action,
                 protocol: None,
                 metadata_info: None,
+                in_commit_timestamp: None,
             };
             visitor.visit_rows_of(actions.as_ref())?;
 
+            // only the first commitInfo action of a commit carries its in-commit timestamp
+            if let Some(timestamp) = visitor.in_commit_timestamp {
+                in_commit_timestamp.get_or_insert(timestamp);
+            }
+
             if let Some(protocol) = visitor.protocol {
                 ensure_cdf_read_supported(&protocol)
                     .map_err(|_| Error::change_data_feed_unsupported(commit_file.version))?;
@@ -202,7 +202,7 @@ impl LogReplayScanner {
             remove_dvs.retain(|rm_path, _| add_paths.contains(rm_path));
         }
         Ok(LogReplayScanner {
-            timestamp: commit_file.location.last_modified,
+            timestamp: in_commit_timestamp.unwrap_or(commit_file.location.last_modified),
             commit_file,
             has_cdc_action,
             remove_dvs,
@@ -220,14 +220,13 @@ impl LogReplayScanner {
             has_cdc_action,
             remove_dvs,
             commit_file,
-            // TODO: Add the timestamp as a column with an expression
             timestamp,
         } = self;
         let remove_dvs = Arc::new(remove_dvs);
 
         let schema = FileActionSelectionVisitor::schema();
         let action_iter = engine.get_json_handler().read_json_files(
-            &[commit_file.location.clone()],
+            std::slice::from_ref(&commit_file.location),
             schema,
             None,
         )?;
@@ -271,6 +270,7 @@ impl LogReplayScanner {
 struct PreparePhaseVisitor<'a> {
     protocol: Option<Protocol>,
     metadata_info: Option<(String, HashMap<String, String>)>,
+    in_commit_timestamp: Option<i64>,
     has_cdc_action: &'a mut bool,
     add_paths: &'a mut HashSet<String>,
     remove_dvs: &'a mut HashMap<String, DvInfo>,
@@ -283,6 +283,7 @@ impl PreparePhaseVisitor<'_> {
             Option::<Cdc>::get_struct_field(CDC_NAME),
             Option::<Metadata>::get_struct_field(METADATA_NAME),
             Option::<Protocol>::get_struct_field(PROTOCOL_NAME),
+            in_commit_timestamp_field(),
         ]))
     }
 }
@@ -314,6 +315,7 @@ impl RowVisitor for PreparePhaseVisitor<'_> {
                 (INTEGER, column_name!("protocol.minWriterVersion")),
                 (string_list.clone(), column_name!("protocol.readerFeatures")),
                 (string_list, column_name!("protocol.writerFeatures")),
+                (LONG, column_name!("commitInfo.inCommitTimestamp")),
             ];
             let (types, names) = types_and_names.into_iter().unzip();
             (names, types).into()
@@ -323,7 +325,7 @@ impl RowVisitor for PreparePhaseVisitor<'_> {
 
     fn visit<'b>(&mut self, row_count: usize, getters: &[&'b dyn GetData<'b>]) -> DeltaResult<()> {
         require!(
-            getters.len() == 16,
+            getters.len() == 17,
             Error::InternalError(format!(
                 "Wrong number of PreparePhaseVisitor getters: {}",
                 getters.len()
@@ -354,6 +356,10 @@ impl RowVisitor for PreparePhaseVisitor<'_> {
                 let protocol =
                     ProtocolVisitor::visit_protocol(i, min_reader_version, &getters[12..=15])?;
                 self.protocol = Some(protocol);
+            } else if let Some(timestamp) =
+                getters[16].get_long(i, "commitInfo.inCommitTimestamp")?
+            {
+                self.in_commit_timestamp.get_or_insert(timestamp);
             }
         }
         Ok(())
@@ -1,7 +1,7 @@
 use super::table_changes_action_iter;
 use super::TableChangesScanData;
 use crate::actions::deletion_vector::DeletionVectorDescriptor;
-use crate::actions::{Add, Cdc, Metadata, Protocol, Remove};
+use crate::actions::{Add, Cdc, CommitInfo, Metadata, Protocol, Remove};
 use crate::engine::sync::SyncEngine;
 use crate::expressions::Scalar;
 use crate::expressions::{column_expr, BinaryOperator};
@@ -606,3 +606,31 @@ async fn file_meta_timestamp() {
     let scanner = LogReplayScanner::try_new(engine.as_ref(), commit, &get_schema().into()).unwrap();
     assert_eq!(scanner.timestamp, file_meta_ts);
 }
+
+#[tokio::test]
+async fn in_commit_timestamp() {
+    let engine = Arc::new(SyncEngine::new());
+    let mut mock_table = LocalMockTable::new();
+
+    mock_table
+        .commit([
+            Action::CommitInfo(CommitInfo {
+                in_commit_timestamp: Some(1_000),
+                ..Default::default()
+            }),
+            Action::Add(Add {
+                path: "fake_path_1".into(),
+                data_change: true,
+                ..Default::default()
+            }),
+        ])
+        .await;
+
+    let mut commits = get_segment(engine.as_ref(), mock_table.table_root(), 0, None)
+        .unwrap()
+        .into_iter();
+
+    let commit = commits.next().unwrap();
+    let scanner = LogReplayScanner::try_new(engine.as_ref(), commit, &get_schema().into()).unwrap();
+    assert_eq!(scanner.timestamp, 1_000);
+}
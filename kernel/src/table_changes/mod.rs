@@ -72,14 +72,12 @@ static CDF_FIELDS: LazyLock<[StructField; 3]> = LazyLock::new(|| {
 ///   of `delete`, `insert`, `update_preimage`, or `update_postimage`.
 /// - `_commit_version`: Long representing the commit the change occurred in.
 /// - `_commit_timestamp`: Time at which the commit occurred. The timestamp is retrieved from the
-///   file modification time of the log file. No timezone is associated with the timestamp.
+///   `inCommitTimestamp` field of the commit's `CommitInfo` action if the commit was made with
+///   in-commit timestamps (ICT) enabled, and from the file modification time of the log file
+///   otherwise. No timezone is associated with the timestamp.
 ///
-///   Currently, in-commit timestamps (ICT) is not supported. In the future when ICT is enabled, the
-///   timestamp will be retrieved from the `inCommitTimestamp` field of the CommitInfo` action.
-///   See issue [#559](https://github.com/delta-io/delta-kernel-rs/issues/559)
 ///   For details on In-Commit Timestamps, see the [Protocol](https://github.com/delta-io/delta/blob/master/PROTOCOL.md#in-commit-timestamps).
 ///
-///
 /// Three properties must hold for the entire CDF range:
 /// - Reading must be supported for every commit in the range. Currently the only read feature allowed
 ///   is deletion vectors. This will be expanded in the future to support more delta table features.
@@ -14,13 +14,16 @@ use std::sync::{Arc, LazyLock};
 use url::Url;
 
 use crate::actions::{ensure_supported_features, Metadata, Protocol};
-use crate::schema::{InvariantChecker, SchemaRef};
+use crate::schema::SchemaRef;
+use crate::table_features::constraints::{check_constraints, column_invariants, Constraint};
+use crate::table_features::generated_columns::{generated_columns, GeneratedColumn};
+use crate::table_features::identity_columns::{identity_columns, IdentityColumn};
 use crate::table_features::{
     column_mapping_mode, validate_schema_column_mapping, ColumnMappingMode, ReaderFeature,
     WriterFeature,
 };
 use crate::table_properties::TableProperties;
-use crate::{DeltaResult, Error, Version};
+use crate::{DeltaResult, Version};
 
 /// Holds all the configuration for a table at a specific version. This includes the supported
 /// reader and writer features, table properties, schema, version, and table root. This can be used
@@ -137,15 +140,11 @@ impl TableConfiguration {
     pub(crate) fn ensure_write_supported(&self) -> DeltaResult<()> {
         self.protocol.ensure_write_supported()?;
 
-        // for now we don't allow invariants so although we support writer version 2 and the
-        // ColumnInvariant TableFeature we _must_ check here that they are not actually in use
-        if self.is_invariants_supported()
-            && InvariantChecker::has_invariants(self.schema().as_ref())
-        {
-            return Err(Error::unsupported(
-                "Column invariants are not yet supported",
-            ));
-        }
+        // writers must be able to generate the values of the identity and generated columns, and
+        // to check the constraints of the table
+        self.identity_columns()?;
+        self.generated_columns()?;
+        self.constraints()?;
 
         Ok(())
     }
@@ -225,11 +224,158 @@ impl TableConfiguration {
         }
     }
 
-    #[allow(unused)]
     pub(crate) fn is_append_only_enabled(&self) -> bool {
         self.is_append_only_supported() && self.table_properties.append_only.unwrap_or(false)
     }
 
+    /// Returns `true` if the table supports the typeWidening (or typeWidening-preview) table
+    /// feature. To support this feature, the table must have reader version 3 and writer version 7
+    /// with the feature in both the protocol's readerFeatures and writerFeatures.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#type-widening>
+    pub(crate) fn is_type_widening_supported(&self) -> bool {
+        let protocol = &self.protocol;
+        let has_feature = |reader: ReaderFeature, writer: WriterFeature| {
+            protocol.has_reader_feature(&reader) && protocol.has_writer_feature(&writer)
+        };
+        protocol.min_reader_version() == 3
+            && protocol.min_writer_version() == 7
+            && (has_feature(ReaderFeature::TypeWidening, WriterFeature::TypeWidening)
+                || has_feature(
+                    ReaderFeature::TypeWideningPreview,
+                    WriterFeature::TypeWideningPreview,
+                ))
+    }
+
+    /// Returns `true` if widening the types of columns is enabled for this table. This is the case
+    /// when type widening is supported and the `delta.enableTypeWidening` table property is set to
+    /// `true`.
+    pub(crate) fn is_type_widening_enabled(&self) -> bool {
+        self.is_type_widening_supported()
+            && self.table_properties.enable_type_widening.unwrap_or(false)
+    }
+
+    /// Returns `true` if the table supports the inCommitTimestamp writer feature.
+    pub(crate) fn is_in_commit_timestamps_supported(&self) -> bool {
+        self.protocol
+            .has_writer_feature(&WriterFeature::InCommitTimestamp)
+    }
+
+    /// Returns `true` if in-commit timestamps are enabled for this table, i.e. the table supports
+    /// the inCommitTimestamp writer feature and the `delta.enableInCommitTimestamps` table property
+    /// is set to `true`. Commits made since the version in
+    /// [`TableProperties::in_commit_timestamp_enablement_version`] (or all commits, if it's not
+    /// set) have an in-commit timestamp.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#in-commit-timestamps>
+    pub(crate) fn is_in_commit_timestamps_enabled(&self) -> bool {
+        self.is_in_commit_timestamps_supported()
+            && self
+                .table_properties
+                .enable_in_commit_timestamps
+                .unwrap_or(false)
+    }
+
+    /// Returns `true` if the table supports the v2Checkpoint table feature, i.e. if its checkpoints
+    /// must follow the V2 spec.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#v2-checkpoint-table-feature>
+    pub(crate) fn is_v2_checkpoint_supported(&self) -> bool {
+        let protocol = &self.protocol;
+        protocol.has_reader_feature(&ReaderFeature::V2Checkpoint)
+            || protocol.has_writer_feature(&WriterFeature::V2Checkpoint)
+    }
+
+    /// Returns `true` if the table supports the timestampNtz table feature, which the table must
+    /// support to have `timestamp_ntz` columns.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#timestamp-without-timezone-timestampntz>
+    pub(crate) fn is_timestamp_ntz_supported(&self) -> bool {
+        let protocol = &self.protocol;
+        protocol.has_reader_feature(&ReaderFeature::TimestampWithoutTimezone)
+            && protocol.has_writer_feature(&WriterFeature::TimestampWithoutTimezone)
+    }
+
+    /// Returns `true` if the table supports the vacuumProtocolCheck table feature, i.e. if VACUUM
+    /// must check that the writer supports the protocol of the table.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#vacuum-protocol-check>
+    pub(crate) fn is_vacuum_protocol_check_supported(&self) -> bool {
+        let protocol = &self.protocol;
+        protocol.has_reader_feature(&ReaderFeature::VacuumProtocolCheck)
+            || protocol.has_writer_feature(&WriterFeature::VacuumProtocolCheck)
+    }
+
+    /// Returns `true` if the table supports the domain metadata writer feature, i.e. if domain
+    /// metadata actions may be written to its log.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#domain-metadata>
+    pub(crate) fn is_domain_metadata_supported(&self) -> bool {
+        self.protocol
+            .has_writer_feature(&WriterFeature::DomainMetadata)
+    }
+
+    /// Returns `true` if the table supports the clustering writer feature, i.e. if it is a clustered
+    /// table whose clustering columns are stored in the `delta.clustering` metadata domain.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#clustered-table>
+    pub(crate) fn is_clustering_supported(&self) -> bool {
+        self.protocol.has_writer_feature(&WriterFeature::Clustering)
+    }
+
+    /// Returns `true` if the table supports the row tracking writer feature, i.e. if writers must
+    /// assign row IDs and row commit versions to the files they add.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#row-tracking>
+    pub(crate) fn is_row_tracking_supported(&self) -> bool {
+        self.protocol
+            .has_writer_feature(&WriterFeature::RowTracking)
+    }
+
+    /// Returns `true` if row tracking is supported and enabled on this table, i.e. if every row of
+    /// the table has a row ID and a row commit version that readers can rely on.
+    pub(crate) fn is_row_tracking_enabled(&self) -> bool {
+        self.is_row_tracking_supported()
+            && self.table_properties().enable_row_tracking == Some(true)
+    }
+
+    /// Returns `true` if the table supports the identity columns writer feature, i.e. if writers
+    /// must generate the values of the identity columns of the table.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#identity-columns>
+    pub(crate) fn is_identity_columns_supported(&self) -> bool {
+        self.protocol
+            .has_writer_feature(&WriterFeature::IdentityColumns)
+    }
+
+    /// The identity columns of the table, which only tables that support the identity columns
+    /// writer feature have. Returns an error if an identity column is not valid.
+    pub(crate) fn identity_columns(&self) -> DeltaResult<Vec<IdentityColumn>> {
+        if !self.is_identity_columns_supported() {
+            return Ok(vec![]);
+        }
+        identity_columns(&self.schema, self.metadata.partition_columns())
+    }
+
+    /// Returns `true` if the table supports the generated columns writer feature, i.e. if writers
+    /// must write the values of the generation expressions of the generated columns of the table.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#generated-columns>
+    pub(crate) fn is_generated_columns_supported(&self) -> bool {
+        self.protocol
+            .has_writer_feature(&WriterFeature::GeneratedColumns)
+    }
+
+    /// The generated columns of the table, which only tables that support the generated columns
+    /// writer feature have. Returns an error if a generation expression is not valid or not
+    /// supported.
+    pub(crate) fn generated_columns(&self) -> DeltaResult<Vec<GeneratedColumn>> {
+        if !self.is_generated_columns_supported() {
+            return Ok(vec![]);
+        }
+        generated_columns(&self.schema)
+    }
+
     /// Returns `true` if the table supports the column invariant table feature.
     pub(crate) fn is_invariants_supported(&self) -> bool {
         let protocol = &self.protocol;
@@ -238,6 +384,34 @@ impl TableConfiguration {
             version => (2..=6).contains(&version),
         }
     }
+
+    /// Returns `true` if the table supports the CHECK constraints table feature.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#check-constraints>
+    pub(crate) fn is_check_constraints_supported(&self) -> bool {
+        let protocol = &self.protocol;
+        match protocol.min_writer_version() {
+            7 => protocol.has_writer_feature(&WriterFeature::CheckConstraints),
+            version => (3..=6).contains(&version),
+        }
+    }
+
+    /// The constraints that the rows written to the table must satisfy: its CHECK constraints and
+    /// column invariants, if the table supports them. Returns an error if a constraint is not
+    /// valid or not supported.
+    pub(crate) fn constraints(&self) -> DeltaResult<Vec<Constraint>> {
+        let mut constraints = vec![];
+        if self.is_check_constraints_supported() {
+            constraints.extend(check_constraints(
+                &self.metadata.configuration,
+                &self.schema,
+            )?);
+        }
+        if self.is_invariants_supported() {
+            constraints.extend(column_invariants(&self.schema)?);
+        }
+        Ok(constraints)
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,342 @@
+//! Code to handle the clustering columns of [clustered tables].
+//!
+//! The clustering columns of a table with the `clustering` writer feature are stored, by their
+//! physical names, in the configuration of the `delta.clustering` metadata domain:
+//! `{"clusteringColumns":[["a"],["b","c"]]}`. Clustering columns must be non-partition columns of
+//! primitive types, and writers must collect the statistics of the clustering columns for every
+//! data file, so they must be among the columns with statistics of the table.
+//!
+//! [clustered tables]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#clustered-table
+
+use serde::{Deserialize, Serialize};
+
+use crate::actions::DomainMetadata;
+use crate::schema::{ColumnName, DataType, StructType};
+use crate::table_configuration::TableConfiguration;
+use crate::table_properties::{DataSkippingNumIndexedCols, TableProperties};
+use crate::utils::require;
+use crate::{DeltaResult, Error};
+
+/// The metadata domain that stores the clustering columns of a table.
+pub(crate) const CLUSTERING_DOMAIN_NAME: &str = "delta.clustering";
+
+/// The number of leaf columns with statistics when the table doesn't set
+/// `delta.dataSkippingNumIndexedCols`.
+const DEFAULT_NUM_INDEXED_COLS: u64 = 32;
+
+// The configuration of the clustering domain
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ClusteringConfiguration {
+    clustering_columns: Vec<Vec<String>>,
+}
+
+/// Parse the (physical) clustering columns from the configuration of the clustering domain.
+pub(crate) fn parse_clustering_columns(configuration: &str) -> DeltaResult<Vec<ColumnName>> {
+    let configuration: ClusteringConfiguration = serde_json::from_str(configuration)?;
+    Ok(configuration
+        .clustering_columns
+        .into_iter()
+        .map(ColumnName::new)
+        .collect())
+}
+
+/// The domain metadata action that sets the clustering columns of a table to the given physical
+/// columns.
+pub(crate) fn clustering_domain_metadata(
+    physical_columns: &[ColumnName],
+) -> DeltaResult<DomainMetadata> {
+    let configuration = ClusteringConfiguration {
+        clustering_columns: physical_columns
+            .iter()
+            .map(|column| column.path().to_vec())
+            .collect(),
+    };
+    Ok(DomainMetadata {
+        domain: CLUSTERING_DOMAIN_NAME.to_string(),
+        configuration: serde_json::to_string(&configuration)?,
+        removed: false,
+    })
+}
+
+/// The physical name of a clustering column, which must be a non-partition column of a primitive
+/// type of the schema other than binary, since no min and max values are collected for binary
+/// columns.
+pub(crate) fn physical_clustering_column(
+    schema: &StructType,
+    partition_columns: &[String],
+    column: &ColumnName,
+) -> DeltaResult<ColumnName> {
+    if let [name] = column.path() {
+        require!(
+            !partition_columns.contains(name),
+            Error::invalid_clustering_column(format!("{column} is a partition column"))
+        );
+    }
+    let missing_column =
+        || Error::invalid_clustering_column(format!("{column} is not a column of the schema"));
+    let mut struct_type = schema;
+    let mut physical_path = vec![];
+    let mut data_type: Option<&DataType> = None;
+    for name in column.path() {
+        match data_type {
+            Some(DataType::Struct(child)) => struct_type = child.as_ref(),
+            Some(_) => return Err(missing_column()),
+            None => {}
+        }
+        let field = struct_type.field(name).ok_or_else(missing_column)?;
+        physical_path.push(field.physical_name().to_string());
+        data_type = Some(field.data_type());
+    }
+    match data_type {
+        Some(&DataType::BINARY) => Err(Error::invalid_clustering_column(format!(
+            "{column} is a binary column, which has no min and max statistics"
+        ))),
+        Some(DataType::Primitive(_)) => Ok(ColumnName::new(physical_path)),
+        Some(_) => Err(Error::invalid_clustering_column(format!(
+            "{column} is not of a primitive type"
+        ))),
+        None => Err(missing_column()),
+    }
+}
+
+/// The logical name of the column with the given physical name in the schema.
+pub(crate) fn logical_clustering_column(
+    schema: &StructType,
+    physical_column: &ColumnName,
+) -> DeltaResult<ColumnName> {
+    let missing_column = || {
+        Error::invalid_clustering_column(format!(
+            "{physical_column} is not a physical column of the schema"
+        ))
+    };
+    let mut struct_type = Some(schema);
+    let mut logical_path = vec![];
+    for physical_name in physical_column.path() {
+        let field = struct_type
+            .and_then(|struct_type| {
+                struct_type
+                    .fields()
+                    .find(|field| field.physical_name() == physical_name)
+            })
+            .ok_or_else(missing_column)?;
+        logical_path.push(field.name().clone());
+        struct_type = match field.data_type() {
+            DataType::Struct(child) => Some(child.as_ref()),
+            _ => None,
+        };
+    }
+    Ok(ColumnName::new(logical_path))
+}
+
+/// Validate the (logical) clustering columns of a table and return their physical names. On top of
+/// the requirements of [`physical_clustering_column`], the columns must be distinct and have
+/// statistics collected according to the table properties.
+pub(crate) fn validate_clustering_columns(
+    table_configuration: &TableConfiguration,
+    columns: &[ColumnName],
+) -> DeltaResult<Vec<ColumnName>> {
+    let schema = table_configuration.schema();
+    let partition_columns = table_configuration.metadata().partition_columns();
+    let table_properties = table_configuration.table_properties();
+    columns
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            require!(
+                !columns[..i].contains(column),
+                Error::invalid_clustering_column(format!("{column} is given more than once"))
+            );
+            let physical_column = physical_clustering_column(&schema, partition_columns, column)?;
+            require!(
+                has_stats(&schema, partition_columns, table_properties, column),
+                Error::invalid_clustering_column(format!(
+                    "{column} has no statistics collected, see `delta.dataSkippingNumIndexedCols` \
+                     and `delta.dataSkippingStatsColumns`"
+                ))
+            );
+            Ok(physical_column)
+        })
+        .collect()
+}
+
+// Whether statistics are collected for the (logical) column: the columns of
+// `delta.dataSkippingStatsColumns` (and their nested columns) if set, or else the first
+// `delta.dataSkippingNumIndexedCols` leaf columns of the schema, not counting partition columns.
+fn has_stats(
+    schema: &StructType,
+    partition_columns: &[String],
+    table_properties: &TableProperties,
+    column: &ColumnName,
+) -> bool {
+    if let Some(stats_columns) = &table_properties.data_skipping_stats_columns {
+        return stats_columns
+            .iter()
+            .any(|stats_column| column.path().starts_with(stats_column.path()));
+    }
+    let num_indexed_cols = match table_properties.data_skipping_num_indexed_cols {
+        Some(DataSkippingNumIndexedCols::AllColumns) => return true,
+        Some(DataSkippingNumIndexedCols::NumColumns(n)) => n,
+        None => DEFAULT_NUM_INDEXED_COLS,
+    };
+    let mut leaves = vec![];
+    for field in schema.fields() {
+        if !partition_columns.contains(field.name()) {
+            collect_leaves(field.data_type(), vec![field.name().clone()], &mut leaves);
+        }
+    }
+    leaves
+        .iter()
+        .position(|leaf| leaf == column.path())
+        .is_some_and(|position| (position as u64) < num_indexed_cols)
+}
+
+// Collect the paths of the leaf columns of a column of the given type, in schema order
+fn collect_leaves(data_type: &DataType, path: Vec<String>, leaves: &mut Vec<Vec<String>>) {
+    match data_type {
+        DataType::Struct(struct_type) => {
+            for field in struct_type.fields() {
+                let mut child_path = path.clone();
+                child_path.push(field.name().clone());
+                collect_leaves(field.data_type(), child_path, leaves);
+            }
+        }
+        _ => leaves.push(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::collections::HashMap;
+
+    use url::Url;
+
+    use crate::actions::{Metadata, Protocol};
+    use crate::schema::{column_name, MetadataValue, StructField};
+    use crate::table_features::WriterFeature;
+
+    fn schema() -> StructType {
+        StructType::new([
+            StructField::nullable("id", DataType::LONG),
+            StructField::nullable("date", DataType::DATE),
+            StructField::nullable(
+                "nested",
+                StructType::new([
+                    StructField::nullable("x", DataType::INTEGER),
+                    StructField::nullable("y", DataType::STRING),
+                ]),
+            ),
+            StructField::nullable("tags", DataType::struct_type([])),
+            StructField::nullable("payload", DataType::BINARY),
+        ])
+    }
+
+    fn configuration(properties: &[(&str, &str)]) -> TableConfiguration {
+        let metadata = Metadata {
+            schema_string: serde_json::to_string(&schema()).unwrap(),
+            partition_columns: vec!["date".to_string()],
+            configuration: properties
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect::<HashMap<_, _>>(),
+            ..Default::default()
+        };
+        let protocol = Protocol::try_new(
+            1,
+            7,
+            None::<Vec<String>>,
+            Some([WriterFeature::DomainMetadata, WriterFeature::Clustering]),
+        )
+        .unwrap();
+        let table_root = Url::parse("memory:///").unwrap();
+        TableConfiguration::try_new(metadata, protocol, table_root, 0).unwrap()
+    }
+
+    #[test]
+    fn test_clustering_domain_metadata() {
+        let columns = vec![column_name!("a"), column_name!("b.c")];
+        let domain_metadata = clustering_domain_metadata(&columns).unwrap();
+        assert_eq!(domain_metadata.domain, "delta.clustering");
+        assert_eq!(
+            domain_metadata.configuration,
+            r#"{"clusteringColumns":[["a"],["b","c"]]}"#
+        );
+        assert!(!domain_metadata.removed);
+        assert_eq!(
+            parse_clustering_columns(&domain_metadata.configuration).unwrap(),
+            columns
+        );
+        assert!(parse_clustering_columns(r#"{"columns":[]}"#).is_err());
+    }
+
+    #[test]
+    fn test_physical_and_logical_clustering_columns() {
+        let schema = StructType::new([StructField::nullable(
+            "nested",
+            StructType::new([StructField::nullable("x", DataType::INTEGER)
+                .with_metadata([("delta.columnMapping.physicalName", "col-x")])]),
+        )
+        .with_metadata([(
+            "delta.columnMapping.physicalName",
+            MetadataValue::from("col-nested"),
+        )])]);
+        let physical = physical_clustering_column(&schema, &[], &column_name!("nested.x")).unwrap();
+        assert_eq!(physical, ColumnName::new(["col-nested", "col-x"]));
+        let logical = logical_clustering_column(&schema, &physical).unwrap();
+        assert_eq!(logical, column_name!("nested.x"));
+
+        assert!(logical_clustering_column(&schema, &column_name!("nested.x")).is_err());
+        for column in [column_name!("nested"), column_name!("nested.z")] {
+            assert!(matches!(
+                physical_clustering_column(&schema, &[], &column),
+                Err(Error::InvalidClusteringColumn(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_validate_clustering_columns() {
+        let table_configuration = configuration(&[]);
+        let columns = [column_name!("nested.y"), column_name!("id")];
+        assert_eq!(
+            validate_clustering_columns(&table_configuration, &columns).unwrap(),
+            columns
+        );
+        let invalid = [
+            vec![column_name!("date")],
+            vec![column_name!("nested")],
+            vec![column_name!("tags")],
+            vec![column_name!("payload")],
+            vec![column_name!("id"), column_name!("id")],
+        ];
+        for columns in invalid {
+            assert!(matches!(
+                validate_clustering_columns(&table_configuration, &columns),
+                Err(Error::InvalidClusteringColumn(_))
+            ));
+        }
+
+        // the leaf columns are counted in schema order, without the partition columns
+        let table_configuration = configuration(&[("delta.dataSkippingNumIndexedCols", "2")]);
+        let cases = [
+            (column_name!("id"), true),
+            (column_name!("nested.x"), true),
+            (column_name!("nested.y"), false),
+        ];
+        for (column, has_stats) in cases {
+            let result = validate_clustering_columns(&table_configuration, &[column]);
+            assert_eq!(result.is_ok(), has_stats);
+        }
+
+        // the stats columns take precedence and include the nested columns of structs
+        let table_configuration = configuration(&[
+            ("delta.dataSkippingNumIndexedCols", "1"),
+            ("delta.dataSkippingStatsColumns", "nested"),
+        ]);
+        let columns = [column_name!("nested.x"), column_name!("nested.y")];
+        assert!(validate_clustering_columns(&table_configuration, &columns).is_ok());
+        assert!(validate_clustering_columns(&table_configuration, &[column_name!("id")]).is_err());
+    }
+}
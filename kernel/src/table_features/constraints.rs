@@ -0,0 +1,294 @@
+//! Code to handle [CHECK constraints] and [column invariants].
+//!
+//! CHECK constraints are named SQL predicates over the columns of a table, stored in its
+//! `delta.constraints.<name>` table properties by tables with the `checkConstraints` writer
+//! feature. Column invariants are SQL predicates stored in the `delta.invariants` metadata of the
+//! fields of tables with the `invariants` writer feature. Writers must make sure that every row
+//! they write satisfies all the constraints of the table: as in Delta, a predicate that is false
+//! or null for a row is violated by it.
+//!
+//! [CHECK constraints]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#check-constraints
+//! [column invariants]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#column-invariants
+
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+use crate::expressions::sql::parse_sql_expression;
+use crate::expressions::{ColumnName, Expression};
+use crate::schema::{ColumnMetadataKey, DataType, MetadataValue, StructType};
+use crate::{DeltaResult, Error};
+
+/// The prefix of the table properties that store the CHECK constraints of a table.
+pub(crate) const CONSTRAINT_PROPERTY_PREFIX: &str = "delta.constraints.";
+
+/// The kind of a [`Constraint`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConstraintKind {
+    /// A CHECK constraint, named by its name
+    Check,
+    /// A column invariant, named by the path of its column
+    Invariant,
+}
+
+/// A constraint that the rows of a table must satisfy.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Constraint {
+    pub(crate) kind: ConstraintKind,
+    pub(crate) name: String,
+    /// The SQL text of the predicate, as stored in the table.
+    pub(crate) sql: String,
+    /// The predicate over the (logical) columns of the table.
+    pub(crate) expression: Expression,
+}
+
+impl Constraint {
+    /// Parse the constraint with SQL predicate `sql` over the columns of `schema`.
+    pub(crate) fn try_new(
+        kind: ConstraintKind,
+        name: impl Into<String>,
+        sql: impl Into<String>,
+        schema: &StructType,
+    ) -> DeltaResult<Self> {
+        let name = name.into();
+        let sql = sql.into();
+        let expression =
+            parse_sql_expression(&sql, schema, &DataType::BOOLEAN).map_err(|err| match err {
+                Error::Unsupported(_) => err,
+                err => Error::invalid_constraint(format!(
+                    "{} has an invalid expression: {err}",
+                    Self::describe(kind, &name, &sql)
+                )),
+            })?;
+        Ok(Self {
+            kind,
+            name,
+            sql,
+            expression,
+        })
+    }
+
+    fn describe(kind: ConstraintKind, name: &str, sql: &str) -> String {
+        match kind {
+            ConstraintKind::Check => format!("CHECK constraint {name} ({sql})"),
+            ConstraintKind::Invariant => format!("Invariant of column {name} ({sql})"),
+        }
+    }
+}
+
+impl Display for Constraint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&Self::describe(self.kind, &self.name, &self.sql))
+    }
+}
+
+/// The CHECK constraints of a table with the given configuration and schema, sorted by name.
+pub(crate) fn check_constraints(
+    configuration: &HashMap<String, String>,
+    schema: &StructType,
+) -> DeltaResult<Vec<Constraint>> {
+    let mut constraints = configuration
+        .iter()
+        .filter_map(|(key, sql)| {
+            let name = key.strip_prefix(CONSTRAINT_PROPERTY_PREFIX)?;
+            Some(Constraint::try_new(
+                ConstraintKind::Check,
+                name,
+                sql,
+                schema,
+            ))
+        })
+        .collect::<DeltaResult<Vec<_>>>()?;
+    constraints.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(constraints)
+}
+
+/// The column invariants of a table with the given schema, in schema order. Invariants of columns
+/// nested in arrays or maps are not supported.
+pub(crate) fn column_invariants(schema: &StructType) -> DeltaResult<Vec<Constraint>> {
+    let mut invariants = vec![];
+    collect_invariants(
+        schema,
+        Some(&ColumnName::new::<&str>([])),
+        schema,
+        &mut invariants,
+    )?;
+    Ok(invariants)
+}
+
+// Collect the invariants of the fields nested in `data_type`, whose path is `path` unless it is
+// nested in an array or map
+fn collect_invariants(
+    data_type: &StructType,
+    path: Option<&ColumnName>,
+    schema: &StructType,
+    invariants: &mut Vec<Constraint>,
+) -> DeltaResult<()> {
+    for field in data_type.fields() {
+        let field_path = path.map(|path| path.join(&ColumnName::new([field.name()])));
+        if let Some(value) = field.get_config_value(&ColumnMetadataKey::Invariants) {
+            let Some(field_path) = &field_path else {
+                return Err(Error::unsupported(format!(
+                    "Invariants of columns nested in arrays or maps are not supported: {}",
+                    field.name()
+                )));
+            };
+            let sql = invariant_sql(value).ok_or_else(|| {
+                Error::invalid_constraint(format!("Column {field_path} has an invalid invariant"))
+            })?;
+            invariants.push(Constraint::try_new(
+                ConstraintKind::Invariant,
+                field_path.to_string(),
+                sql,
+                schema,
+            )?);
+        }
+        let mut nested_types = vec![(field.data_type(), field_path.as_ref())];
+        while let Some((nested_type, nested_path)) = nested_types.pop() {
+            match nested_type {
+                DataType::Struct(struct_type) => {
+                    collect_invariants(struct_type, nested_path, schema, invariants)?
+                }
+                DataType::Array(array_type) => {
+                    nested_types.push((array_type.element_type(), None));
+                }
+                DataType::Map(map_type) => {
+                    nested_types.push((map_type.key_type(), None));
+                    nested_types.push((map_type.value_type(), None));
+                }
+                DataType::Primitive(_) => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+// The SQL predicate of an invariant, stored as `{"expression": {"expression": "<sql>"}}` in a
+// JSON string (or object)
+fn invariant_sql(value: &MetadataValue) -> Option<String> {
+    let invariant = match value {
+        MetadataValue::String(json) => serde_json::from_str(json).ok()?,
+        MetadataValue::Other(invariant) => invariant.clone(),
+        _ => return None,
+    };
+    let sql = invariant.pointer("/expression/expression")?.as_str()?;
+    Some(sql.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expressions::column_expr;
+    use crate::schema::{ArrayType, StructField};
+
+    fn invariant_field(name: &str, data_type: impl Into<DataType>, sql: &str) -> StructField {
+        let invariant = serde_json::json!({ "expression": { "expression": sql } });
+        StructField::nullable(name, data_type)
+            .with_metadata([("delta.invariants", invariant.to_string())])
+    }
+
+    #[test]
+    fn test_check_constraints() {
+        let schema = StructType::new([
+            StructField::nullable("value", DataType::INTEGER),
+            StructField::nullable("name", DataType::STRING),
+        ]);
+        let configuration = HashMap::from([
+            (
+                "delta.constraints.positive".to_string(),
+                "value > 0".to_string(),
+            ),
+            (
+                "delta.constraints.named".to_string(),
+                "name IS NOT NULL".to_string(),
+            ),
+            ("delta.appendOnly".to_string(), "true".to_string()),
+        ]);
+        let constraints = check_constraints(&configuration, &schema).unwrap();
+        assert_eq!(
+            constraints,
+            [
+                Constraint {
+                    kind: ConstraintKind::Check,
+                    name: "named".to_string(),
+                    sql: "name IS NOT NULL".to_string(),
+                    expression: column_expr!("name").is_not_null(),
+                },
+                Constraint {
+                    kind: ConstraintKind::Check,
+                    name: "positive".to_string(),
+                    sql: "value > 0".to_string(),
+                    expression: column_expr!("value").gt(Expression::literal(0)),
+                },
+            ]
+        );
+        assert_eq!(
+            constraints[1].to_string(),
+            "CHECK constraint positive (value > 0)"
+        );
+
+        // constraints must be valid boolean expressions
+        for sql in ["value + 1", "missing > 0", "value >"] {
+            let configuration =
+                HashMap::from([("delta.constraints.invalid".to_string(), sql.to_string())]);
+            assert!(matches!(
+                check_constraints(&configuration, &schema),
+                Err(Error::InvalidConstraint(_))
+            ));
+        }
+        let configuration = HashMap::from([(
+            "delta.constraints.short".to_string(),
+            "length(name) < 10".to_string(),
+        )]);
+        assert!(matches!(
+            check_constraints(&configuration, &schema),
+            Err(Error::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn test_column_invariants() {
+        let schema = StructType::new([
+            invariant_field("value", DataType::INTEGER, "value > 0"),
+            StructField::nullable(
+                "nested",
+                StructType::new([invariant_field("x", DataType::LONG, "nested.x < 10")]),
+            ),
+        ]);
+        let invariants = column_invariants(&schema).unwrap();
+        assert_eq!(
+            invariants,
+            [
+                Constraint {
+                    kind: ConstraintKind::Invariant,
+                    name: "value".to_string(),
+                    sql: "value > 0".to_string(),
+                    expression: column_expr!("value").gt(Expression::literal(0)),
+                },
+                Constraint {
+                    kind: ConstraintKind::Invariant,
+                    name: "nested.x".to_string(),
+                    sql: "nested.x < 10".to_string(),
+                    expression: column_expr!("nested.x").lt(Expression::literal(10i64)),
+                },
+            ]
+        );
+
+        let schema = StructType::new([StructField::nullable("value", DataType::INTEGER)
+            .with_metadata([("delta.invariants", "value > 0")])]);
+        assert!(matches!(
+            column_invariants(&schema),
+            Err(Error::InvalidConstraint(_))
+        ));
+        let schema = StructType::new([StructField::nullable(
+            "values",
+            ArrayType::new(
+                StructType::new([invariant_field("x", DataType::INTEGER, "x > 0")]).into(),
+                true,
+            ),
+        )]);
+        assert!(matches!(
+            column_invariants(&schema),
+            Err(Error::Unsupported(_))
+        ));
+    }
+}
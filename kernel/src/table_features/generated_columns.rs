@@ -0,0 +1,156 @@
+//! Code to handle [generated columns].
+//!
+//! Generated columns are top-level columns of tables with the `generatedColumns` writer feature
+//! whose values are computed from the other columns of the table by the SQL expression in the
+//! `delta.generationExpression` metadata of their field. Writers must make sure that the values
+//! they write are those of the expression: the kernel computes the values of the generated
+//! columns that the written data omits, and checks those that it has.
+//!
+//! [generated columns]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#generated-columns
+
+use crate::expressions::sql::parse_sql_expression;
+use crate::expressions::Expression;
+use crate::schema::{ColumnMetadataKey, MetadataValue, StructField, StructType};
+use crate::utils::require;
+use crate::{DeltaResult, Error};
+
+/// The definition of a generated column.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GeneratedColumn {
+    /// The (logical) name of the column.
+    pub(crate) name: String,
+    /// The expression that computes the values of the column from the other (logical) columns of
+    /// the table.
+    pub(crate) expression: Expression,
+}
+
+impl GeneratedColumn {
+    /// The generated column defined by the metadata of `field`, a field of `schema`, if it is one.
+    pub(crate) fn try_from_field(
+        field: &StructField,
+        schema: &StructType,
+    ) -> DeltaResult<Option<Self>> {
+        let name = field.name();
+        let sql = match field.get_config_value(&ColumnMetadataKey::GenerationExpression) {
+            Some(MetadataValue::String(sql)) => sql,
+            Some(_) => {
+                return Err(Error::invalid_generated_column(format!(
+                    "{name} has a non-string generation expression"
+                )))
+            }
+            None => return Ok(None),
+        };
+        let expression =
+            parse_sql_expression(sql, schema, field.data_type()).map_err(|err| match err {
+                Error::Unsupported(_) => err,
+                err => Error::invalid_generated_column(format!(
+                    "{name} has an invalid generation expression: {err}"
+                )),
+            })?;
+        Ok(Some(Self {
+            name: name.clone(),
+            expression,
+        }))
+    }
+
+    /// The predicate that holds for the rows whose value of the column is `value`, the value of
+    /// the generation expression.
+    pub(crate) fn matches(&self, value: Expression) -> Expression {
+        !value.distinct(self.expression.clone())
+    }
+}
+
+/// The generated columns of a table with the given schema. Generation expressions can't refer to
+/// generated columns.
+pub(crate) fn generated_columns(schema: &StructType) -> DeltaResult<Vec<GeneratedColumn>> {
+    let mut columns = vec![];
+    for field in schema.fields() {
+        if let Some(column) = GeneratedColumn::try_from_field(field, schema)? {
+            columns.push(column);
+        }
+    }
+    for column in &columns {
+        let references = column.expression.references();
+        let generated_reference = columns
+            .iter()
+            .find(|other| references.iter().any(|name| name[0] == other.name));
+        require!(
+            generated_reference.is_none(),
+            Error::invalid_generated_column(format!(
+                "{} refers to a generated column",
+                column.name
+            ))
+        );
+    }
+    Ok(columns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expressions::column_expr;
+    use crate::schema::DataType;
+
+    fn generated_field(name: &str, data_type: DataType, sql: &str) -> StructField {
+        StructField::nullable(name, data_type).with_metadata([("delta.generationExpression", sql)])
+    }
+
+    #[test]
+    fn test_generated_columns() {
+        let schema = StructType::new([
+            StructField::nullable("value", DataType::INTEGER),
+            generated_field("double", DataType::INTEGER, "VALUE * 2"),
+            generated_field("tag", DataType::STRING, "'a'"),
+        ]);
+        let columns = generated_columns(&schema).unwrap();
+        assert_eq!(
+            columns,
+            [
+                GeneratedColumn {
+                    name: "double".to_string(),
+                    expression: column_expr!("value") * Expression::literal(2),
+                },
+                GeneratedColumn {
+                    name: "tag".to_string(),
+                    expression: Expression::literal("a"),
+                },
+            ]
+        );
+        assert_eq!(
+            columns[1].matches(column_expr!("tag")),
+            !column_expr!("tag").distinct(Expression::literal("a"))
+        );
+
+        // generation expressions must be valid, of the type of their column, and can't refer to
+        // generated columns
+        let invalid = [
+            generated_field("double", DataType::LONG, "value * 2"),
+            generated_field("double", DataType::INTEGER, "missing * 2"),
+            generated_field("double", DataType::INTEGER, "value *"),
+            generated_field("double", DataType::INTEGER, "double + 1"),
+        ];
+        for field in invalid {
+            let schema =
+                StructType::new([StructField::nullable("value", DataType::INTEGER), field]);
+            assert!(matches!(
+                generated_columns(&schema),
+                Err(Error::InvalidGeneratedColumn(_))
+            ));
+        }
+        let schema =
+            StructType::new([generated_field("day", DataType::DATE, "CAST(ts AS STRING)")]);
+        assert!(matches!(
+            generated_columns(&schema),
+            Err(Error::Unsupported(_))
+        ));
+        let schema = StructType::new([
+            StructField::nullable("value", DataType::INTEGER),
+            generated_field("double", DataType::INTEGER, "value * 2"),
+            generated_field("quadruple", DataType::INTEGER, "double * 2"),
+        ]);
+        assert!(matches!(
+            generated_columns(&schema),
+            Err(Error::InvalidGeneratedColumn(_))
+        ));
+    }
+}
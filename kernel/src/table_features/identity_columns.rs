@@ -0,0 +1,287 @@
+//! Code to handle [identity columns].
+//!
+//! Identity columns are top-level `long` columns of tables with the `identityColumns` writer
+//! feature whose values are generated by writers. The metadata of an identity column's field sets
+//! the first value, `delta.identity.start`, and the increment between values,
+//! `delta.identity.step`. Writers generate values of the form `start + k * step` past the last
+//! generated value, `delta.identity.highWaterMark`, and update the high water mark in the schema
+//! of the table when they commit. Users may only insert their own values into the column if
+//! `delta.identity.allowExplicitInsert` is true. These values don't move the high water mark, but
+//! the rows that have them still use up a generated value, which leaves gaps in the generated
+//! values.
+//!
+//! [identity columns]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#identity-columns
+
+use std::sync::LazyLock;
+
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
+use crate::expressions::Expression;
+use crate::schema::{
+    column_name, ColumnMetadataKey, ColumnName, ColumnNamesAndTypes, DataType, MetadataValue,
+    StructField, StructType,
+};
+use crate::utils::require;
+use crate::{DeltaResult, EngineData, Error};
+
+/// The definition of an identity column and its high water mark.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct IdentityColumn {
+    /// The (logical) name of the column.
+    pub(crate) name: String,
+    start: i64,
+    step: i64,
+    /// The last generated value, if any.
+    pub(crate) high_water_mark: Option<i64>,
+    /// Whether users may insert their own values into the column.
+    pub(crate) allow_explicit_insert: bool,
+}
+
+impl IdentityColumn {
+    /// The identity column defined by the metadata of `field`, if it is one.
+    pub(crate) fn try_from_field(field: &StructField) -> DeltaResult<Option<Self>> {
+        let start = field.get_config_value(&ColumnMetadataKey::IdentityStart);
+        let step = field.get_config_value(&ColumnMetadataKey::IdentityStep);
+        if start.is_none() && step.is_none() {
+            return Ok(None);
+        }
+        let name = field.name();
+        let invalid = |msg: &str| Error::invalid_identity_column(format!("{name} {msg}"));
+        let number = |key: ColumnMetadataKey| match field.get_config_value(&key) {
+            Some(MetadataValue::Number(value)) => Ok(Some(*value)),
+            Some(_) => Err(invalid(&format!("has a non-numeric {}", key.as_ref()))),
+            None => Ok(None),
+        };
+        let start = number(ColumnMetadataKey::IdentityStart)?
+            .ok_or_else(|| invalid("has no delta.identity.start"))?;
+        let step = number(ColumnMetadataKey::IdentityStep)?
+            .ok_or_else(|| invalid("has no delta.identity.step"))?;
+        let high_water_mark = number(ColumnMetadataKey::IdentityHighWaterMark)?;
+        let allow_explicit_insert =
+            match field.get_config_value(&ColumnMetadataKey::IdentityAllowExplicitInsert) {
+                Some(MetadataValue::Boolean(allow)) => *allow,
+                Some(_) => return Err(invalid("has a non-boolean allowExplicitInsert")),
+                None => false,
+            };
+        require!(
+            field.data_type() == &DataType::LONG,
+            invalid("is not of type long")
+        );
+        require!(step != 0, invalid("has a step of 0"));
+        Ok(Some(Self {
+            name: name.clone(),
+            start,
+            step,
+            high_water_mark,
+            allow_explicit_insert,
+        }))
+    }
+
+    /// Reserve `num_values` new values of the column after `high_water_mark` (or the column's
+    /// high water mark if it is `None`), and return the first one and the new high water mark.
+    /// Returns `None` if no values are reserved.
+    pub(crate) fn reserve(
+        &self,
+        high_water_mark: Option<i64>,
+        num_values: usize,
+    ) -> DeltaResult<Option<(i64, i64)>> {
+        if num_values == 0 {
+            return Ok(None);
+        }
+        let first = match high_water_mark.or(self.high_water_mark) {
+            Some(high_water_mark) => high_water_mark.checked_add(self.step),
+            None => Some(self.start),
+        };
+        let last = first.and_then(|first| {
+            let num_steps = i64::try_from(num_values - 1).ok()?;
+            first.checked_add(num_steps.checked_mul(self.step)?)
+        });
+        match (first, last) {
+            (Some(first), Some(last)) => Ok(Some((first, last))),
+            _ => Err(Error::invalid_identity_column(format!(
+                "{} has run out of values",
+                self.name
+            ))),
+        }
+    }
+
+    /// The expression that computes the values of the column for a batch of data whose generated
+    /// values start at `first`: the explicit values of the batch if allowed and the batch has the
+    /// column (`has_column`), or else the generated values.
+    pub(crate) fn values_expression(&self, first: i64, has_column: bool) -> Expression {
+        let generated =
+            Expression::literal(first) + Expression::literal(self.step) * Expression::row_index();
+        if self.allow_explicit_insert && has_column {
+            Expression::column([&self.name]).coalesce(generated)
+        } else {
+            generated
+        }
+    }
+}
+
+/// Check that the data to write has no explicit values for the identity column, i.e. that its
+/// values are all null, unless explicit values are allowed.
+pub(crate) fn ensure_no_explicit_values(
+    column: &IdentityColumn,
+    data: &dyn EngineData,
+) -> DeltaResult<()> {
+    if column.allow_explicit_insert {
+        return Ok(());
+    }
+    let mut visitor = ExplicitValuesVisitor::default();
+    data.visit_rows(&[ColumnName::new([&column.name])], &mut visitor)?;
+    require!(
+        !visitor.has_explicit_values,
+        Error::invalid_identity_column(format!(
+            "Cannot insert explicit values into {}, see delta.identity.allowExplicitInsert",
+            column.name
+        ))
+    );
+    Ok(())
+}
+
+// check whether a column of the data has non-null values. The column is selected by the caller,
+// see `EngineData::visit_rows`.
+#[derive(Default)]
+struct ExplicitValuesVisitor {
+    has_explicit_values: bool,
+}
+
+impl RowVisitor for ExplicitValuesVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| (vec![column_name!("identity")], vec![DataType::LONG]).into());
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for row in 0..row_count {
+            let value: Option<i64> = getters[0].get_opt(row, "identity")?;
+            self.has_explicit_values |= value.is_some();
+        }
+        Ok(())
+    }
+}
+
+/// The identity columns of a table with the given schema and partition columns. Identity columns
+/// can't be partition columns.
+pub(crate) fn identity_columns(
+    schema: &StructType,
+    partition_columns: &[String],
+) -> DeltaResult<Vec<IdentityColumn>> {
+    let mut columns = vec![];
+    for field in schema.fields() {
+        if let Some(column) = IdentityColumn::try_from_field(field)? {
+            require!(
+                !partition_columns.contains(&column.name),
+                Error::invalid_identity_column(format!("{} is a partition column", column.name))
+            );
+            columns.push(column);
+        }
+    }
+    Ok(columns)
+}
+
+/// The schema with the high water marks of its identity columns set to the given values.
+pub(crate) fn with_high_water_marks<'a>(
+    schema: &StructType,
+    high_water_marks: impl IntoIterator<Item = (&'a String, &'a i64)>,
+) -> StructType {
+    let mut fields: Vec<_> = schema.fields().cloned().collect();
+    for (name, high_water_mark) in high_water_marks {
+        if let Some(field) = fields.iter_mut().find(|field| field.name() == name) {
+            field.metadata.insert(
+                ColumnMetadataKey::IdentityHighWaterMark
+                    .as_ref()
+                    .to_string(),
+                MetadataValue::Number(*high_water_mark),
+            );
+        }
+    }
+    StructType::new(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn identity_field(step: i64, high_water_mark: Option<i64>) -> StructField {
+        let high_water_mark = high_water_mark
+            .map(|high_water_mark| ("delta.identity.highWaterMark", high_water_mark.into()));
+        StructField::not_null("id", DataType::LONG).with_metadata(
+            [
+                ("delta.identity.start", MetadataValue::Number(10)),
+                ("delta.identity.step", MetadataValue::Number(step)),
+            ]
+            .into_iter()
+            .chain(high_water_mark),
+        )
+    }
+
+    #[test]
+    fn test_identity_columns() {
+        let schema = StructType::new([
+            identity_field(2, None),
+            StructField::nullable("value", DataType::STRING),
+        ]);
+        let columns = identity_columns(&schema, &[]).unwrap();
+        assert_eq!(
+            columns,
+            [IdentityColumn {
+                name: "id".to_string(),
+                start: 10,
+                step: 2,
+                high_water_mark: None,
+                allow_explicit_insert: false,
+            }]
+        );
+        assert!(matches!(
+            identity_columns(&schema, &["id".to_string()]),
+            Err(Error::InvalidIdentityColumn(_))
+        ));
+
+        let invalid = [
+            identity_field(0, None),
+            StructField::nullable("id", DataType::INTEGER).with_metadata([
+                ("delta.identity.start", 1i64),
+                ("delta.identity.step", 1i64),
+            ]),
+            StructField::nullable("id", DataType::LONG)
+                .with_metadata([("delta.identity.step", 1i64)]),
+            StructField::nullable("id", DataType::LONG).with_metadata([
+                ("delta.identity.start", MetadataValue::Number(1)),
+                ("delta.identity.step", MetadataValue::Number(1)),
+                ("delta.identity.allowExplicitInsert", "yes".into()),
+            ]),
+        ];
+        for field in invalid {
+            assert!(matches!(
+                IdentityColumn::try_from_field(&field),
+                Err(Error::InvalidIdentityColumn(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_reserve_identity_values() {
+        let column = IdentityColumn::try_from_field(&identity_field(2, None))
+            .unwrap()
+            .unwrap();
+        assert_eq!(column.reserve(None, 0).unwrap(), None);
+        assert_eq!(column.reserve(None, 3).unwrap(), Some((10, 14)));
+        assert_eq!(column.reserve(Some(14), 1).unwrap(), Some((16, 16)));
+
+        let column = IdentityColumn::try_from_field(&identity_field(-5, Some(0)))
+            .unwrap()
+            .unwrap();
+        assert_eq!(column.reserve(None, 2).unwrap(), Some((-5, -10)));
+        assert!(matches!(
+            column.reserve(Some(i64::MIN + 7), 2),
+            Err(Error::InvalidIdentityColumn(_))
+        ));
+
+        let schema = StructType::new([identity_field(2, Some(14))]);
+        let schema = with_high_water_marks(&schema, [(&"id".to_string(), &20)]);
+        let columns = identity_columns(&schema, &[]).unwrap();
+        assert_eq!(columns[0].high_water_mark, Some(20));
+    }
+}
@@ -6,7 +6,13 @@ use strum::{AsRefStr, Display as StrumDisplay, EnumString, VariantNames};
 
 pub(crate) use column_mapping::column_mapping_mode;
 pub use column_mapping::{validate_schema_column_mapping, ColumnMappingMode};
+pub(crate) mod clustering;
 mod column_mapping;
+pub(crate) mod constraints;
+pub(crate) mod generated_columns;
+pub(crate) mod identity_columns;
+pub(crate) mod row_tracking;
+pub(crate) mod timestamp_ntz;
 
 /// Reader features communicate capabilities that must be implemented in order to correctly read a
 /// given table. That is, readers must implement and respect all features listed in a table's
@@ -109,6 +115,10 @@ pub enum WriterFeature {
     /// vacuumProtocolCheck ReaderWriter feature ensures consistent application of reader and writer
     /// protocol checks during VACUUM operations
     VacuumProtocolCheck,
+    /// Monotonically increasing commit timestamps stored in the commitInfo of each commit
+    InCommitTimestamp,
+    /// Liquid clustering of the data files over the clustering columns of the table
+    Clustering,
 }
 
 impl From<ReaderFeature> for String {
@@ -137,14 +147,32 @@ pub(crate) static SUPPORTED_READER_FEATURES: LazyLock<HashSet<ReaderFeature>> =
     });
 
 pub(crate) static SUPPORTED_WRITER_FEATURES: LazyLock<HashSet<WriterFeature>> =
-    // note: we 'support' Invariants, but only insofar as we check that they are not present.
-    // we support writing to tables that have Invariants enabled but not used. similarly, we only
-    // support DeletionVectors in that we never write them (no DML).
+    // note: Invariants and CheckConstraints require writers to check that the written rows
+    // satisfy the constraints of the table. TypeWidening only requires writers to record the type
+    // changes they make to the schema, V2Checkpoint requires checkpoints to follow the V2 spec,
+    // and InCommitTimestamp requires commits to carry monotonic timestamps in their commitInfo.
+    // DomainMetadata requires checkpoints to keep the domain metadata of the table, Clustering
+    // requires writers to collect the statistics of the clustering columns, RowTracking requires
+    // writers to assign row IDs to new files, IdentityColumns requires writers to generate the
+    // values of identity columns, and GeneratedColumns requires writers to write the values of the
+    // generation expressions of generated columns. TimestampWithoutTimezone only allows the schema
+    // to have `timestamp_ntz` columns.
     LazyLock::new(|| {
             HashSet::from([
                 WriterFeature::AppendOnly,
+                WriterFeature::CheckConstraints,
+                WriterFeature::Clustering,
                 WriterFeature::DeletionVectors,
+                WriterFeature::DomainMetadata,
+                WriterFeature::GeneratedColumns,
+                WriterFeature::IdentityColumns,
+                WriterFeature::InCommitTimestamp,
                 WriterFeature::Invariants,
+                WriterFeature::RowTracking,
+                WriterFeature::TimestampWithoutTimezone,
+                WriterFeature::TypeWidening,
+                WriterFeature::TypeWideningPreview,
+                WriterFeature::V2Checkpoint,
             ])
         });
 
@@ -200,6 +228,8 @@ mod tests {
             (WriterFeature::IcebergCompatV1, "icebergCompatV1"),
             (WriterFeature::IcebergCompatV2, "icebergCompatV2"),
             (WriterFeature::VacuumProtocolCheck, "vacuumProtocolCheck"),
+            (WriterFeature::InCommitTimestamp, "inCommitTimestamp"),
+            (WriterFeature::Clustering, "clustering"),
         ];
 
         assert_eq!(WriterFeature::VARIANTS.len(), cases.len());
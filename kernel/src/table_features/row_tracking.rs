@@ -0,0 +1,204 @@
+//! Code to handle [row tracking].
+//!
+//! Tables with the `rowTracking` writer feature assign every new `add` action a `baseRowId`, the
+//! row ID of its first row, and a `defaultRowCommitVersion`, the version that committed it. The
+//! row IDs of the rows of a file are `baseRowId` plus their index in the file, unless they are
+//! materialized in the file (e.g. after the file is rewritten) in the column named by the
+//! `delta.rowTracking.materializedRowIdColumnName` table property, and likewise for the row commit
+//! versions. The highest row ID assigned so far is stored in the `delta.rowTracking` metadata
+//! domain: `{"rowIdHighWaterMark":41}`.
+//!
+//! [row tracking]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#row-tracking
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::actions::DomainMetadata;
+use crate::expressions::Expression;
+use crate::schema::{DataType, MetadataColumnSpec, StructField, StructType};
+use crate::table_configuration::TableConfiguration;
+use crate::DeltaResult;
+
+/// The metadata domain that stores the row ID high water mark of a table.
+pub(crate) const ROW_TRACKING_DOMAIN_NAME: &str = "delta.rowTracking";
+
+/// The table property with the name of the materialized row ID column.
+pub(crate) const MATERIALIZED_ROW_ID_COLUMN_NAME_KEY: &str =
+    "delta.rowTracking.materializedRowIdColumnName";
+
+/// The table property with the name of the materialized row commit version column.
+pub(crate) const MATERIALIZED_ROW_COMMIT_VERSION_COLUMN_NAME_KEY: &str =
+    "delta.rowTracking.materializedRowCommitVersionColumnName";
+
+/// The name of the metadata column that exposes the row tracking columns of a scan.
+pub(crate) const METADATA_COLUMN_NAME: &str = "_metadata";
+
+/// The field of the metadata column with the row IDs.
+pub(crate) const ROW_ID_FIELD_NAME: &str = "row_id";
+
+/// The field of the metadata column with the row commit versions.
+pub(crate) const ROW_COMMIT_VERSION_FIELD_NAME: &str = "row_commit_version";
+
+/// The name of the row index column that scans read to compute the row IDs.
+pub(crate) const ROW_INDEX_COLUMN_NAME: &str = "__delta_internal_row_index";
+
+/// The high water mark of a table without any row IDs assigned.
+pub(crate) const INITIAL_ROW_ID_HIGH_WATER_MARK: i64 = -1;
+
+// The configuration of the row tracking domain
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RowTrackingConfiguration {
+    row_id_high_water_mark: i64,
+}
+
+/// Parse the row ID high water mark from the configuration of the row tracking domain.
+pub(crate) fn parse_row_id_high_water_mark(configuration: &str) -> DeltaResult<i64> {
+    let configuration: RowTrackingConfiguration = serde_json::from_str(configuration)?;
+    Ok(configuration.row_id_high_water_mark)
+}
+
+/// The domain metadata action that sets the row ID high water mark of a table.
+pub(crate) fn row_tracking_domain_metadata(high_water_mark: i64) -> DeltaResult<DomainMetadata> {
+    let configuration = RowTrackingConfiguration {
+        row_id_high_water_mark: high_water_mark,
+    };
+    Ok(DomainMetadata {
+        domain: ROW_TRACKING_DOMAIN_NAME.to_string(),
+        configuration: serde_json::to_string(&configuration)?,
+        removed: false,
+    })
+}
+
+/// New, unique names for the materialized row tracking columns of a table, as the table properties
+/// that store them.
+pub(crate) fn new_materialized_column_names() -> [(String, String); 2] {
+    [
+        (
+            MATERIALIZED_ROW_ID_COLUMN_NAME_KEY.to_string(),
+            format!("_row-id-col-{}", Uuid::new_v4()),
+        ),
+        (
+            MATERIALIZED_ROW_COMMIT_VERSION_COLUMN_NAME_KEY.to_string(),
+            format!("_row-commit-version-col-{}", Uuid::new_v4()),
+        ),
+    ]
+}
+
+/// The (physical) names of the materialized row tracking columns of a table, if the table
+/// properties name them.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct MaterializedRowTrackingColumns {
+    pub(crate) row_id: Option<String>,
+    pub(crate) row_commit_version: Option<String>,
+}
+
+impl MaterializedRowTrackingColumns {
+    pub(crate) fn new(table_configuration: &TableConfiguration) -> Self {
+        let configuration = table_configuration.metadata().configuration();
+        Self {
+            row_id: configuration
+                .get(MATERIALIZED_ROW_ID_COLUMN_NAME_KEY)
+                .cloned(),
+            row_commit_version: configuration
+                .get(MATERIALIZED_ROW_COMMIT_VERSION_COLUMN_NAME_KEY)
+                .cloned(),
+        }
+    }
+
+    /// The fields to read the materialized columns with. Files that don't have the columns read
+    /// them as nulls.
+    pub(crate) fn read_fields(&self) -> impl Iterator<Item = StructField> + '_ {
+        [&self.row_id, &self.row_commit_version]
+            .into_iter()
+            .flatten()
+            .map(|name| StructField::nullable(name, DataType::LONG))
+    }
+
+    /// The expression that computes the row tracking struct of the rows of a file with the given
+    /// `baseRowId` and `defaultRowCommitVersion`, from the data read with [`Self::read_fields`] and
+    /// [`row_index_field`]. Values materialized in the file take precedence.
+    pub(crate) fn row_tracking_expression(
+        &self,
+        base_row_id: i64,
+        default_row_commit_version: i64,
+    ) -> Expression {
+        let row_id = Expression::literal(base_row_id) + Expression::column([ROW_INDEX_COLUMN_NAME]);
+        let row_commit_version = Expression::literal(default_row_commit_version);
+        Expression::struct_from([
+            materialized_or(&self.row_id, row_id),
+            materialized_or(&self.row_commit_version, row_commit_version),
+        ])
+    }
+}
+
+// The value of the materialized column if it is not null, or else `fallback`
+fn materialized_or(materialized: &Option<String>, fallback: Expression) -> Expression {
+    match materialized {
+        Some(name) => Expression::column([name]).coalesce(fallback),
+        None => fallback,
+    }
+}
+
+/// The field of the row tracking metadata column of scans, with the row ID and row commit version
+/// of every row.
+pub(crate) fn metadata_column_field() -> StructField {
+    StructField::not_null(
+        METADATA_COLUMN_NAME,
+        StructType::new([
+            StructField::not_null(ROW_ID_FIELD_NAME, DataType::LONG),
+            StructField::not_null(ROW_COMMIT_VERSION_FIELD_NAME, DataType::LONG),
+        ]),
+    )
+}
+
+/// The row index metadata column that scans read to compute the row IDs.
+pub(crate) fn row_index_field() -> StructField {
+    StructField::create_metadata_column(ROW_INDEX_COLUMN_NAME, MetadataColumnSpec::RowIndex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_row_tracking_domain_metadata() {
+        let domain_metadata = row_tracking_domain_metadata(41).unwrap();
+        assert_eq!(domain_metadata.domain, "delta.rowTracking");
+        assert_eq!(
+            domain_metadata.configuration,
+            r#"{"rowIdHighWaterMark":41}"#
+        );
+        assert!(!domain_metadata.removed);
+        assert_eq!(
+            parse_row_id_high_water_mark(&domain_metadata.configuration).unwrap(),
+            41
+        );
+        assert!(parse_row_id_high_water_mark(r#"{"highWaterMark":41}"#).is_err());
+    }
+
+    #[test]
+    fn test_row_tracking_expression() {
+        let materialized = MaterializedRowTrackingColumns {
+            row_id: Some("_row-id-col".to_string()),
+            row_commit_version: None,
+        };
+        assert_eq!(materialized.read_fields().count(), 1);
+        let expected = Expression::struct_from([
+            Expression::column(["_row-id-col"])
+                .coalesce(Expression::literal(10i64) + Expression::column([ROW_INDEX_COLUMN_NAME])),
+            Expression::literal(3i64),
+        ]);
+        assert_eq!(materialized.row_tracking_expression(10, 3), expected);
+
+        let [(row_id_key, row_id), (row_commit_version_key, row_commit_version)] =
+            new_materialized_column_names();
+        assert_eq!(row_id_key, MATERIALIZED_ROW_ID_COLUMN_NAME_KEY);
+        assert_eq!(
+            row_commit_version_key,
+            MATERIALIZED_ROW_COMMIT_VERSION_COLUMN_NAME_KEY
+        );
+        assert!(row_id.starts_with("_row-id-col-"));
+        assert!(row_commit_version.starts_with("_row-commit-version-col-"));
+    }
+}
@@ -0,0 +1,61 @@
+//! Code to handle the [timestamp without timezone] table feature.
+//!
+//! Tables may only have `timestamp_ntz` columns, at the top level or nested in structs, arrays and
+//! maps, if their protocol supports the `timestampNtz` reader and writer feature.
+//!
+//! [timestamp without timezone]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#timestamp-without-timezone-timestampntz
+
+use std::borrow::Cow;
+
+use crate::schema::{PrimitiveType, SchemaTransform, StructType};
+
+#[derive(Debug, Default)]
+struct TimestampNtzChecker {
+    has_timestamp_ntz: bool,
+}
+
+impl<'a> SchemaTransform<'a> for TimestampNtzChecker {
+    fn transform_primitive(&mut self, ptype: &'a PrimitiveType) -> Option<Cow<'a, PrimitiveType>> {
+        if *ptype == PrimitiveType::TimestampNtz {
+            self.has_timestamp_ntz = true;
+        }
+        Some(Cow::Borrowed(ptype))
+    }
+}
+
+/// Checks if any column of the schema (including nested columns) is of type `timestamp_ntz`, which
+/// requires the `timestampNtz` table feature.
+pub(crate) fn schema_contains_timestamp_ntz(schema: &StructType) -> bool {
+    let mut checker = TimestampNtzChecker::default();
+    let _ = checker.transform_struct(schema);
+    checker.has_timestamp_ntz
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::schema::{ArrayType, DataType, MapType, StructField};
+
+    #[test]
+    fn test_schema_contains_timestamp_ntz() {
+        let schema = |data_type: DataType| {
+            StructType::new([
+                StructField::nullable("id", DataType::LONG),
+                StructField::nullable("value", data_type),
+            ])
+        };
+        assert!(!schema_contains_timestamp_ntz(&schema(DataType::TIMESTAMP)));
+        assert!(schema_contains_timestamp_ntz(&schema(
+            DataType::TIMESTAMP_NTZ
+        )));
+        let nested = [
+            DataType::struct_type([StructField::nullable("ts", DataType::TIMESTAMP_NTZ)]),
+            ArrayType::new(DataType::TIMESTAMP_NTZ, true).into(),
+            MapType::new(DataType::STRING, DataType::TIMESTAMP_NTZ, true).into(),
+        ];
+        for data_type in nested {
+            assert!(schema_contains_timestamp_ntz(&schema(data_type)));
+        }
+    }
+}
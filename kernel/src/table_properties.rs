@@ -23,6 +23,13 @@ use strum::EnumString;
 mod deserialize;
 pub use deserialize::ParseIntervalError;
 
+/// The default for [`TableProperties::deleted_file_retention_duration`]: one week.
+pub(crate) const DEFAULT_DELETED_FILE_RETENTION_DURATION: Duration =
+    Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The default for [`TableProperties::log_retention_duration`]: 30 days.
+pub(crate) const DEFAULT_LOG_RETENTION_DURATION: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
 /// Delta table properties. These are parsed from the 'configuration' map in the most recent
 /// 'Metadata' action of a table.
 ///
@@ -151,6 +158,11 @@ pub struct TableProperties {
     /// as the inCommitTimestamp of the commit when this feature was enabled.
     pub in_commit_timestamp_enablement_timestamp: Option<i64>,
 
+    /// Whether to allow widening the types of columns, see [Type Widening].
+    ///
+    /// [Type Widening]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#type-widening
+    pub enable_type_widening: Option<bool>,
+
     /// any unrecognized properties are passed through and ignored by the parser
     pub unknown_properties: HashMap<String, String>,
 }
@@ -285,6 +297,7 @@ mod tests {
             ("delta.enableInCommitTimestamps", "true"),
             ("delta.inCommitTimestampEnablementVersion", "15"),
             ("delta.inCommitTimestampEnablementTimestamp", "1612345678"),
+            ("delta.enableTypeWidening", "true"),
         ];
         let actual = TableProperties::from(properties.into_iter());
         let expected = TableProperties {
@@ -313,6 +326,7 @@ mod tests {
             enable_in_commit_timestamps: Some(true),
             in_commit_timestamp_enablement_version: Some(15),
             in_commit_timestamp_enablement_timestamp: Some(1_612_345_678),
+            enable_type_widening: Some(true),
             unknown_properties: HashMap::new(),
         };
         assert_eq!(actual, expected);
@@ -88,6 +88,7 @@ fn try_parse(props: &mut TableProperties, k: &str, v: &str) -> Option<()> {
         "delta.inCommitTimestampEnablementTimestamp" => {
             props.in_commit_timestamp_enablement_timestamp = Some(parse_non_negative(v)?)
         }
+        "delta.enableTypeWidening" => props.enable_type_widening = Some(parse_bool(v)?),
         _ => return None,
     }
     Some(())
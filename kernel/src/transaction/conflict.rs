@@ -0,0 +1,464 @@
+//! Detection of logical conflicts between a transaction and the transactions that were committed
+//! after its read snapshot (the "winning" commits).
+//!
+//! When a commit fails because its version was already written, the transaction summarizes each
+//! winning commit with a [`WinningCommitSummary`] and checks it against what the transaction read
+//! and removed ([`TransactionSummary`]) according to the table's [`IsolationLevel`]. If none of
+//! the winning commits conflict, the transaction can safely be retried on top of them.
+
+use std::collections::HashSet;
+use std::sync::{Arc, LazyLock};
+
+use crate::actions::schemas::GetStructField;
+use crate::actions::{
+    Add, CommitInfo, DomainMetadata, Metadata, Protocol, Remove, SetTransaction, ADD_NAME,
+    COMMIT_INFO_NAME, DOMAIN_METADATA_NAME, METADATA_NAME, PROTOCOL_NAME, REMOVE_NAME,
+    SET_TRANSACTION_NAME,
+};
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
+use crate::expressions::{column_name, ColumnName};
+use crate::path::ParsedLogPath;
+use crate::schema::{ColumnNamesAndTypes, DataType, StructType};
+use crate::table_properties::IsolationLevel;
+use crate::{DeltaResult, Engine, Version};
+
+/// A logical conflict between a transaction and a transaction that was committed concurrently
+/// (that is, after the snapshot the transaction was created from). The version is the version of
+/// the conflicting commit.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum ConflictError {
+    /// A concurrent transaction added data files that the transaction would have read
+    #[error("Concurrent transaction at version {0} added files to the table")]
+    ConcurrentAppend(Version),
+
+    /// A concurrent transaction removed a file that the transaction read
+    #[error("Concurrent transaction at version {version} removed file {path} which was read")]
+    ConcurrentDeleteRead { version: Version, path: String },
+
+    /// A concurrent transaction removed a file that the transaction also removes
+    #[error("Concurrent transaction at version {version} removed file {path} which is removed")]
+    ConcurrentDeleteDelete { version: Version, path: String },
+
+    /// A concurrent transaction changed the table metadata
+    #[error("Concurrent transaction at version {0} changed the table metadata")]
+    MetadataChanged(Version),
+
+    /// A concurrent transaction changed the table protocol
+    #[error("Concurrent transaction at version {0} changed the table protocol")]
+    ProtocolChanged(Version),
+
+    /// A concurrent transaction committed a transaction identifier for the same application
+    #[error("Concurrent transaction at version {version} committed a transaction of application {app_id}")]
+    ConcurrentTransaction { version: Version, app_id: String },
+
+    /// A concurrent transaction changed a metadata domain that the transaction also changes
+    #[error("Concurrent transaction at version {version} changed the metadata domain {domain}")]
+    ConcurrentDomainMetadata { version: Version, domain: String },
+}
+
+/// What a transaction read from and removed from its read snapshot.
+#[derive(Debug, Default)]
+pub(crate) struct TransactionSummary {
+    /// Whether the transaction depends on the data of the table. Since kernel does not track the
+    /// predicates a transaction read with, any such transaction has read the whole table.
+    pub(crate) read_whole_table: bool,
+    /// The paths of the files the transaction removes (or updates the deletion vectors of).
+    pub(crate) removed_paths: HashSet<String>,
+    /// The application id of the transaction identifier committed by the transaction, if any.
+    pub(crate) app_id: Option<String>,
+    /// The metadata domains changed by the transaction.
+    pub(crate) domains: HashSet<String>,
+    /// Whether the transaction changes the table metadata.
+    pub(crate) metadata_changed: bool,
+}
+
+impl TransactionSummary {
+    /// Check the transaction against a winning commit.
+    pub(crate) fn check(
+        &self,
+        winning_commit: &WinningCommitSummary,
+        isolation_level: IsolationLevel,
+    ) -> Result<(), ConflictError> {
+        let version = winning_commit.version;
+        if winning_commit.protocol_changed {
+            return Err(ConflictError::ProtocolChanged(version));
+        }
+        if winning_commit.metadata_changed {
+            return Err(ConflictError::MetadataChanged(version));
+        }
+        // the versions of an application's transactions must be committed in order, so only one
+        // of two concurrent transactions of the same application may commit
+        if let Some(app_id) = &self.app_id {
+            if winning_commit.app_ids.contains(app_id) {
+                return Err(ConflictError::ConcurrentTransaction {
+                    version,
+                    app_id: app_id.clone(),
+                });
+            }
+        }
+        // the configuration of a domain may depend on its previous configuration, so a domain
+        // can't be changed by two concurrent transactions
+        if let Some(domain) = winning_commit
+            .domains
+            .iter()
+            .find(|domain| self.domains.contains(*domain))
+        {
+            return Err(ConflictError::ConcurrentDomainMetadata {
+                version,
+                domain: domain.clone(),
+            });
+        }
+
+        // Added files only matter if the transaction read the table. Under WriteSerializable, the
+        // files of blind appends are allowed since the writes can still be serialized by moving
+        // the append after this transaction, unless this transaction changes the metadata (e.g.
+        // adds a constraint) that the appended files would then have to follow.
+        let appends_conflict = match isolation_level {
+            IsolationLevel::Serializable => true,
+            IsolationLevel::WriteSerializable => {
+                self.metadata_changed || !winning_commit.is_blind_append
+            }
+            IsolationLevel::SnapshotIsolation => false,
+        };
+        if self.read_whole_table && appends_conflict && winning_commit.added_data_files {
+            return Err(ConflictError::ConcurrentAppend(version));
+        }
+
+        for path in &winning_commit.removed_paths {
+            if self.removed_paths.contains(path) {
+                return Err(ConflictError::ConcurrentDeleteDelete {
+                    version,
+                    path: path.clone(),
+                });
+            }
+            if self.read_whole_table {
+                return Err(ConflictError::ConcurrentDeleteRead {
+                    version,
+                    path: path.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The parts of a winning commit that are relevant for conflict detection.
+#[derive(Debug, Default)]
+pub(crate) struct WinningCommitSummary {
+    pub(crate) version: Version,
+    /// Whether the commit added any files with `dataChange` set.
+    pub(crate) added_data_files: bool,
+    /// The paths of all the files removed by the commit.
+    pub(crate) removed_paths: Vec<String>,
+    pub(crate) metadata_changed: bool,
+    pub(crate) protocol_changed: bool,
+    /// The `isBlindAppend` field of the commit info. Commits without it are not blind appends.
+    pub(crate) is_blind_append: bool,
+    /// The application ids of the transaction identifiers (`txn` actions) in the commit.
+    pub(crate) app_ids: HashSet<String>,
+    /// The metadata domains changed (`domainMetadata` actions) by the commit.
+    pub(crate) domains: HashSet<String>,
+}
+
+impl WinningCommitSummary {
+    /// Read the commit file and summarize it.
+    pub(crate) fn try_new(engine: &dyn Engine, commit: &ParsedLogPath) -> DeltaResult<Self> {
+        let mut visitor = WinningCommitVisitor {
+            summary: WinningCommitSummary {
+                version: commit.version,
+                ..Default::default()
+            },
+        };
+        let actions = engine.get_json_handler().read_json_files(
+            std::slice::from_ref(&commit.location),
+            WinningCommitVisitor::schema(),
+            None,
+        )?;
+        for actions in actions {
+            visitor.visit_rows_of(actions?.as_ref())?;
+        }
+        Ok(visitor.summary)
+    }
+}
+
+struct WinningCommitVisitor {
+    summary: WinningCommitSummary,
+}
+
+impl WinningCommitVisitor {
+    fn schema() -> Arc<StructType> {
+        Arc::new(StructType::new(vec![
+            Option::<Add>::get_struct_field(ADD_NAME),
+            Option::<Remove>::get_struct_field(REMOVE_NAME),
+            Option::<Metadata>::get_struct_field(METADATA_NAME),
+            Option::<Protocol>::get_struct_field(PROTOCOL_NAME),
+            Option::<CommitInfo>::get_struct_field(COMMIT_INFO_NAME),
+            Option::<SetTransaction>::get_struct_field(SET_TRANSACTION_NAME),
+            Option::<DomainMetadata>::get_struct_field(DOMAIN_METADATA_NAME),
+        ]))
+    }
+}
+
+impl RowVisitor for WinningCommitVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        // NOTE: The order of the names and types is based on [`WinningCommitVisitor::schema`]
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            let types_and_names = vec![
+                (DataType::STRING, column_name!("add.path")),
+                (DataType::BOOLEAN, column_name!("add.dataChange")),
+                (DataType::STRING, column_name!("remove.path")),
+                (DataType::STRING, column_name!("metaData.id")),
+                (DataType::INTEGER, column_name!("protocol.minReaderVersion")),
+                (DataType::BOOLEAN, column_name!("commitInfo.isBlindAppend")),
+                (DataType::STRING, column_name!("txn.appId")),
+                (DataType::STRING, column_name!("domainMetadata.domain")),
+            ];
+            let (types, names) = types_and_names.into_iter().unzip();
+            (names, types).into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for i in 0..row_count {
+            let add_path: Option<String> = getters[0].get_opt(i, "add.path")?;
+            if add_path.is_some() {
+                let data_change: bool = getters[1].get(i, "add.dataChange")?;
+                self.summary.added_data_files |= data_change;
+                continue;
+            }
+            if let Some(path) = getters[2].get_opt(i, "remove.path")? {
+                self.summary.removed_paths.push(path);
+                continue;
+            }
+            let metadata_id: Option<String> = getters[3].get_opt(i, "metaData.id")?;
+            let min_reader_version: Option<i32> =
+                getters[4].get_opt(i, "protocol.minReaderVersion")?;
+            self.summary.metadata_changed |= metadata_id.is_some();
+            self.summary.protocol_changed |= min_reader_version.is_some();
+            if let Some(is_blind_append) = getters[5].get_opt(i, "commitInfo.isBlindAppend")? {
+                self.summary.is_blind_append = is_blind_append;
+            }
+            if let Some(app_id) = getters[6].get_opt(i, "txn.appId")? {
+                self.summary.app_ids.insert(app_id);
+            }
+            if let Some(domain) = getters[7].get_opt(i, "domainMetadata.domain")? {
+                self.summary.domains.insert(domain);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::engine::sync::SyncEngine;
+    use crate::utils::test_utils::string_array_to_engine_data;
+
+    use crate::arrow::array::StringArray;
+
+    fn winning_commit(version: Version) -> WinningCommitSummary {
+        WinningCommitSummary {
+            version,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_winning_commit_summary() {
+        let engine = SyncEngine::new();
+        let json_strings: StringArray = vec![
+            r#"{"commitInfo":{"timestamp":1,"operation":"DELETE","isBlindAppend":false}}"#,
+            r#"{"add":{"path":"c.parquet","partitionValues":{},"size":1,"modificationTime":1,"dataChange":false}}"#,
+            r#"{"remove":{"path":"a.parquet","deletionTimestamp":1,"dataChange":true}}"#,
+            r#"{"remove":{"path":"b.parquet","deletionTimestamp":1,"dataChange":true}}"#,
+            r#"{"metaData":{"id":"id","format":{"provider":"parquet","options":{}},"schemaString":"{\"type\":\"struct\",\"fields\":[]}","partitionColumns":[],"configuration":{}}}"#,
+            r#"{"txn":{"appId":"my-app","version":2,"lastUpdated":1}}"#,
+            r#"{"domainMetadata":{"domain":"delta.clustering","configuration":"{}","removed":false}}"#,
+        ]
+        .into();
+        let mut visitor = WinningCommitVisitor {
+            summary: winning_commit(3),
+        };
+        let data = engine
+            .get_json_handler()
+            .parse_json(
+                string_array_to_engine_data(json_strings),
+                WinningCommitVisitor::schema(),
+            )
+            .unwrap();
+        visitor.visit_rows_of(data.as_ref()).unwrap();
+
+        let summary = visitor.summary;
+        assert_eq!(summary.version, 3);
+        assert!(!summary.added_data_files);
+        assert_eq!(summary.removed_paths, vec!["a.parquet", "b.parquet"]);
+        assert!(summary.metadata_changed);
+        assert!(!summary.protocol_changed);
+        assert!(!summary.is_blind_append);
+        assert_eq!(summary.app_ids, HashSet::from(["my-app".to_string()]));
+        assert_eq!(
+            summary.domains,
+            HashSet::from(["delta.clustering".to_string()])
+        );
+    }
+
+    #[test]
+    fn test_check_blind_append() {
+        // a blind append only conflicts with protocol and metadata changes
+        let txn = TransactionSummary::default();
+        let append = WinningCommitSummary {
+            added_data_files: true,
+            removed_paths: vec!["a".to_string()],
+            ..winning_commit(1)
+        };
+        assert_eq!(txn.check(&append, IsolationLevel::Serializable), Ok(()));
+
+        let metadata = WinningCommitSummary {
+            metadata_changed: true,
+            ..winning_commit(1)
+        };
+        assert_eq!(
+            txn.check(&metadata, IsolationLevel::Serializable),
+            Err(ConflictError::MetadataChanged(1))
+        );
+
+        let protocol = WinningCommitSummary {
+            protocol_changed: true,
+            metadata_changed: true,
+            ..winning_commit(2)
+        };
+        assert_eq!(
+            txn.check(&protocol, IsolationLevel::WriteSerializable),
+            Err(ConflictError::ProtocolChanged(2))
+        );
+    }
+
+    #[test]
+    fn test_check_concurrent_append() {
+        let txn = TransactionSummary {
+            read_whole_table: true,
+            removed_paths: HashSet::from(["a".to_string()]),
+            ..Default::default()
+        };
+        let blind_append = WinningCommitSummary {
+            added_data_files: true,
+            is_blind_append: true,
+            ..winning_commit(1)
+        };
+        let append = WinningCommitSummary {
+            added_data_files: true,
+            ..winning_commit(1)
+        };
+        let compaction_add = winning_commit(1);
+
+        let conflict = Err(ConflictError::ConcurrentAppend(1));
+        let cases = [
+            (&blind_append, IsolationLevel::Serializable, &conflict),
+            (&blind_append, IsolationLevel::WriteSerializable, &Ok(())),
+            (&blind_append, IsolationLevel::SnapshotIsolation, &Ok(())),
+            (&append, IsolationLevel::Serializable, &conflict),
+            (&append, IsolationLevel::WriteSerializable, &conflict),
+            (&append, IsolationLevel::SnapshotIsolation, &Ok(())),
+            (&compaction_add, IsolationLevel::Serializable, &Ok(())),
+        ];
+        for (winning_commit, isolation_level, expected) in cases {
+            assert_eq!(&txn.check(winning_commit, isolation_level), expected);
+        }
+
+        // transactions that change the metadata are serializable with blind appends
+        let txn = TransactionSummary {
+            read_whole_table: true,
+            metadata_changed: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            txn.check(&blind_append, IsolationLevel::WriteSerializable),
+            conflict
+        );
+        assert_eq!(
+            txn.check(&blind_append, IsolationLevel::SnapshotIsolation),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn test_check_concurrent_delete() {
+        let remove = |path: &str| WinningCommitSummary {
+            removed_paths: vec![path.to_string()],
+            ..winning_commit(1)
+        };
+        let txn = TransactionSummary {
+            read_whole_table: true,
+            removed_paths: HashSet::from(["a".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(
+            txn.check(&remove("a"), IsolationLevel::SnapshotIsolation),
+            Err(ConflictError::ConcurrentDeleteDelete {
+                version: 1,
+                path: "a".to_string()
+            })
+        );
+        assert_eq!(
+            txn.check(&remove("b"), IsolationLevel::SnapshotIsolation),
+            Err(ConflictError::ConcurrentDeleteRead {
+                version: 1,
+                path: "b".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_concurrent_transaction() {
+        let txn = TransactionSummary {
+            app_id: Some("my-app".to_string()),
+            ..Default::default()
+        };
+        let other_app = WinningCommitSummary {
+            app_ids: HashSet::from(["other-app".to_string()]),
+            ..winning_commit(1)
+        };
+        let same_app = WinningCommitSummary {
+            app_ids: HashSet::from(["my-app".to_string()]),
+            ..winning_commit(2)
+        };
+        assert_eq!(txn.check(&other_app, IsolationLevel::Serializable), Ok(()));
+        assert_eq!(
+            txn.check(&same_app, IsolationLevel::SnapshotIsolation),
+            Err(ConflictError::ConcurrentTransaction {
+                version: 2,
+                app_id: "my-app".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_check_concurrent_domain_metadata() {
+        let txn = TransactionSummary {
+            domains: HashSet::from(["delta.clustering".to_string()]),
+            ..Default::default()
+        };
+        let other_domain = WinningCommitSummary {
+            domains: HashSet::from(["my-domain".to_string()]),
+            ..winning_commit(1)
+        };
+        let same_domain = WinningCommitSummary {
+            domains: HashSet::from(["delta.clustering".to_string()]),
+            ..winning_commit(2)
+        };
+        assert_eq!(
+            txn.check(&other_domain, IsolationLevel::Serializable),
+            Ok(())
+        );
+        assert_eq!(
+            txn.check(&same_domain, IsolationLevel::SnapshotIsolation),
+            Err(ConflictError::ConcurrentDomainMetadata {
+                version: 2,
+                domain: "delta.clustering".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,511 @@
+//! Create new tables by committing version 0 of their log with a [`CreateTableBuilder`].
+
+use std::collections::HashMap;
+
+use url::Url;
+use uuid::Uuid;
+
+use crate::actions::{
+    action_to_engine_data, Format, Metadata, Protocol, DOMAIN_METADATA_NAME, METADATA_NAME,
+    PROTOCOL_NAME,
+};
+use crate::path::ParsedLogPath;
+use crate::schema::{ColumnName, DataType, InvariantChecker, SchemaRef};
+use crate::table::Table;
+use crate::table_configuration::TableConfiguration;
+use crate::table_features::clustering::{clustering_domain_metadata, validate_clustering_columns};
+use crate::table_features::constraints::CONSTRAINT_PROPERTY_PREFIX;
+use crate::table_features::generated_columns::generated_columns;
+use crate::table_features::identity_columns::identity_columns;
+use crate::table_features::row_tracking::new_materialized_column_names;
+use crate::table_features::timestamp_ntz::schema_contains_timestamp_ntz;
+use crate::table_features::{ColumnMappingMode, ReaderFeature, WriterFeature};
+use crate::table_properties::{CheckpointPolicy, TableProperties};
+use crate::utils::{current_time_ms, require};
+use crate::{DeltaResult, Engine, EngineData, Error};
+
+use super::{ensure_table_properties_supported, generate_commit_info};
+
+const CREATE_TABLE_OPERATION: &str = "CREATE TABLE";
+
+/// Builder for a new table, created with [`Table::create`]. Committing the builder atomically
+/// writes version 0 of the table: a `commitInfo`, a `protocol` and a `metaData` action, and the
+/// `domainMetadata` action with the clustering columns of clustered tables.
+///
+/// The protocol is the minimal protocol that supports the requested reader and writer features,
+/// along with the features required by the table properties (e.g. `delta.appendOnly=true`
+/// requires the `appendOnly` writer feature). Tables without any feature use reader and writer
+/// version 1, and tables with features use the table features protocol (reader version 3 and/or
+/// writer version 7).
+///
+/// Tables with identity columns (see [`ColumnMetadataKey::IdentityStart`]) get the
+/// `identityColumns` writer feature, and tables with generated columns (see
+/// [`ColumnMetadataKey::GenerationExpression`]) the `generatedColumns` writer feature. Tables with
+/// CHECK constraints (`delta.constraints.<name>` table properties) get the `checkConstraints`
+/// writer feature, and tables with column invariants (see [`ColumnMetadataKey::Invariants`]) the
+/// `invariants` writer feature. Tables with `timestamp_ntz` columns get the `timestampNtz` reader
+/// and writer feature. Tables with the `rowTracking` writer feature (e.g. with
+/// `delta.enableRowTracking=true`) also get the `domainMetadata` writer feature, and unique names
+/// for their materialized row tracking columns in the
+/// `delta.rowTracking.materializedRowIdColumnName` and
+/// `delta.rowTracking.materializedRowCommitVersionColumnName` table properties unless those are
+/// set.
+///
+/// [`ColumnMetadataKey::IdentityStart`]: crate::schema::ColumnMetadataKey::IdentityStart
+/// [`ColumnMetadataKey::GenerationExpression`]: crate::schema::ColumnMetadataKey::GenerationExpression
+/// [`ColumnMetadataKey::Invariants`]: crate::schema::ColumnMetadataKey::Invariants
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// let table = Table::create(location, schema)
+///     .with_partition_columns(["date"])
+///     .with_table_properties([("delta.appendOnly", "true")])
+///     .with_commit_info(engine_commit_info)
+///     .commit(&engine)?;
+/// ```
+pub struct CreateTableBuilder {
+    table_root: Url,
+    schema: SchemaRef,
+    partition_columns: Vec<String>,
+    table_properties: HashMap<String, String>,
+    reader_features: Vec<ReaderFeature>,
+    writer_features: Vec<WriterFeature>,
+    clustering_columns: Option<Vec<ColumnName>>,
+    operation: Option<String>,
+    commit_info: Option<Box<dyn EngineData>>,
+}
+
+impl std::fmt::Debug for CreateTableBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CreateTableBuilder")
+            .field("table_root", &self.table_root)
+            .field("partition_columns", &self.partition_columns)
+            .field("table_properties", &self.table_properties)
+            .field("clustering_columns", &self.clustering_columns)
+            .finish()
+    }
+}
+
+impl CreateTableBuilder {
+    pub(crate) fn new(table_root: Url, schema: SchemaRef) -> Self {
+        Self {
+            table_root,
+            schema,
+            partition_columns: vec![],
+            table_properties: HashMap::new(),
+            reader_features: vec![],
+            writer_features: vec![],
+            clustering_columns: None,
+            operation: None,
+            commit_info: None,
+        }
+    }
+
+    /// Partition the table by the given top-level columns of the schema.
+    pub fn with_partition_columns(
+        mut self,
+        partition_columns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.partition_columns = partition_columns.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set table properties (the `configuration` of the table metadata), e.g.
+    /// `delta.enableDeletionVectors`.
+    pub fn with_table_properties(
+        mut self,
+        table_properties: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> Self {
+        self.table_properties.extend(
+            table_properties
+                .into_iter()
+                .map(|(k, v)| (k.into(), v.into())),
+        );
+        self
+    }
+
+    /// Require readers of the table to support the given features. Every reader feature is also
+    /// added as a writer feature.
+    pub fn with_reader_features(
+        mut self,
+        reader_features: impl IntoIterator<Item = ReaderFeature>,
+    ) -> Self {
+        self.reader_features.extend(reader_features);
+        self
+    }
+
+    /// Require writers of the table to support the given features.
+    pub fn with_writer_features(
+        mut self,
+        writer_features: impl IntoIterator<Item = WriterFeature>,
+    ) -> Self {
+        self.writer_features.extend(writer_features);
+        self
+    }
+
+    /// Create a clustered table, clustered over the given columns, which requires the `clustering`
+    /// and `domainMetadata` writer features. The columns must be distinct non-partition columns of
+    /// primitive types with statistics collected (see `delta.dataSkippingNumIndexedCols` and
+    /// `delta.dataSkippingStatsColumns`), and clustered tables can't be partitioned.
+    pub fn with_clustering_columns(
+        mut self,
+        clustering_columns: impl IntoIterator<Item = ColumnName>,
+    ) -> Self {
+        self.clustering_columns = Some(clustering_columns.into_iter().collect());
+        self
+    }
+
+    /// Set the operation recorded in the commit info. Defaults to `CREATE TABLE`.
+    pub fn with_operation(mut self, operation: String) -> Self {
+        self.operation = Some(operation);
+        self
+    }
+
+    /// Add commit info to the commit. This is required, see [`Transaction::with_commit_info`] for
+    /// the expected data.
+    ///
+    /// [`Transaction::with_commit_info`]: crate::transaction::Transaction::with_commit_info
+    pub fn with_commit_info(mut self, commit_info: Box<dyn EngineData>) -> Self {
+        self.commit_info = Some(commit_info);
+        self
+    }
+
+    /// Validate the table definition and commit version 0 of the table. Returns the new table.
+    ///
+    /// Fails with [`Error::TableAlreadyExists`] if there is already a table at the location, and
+    /// with [`Error::MissingCommitInfo`] if no commit info was provided. The schema must be
+    /// annotated with column mapping metadata if and only if column mapping is enabled through
+    /// `delta.columnMapping.mode`, invalid clustering columns fail with
+    /// [`Error::InvalidClusteringColumn`], invalid identity columns with
+    /// [`Error::InvalidIdentityColumn`], generated columns whose generation expression is not
+    /// valid or not supported with [`Error::InvalidGeneratedColumn`] or [`Error::Unsupported`],
+    /// constraints that are not valid or not supported with [`Error::InvalidConstraint`] or
+    /// [`Error::Unsupported`], and table properties enabling features that kernel can't write
+    /// (like change data feed) with [`Error::InvalidTransaction`]. Reader and writer features that
+    /// kernel can't write fail with [`Error::Unsupported`].
+    pub fn commit(self, engine: &dyn Engine) -> DeltaResult<Table> {
+        let engine_commit_info = self.commit_info.ok_or(Error::MissingCommitInfo)?;
+        validate_partition_columns(&self.schema, &self.partition_columns)?;
+
+        let mut reader_features = self.reader_features;
+        let mut writer_features = self.writer_features;
+        if schema_contains_timestamp_ntz(&self.schema) {
+            // the reader feature is also added as a writer feature by `minimal_protocol`
+            reader_features.push(ReaderFeature::TimestampWithoutTimezone);
+        }
+        if !identity_columns(&self.schema, &self.partition_columns)?.is_empty() {
+            writer_features.push(WriterFeature::IdentityColumns);
+        }
+        if !generated_columns(&self.schema)?.is_empty() {
+            writer_features.push(WriterFeature::GeneratedColumns);
+        }
+        if self
+            .table_properties
+            .keys()
+            .any(|key| key.starts_with(CONSTRAINT_PROPERTY_PREFIX))
+        {
+            writer_features.push(WriterFeature::CheckConstraints);
+        }
+        if InvariantChecker::has_invariants(&self.schema) {
+            writer_features.push(WriterFeature::Invariants);
+        }
+        if self.clustering_columns.is_some() {
+            require!(
+                self.partition_columns.is_empty(),
+                Error::invalid_clustering_column("Clustered tables cannot be partitioned")
+            );
+            writer_features.extend([WriterFeature::DomainMetadata, WriterFeature::Clustering]);
+        }
+        let table_properties = TableProperties::from(self.table_properties.iter());
+        let protocol = minimal_protocol(reader_features, writer_features, &table_properties)?;
+        let mut configuration = self.table_properties;
+        if protocol.has_writer_feature(&WriterFeature::RowTracking) {
+            for (key, name) in new_materialized_column_names() {
+                configuration.entry(key).or_insert(name);
+            }
+        }
+        let commit_timestamp = current_time_ms()?;
+        let metadata = Metadata {
+            id: Uuid::new_v4().to_string(),
+            name: None,
+            description: None,
+            format: Format::default(),
+            schema_string: serde_json::to_string(&self.schema)?,
+            partition_columns: self.partition_columns,
+            created_time: Some(commit_timestamp),
+            configuration,
+        };
+        // validates that kernel can read the table and that the schema matches the column mapping
+        // mode
+        let table_configuration = TableConfiguration::try_new(
+            metadata.clone(),
+            protocol.clone(),
+            self.table_root.clone(),
+            0,
+        )?;
+        // kernel may only create the tables it can write to, and like existing tables, new tables
+        // may only enable the features that kernel can write
+        table_configuration.ensure_write_supported()?;
+        ensure_table_properties_supported(&table_configuration)?;
+        let clustering_domain_metadata = self
+            .clustering_columns
+            .map(|columns| {
+                let physical_columns = validate_clustering_columns(&table_configuration, &columns)?;
+                clustering_domain_metadata(&physical_columns)
+            })
+            .transpose()?;
+
+        let log_root = self.table_root.join("_delta_log/")?;
+        if log_exists(engine, &log_root)? {
+            return Err(Error::TableAlreadyExists(self.table_root.to_string()));
+        }
+
+        // the first commit of a table with in-commit timestamps has no previous commit to follow
+        let in_commit_timestamp = table_configuration
+            .is_in_commit_timestamps_enabled()
+            .then_some(commit_timestamp);
+        let commit_info = generate_commit_info(
+            engine,
+            commit_timestamp,
+            in_commit_timestamp,
+            Some(self.operation.as_deref().unwrap_or(CREATE_TABLE_OPERATION)),
+            true,
+            engine_commit_info.as_ref(),
+        )?;
+        let mut actions = vec![
+            commit_info,
+            action_to_engine_data(engine, PROTOCOL_NAME, &protocol)?,
+            action_to_engine_data(engine, METADATA_NAME, &metadata)?,
+        ];
+        if let Some(domain_metadata) = clustering_domain_metadata {
+            actions.push(action_to_engine_data(
+                engine,
+                DOMAIN_METADATA_NAME,
+                &domain_metadata,
+            )?);
+        }
+        let actions = actions.into_iter().map(|action| Ok(action.into()));
+
+        let commit_path = ParsedLogPath::new_commit(&self.table_root, 0)?;
+        let json_handler = engine.get_json_handler();
+        match json_handler.write_json_file(&commit_path.location, Box::new(actions), false) {
+            Ok(()) => Ok(Table::new(self.table_root)),
+            Err(Error::FileAlreadyExists(_)) => {
+                Err(Error::TableAlreadyExists(self.table_root.to_string()))
+            }
+            Err(e) => Err(e),
+        }
+    }
+}
+
+// partition columns must be distinct top-level columns of primitive type, and at least one column
+// must not be a partition column since partition values aren't stored in the data files
+fn validate_partition_columns(schema: &SchemaRef, partition_columns: &[String]) -> DeltaResult<()> {
+    for (i, column) in partition_columns.iter().enumerate() {
+        let field = schema.field(column).ok_or_else(|| {
+            Error::invalid_partition_column(format!("{column} is not a column of the schema"))
+        })?;
+        require!(
+            matches!(field.data_type(), DataType::Primitive(_)),
+            Error::invalid_partition_column(format!("{column} is not of a primitive type"))
+        );
+        require!(
+            !partition_columns[..i].contains(column),
+            Error::invalid_partition_column(format!("{column} is given more than once"))
+        );
+    }
+    require!(
+        partition_columns.len() < schema.fields_len(),
+        Error::invalid_partition_column("Cannot partition a table by all of its columns")
+    );
+    Ok(())
+}
+
+// derive the protocol with the given features and the features required by the table properties
+fn minimal_protocol(
+    reader_features: Vec<ReaderFeature>,
+    writer_features: Vec<WriterFeature>,
+    table_properties: &TableProperties,
+) -> DeltaResult<Protocol> {
+    let mut reader_features = reader_features;
+    let mut writer_features = writer_features;
+    if table_properties.append_only == Some(true) {
+        writer_features.push(WriterFeature::AppendOnly);
+    }
+    if table_properties.enable_deletion_vectors == Some(true) {
+        reader_features.push(ReaderFeature::DeletionVectors);
+    }
+    if table_properties.enable_in_commit_timestamps == Some(true) {
+        writer_features.push(WriterFeature::InCommitTimestamp);
+    }
+    if table_properties.enable_type_widening == Some(true) {
+        reader_features.push(ReaderFeature::TypeWidening);
+    }
+    if table_properties.enable_row_tracking == Some(true) {
+        writer_features.push(WriterFeature::RowTracking);
+    }
+    if table_properties.checkpoint_policy == Some(CheckpointPolicy::V2) {
+        reader_features.push(ReaderFeature::V2Checkpoint);
+    }
+    if table_properties
+        .column_mapping_mode
+        .is_some_and(|mode| mode != ColumnMappingMode::None)
+    {
+        reader_features.push(ReaderFeature::ColumnMapping);
+    }
+    for feature in &reader_features {
+        // every reader feature is a reader-writer feature with the same name
+        let feature = feature.as_ref().parse().map_err(|_| {
+            Error::internal_error(format!("{feature} has no matching writer feature"))
+        })?;
+        writer_features.push(feature);
+    }
+    // the row ID high water mark is stored in a metadata domain
+    if writer_features.contains(&WriterFeature::RowTracking) {
+        writer_features.push(WriterFeature::DomainMetadata);
+    }
+
+    let reader_features = dedup(reader_features);
+    let writer_features = dedup(writer_features);
+    let min_reader_version = if reader_features.is_empty() { 1 } else { 3 };
+    let min_writer_version = if writer_features.is_empty() { 1 } else { 7 };
+    Protocol::try_new(
+        min_reader_version,
+        min_writer_version,
+        (min_reader_version == 3).then_some(reader_features),
+        (min_writer_version == 7).then_some(writer_features),
+    )
+}
+
+// remove duplicates, keeping the first occurrence of each element
+fn dedup<T: PartialEq>(items: Vec<T>) -> Vec<T> {
+    let mut deduped = Vec::with_capacity(items.len());
+    for item in items {
+        if !deduped.contains(&item) {
+            deduped.push(item);
+        }
+    }
+    deduped
+}
+
+// true if there are any commit or checkpoint files in the log
+fn log_exists(engine: &dyn Engine, log_root: &Url) -> DeltaResult<bool> {
+    let files = match engine.get_file_system_client().list_from(log_root) {
+        Ok(files) => files,
+        // some file systems fail to list a directory that doesn't exist
+        Err(Error::IOError(e)) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e),
+    };
+    for file in files {
+        if let Some(path) = ParsedLogPath::try_from(file?)? {
+            if path.is_commit() || path.is_checkpoint() {
+                return Ok(true);
+            }
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+
+    use crate::schema::{StructField, StructType};
+
+    fn schema() -> SchemaRef {
+        Arc::new(StructType::new([
+            StructField::nullable("id", DataType::LONG),
+            StructField::nullable("date", DataType::DATE),
+            StructField::nullable(
+                "nested",
+                StructType::new([StructField::nullable("x", DataType::INTEGER)]),
+            ),
+        ]))
+    }
+
+    #[test]
+    fn test_validate_partition_columns() {
+        let schema = schema();
+        let validate = |columns: &[&str]| {
+            let columns: Vec<_> = columns.iter().map(|c| c.to_string()).collect();
+            validate_partition_columns(&schema, &columns)
+        };
+        assert!(validate(&[]).is_ok());
+        assert!(validate(&["date"]).is_ok());
+        assert!(validate(&["id", "date"]).is_ok());
+        for invalid in [
+            &["missing"][..],
+            &["nested"],
+            &["date", "date"],
+            &["id", "date", "nested"],
+        ] {
+            assert!(matches!(
+                validate(invalid),
+                Err(Error::InvalidPartitionColumn(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_minimal_protocol() {
+        let no_properties = TableProperties::default();
+        let protocol = minimal_protocol(vec![], vec![], &no_properties).unwrap();
+        assert_eq!(
+            protocol,
+            Protocol::try_new(1, 1, None::<Vec<String>>, None::<Vec<String>>).unwrap()
+        );
+
+        let protocol =
+            minimal_protocol(vec![], vec![WriterFeature::Invariants], &no_properties).unwrap();
+        assert_eq!(
+            protocol,
+            Protocol::try_new(1, 7, None::<Vec<String>>, Some(["invariants"])).unwrap()
+        );
+
+        let properties = TableProperties::from([
+            ("delta.appendOnly", "true"),
+            ("delta.enableDeletionVectors", "true"),
+            ("delta.enableInCommitTimestamps", "true"),
+            ("delta.checkpointPolicy", "v2"),
+            ("delta.columnMapping.mode", "name"),
+        ]);
+        let protocol = minimal_protocol(
+            vec![ReaderFeature::DeletionVectors],
+            vec![WriterFeature::AppendOnly],
+            &properties,
+        )
+        .unwrap();
+        assert_eq!(
+            protocol,
+            Protocol::try_new(
+                3,
+                7,
+                Some(["deletionVectors", "v2Checkpoint", "columnMapping"]),
+                Some([
+                    "appendOnly",
+                    "inCommitTimestamp",
+                    "deletionVectors",
+                    "v2Checkpoint",
+                    "columnMapping"
+                ])
+            )
+            .unwrap()
+        );
+
+        let properties = TableProperties::from([("delta.enableRowTracking", "true")]);
+        let protocol = minimal_protocol(vec![], vec![], &properties).unwrap();
+        assert_eq!(
+            protocol,
+            Protocol::try_new(
+                1,
+                7,
+                None::<Vec<String>>,
+                Some(["rowTracking", "domainMetadata"])
+            )
+            .unwrap()
+        );
+    }
+}
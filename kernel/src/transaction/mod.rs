@@ -0,0 +1,2413 @@
+use std::collections::{HashMap, HashSet};
+use std::iter;
+use std::num::NonZero;
+use std::sync::{Arc, LazyLock, Mutex};
+
+use crate::actions::deletion_vector::DeletionVectorDescriptor;
+use crate::actions::domain_metadata::is_system_domain;
+use crate::actions::schemas::{GetNullableContainerStructField, GetStructField};
+use crate::actions::{action_to_engine_data, DomainMetadata, Metadata, SetTransaction};
+use crate::actions::{get_log_add_schema, get_log_commit_info_schema};
+use crate::actions::{
+    ADD_NAME, COMMIT_INFO_NAME, DOMAIN_METADATA_NAME, METADATA_NAME, REMOVE_NAME,
+    SET_TRANSACTION_NAME,
+};
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
+use crate::error::Error;
+use crate::expressions::{column_expr, column_name, ArrayData, ColumnName, Scalar, StructData};
+use crate::log_segment::LogSegment;
+use crate::path::ParsedLogPath;
+use crate::scan::state::Stats;
+use crate::schema::{ArrayType, ColumnNamesAndTypes, MapType, SchemaRef, StructField, StructType};
+use crate::snapshot::Snapshot;
+use crate::table_configuration::TableConfiguration;
+use crate::table_features::clustering::{
+    clustering_domain_metadata, physical_clustering_column, validate_clustering_columns,
+    CLUSTERING_DOMAIN_NAME,
+};
+use crate::table_features::constraints::{Constraint, ConstraintKind, CONSTRAINT_PROPERTY_PREFIX};
+use crate::table_features::generated_columns::GeneratedColumn;
+use crate::table_features::identity_columns::{
+    ensure_no_explicit_values, with_high_water_marks, IdentityColumn,
+};
+use crate::table_features::row_tracking::{
+    parse_row_id_high_water_mark, row_tracking_domain_metadata, MaterializedRowTrackingColumns,
+    INITIAL_ROW_ID_HIGH_WATER_MARK, METADATA_COLUMN_NAME, ROW_COMMIT_VERSION_FIELD_NAME,
+    ROW_ID_FIELD_NAME, ROW_TRACKING_DOMAIN_NAME,
+};
+use crate::table_features::timestamp_ntz::schema_contains_timestamp_ntz;
+use crate::table_features::ColumnMappingMode;
+use crate::table_properties::{CheckpointPolicy, IsolationLevel};
+use crate::utils::{current_time_ms, require};
+use crate::{DataType, DeltaResult, Engine, EngineData, Expression, FilteredEngineData, Version};
+
+use itertools::chain;
+use url::Url;
+
+use conflict::{TransactionSummary, WinningCommitSummary};
+use validation::{
+    holds_for_all_rows, holds_for_selected_rows, partition_literals, with_column_values,
+};
+
+mod conflict;
+mod create_table;
+mod schema_evolution;
+mod validation;
+
+pub use conflict::ConflictError;
+pub use create_table::CreateTableBuilder;
+
+const KERNEL_VERSION: &str = env!("CARGO_PKG_VERSION");
+const UNKNOWN_OPERATION: &str = "UNKNOWN";
+const DEFAULT_MAX_COMMIT_ATTEMPTS: usize = 10;
+const COLUMN_MAPPING_MODE: &str = "delta.columnMapping.mode";
+
+pub(crate) static WRITE_METADATA_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    Arc::new(StructType::new(vec![
+        <String>::get_struct_field("path"),
+        <HashMap<String, String>>::get_nullable_container_struct_field("partitionValues"),
+        <i64>::get_struct_field("size"),
+        <i64>::get_struct_field("modificationTime"),
+        <bool>::get_struct_field("dataChange"),
+        <Option<String>>::get_struct_field("stats"),
+    ]))
+});
+
+/// Get the expected schema for engine data passed to [`add_write_metadata`].
+///
+/// [`add_write_metadata`]: crate::transaction::Transaction::add_write_metadata
+pub fn get_write_metadata_schema() -> &'static SchemaRef {
+    &WRITE_METADATA_SCHEMA
+}
+
+// The schema of the remove actions generated from scan rows by [`generate_removes`]. Every field is
+// nullable because the scan rows that are not selected (e.g. rows that held other actions in the
+// log) produce nulls, even though they are never written.
+static LOG_REMOVE_FROM_SCAN_ROW_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    let remove = StructType::new([
+        StructField::nullable("path", DataType::STRING),
+        StructField::nullable("deletionTimestamp", DataType::LONG),
+        StructField::nullable("dataChange", DataType::BOOLEAN),
+        StructField::nullable("extendedFileMetadata", DataType::BOOLEAN),
+        StructField::nullable(
+            "partitionValues",
+            MapType::new(DataType::STRING, DataType::STRING, true),
+        ),
+        StructField::nullable("size", DataType::LONG),
+        StructField::nullable("stats", DataType::STRING),
+        StructField::nullable("deletionVector", DV_FROM_SCAN_ROW_SCHEMA.clone()),
+        StructField::nullable("baseRowId", DataType::LONG),
+        StructField::nullable("defaultRowCommitVersion", DataType::LONG),
+    ]);
+    Arc::new(StructType::new([StructField::nullable(
+        REMOVE_NAME,
+        remove,
+    )]))
+});
+
+// The schema of the add actions generated from scan rows by [`generate_dv_adds`]. Like the remove
+// actions above, every field is nullable.
+static LOG_ADD_FROM_SCAN_ROW_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    let add = StructType::new([
+        StructField::nullable("path", DataType::STRING),
+        StructField::nullable(
+            "partitionValues",
+            MapType::new(DataType::STRING, DataType::STRING, true),
+        ),
+        StructField::nullable("size", DataType::LONG),
+        StructField::nullable("modificationTime", DataType::LONG),
+        StructField::nullable("dataChange", DataType::BOOLEAN),
+        StructField::nullable("stats", DataType::STRING),
+        StructField::nullable("deletionVector", DV_FROM_SCAN_ROW_SCHEMA.clone()),
+        StructField::nullable("baseRowId", DataType::LONG),
+        StructField::nullable("defaultRowCommitVersion", DataType::LONG),
+    ]);
+    Arc::new(StructType::new([StructField::nullable(ADD_NAME, add)]))
+});
+
+// The schema of the add actions generated from write metadata by [`generate_row_tracking_adds`]:
+// the write metadata with the row tracking fields.
+static LOG_ADD_WITH_ROW_TRACKING_SCHEMA: LazyLock<SchemaRef> = LazyLock::new(|| {
+    let add = StructType::new(WRITE_METADATA_SCHEMA.fields().cloned().chain([
+        StructField::nullable("baseRowId", DataType::LONG),
+        StructField::nullable("defaultRowCommitVersion", DataType::LONG),
+    ]));
+    Arc::new(StructType::new([StructField::nullable(ADD_NAME, add)]))
+});
+
+// The column with the new deletion vector of each updated file that [`generate_dv_adds`] appends
+// to the scan rows.
+const NEW_DELETION_VECTOR_NAME: &str = "newDeletionVector";
+
+// The column with the statistics of each updated file that [`generate_dv_adds`] appends to the scan
+// rows, whose bounds are no longer tight.
+const NEW_STATS_NAME: &str = "newStats";
+
+// The column with the base row ID assigned to each added file that
+// [`generate_row_tracking_adds`] appends to the write metadata.
+const BASE_ROW_ID_NAME: &str = "baseRowId";
+
+static DV_FROM_SCAN_ROW_SCHEMA: LazyLock<StructType> = LazyLock::new(|| {
+    StructType::new([
+        StructField::nullable("storageType", DataType::STRING),
+        StructField::nullable("pathOrInlineDv", DataType::STRING),
+        StructField::nullable("offset", DataType::INTEGER),
+        StructField::nullable("sizeInBytes", DataType::INTEGER),
+        StructField::nullable("cardinality", DataType::LONG),
+    ])
+});
+
+/// A transaction represents an in-progress write to a table. After creating a transaction, changes
+/// to the table may be staged via the transaction methods before calling `commit` to commit the
+/// changes to the table.
+///
+/// If another transaction commits first, the commit is checked against the concurrent commits and
+/// retried at the next version unless they conflict (see [`Transaction::commit`]).
+///
+/// Besides changing the table's data, a transaction may change the table's metadata: evolve its
+/// schema with [`Transaction::update_schema`], change its properties with
+/// [`Transaction::set_table_properties`], set its description with
+/// [`Transaction::set_description`] and change the clustering columns of clustered tables with
+/// [`Transaction::set_clustering_columns`]. It may also set or remove user-controlled metadata
+/// domains with [`Transaction::set_domain_metadata`] and [`Transaction::remove_domain_metadata`].
+/// Metadata changes should be made before calling
+/// [`Transaction::get_write_context`], since data must be written with the updated schema.
+///
+/// # Examples
+///
+/// ```rust,ignore
+/// // create a transaction
+/// let mut txn = table.new_transaction(&engine)?;
+/// // stage table changes (commit info, files to add and files to remove)
+/// txn.commit_info(Box::new(ArrowEngineData::new(engine_commit_info)));
+/// // commit! (consume the transaction)
+/// txn.commit(&engine)?;
+/// ```
+pub struct Transaction {
+    read_snapshot: Arc<Snapshot>,
+    operation: Option<String>,
+    commit_info: Option<Arc<dyn EngineData>>,
+    write_metadata: Vec<Box<dyn EngineData>>,
+    remove_files_metadata: Vec<FilteredEngineData>,
+    deletion_vector_updates: Vec<DeletionVectorUpdate>,
+    read_whole_table: bool,
+    // whether the transaction changes the data of the table, i.e. the dataChange of its actions
+    data_change: bool,
+    // the application id and version of the transaction identifier committed with the transaction
+    transaction_id: Option<(String, i64)>,
+    // the table configuration with the metadata changes made by this transaction, if any
+    updated_table_configuration: Option<TableConfiguration>,
+    // the (logical) clustering columns of the table if it is clustered, with the changes made by
+    // this transaction
+    clustering_columns: Option<Vec<ColumnName>>,
+    // whether this transaction changes the clustering columns
+    clustering_columns_changed: bool,
+    // the changes to user-controlled metadata domains, at most one per domain. The configuration
+    // of a removed domain is filled in with its current configuration at commit.
+    domain_metadatas: Vec<DomainMetadata>,
+    // whether the written files materialize the row IDs and row commit versions of their rows, so
+    // that rewritten rows keep them
+    materialize_row_tracking: bool,
+    // the high water marks of the identity columns whose values were generated for the data
+    // written with the write contexts of this transaction, by column name
+    identity_high_water_marks: Arc<Mutex<HashMap<String, i64>>>,
+    // the number of versions the transaction tries to commit at before giving up
+    max_commit_attempts: usize,
+}
+
+impl std::fmt::Debug for Transaction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!(
+            "Transaction {{ read_snapshot version: {}, commit_info: {} }}",
+            self.read_snapshot.version(),
+            self.commit_info.is_some()
+        ))
+    }
+}
+
+impl Transaction {
+    /// Create a new transaction from a snapshot. The snapshot will be used to read the current
+    /// state of the table (e.g. to read the current version).
+    ///
+    /// Instead of using this API, the more typical (user-facing) API is
+    /// [Table::new_transaction](crate::table::Table::new_transaction) to create a transaction from
+    /// a table automatically backed by the latest snapshot.
+    pub(crate) fn try_new(
+        snapshot: impl Into<Arc<Snapshot>>,
+        engine: &dyn Engine,
+    ) -> DeltaResult<Self> {
+        let read_snapshot = snapshot.into();
+
+        // important! before a read/write to the table we must check it is supported
+        read_snapshot
+            .table_configuration()
+            .ensure_write_supported()?;
+        // writers of clustered tables must collect the statistics of the clustering columns
+        let clustering_columns = read_snapshot.clustering_columns(engine)?;
+
+        Ok(Transaction {
+            read_snapshot,
+            operation: None,
+            commit_info: None,
+            write_metadata: vec![],
+            remove_files_metadata: vec![],
+            deletion_vector_updates: vec![],
+            read_whole_table: false,
+            data_change: true,
+            transaction_id: None,
+            updated_table_configuration: None,
+            clustering_columns,
+            clustering_columns_changed: false,
+            domain_metadatas: vec![],
+            materialize_row_tracking: false,
+            identity_high_water_marks: Arc::default(),
+            max_commit_attempts: DEFAULT_MAX_COMMIT_ATTEMPTS,
+        })
+    }
+
+    /// Consume the transaction and commit it to the table. The result is a [CommitResult] with the
+    /// version the transaction was committed at.
+    ///
+    /// If another transaction committed the version first, the transactions committed since the
+    /// read snapshot are checked for conflicts with this one according to the table's
+    /// [`IsolationLevel`] (`WriteSerializable` unless configured otherwise). If there are none,
+    /// the transaction is rebased onto the latest version and the commit is retried. Otherwise,
+    /// the commit fails with [`Error::TransactionConflict`]. After
+    /// [`Transaction::with_max_commit_attempts`] attempts (10 by default), the transaction gives
+    /// up and is returned in a [`CommitResult::Conflict`], rebased onto the latest version, so
+    /// that it can be committed again later.
+    pub fn commit(mut self, engine: &dyn Engine) -> DeltaResult<CommitResult> {
+        // step zero: make sure the staged changes are allowed for this table
+        let has_removes =
+            !self.remove_files_metadata.is_empty() || !self.deletion_vector_updates.is_empty();
+        if has_removes && self.table_configuration().is_append_only_enabled() {
+            return Err(Error::invalid_transaction(
+                "Cannot remove files or rows from an append-only table",
+            ));
+        }
+        let engine_commit_info = self
+            .commit_info
+            .clone()
+            .ok_or_else(|| Error::MissingCommitInfo)?;
+        // the clustering columns must still have statistics after the metadata changes
+        let clustering_domain_metadata = match &self.clustering_columns {
+            Some(columns)
+                if self.clustering_columns_changed
+                    || self.updated_table_configuration.is_some() =>
+            {
+                let physical_columns =
+                    validate_clustering_columns(self.table_configuration(), columns)?;
+                self.clustering_columns_changed
+                    .then(|| clustering_domain_metadata(&physical_columns))
+                    .transpose()?
+            }
+            _ => None,
+        };
+        let domain_metadatas = self.resolve_domain_metadatas(engine)?;
+        // tables with row tracking assign row IDs to the added files by their number of records
+        let num_records = self
+            .table_configuration()
+            .is_row_tracking_supported()
+            .then(|| self.num_records())
+            .transpose()?;
+        let identity_high_water_marks = self
+            .identity_high_water_marks
+            .lock()
+            .map_err(|_| Error::internal_error("Identity high water marks lock poisoned"))?
+            .clone();
+        let summary = self.summary()?;
+        let is_blind_append = !summary.read_whole_table && summary.removed_paths.is_empty();
+
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            // step one: construct the iterator of actions we want to commit
+            let commit_timestamp = current_time_ms()?;
+            let commit_version = self.read_snapshot.version() + 1;
+            let in_commit_timestamp = self.in_commit_timestamp(engine, commit_timestamp)?;
+            let commit_info = generate_commit_info(
+                engine,
+                commit_timestamp,
+                in_commit_timestamp,
+                self.operation.as_deref(),
+                is_blind_append,
+                engine_commit_info.as_ref(),
+            );
+            let metadata = self
+                .updated_metadata(
+                    commit_version,
+                    in_commit_timestamp,
+                    &identity_high_water_marks,
+                )?
+                .map(|metadata| action_to_engine_data(engine, METADATA_NAME, &metadata));
+            let set_transaction = self.transaction_id.as_ref().map(|(app_id, version)| {
+                let set_transaction = SetTransaction {
+                    app_id: app_id.clone(),
+                    version: *version,
+                    last_updated: Some(commit_timestamp),
+                };
+                action_to_engine_data(engine, SET_TRANSACTION_NAME, &set_transaction)
+            });
+            // row IDs are assigned from the high water mark of the version the transaction commits
+            // at, so they are assigned again after every rebase
+            let row_ids = num_records
+                .as_ref()
+                .map(|num_records| self.assign_row_ids(engine, num_records))
+                .transpose()?;
+            let row_tracking_domain_metadata = row_ids
+                .as_ref()
+                .filter(|row_ids| row_ids.high_water_mark != row_ids.previous_high_water_mark)
+                .map(|row_ids| row_tracking_domain_metadata(row_ids.high_water_mark))
+                .transpose()?;
+            let domain_metadata = chain(&domain_metadatas, &clustering_domain_metadata)
+                .chain(&row_tracking_domain_metadata)
+                .map(|domain_metadata| {
+                    action_to_engine_data(engine, DOMAIN_METADATA_NAME, domain_metadata)
+                });
+            let adds: Box<dyn Iterator<Item = DeltaResult<FilteredEngineData>> + Send + '_> =
+                match &row_ids {
+                    Some(row_ids) => Box::new(generate_row_tracking_adds(
+                        engine,
+                        self.data_change,
+                        &self.write_metadata,
+                        &row_ids.base_row_ids,
+                        commit_version,
+                    )),
+                    None => Box::new(
+                        generate_adds(
+                            engine,
+                            self.data_change,
+                            self.write_metadata.iter().map(|a| a.as_ref()),
+                        )
+                        .map(|adds| adds.map(Into::into)),
+                    ),
+                };
+            let removes = generate_removes(
+                engine,
+                commit_timestamp,
+                self.data_change,
+                chain(
+                    &self.remove_files_metadata,
+                    self.deletion_vector_updates.iter().map(|u| &u.files),
+                ),
+            );
+            let dv_adds = generate_dv_adds(engine, self.data_change, &self.deletion_vector_updates);
+            let actions = chain(
+                chain(iter::once(commit_info), metadata)
+                    .chain(set_transaction)
+                    .chain(domain_metadata)
+                    .map(|action| action.map(Into::into)),
+                adds.chain(removes).chain(dv_adds),
+            );
+
+            // step two: set the path to write the new commit version (current_version + 1) to
+            let commit_path =
+                ParsedLogPath::new_commit(self.read_snapshot.table_root(), commit_version)?;
+
+            // step three: commit the actions as a json file in the log. If the version was taken
+            // by another transaction, rebase onto it (unless it conflicts) and try again.
+            let json_handler = engine.get_json_handler();
+            match json_handler.write_json_file(&commit_path.location, Box::new(actions), false) {
+                Ok(()) => return Ok(CommitResult::Committed(commit_version)),
+                Err(Error::FileAlreadyExists(_)) => {
+                    self.rebase(engine, &summary)?;
+                    if attempts >= self.max_commit_attempts {
+                        return Ok(CommitResult::Conflict(Box::new(self), commit_version));
+                    }
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    // The in-commit timestamp of the commit, if in-commit timestamps are enabled: the commit time,
+    // unless the previous commit has a later timestamp, since in-commit timestamps must increase
+    // with every commit.
+    fn in_commit_timestamp(
+        &self,
+        engine: &dyn Engine,
+        commit_timestamp: i64,
+    ) -> DeltaResult<Option<i64>> {
+        if !self.table_configuration().is_in_commit_timestamps_enabled() {
+            return Ok(None);
+        }
+        let previous_timestamp = self.read_snapshot.commit_timestamp(engine)?;
+        Ok(Some(commit_timestamp.max(previous_timestamp + 1)))
+    }
+
+    // The number of records of every file added by the transaction, by write metadata batch, from
+    // the statistics of the files, which must include it.
+    fn num_records(&self) -> DeltaResult<Vec<Vec<i64>>> {
+        self.write_metadata
+            .iter()
+            .map(|write_metadata| {
+                let mut visitor = NumRecordsVisitor::default();
+                visitor.visit_rows_of(write_metadata.as_ref())?;
+                Ok(visitor.num_records)
+            })
+            .collect()
+    }
+
+    // Assign consecutive row IDs to the rows of the added files, starting after the row ID high
+    // water mark of the read snapshot.
+    fn assign_row_ids(&self, engine: &dyn Engine, num_records: &[Vec<i64>]) -> DeltaResult<RowIds> {
+        let previous_high_water_mark = self
+            .read_snapshot
+            .get_domain_metadata(ROW_TRACKING_DOMAIN_NAME, engine)?
+            .map(|configuration| parse_row_id_high_water_mark(&configuration))
+            .transpose()?
+            .unwrap_or(INITIAL_ROW_ID_HIGH_WATER_MARK);
+        let mut high_water_mark = previous_high_water_mark;
+        let base_row_ids = num_records
+            .iter()
+            .map(|num_records| {
+                num_records
+                    .iter()
+                    .map(|num_records| {
+                        let base_row_id = high_water_mark + 1;
+                        high_water_mark += num_records;
+                        base_row_id
+                    })
+                    .collect()
+            })
+            .collect();
+        Ok(RowIds {
+            base_row_ids,
+            previous_high_water_mark,
+            high_water_mark,
+        })
+    }
+
+    // The metadata changed by this transaction, if any. A transaction that enables in-commit
+    // timestamps records the version and in-commit timestamp of the commit that enabled them, and
+    // a transaction that generated identity values records the new high water marks of their
+    // columns in the schema.
+    fn updated_metadata(
+        &self,
+        commit_version: Version,
+        in_commit_timestamp: Option<i64>,
+        identity_high_water_marks: &HashMap<String, i64>,
+    ) -> DeltaResult<Option<Metadata>> {
+        if self.updated_table_configuration.is_none() && identity_high_water_marks.is_empty() {
+            return Ok(None);
+        }
+        let table_configuration = self.table_configuration();
+        let mut metadata = table_configuration.metadata().clone();
+        if !identity_high_water_marks.is_empty() {
+            let schema =
+                with_high_water_marks(&table_configuration.schema(), identity_high_water_marks);
+            metadata.schema_string = serde_json::to_string(&schema)?;
+        }
+        let enables_in_commit_timestamps = !self
+            .read_snapshot
+            .table_configuration()
+            .is_in_commit_timestamps_enabled();
+        if let Some(timestamp) = in_commit_timestamp.filter(|_| enables_in_commit_timestamps) {
+            metadata.configuration.insert(
+                "delta.inCommitTimestampEnablementVersion".to_string(),
+                commit_version.to_string(),
+            );
+            metadata.configuration.insert(
+                "delta.inCommitTimestampEnablementTimestamp".to_string(),
+                timestamp.to_string(),
+            );
+        }
+        Ok(Some(metadata))
+    }
+
+    // Check the commits written since the read snapshot for conflicts with this transaction and, if
+    // there are none, move the read snapshot to the latest version of the table.
+    fn rebase(&mut self, engine: &dyn Engine, summary: &TransactionSummary) -> DeltaResult<()> {
+        let isolation_level = self
+            .read_snapshot
+            .table_properties()
+            .isolation_level
+            .unwrap_or(IsolationLevel::WriteSerializable);
+        let winning_commits = LogSegment::for_table_changes(
+            engine.get_file_system_client().as_ref(),
+            self.read_snapshot.log_segment().log_root.clone(),
+            self.read_snapshot.version() + 1,
+            None,
+        )?;
+        for commit in &winning_commits.ascending_commit_files {
+            summary.check(
+                &WinningCommitSummary::try_new(engine, commit)?,
+                isolation_level,
+            )?;
+        }
+        self.read_snapshot = Arc::new(Snapshot::try_new(
+            self.read_snapshot.table_root().clone(),
+            engine,
+            Some(winning_commits.end_version),
+        )?);
+        Ok(())
+    }
+
+    // Summarize what this transaction read and removed for conflict detection. A transaction that
+    // removes files must have read them, and since we don't know which predicate it read them
+    // with, it is considered to have read the whole table. Transactions that don't change the data
+    // (e.g. compactions) only read the files they remove.
+    fn summary(&self) -> DeltaResult<TransactionSummary> {
+        let mut removed_paths = HashSet::new();
+        let removed_files = chain(
+            &self.remove_files_metadata,
+            self.deletion_vector_updates.iter().map(|u| &u.files),
+        );
+        for files in removed_files {
+            let mut visitor = ScanRowPathVisitor {
+                selection_vector: files.selection_vector(),
+                paths: vec![],
+            };
+            visitor.visit_rows_of(files.data())?;
+            removed_paths.extend(visitor.paths.into_iter().map(|(_, path)| path));
+        }
+        Ok(TransactionSummary {
+            read_whole_table: self.read_whole_table
+                || (self.data_change && !removed_paths.is_empty()),
+            removed_paths,
+            app_id: self
+                .transaction_id
+                .as_ref()
+                .map(|(app_id, _)| app_id.clone()),
+            domains: self
+                .clustering_columns_changed
+                .then(|| CLUSTERING_DOMAIN_NAME.to_string())
+                .into_iter()
+                .chain(self.domain_metadatas.iter().map(|dm| dm.domain.clone()))
+                .collect(),
+            metadata_changed: self.updated_table_configuration.is_some(),
+        })
+    }
+
+    /// Set the operation that this transaction is performing. This string will be persisted in the
+    /// commit and visible to anyone who describes the table history.
+    pub fn with_operation(mut self, operation: String) -> Self {
+        self.operation = Some(operation);
+        self
+    }
+
+    /// Mark the transaction as having read the table, e.g. because the data it writes was computed
+    /// from the table's current data. Such a transaction is not a blind append, so it conflicts
+    /// with concurrent transactions that changed the data it read. Transactions that remove files
+    /// or update deletion vectors are always considered to have read the table.
+    pub fn with_read_whole_table(mut self) -> Self {
+        self.read_whole_table = true;
+        self
+    }
+
+    /// Mark the transaction as not changing the data of the table, e.g. because it only rewrites
+    /// existing data into different files, like a compaction. The `add` and `remove` actions of
+    /// the transaction are committed with `dataChange` set to `false`, so that readers of the
+    /// change data feed and streaming readers skip them, and the transaction only conflicts with
+    /// concurrent transactions that remove the same files.
+    pub fn with_data_change(mut self, data_change: bool) -> Self {
+        self.data_change = data_change;
+        self
+    }
+
+    /// Commit a transaction identifier (a `txn` action) with this transaction, recording that the
+    /// application `app_id` committed its transaction `version`. Applications that need to write
+    /// exactly once can skip the transactions that were already committed, as reported by
+    /// [`Snapshot::get_app_id_version`] after a restart.
+    ///
+    /// The commit fails with [`ConflictError::ConcurrentTransaction`] if a concurrent transaction
+    /// committed a transaction identifier for the same application.
+    pub fn with_transaction_id(mut self, app_id: impl Into<String>, version: i64) -> Self {
+        self.transaction_id = Some((app_id.into(), version));
+        self
+    }
+
+    /// Set the number of versions [`Transaction::commit`] tries to commit the transaction at
+    /// before returning it in a [`CommitResult::Conflict`], when other transactions keep
+    /// committing these versions first. Defaults to 10.
+    pub fn with_max_commit_attempts(mut self, max_commit_attempts: NonZero<usize>) -> Self {
+        self.max_commit_attempts = max_commit_attempts.get();
+        self
+    }
+
+    /// WARNING: This is an unstable API and will likely change in the future.
+    ///
+    /// Add commit info to the transaction. This is commit-wide metadata that is written as the
+    /// first action in the commit. The engine data passed here must have exactly one row, and we
+    /// only read one column: `engineCommitInfo` which must be a map<string, string> encoding the
+    /// metadata.
+    ///
+    /// The engine is required to provide commit info before committing the transaction. If the
+    /// engine would like to omit engine-specific commit info, it can do so by passing pass a
+    /// commit_info engine data chunk with one row and one column of type `Map<string, string>`
+    /// that can either be `null` or contain an empty map.
+    ///
+    /// Any other columns in the data chunk are ignored.
+    pub fn with_commit_info(mut self, commit_info: Box<dyn EngineData>) -> Self {
+        self.commit_info = Some(commit_info.into());
+        self
+    }
+
+    // Generate the logical-to-physical transform expression which must be evaluated on every data
+    // chunk before writing. At the moment, this is a transaction-wide expression.
+    fn generate_logical_to_physical(&self) -> Expression {
+        // for now, we just pass through all the columns except partition columns.
+        // note this is _incorrect_ if table config deems we need partition columns.
+        let table_configuration = self.table_configuration();
+        let partition_columns = &table_configuration.metadata().partition_columns;
+        let schema = table_configuration.schema();
+        let fields = schema
+            .fields()
+            .filter(|f| !partition_columns.contains(f.name()))
+            .map(|f| Expression::column([f.name()]));
+        // the materialized row tracking columns come from the row tracking column of the data
+        let materialized = self.materialized_row_tracking_columns().unwrap_or_default();
+        let row_tracking_fields = [
+            (materialized.row_id, ROW_ID_FIELD_NAME),
+            (
+                materialized.row_commit_version,
+                ROW_COMMIT_VERSION_FIELD_NAME,
+            ),
+        ]
+        .into_iter()
+        .filter_map(|(materialized, field)| {
+            materialized.map(|_| Expression::column([METADATA_COLUMN_NAME, field]))
+        });
+        Expression::struct_from(fields.chain(row_tracking_fields))
+    }
+
+    /// Get the write context for this transaction. The write context reflects the table schema
+    /// including any change made by [`Transaction::update_schema`], so a write context obtained
+    /// before a schema change must not be used to write data after it. For clustered tables, it
+    /// requests the statistics of the clustering columns for the written files. For tables with
+    /// identity columns, the values generated by its [`WriteContext::logical_to_physical`]
+    /// transforms move the high water marks of the columns committed by this transaction. For
+    /// tables with generated columns or constraints, the written data must be checked with
+    /// [`WriteContext::validate`].
+    pub fn get_write_context(&self) -> WriteContext {
+        let target_dir = self.read_snapshot.table_root();
+        let table_configuration = self.table_configuration();
+        let schema = table_configuration.schema();
+        let partition_columns = table_configuration.metadata().partition_columns();
+        // the clustering columns were validated against the schema, whose columns can't be dropped
+        let stats_columns = self
+            .clustering_columns
+            .iter()
+            .flatten()
+            .filter_map(|column| {
+                physical_clustering_column(&schema, partition_columns, column).ok()
+            })
+            .collect();
+        let logical_to_physical = self.generate_logical_to_physical();
+        // the identity columns were validated by `ensure_write_supported`, and aren't partition
+        // columns
+        let identity_columns = table_configuration
+            .identity_columns()
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|column| {
+                let position = schema
+                    .fields()
+                    .filter(|f| !partition_columns.contains(f.name()))
+                    .position(|f| f.name() == &column.name)?;
+                Some((position, column))
+            })
+            .collect();
+        // the generated columns were validated by `ensure_write_supported`, and partition columns
+        // have no position
+        let generated_columns = table_configuration
+            .generated_columns()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|column| {
+                let position = schema
+                    .fields()
+                    .filter(|f| !partition_columns.contains(f.name()))
+                    .position(|f| f.name() == &column.name);
+                (position, column)
+            })
+            .collect();
+        let partition_fields = schema
+            .fields()
+            .filter(|f| partition_columns.contains(f.name()))
+            .cloned()
+            .collect();
+        let schema = match self.materialized_row_tracking_columns() {
+            Some(materialized) => Arc::new(StructType::new(
+                schema
+                    .fields()
+                    .filter(|f| !partition_columns.contains(f.name()))
+                    .cloned()
+                    .chain(materialized.read_fields()),
+            )),
+            None => schema,
+        };
+        WriteContext::new(target_dir.clone(), schema, logical_to_physical)
+            .with_stats_columns(stats_columns)
+            .with_collect_stats(table_configuration.is_row_tracking_supported())
+            .with_identity_columns(identity_columns, self.identity_high_water_marks.clone())
+            .with_generated_columns(generated_columns, partition_fields)
+            .with_constraints(table_configuration.constraints().unwrap_or_default())
+    }
+
+    // Make the written files materialize the row IDs and row commit versions of their rows, which
+    // the written data must have in the row tracking `_metadata` column of scans (see
+    // [`ScanBuilder::with_row_tracking_columns`]). Rewrites of existing rows (e.g. OPTIMIZE) use
+    // this to keep their row IDs.
+    //
+    // [`ScanBuilder::with_row_tracking_columns`]: crate::scan::ScanBuilder::with_row_tracking_columns
+    pub(crate) fn with_materialized_row_tracking(mut self) -> Self {
+        self.materialize_row_tracking = true;
+        self
+    }
+
+    // The materialized row tracking columns that the written files include, if any
+    fn materialized_row_tracking_columns(&self) -> Option<MaterializedRowTrackingColumns> {
+        let table_configuration = self.table_configuration();
+        (self.materialize_row_tracking && table_configuration.is_row_tracking_enabled())
+            .then(|| MaterializedRowTrackingColumns::new(table_configuration))
+    }
+
+    // The table configuration this transaction writes with: the read snapshot's, updated with the
+    // metadata changes made by the transaction.
+    fn table_configuration(&self) -> &TableConfiguration {
+        self.updated_table_configuration
+            .as_ref()
+            .unwrap_or_else(|| self.read_snapshot.table_configuration())
+    }
+
+    // Replace the table metadata with `metadata`, which is committed along with the transaction.
+    // The updated table must still be writable by kernel, and its properties may only enable the
+    // table features supported by the protocol.
+    fn update_metadata(&mut self, metadata: Metadata) -> DeltaResult<()> {
+        self.update_metadata_with_checked_constraint(metadata, None)
+    }
+
+    // Update the table metadata like `update_metadata`, where `checked_constraint` is the name of
+    // a new CHECK constraint that the existing rows of the table were checked to satisfy.
+    fn update_metadata_with_checked_constraint(
+        &mut self,
+        metadata: Metadata,
+        checked_constraint: Option<&str>,
+    ) -> DeltaResult<()> {
+        let current = self.table_configuration();
+        let updated = TableConfiguration::try_new(
+            metadata,
+            current.protocol().clone(),
+            current.table_root().clone(),
+            current.version(),
+        )?;
+        updated.ensure_write_supported()?;
+        ensure_table_properties_supported(&updated)?;
+        // kernel doesn't upgrade the protocol, so timestamp_ntz columns can only be added (or
+        // widened to) if the protocol already supports them
+        require!(
+            !schema_contains_timestamp_ntz(&updated.schema())
+                || updated.is_timestamp_ntz_supported(),
+            Error::invalid_transaction(
+                "Cannot add timestamp_ntz columns to a table whose protocol doesn't support the timestampNtz feature"
+            )
+        );
+        // the existing rows of the table may lack row IDs, which kernel can't backfill, and the
+        // materialized row IDs of the existing files must stay readable
+        require!(
+            !updated.is_row_tracking_enabled() || current.is_row_tracking_enabled(),
+            Error::invalid_transaction(
+                "Cannot enable row tracking on an existing table: backfilling row IDs is not supported"
+            )
+        );
+        require!(
+            !updated.is_row_tracking_supported()
+                || MaterializedRowTrackingColumns::new(&updated)
+                    == MaterializedRowTrackingColumns::new(current),
+            Error::invalid_transaction(
+                "Cannot change the materialized row tracking columns of a table"
+            )
+        );
+        // the values of identity columns are only generated, with their high water marks, on
+        // write
+        require!(
+            updated.identity_columns()? == current.identity_columns()?,
+            Error::invalid_transaction("Cannot change the identity columns of a table")
+        );
+        // the generated values of existing rows are not recomputed
+        require!(
+            updated.generated_columns()? == current.generated_columns()?,
+            Error::invalid_transaction("Cannot change the generated columns of a table")
+        );
+        // the existing rows of the table must satisfy the constraints added to it
+        let current_constraints = current.constraints()?;
+        let added_constraint = updated.constraints()?.into_iter().find(|constraint| {
+            let is_current = current_constraints.iter().any(|current| {
+                (current.kind, &current.name, &current.sql)
+                    == (constraint.kind, &constraint.name, &constraint.sql)
+            });
+            let is_checked = constraint.kind == ConstraintKind::Check
+                && checked_constraint == Some(constraint.name.as_str());
+            !is_current && !is_checked
+        });
+        if let Some(constraint) = added_constraint {
+            return Err(Error::invalid_transaction(format!(
+                "Cannot add {constraint} without checking the existing rows of the table"
+            )));
+        }
+        self.updated_table_configuration = Some(updated);
+        Ok(())
+    }
+
+    /// Change the schema of the table to `schema`. The new schema may add nullable columns
+    /// (including nested struct fields) and make non-nullable columns nullable, but may not drop
+    /// or rename columns. If type widening is enabled on the table (`delta.enableTypeWidening`),
+    /// the types of columns may also be widened, e.g. from `integer` to `long`; the type changes
+    /// are recorded in the `delta.typeChanges` metadata of the changed fields. If column mapping is
+    /// enabled, new columns are assigned a column id and physical name, and existing columns keep
+    /// theirs. Columns of type `timestamp_ntz` (including columns widened from `date`) can only be
+    /// added if the table protocol supports the `timestampNtz` feature, since kernel doesn't
+    /// upgrade the protocol.
+    ///
+    /// Returns [`Error::InvalidTransaction`] if the schema change is not allowed.
+    pub fn update_schema(&mut self, schema: impl Into<SchemaRef>) -> DeltaResult<()> {
+        let current = self.table_configuration();
+        let mut configuration = current.metadata().configuration.clone();
+        let mut max_column_id = match current.column_mapping_mode() {
+            ColumnMappingMode::None => None,
+            ColumnMappingMode::Id | ColumnMappingMode::Name => Some(
+                schema_evolution::max_column_id(&configuration, &current.schema()),
+            ),
+        };
+        let schema = schema_evolution::evolve_schema(
+            &current.schema(),
+            &schema.into(),
+            current.is_type_widening_enabled(),
+            max_column_id.as_mut(),
+        )?;
+        if let Some(max_column_id) = max_column_id {
+            configuration.insert(
+                schema_evolution::COLUMN_MAPPING_MAX_COLUMN_ID.to_string(),
+                max_column_id.to_string(),
+            );
+        }
+        let metadata = Metadata {
+            schema_string: serde_json::to_string(&schema)?,
+            configuration,
+            ..current.metadata().clone()
+        };
+        self.update_metadata(metadata)
+    }
+
+    /// Add nullable columns to the end of the table schema. This is a shorthand for
+    /// [`Transaction::update_schema`] with the current schema extended by `fields`.
+    pub fn add_columns(
+        &mut self,
+        fields: impl IntoIterator<Item = StructField>,
+    ) -> DeltaResult<()> {
+        let schema = self.table_configuration().schema();
+        self.update_schema(StructType::new(schema.fields().cloned().chain(fields)))
+    }
+
+    /// Set the given table properties, replacing the current value of properties that are already
+    /// set. Enabling a table feature through its property (e.g. `delta.enableDeletionVectors`)
+    /// requires the table protocol to support the feature, the column mapping mode cannot be
+    /// changed, and CHECK constraints can only be added with [`Transaction::add_constraint`].
+    ///
+    /// Returns [`Error::InvalidTransaction`] if a property cannot be set on this table.
+    pub fn set_table_properties(
+        &mut self,
+        properties: impl IntoIterator<Item = (impl Into<String>, impl Into<String>)>,
+    ) -> DeltaResult<()> {
+        let mut metadata = self.table_configuration().metadata().clone();
+        for (key, value) in properties {
+            let key = key.into();
+            if key == COLUMN_MAPPING_MODE {
+                return Err(Error::invalid_transaction(
+                    "Cannot change the column mapping mode of a table",
+                ));
+            }
+            if key.starts_with(CONSTRAINT_PROPERTY_PREFIX) {
+                return Err(Error::invalid_transaction(
+                    "CHECK constraints can only be added with Transaction::add_constraint",
+                ));
+            }
+            metadata.configuration.insert(key, value.into());
+        }
+        self.update_metadata(metadata)
+    }
+
+    /// Unset the given table properties. Properties that are not set are ignored.
+    ///
+    /// Returns [`Error::InvalidTransaction`] if a property cannot be unset on this table.
+    pub fn unset_table_properties(
+        &mut self,
+        keys: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> DeltaResult<()> {
+        let mut metadata = self.table_configuration().metadata().clone();
+        for key in keys {
+            let key = key.as_ref();
+            if key == COLUMN_MAPPING_MODE {
+                return Err(Error::invalid_transaction(
+                    "Cannot change the column mapping mode of a table",
+                ));
+            }
+            metadata.configuration.remove(key);
+        }
+        self.update_metadata(metadata)
+    }
+
+    /// Add a CHECK constraint to the table: a SQL predicate over the columns of the table, e.g.
+    /// `value > 0`, that every row written to the table must satisfy. The constraint is stored in
+    /// the `delta.constraints.<name>` table property, with the name lowercased. Every existing row
+    /// of the table (read from the snapshot of the transaction) must satisfy the constraint, which
+    /// may take a full scan of the table, so the transaction conflicts with concurrent transactions
+    /// that add or remove files. As with writes, rows for which the predicate is null violate the
+    /// constraint.
+    ///
+    /// Returns [`Error::InvalidTransaction`] if the table protocol doesn't support the
+    /// `checkConstraints` writer feature, if the table already has a constraint with the name, or
+    /// if the transaction changes the schema of the table or already adds files;
+    /// [`Error::InvalidConstraint`] if the predicate is not valid or existing rows violate it; and
+    /// [`Error::Unsupported`] if the predicate is not supported.
+    pub fn add_constraint(
+        &mut self,
+        engine: Arc<dyn Engine>,
+        name: &str,
+        expression: &str,
+    ) -> DeltaResult<()> {
+        let current = self.table_configuration();
+        require!(
+            current.is_check_constraints_supported(),
+            Error::invalid_transaction(
+                "The table protocol does not support the checkConstraints writer feature"
+            )
+        );
+        require!(
+            current.schema() == self.read_snapshot.schema(),
+            Error::invalid_transaction(
+                "Cannot add a constraint in a transaction that changes the table schema"
+            )
+        );
+        require!(
+            self.write_metadata.is_empty(),
+            Error::invalid_transaction("Cannot add a constraint in a transaction that adds files")
+        );
+        let name = name.to_lowercase();
+        require!(
+            !name.is_empty(),
+            Error::invalid_constraint("CHECK constraints must have a name")
+        );
+        let key = format!("{CONSTRAINT_PROPERTY_PREFIX}{name}");
+        require!(
+            !current.metadata().configuration.contains_key(&key),
+            Error::invalid_transaction(format!("The table already has a constraint {name}"))
+        );
+        let constraint =
+            Constraint::try_new(ConstraintKind::Check, &name, expression, &current.schema())?;
+
+        let scan = self.read_snapshot.clone().scan_builder().build()?;
+        for result in scan.execute(engine.clone())? {
+            let result = result?;
+            let data = result.raw_data?;
+            let selection = result.raw_mask.as_deref().unwrap_or_default();
+            require!(
+                holds_for_selected_rows(
+                    engine.as_ref(),
+                    data.as_ref(),
+                    scan.schema(),
+                    constraint.expression.clone(),
+                    selection,
+                )?,
+                Error::invalid_constraint(format!(
+                    "{constraint} is violated by existing rows of the table"
+                ))
+            );
+        }
+        // rows added concurrently weren't checked against the constraint
+        self.read_whole_table = true;
+
+        let mut metadata = self.table_configuration().metadata().clone();
+        metadata.configuration.insert(key, expression.to_string());
+        self.update_metadata_with_checked_constraint(metadata, Some(&name))
+    }
+
+    /// Drop the CHECK constraint with the given name (case-insensitive) from the table.
+    ///
+    /// Returns [`Error::InvalidTransaction`] if the table has no constraint with the name.
+    pub fn drop_constraint(&mut self, name: &str) -> DeltaResult<()> {
+        let mut metadata = self.table_configuration().metadata().clone();
+        let name = name.to_lowercase();
+        let key = format!("{CONSTRAINT_PROPERTY_PREFIX}{name}");
+        require!(
+            metadata.configuration.remove(&key).is_some(),
+            Error::invalid_transaction(format!("The table has no constraint {name}"))
+        );
+        self.update_metadata(metadata)
+    }
+
+    /// Set the user-provided description of the table, or clear it if `description` is `None`.
+    pub fn set_description(&mut self, description: Option<String>) -> DeltaResult<()> {
+        let metadata = Metadata {
+            description,
+            ..self.table_configuration().metadata().clone()
+        };
+        self.update_metadata(metadata)
+    }
+
+    /// Set the clustering columns of a clustered table, i.e. a table that supports the
+    /// `clustering` writer feature, replacing its current clustering columns. The columns must
+    /// be distinct non-partition columns of primitive types with statistics collected (see
+    /// `delta.dataSkippingNumIndexedCols` and `delta.dataSkippingStatsColumns`). The files
+    /// already written are not reclustered; this only affects how the table is clustered from
+    /// now on, e.g. by [`OptimizeBuilder`]. Setting no columns leaves the table unclustered.
+    ///
+    /// Returns [`Error::Unsupported`] if the table is not clustered, and
+    /// [`Error::InvalidClusteringColumn`] if a column is not valid.
+    ///
+    /// [`OptimizeBuilder`]: crate::optimize::OptimizeBuilder
+    pub fn set_clustering_columns(&mut self, columns: Vec<ColumnName>) -> DeltaResult<()> {
+        let table_configuration = self.table_configuration();
+        if !table_configuration.is_clustering_supported() {
+            return Err(Error::unsupported(
+                "Cannot set the clustering columns of a table without the clustering feature",
+            ));
+        }
+        validate_clustering_columns(table_configuration, &columns)?;
+        self.clustering_columns = Some(columns);
+        self.clustering_columns_changed = true;
+        Ok(())
+    }
+
+    /// Set the configuration of the user-controlled metadata domain `domain`, creating the domain if
+    /// it doesn't exist. Domains are typically used by applications to store their own metadata in
+    /// the table (e.g. a watermark of the data written), which can be read back with
+    /// [`Snapshot::get_domain_metadata`]. Setting or removing the same domain again in this
+    /// transaction replaces the previous change.
+    ///
+    /// Returns [`Error::Unsupported`] if the table doesn't support the `domainMetadata` writer
+    /// feature, and [`Error::InvalidTransaction`] if the domain is a system-controlled `delta.*`
+    /// domain.
+    ///
+    /// See: <https://github.com/delta-io/delta/blob/master/PROTOCOL.md#domain-metadata>
+    pub fn set_domain_metadata(
+        &mut self,
+        domain: impl Into<String>,
+        configuration: impl Into<String>,
+    ) -> DeltaResult<()> {
+        self.stage_domain_metadata(DomainMetadata {
+            domain: domain.into(),
+            configuration: configuration.into(),
+            removed: false,
+        })
+    }
+
+    /// Remove the user-controlled metadata domain `domain`. Removing a domain that doesn't exist
+    /// does nothing. Setting or removing the same domain again in this transaction replaces the
+    /// previous change.
+    ///
+    /// Returns the same errors as [`Transaction::set_domain_metadata`].
+    pub fn remove_domain_metadata(&mut self, domain: impl Into<String>) -> DeltaResult<()> {
+        self.stage_domain_metadata(DomainMetadata {
+            domain: domain.into(),
+            configuration: String::new(),
+            removed: true,
+        })
+    }
+
+    fn stage_domain_metadata(&mut self, domain_metadata: DomainMetadata) -> DeltaResult<()> {
+        if !self.table_configuration().is_domain_metadata_supported() {
+            return Err(Error::unsupported(
+                "Cannot change the metadata domains of a table without the domainMetadata feature",
+            ));
+        }
+        if is_system_domain(&domain_metadata.domain) {
+            return Err(Error::invalid_transaction(format!(
+                "Cannot change the system-controlled metadata domain {}",
+                domain_metadata.domain
+            )));
+        }
+        self.domain_metadatas
+            .retain(|staged| staged.domain != domain_metadata.domain);
+        self.domain_metadatas.push(domain_metadata);
+        Ok(())
+    }
+
+    // The domain metadata actions of the staged domain changes. A removed domain is written with
+    // its current configuration, as the protocol recommends, unless it doesn't exist. Concurrent
+    // changes to the same domains conflict, so this holds for any version the transaction commits.
+    fn resolve_domain_metadatas(&self, engine: &dyn Engine) -> DeltaResult<Vec<DomainMetadata>> {
+        if !self.domain_metadatas.iter().any(|dm| dm.removed) {
+            return Ok(self.domain_metadatas.clone());
+        }
+        let mut current = self.read_snapshot.domain_metadata(engine)?;
+        Ok(self
+            .domain_metadatas
+            .iter()
+            .filter_map(|domain_metadata| {
+                if !domain_metadata.removed {
+                    return Some(domain_metadata.clone());
+                }
+                let configuration = current.remove(&domain_metadata.domain)?;
+                Some(DomainMetadata {
+                    configuration,
+                    ..domain_metadata.clone()
+                })
+            })
+            .collect())
+    }
+
+    /// Add write metadata about files to include in the transaction. This API can be called
+    /// multiple times to add multiple batches.
+    ///
+    /// The expected schema for `write_metadata` is given by [`get_write_metadata_schema`]. On
+    /// tables with row tracking, the base row IDs of the added files are appended to the write
+    /// metadata on commit, which requires it to support [`EngineData::append_columns`].
+    pub fn add_write_metadata(&mut self, write_metadata: Box<dyn EngineData>) {
+        self.write_metadata.push(write_metadata);
+    }
+
+    /// Remove files from the table as part of this transaction. This API can be called multiple
+    /// times to remove multiple batches of files.
+    ///
+    /// The expected schema for `remove_metadata` is given by [`scan_row_schema`], so engines
+    /// can pass the data returned by [`Scan::scan_data`] directly, with the selection vector
+    /// narrowed down to the files that should be removed. A `remove` action is committed for every
+    /// selected row, carrying the file's path, size, partition values, stats and deletion vector,
+    /// with `dataChange` set to `true` (unless the transaction doesn't change data, see
+    /// [`Transaction::with_data_change`]) and `deletionTimestamp` set to the commit timestamp.
+    ///
+    /// Committing a transaction that removes files from an append-only table fails with
+    /// [`Error::InvalidTransaction`].
+    ///
+    /// [`scan_row_schema`]: crate::scan::scan_row_schema
+    /// [`Scan::scan_data`]: crate::scan::Scan::scan_data
+    pub fn remove_files(&mut self, remove_metadata: FilteredEngineData) {
+        self.remove_files_metadata.push(remove_metadata);
+    }
+
+    /// Replace the deletion vectors of existing data files. For every file whose path is a key of
+    /// `new_dv_descriptors`, the commit will contain a `remove` action for the file with its
+    /// current deletion vector and an `add` action for the same file carrying the new descriptor.
+    /// The statistics of the added file are marked as not having tight bounds (`tightBounds` is
+    /// false), since the deleted rows may hold the minimum or maximum values of a column.
+    ///
+    /// `existing_data_files` must contain the scan rows (see [`scan_row_schema`]) of all the files
+    /// being updated, e.g. as returned by [`Scan::scan_data`]. Only selected rows are considered.
+    /// The new descriptors usually point to DVs written with a [`DeletionVectorWriter`], or are
+    /// created inline with [`DeletionVectorDescriptor::try_new_inline`]. Note that the new
+    /// deletion vector replaces the existing one, so it must also contain the rows deleted by it
+    /// (see [`DvInfo::merge_deleted_rows`]). Committing the new descriptors requires the engine's
+    /// data to support [`EngineData::append_columns`].
+    ///
+    /// Returns an error if the table does not support deletion vectors or if any of the paths in
+    /// `new_dv_descriptors` is not found in `existing_data_files`.
+    ///
+    /// [`scan_row_schema`]: crate::scan::scan_row_schema
+    /// [`Scan::scan_data`]: crate::scan::Scan::scan_data
+    /// [`DeletionVectorWriter`]: crate::actions::deletion_vector::DeletionVectorWriter
+    /// [`DvInfo::merge_deleted_rows`]: crate::scan::state::DvInfo::merge_deleted_rows
+    pub fn update_deletion_vectors(
+        &mut self,
+        mut new_dv_descriptors: HashMap<String, DeletionVectorDescriptor>,
+        existing_data_files: impl Iterator<Item = DeltaResult<FilteredEngineData>>,
+    ) -> DeltaResult<()> {
+        if !self
+            .read_snapshot
+            .table_configuration()
+            .is_deletion_vector_supported()
+        {
+            return Err(Error::invalid_transaction(
+                "Cannot write deletion vectors to a table that does not support them",
+            ));
+        }
+        for files in existing_data_files {
+            if new_dv_descriptors.is_empty() {
+                break;
+            }
+            let files = files?;
+            let mut visitor = ScanRowPathVisitor {
+                selection_vector: files.selection_vector(),
+                paths: vec![],
+            };
+            visitor.visit_rows_of(files.data())?;
+            let new_dvs: Vec<_> = visitor
+                .paths
+                .into_iter()
+                .filter_map(|(row, path)| Some((row, new_dv_descriptors.remove(&path)?)))
+                .collect();
+            if new_dvs.is_empty() {
+                continue;
+            }
+            let mut selection_vector = vec![false; files.data().len()];
+            for (row, _) in &new_dvs {
+                selection_vector[*row] = true;
+            }
+            let (data, _) = files.into_parts();
+            self.deletion_vector_updates.push(DeletionVectorUpdate {
+                files: FilteredEngineData::try_new(data, selection_vector)?,
+                new_dvs,
+            });
+        }
+        if let Some(path) = new_dv_descriptors.keys().next() {
+            return Err(Error::invalid_transaction(format!(
+                "Cannot update the deletion vector of {path}: file not found"
+            )));
+        }
+        Ok(())
+    }
+}
+
+// Check that the table features enabled by the table properties are supported by the protocol.
+// Kernel doesn't upgrade the protocol, so a feature has to be supported before it can be enabled.
+fn ensure_table_properties_supported(table_configuration: &TableConfiguration) -> DeltaResult<()> {
+    let properties = table_configuration.table_properties();
+    let unsupported = [
+        (
+            properties.append_only,
+            table_configuration.is_append_only_supported(),
+            "appendOnly",
+        ),
+        (
+            properties.enable_deletion_vectors,
+            table_configuration.is_deletion_vector_supported(),
+            "deletionVectors",
+        ),
+        (
+            properties.enable_type_widening,
+            table_configuration.is_type_widening_supported(),
+            "typeWidening",
+        ),
+        (
+            properties.enable_in_commit_timestamps,
+            table_configuration.is_in_commit_timestamps_supported(),
+            "inCommitTimestamp",
+        ),
+        (
+            properties
+                .checkpoint_policy
+                .as_ref()
+                .map(|policy| *policy == CheckpointPolicy::V2),
+            table_configuration.is_v2_checkpoint_supported(),
+            "v2Checkpoint",
+        ),
+        (
+            properties.enable_row_tracking,
+            table_configuration.is_row_tracking_supported(),
+            "rowTracking",
+        ),
+        // kernel doesn't write change data files, so it can't write to tables with CDF enabled
+        (properties.enable_change_data_feed, false, "changeDataFeed"),
+    ]
+    .into_iter()
+    .find(|(enabled, supported, _)| enabled.unwrap_or(false) && !supported);
+    match unsupported {
+        Some((_, _, feature)) => Err(Error::invalid_transaction(format!(
+            "Cannot enable table feature '{feature}': not supported by the table protocol or kernel"
+        ))),
+        None => Ok(()),
+    }
+}
+
+// The row IDs assigned to the files added by a transaction: the base row ID of every file, by write
+// metadata batch, and the row ID high water mark before and after the assignment.
+struct RowIds {
+    base_row_ids: Vec<Vec<i64>>,
+    previous_high_water_mark: i64,
+    high_water_mark: i64,
+}
+
+// collect the number of records of every file of a write metadata batch from its stats
+#[derive(Default)]
+struct NumRecordsVisitor {
+    num_records: Vec<i64>,
+}
+
+impl RowVisitor for NumRecordsVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| (vec![column_name!("stats")], vec![DataType::STRING]).into());
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for row in 0..row_count {
+            let stats: Option<String> = getters[0].get_opt(row, "stats")?;
+            let stats = stats.ok_or_else(|| {
+                Error::invalid_transaction(
+                    "Files added to tables with row tracking must have statistics with their \
+                     number of records",
+                )
+            })?;
+            // statistics that can't be parsed, or lack the number of records, are malformed
+            let stats: Stats = serde_json::from_str(&stats)?;
+            self.num_records.push(stats.num_records as i64);
+        }
+        Ok(())
+    }
+}
+
+// collect the stats of every scan row
+#[derive(Default)]
+struct ScanRowStatsVisitor {
+    stats: Vec<Option<String>>,
+}
+
+impl RowVisitor for ScanRowStatsVisitor {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| (vec![column_name!("stats")], vec![DataType::STRING]).into());
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for row in 0..row_count {
+            self.stats.push(getters[0].get_opt(row, "stats")?);
+        }
+        Ok(())
+    }
+}
+
+// The scan rows of data files whose deletion vectors are updated in a transaction. `files` selects
+// exactly the rows of the updated files, and `new_dvs` holds the new descriptor for each of them
+// (by row index).
+struct DeletionVectorUpdate {
+    files: FilteredEngineData,
+    new_dvs: Vec<(usize, DeletionVectorDescriptor)>,
+}
+
+// collect the (row index, path) of every selected scan row
+pub(crate) struct ScanRowPathVisitor<'a> {
+    pub(crate) selection_vector: &'a [bool],
+    pub(crate) paths: Vec<(usize, String)>,
+}
+
+impl RowVisitor for ScanRowPathVisitor<'_> {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| (vec![column_name!("path")], vec![DataType::STRING]).into());
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for row in 0..row_count {
+            // rows beyond the end of the selection vector are selected
+            if !self.selection_vector.get(row).copied().unwrap_or(true) {
+                continue;
+            }
+            if let Some(path) = getters[0].get_opt(row, "path")? {
+                self.paths.push((row, path));
+            }
+        }
+        Ok(())
+    }
+}
+
+// convert write_metadata into add actions using an expression to transform the data in a single
+// pass. The dataChange of the write metadata is overridden if the transaction doesn't change data.
+fn generate_adds<'a>(
+    engine: &dyn Engine,
+    data_change: bool,
+    write_metadata: impl Iterator<Item = &'a dyn EngineData> + Send + 'a,
+) -> impl Iterator<Item = DeltaResult<Box<dyn EngineData>>> + Send + 'a {
+    let expression_handler = engine.get_expression_handler();
+    let write_metadata_schema = get_write_metadata_schema();
+    let log_schema = get_log_add_schema();
+
+    write_metadata.map(move |write_metadata_batch| {
+        let adds_expr =
+            Expression::struct_from([Expression::struct_from(write_metadata_schema.fields().map(
+                |f| match f.name().as_str() {
+                    "dataChange" if !data_change => Expression::literal(false),
+                    name => Expression::column([name]),
+                },
+            ))]);
+        let adds_evaluator = expression_handler.get_evaluator(
+            write_metadata_schema.clone(),
+            adds_expr,
+            log_schema.clone().into(),
+        );
+        adds_evaluator.evaluate(write_metadata_batch)
+    })
+}
+
+// convert write_metadata into add actions with their row tracking fields: the base row ID assigned
+// to each file, and the commit version as the default row commit version. The base row IDs of each
+// batch of write metadata are appended to it as one column, so every batch is evaluated once.
+fn generate_row_tracking_adds<'a>(
+    engine: &'a dyn Engine,
+    data_change: bool,
+    write_metadata: &'a [Box<dyn EngineData>],
+    base_row_ids: &'a [Vec<i64>],
+    commit_version: Version,
+) -> impl Iterator<Item = DeltaResult<FilteredEngineData>> + Send + 'a {
+    let write_metadata_schema = get_write_metadata_schema();
+    let base_row_id_field = StructField::nullable(BASE_ROW_ID_NAME, DataType::LONG);
+    let input_schema = StructType::new(
+        write_metadata_schema
+            .fields()
+            .cloned()
+            .chain([base_row_id_field.clone()]),
+    );
+    let fields = write_metadata_schema
+        .fields()
+        .map(|f| match f.name().as_str() {
+            "dataChange" if !data_change => Expression::literal(false),
+            name => Expression::column([name]),
+        });
+    let row_tracking_fields = [
+        Expression::column([BASE_ROW_ID_NAME]),
+        Expression::literal(commit_version as i64),
+    ];
+    let adds_expr =
+        Expression::struct_from([Expression::struct_from(fields.chain(row_tracking_fields))]);
+    let adds_evaluator = engine.get_expression_handler().get_evaluator(
+        input_schema.into(),
+        adds_expr,
+        LOG_ADD_WITH_ROW_TRACKING_SCHEMA.clone().into(),
+    );
+    let base_row_id_schema = Arc::new(StructType::new([base_row_id_field]));
+
+    write_metadata
+        .iter()
+        .zip(base_row_ids)
+        .map(move |(write_metadata_batch, base_row_ids)| {
+            let base_row_ids = ArrayData::new(
+                ArrayType::new(DataType::LONG, true),
+                base_row_ids.iter().copied(),
+            );
+            let write_metadata_batch = write_metadata_batch
+                .append_columns(base_row_id_schema.clone(), vec![base_row_ids])?;
+            let adds = adds_evaluator.evaluate(write_metadata_batch.as_ref())?;
+            Ok(adds.into())
+        })
+}
+
+// convert scan rows into remove actions using an expression to transform the data in a single
+// pass. The selection vector of each batch is kept so only the selected files are removed.
+fn generate_removes<'a>(
+    engine: &dyn Engine,
+    deletion_timestamp: i64,
+    data_change: bool,
+    remove_files_metadata: impl Iterator<Item = &'a FilteredEngineData> + Send + 'a,
+) -> impl Iterator<Item = DeltaResult<FilteredEngineData>> + Send + 'a {
+    let expression_handler = engine.get_expression_handler();
+    let removes_expr = Expression::struct_from([Expression::struct_from([
+        column_expr!("path"),
+        Expression::literal(deletion_timestamp),
+        Expression::literal(data_change),
+        Expression::literal(true),
+        column_expr!("fileConstantValues.partitionValues"),
+        column_expr!("size"),
+        column_expr!("stats"),
+        column_expr!("deletionVector"),
+        column_expr!("fileConstantValues.baseRowId"),
+        column_expr!("fileConstantValues.defaultRowCommitVersion"),
+    ])]);
+    let removes_evaluator = expression_handler.get_evaluator(
+        crate::scan::scan_row_schema().into(),
+        removes_expr,
+        LOG_REMOVE_FROM_SCAN_ROW_SCHEMA.clone().into(),
+    );
+
+    remove_files_metadata.map(move |remove_batch| {
+        let removes = removes_evaluator.evaluate(remove_batch.data())?;
+        FilteredEngineData::try_new(removes, remove_batch.selection_vector().to_vec())
+    })
+}
+
+// convert the scan rows of files with updated deletion vectors into add actions carrying the new
+// deletion vectors. The new descriptors and statistics of each batch of scan rows are appended to
+// it as columns, so every batch is evaluated once and only the rows of the updated files are
+// selected.
+fn generate_dv_adds<'a>(
+    engine: &'a dyn Engine,
+    data_change: bool,
+    deletion_vector_updates: &'a [DeletionVectorUpdate],
+) -> impl Iterator<Item = DeltaResult<FilteredEngineData>> + Send + 'a {
+    let dv_type = DataType::from(DV_FROM_SCAN_ROW_SCHEMA.clone());
+    let dv_field = StructField::nullable(NEW_DELETION_VECTOR_NAME, dv_type.clone());
+    let stats_field = StructField::nullable(NEW_STATS_NAME, DataType::STRING);
+    let new_fields = [dv_field, stats_field];
+    let scan_row_schema = crate::scan::scan_row_schema();
+    let input_schema = StructType::new(scan_row_schema.fields().cloned().chain(new_fields.clone()));
+    let adds_expr = Expression::struct_from([Expression::struct_from([
+        column_expr!("path"),
+        column_expr!("fileConstantValues.partitionValues"),
+        column_expr!("size"),
+        column_expr!("modificationTime"),
+        Expression::literal(data_change),
+        Expression::column([NEW_STATS_NAME]),
+        Expression::column([NEW_DELETION_VECTOR_NAME]),
+        column_expr!("fileConstantValues.baseRowId"),
+        column_expr!("fileConstantValues.defaultRowCommitVersion"),
+    ])]);
+    let adds_evaluator = engine.get_expression_handler().get_evaluator(
+        input_schema.into(),
+        adds_expr,
+        LOG_ADD_FROM_SCAN_ROW_SCHEMA.clone().into(),
+    );
+    let new_schema = Arc::new(StructType::new(new_fields));
+
+    deletion_vector_updates.iter().map(move |update| {
+        let scan_rows = update.files.data();
+        let mut stats_visitor = ScanRowStatsVisitor::default();
+        stats_visitor.visit_rows_of(scan_rows)?;
+        let mut new_dvs = vec![Scalar::Null(dv_type.clone()); scan_rows.len()];
+        let mut new_stats = vec![Scalar::Null(DataType::STRING); scan_rows.len()];
+        let mut selection_vector = vec![false; scan_rows.len()];
+        for (row, dv) in &update.new_dvs {
+            new_dvs[*row] = deletion_vector_scalar(dv)?;
+            if let Some(stats) = &stats_visitor.stats[*row] {
+                new_stats[*row] = wide_bounds_stats(stats);
+            }
+            selection_vector[*row] = true;
+        }
+        let new_columns = vec![
+            ArrayData::new(ArrayType::new(dv_type.clone(), true), new_dvs),
+            ArrayData::new(ArrayType::new(DataType::STRING, true), new_stats),
+        ];
+        let scan_rows = scan_rows.append_columns(new_schema.clone(), new_columns)?;
+        let adds = adds_evaluator.evaluate(scan_rows.as_ref())?;
+        FilteredEngineData::try_new(adds, selection_vector)
+    })
+}
+
+// The statistics of a file whose deletion vector changed. The deleted rows may hold the minimum or
+// maximum values or the nulls of a column, so the statistics no longer have tight bounds. Statistics
+// that aren't a JSON object are dropped.
+fn wide_bounds_stats(stats: &str) -> Scalar {
+    match serde_json::from_str(stats) {
+        Ok(serde_json::Value::Object(mut stats)) => {
+            stats.insert("tightBounds".to_string(), false.into());
+            serde_json::Value::Object(stats).to_string().into()
+        }
+        _ => Scalar::Null(DataType::STRING),
+    }
+}
+
+fn deletion_vector_scalar(dv: &DeletionVectorDescriptor) -> DeltaResult<Scalar> {
+    let fields = DV_FROM_SCAN_ROW_SCHEMA.fields().cloned().collect();
+    let values = vec![
+        dv.storage_type.clone().into(),
+        dv.path_or_inline_dv.clone().into(),
+        dv.offset
+            .map_or(Scalar::Null(DataType::INTEGER), Scalar::from),
+        dv.size_in_bytes.into(),
+        dv.cardinality.into(),
+    ];
+    Ok(Scalar::Struct(StructData::try_new(fields, values)?))
+}
+
+/// WriteContext is data derived from a [`Transaction`] that can be provided to writers in order to
+/// write table data.
+///
+/// [`Transaction`]: struct.Transaction.html
+pub struct WriteContext {
+    target_dir: Url,
+    schema: SchemaRef,
+    logical_to_physical: Expression,
+    stats_columns: Vec<ColumnName>,
+    collect_stats: bool,
+    // the identity columns of the table, by position in the logical_to_physical struct
+    identity_columns: Vec<(usize, IdentityColumn)>,
+    // the high water marks of the identity values generated by the transaction
+    identity_high_water_marks: Arc<Mutex<HashMap<String, i64>>>,
+    // the generated columns of the table, by position in the logical_to_physical struct unless
+    // they are partition columns
+    generated_columns: Vec<(Option<usize>, GeneratedColumn)>,
+    // the (logical) partition columns of the table
+    partition_fields: Vec<StructField>,
+    // the CHECK constraints and column invariants that the written rows must satisfy
+    constraints: Vec<Constraint>,
+}
+
+impl WriteContext {
+    fn new(target_dir: Url, schema: SchemaRef, logical_to_physical: Expression) -> Self {
+        WriteContext {
+            target_dir,
+            schema,
+            logical_to_physical,
+            stats_columns: vec![],
+            collect_stats: false,
+            identity_columns: vec![],
+            identity_high_water_marks: Arc::default(),
+            generated_columns: vec![],
+            partition_fields: vec![],
+            constraints: vec![],
+        }
+    }
+
+    // Set the identity columns whose values are generated, and the high water marks of the values
+    // generated by the transaction
+    fn with_identity_columns(
+        mut self,
+        identity_columns: Vec<(usize, IdentityColumn)>,
+        identity_high_water_marks: Arc<Mutex<HashMap<String, i64>>>,
+    ) -> Self {
+        self.identity_columns = identity_columns;
+        self.identity_high_water_marks = identity_high_water_marks;
+        self
+    }
+
+    // Set the generated columns whose values are computed or checked, and the partition columns
+    // whose values they may refer to
+    fn with_generated_columns(
+        mut self,
+        generated_columns: Vec<(Option<usize>, GeneratedColumn)>,
+        partition_fields: Vec<StructField>,
+    ) -> Self {
+        self.generated_columns = generated_columns;
+        self.partition_fields = partition_fields;
+        self
+    }
+
+    // Set the constraints that the written rows must satisfy
+    fn with_constraints(mut self, constraints: Vec<Constraint>) -> Self {
+        self.constraints = constraints;
+        self
+    }
+
+    // Set whether the written files must have statistics, even without stats columns
+    pub(crate) fn with_collect_stats(mut self, collect_stats: bool) -> Self {
+        self.collect_stats = collect_stats;
+        self
+    }
+
+    // Set the physical columns whose statistics the written files must include
+    pub(crate) fn with_stats_columns(mut self, stats_columns: Vec<ColumnName>) -> Self {
+        self.stats_columns = stats_columns;
+        self
+    }
+
+    pub fn target_dir(&self) -> &Url {
+        &self.target_dir
+    }
+
+    pub fn schema(&self) -> &SchemaRef {
+        &self.schema
+    }
+
+    /// The expression that transforms `data`, a batch of logical data to write, into the physical
+    /// data to write, of the [schema] of the write context. It must be evaluated on every batch
+    /// of data before it is written, and only on that batch. `data_schema` is the schema of
+    /// `data`, and `partition_values` are the partition values it is written with.
+    ///
+    /// For tables with [identity columns], this generates new values of the identity columns for
+    /// the rows of `data`, which may omit the identity columns: rows with a null (or no) value get
+    /// a generated value, and other values are only allowed (and kept) if the column allows
+    /// explicit inserts (`delta.identity.allowExplicitInsert`). Returns
+    /// [`Error::InvalidIdentityColumn`] if `data` has explicit values that are not allowed. Every
+    /// call reserves a generated value for each row of `data`, including the rows with explicit
+    /// values, and so moves the high water marks that the transaction of this write context
+    /// commits, even if the returned expression is never evaluated.
+    ///
+    /// For tables with [generated columns], `data` may omit the generated columns (other than
+    /// partition columns), whose values this computes. The values of the generated columns that
+    /// `data` has must be checked with [`Self::validate`].
+    ///
+    /// [schema]: Self::schema
+    /// [identity columns]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#identity-columns
+    /// [generated columns]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#generated-columns
+    pub fn logical_to_physical(
+        &self,
+        data: &dyn EngineData,
+        data_schema: &StructType,
+        partition_values: &HashMap<String, String>,
+    ) -> DeltaResult<Expression> {
+        let mut logical_to_physical = self.logical_to_physical.clone();
+        let omitted_generated_columns: Vec<_> = self
+            .generated_columns
+            .iter()
+            .filter_map(|(position, column)| Some((position.as_ref()?, column)))
+            .filter(|(_, column)| data_schema.field(&column.name).is_none())
+            .collect();
+        if self.identity_columns.is_empty() && omitted_generated_columns.is_empty() {
+            return Ok(logical_to_physical);
+        }
+        let Expression::Struct(fields) = &mut logical_to_physical else {
+            return Err(Error::internal_error(
+                "The logical to physical transform is not a struct expression",
+            ));
+        };
+        if !omitted_generated_columns.is_empty() {
+            let partition_literals = partition_literals(&self.partition_fields, partition_values)?;
+            for (position, column) in omitted_generated_columns {
+                fields[*position] = with_column_values(&column.expression, &partition_literals);
+            }
+        }
+        if self.identity_columns.is_empty() {
+            return Ok(logical_to_physical);
+        }
+        let mut high_water_marks = self
+            .identity_high_water_marks
+            .lock()
+            .map_err(|_| Error::internal_error("Identity high water marks lock poisoned"))?;
+        for (position, column) in &self.identity_columns {
+            let has_column = data_schema.field(&column.name).is_some();
+            if has_column {
+                ensure_no_explicit_values(column, data)?;
+            }
+            let high_water_mark = high_water_marks.get(&column.name).copied();
+            if let Some((first, last)) = column.reserve(high_water_mark, data.len())? {
+                fields[*position] = column.values_expression(first, has_column);
+                high_water_marks.insert(column.name.clone(), last);
+            }
+        }
+        Ok(logical_to_physical)
+    }
+
+    /// Check that `data`, a batch of logical data to write of schema `data_schema`, can be written
+    /// with the given partition values, by evaluating checks on it with the engine's
+    /// [`ExpressionHandler`]. It must be called on every batch of data before it is written.
+    ///
+    /// For tables with [generated columns], this checks that the values of the generated columns
+    /// of `data` and the partition values of the generated partition columns are those of their
+    /// generation expressions, and returns [`Error::InvalidGeneratedColumn`] if they aren't.
+    ///
+    /// For tables with [CHECK constraints] or [column invariants], this checks that every row of
+    /// `data` satisfies them, i.e. that their predicates are true (not false or null), and returns
+    /// [`Error::InvalidConstraint`] if a row violates one.
+    ///
+    /// [`ExpressionHandler`]: crate::ExpressionHandler
+    /// [generated columns]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#generated-columns
+    /// [CHECK constraints]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#check-constraints
+    /// [column invariants]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#column-invariants
+    pub fn validate(
+        &self,
+        engine: &dyn Engine,
+        data: &dyn EngineData,
+        data_schema: &SchemaRef,
+        partition_values: &HashMap<String, String>,
+    ) -> DeltaResult<()> {
+        if self.generated_columns.is_empty() && self.constraints.is_empty() {
+            return Ok(());
+        }
+        let partition_literals = partition_literals(&self.partition_fields, partition_values)?;
+        for (position, column) in &self.generated_columns {
+            let value = match (position, partition_literals.get(&column.name)) {
+                (None, Some(partition_value)) => Expression::literal(partition_value.clone()),
+                // omitted generated columns get the values of their generation expression
+                _ if data_schema.field(&column.name).is_none() => continue,
+                _ => Expression::column([&column.name]),
+            };
+            let predicate = with_column_values(&column.matches(value), &partition_literals);
+            require!(
+                holds_for_all_rows(engine, data, data_schema, predicate)?,
+                Error::invalid_generated_column(format!(
+                    "The values of {} don't match its generation expression",
+                    column.name
+                ))
+            );
+        }
+        // the constraints apply to the values that logical_to_physical computes for the omitted
+        // generated columns
+        let omitted_generated_values: HashMap<_, _> = self
+            .generated_columns
+            .iter()
+            .filter(|(position, column)| {
+                position.is_some() && data_schema.field(&column.name).is_none()
+            })
+            .map(|(_, column)| (column.name.clone(), column.expression.clone()))
+            .collect();
+        for constraint in &self.constraints {
+            let predicate = with_column_values(&constraint.expression, &omitted_generated_values);
+            let predicate = with_column_values(&predicate, &partition_literals);
+            require!(
+                holds_for_all_rows(engine, data, data_schema, predicate)?,
+                Error::invalid_constraint(format!("{constraint} is violated by the written data"))
+            );
+        }
+        Ok(())
+    }
+
+    /// The physical columns whose [statistics] must be collected for the written files and
+    /// included in their write metadata, if any.
+    ///
+    /// [statistics]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Per-file-Statistics
+    pub fn stats_columns(&self) -> &[ColumnName] {
+        &self.stats_columns
+    }
+
+    /// Whether the write metadata of the written files must include their [statistics], with at
+    /// least their number of records (`numRecords`), e.g. because the table assigns row IDs to
+    /// the rows of new files. This is always the case if there are [stats columns].
+    ///
+    /// [statistics]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#Per-file-Statistics
+    /// [stats columns]: Self::stats_columns
+    pub fn collect_stats(&self) -> bool {
+        self.collect_stats || !self.stats_columns.is_empty()
+    }
+}
+
+/// Result after committing a transaction. If 'committed', the version is the new version written
+/// to the log. If 'conflict', other transactions kept committing first, and the transaction is
+/// returned so the caller can commit it again (along with the last version it tried to commit
+/// at). Conflicts with the changes of concurrent transactions are reported as
+/// [`Error::TransactionConflict`].
+#[derive(Debug)]
+pub enum CommitResult {
+    /// The transaction was successfully committed at the version.
+    Committed(Version),
+    /// The transaction ran out of attempts to commit, the last of which was at the version given.
+    Conflict(Box<Transaction>, Version),
+}
+
+// given the engine's commit info we want to create commitInfo action to commit (and append more actions to)
+fn generate_commit_info(
+    engine: &dyn Engine,
+    timestamp: i64,
+    in_commit_timestamp: Option<i64>,
+    operation: Option<&str>,
+    is_blind_append: bool,
+    engine_commit_info: &dyn EngineData,
+) -> DeltaResult<Box<dyn EngineData>> {
+    if engine_commit_info.len() != 1 {
+        return Err(Error::InvalidCommitInfo(format!(
+            "Engine commit info should have exactly one row, found {}",
+            engine_commit_info.len()
+        )));
+    }
+
+    // TODO(zach): we should probably take a timestamp closer to actual commit time?
+    let timestamp_exprs = chain(
+        iter::once(Expression::literal(timestamp)),
+        in_commit_timestamp.map(Expression::literal),
+    );
+    let commit_info_exprs = [
+        Expression::literal(operation.unwrap_or(UNKNOWN_OPERATION)),
+        // HACK (part 1/2): since we don't have proper map support, we create a literal struct with
+        // one null field to create data that serializes as "operationParameters": {}
+        Expression::literal(Scalar::Struct(StructData::try_new(
+            vec![StructField::nullable(
+                "operation_parameter_int",
+                DataType::INTEGER,
+            )],
+            vec![Scalar::Null(DataType::INTEGER)],
+        )?)),
+        Expression::literal(format!("v{}", KERNEL_VERSION)),
+        column_expr!("engineCommitInfo"),
+        Expression::literal(is_blind_append),
+    ];
+    let commit_info_expr = Expression::struct_from([Expression::struct_from(chain(
+        timestamp_exprs,
+        commit_info_exprs,
+    ))]);
+    let commit_info_schema = get_log_commit_info_schema().as_ref();
+
+    // HACK (part 2/2): we need to modify the commit info schema to match the expression above (a
+    // struct with a single null int field).
+    let mut commit_info_empty_struct_schema = commit_info_schema.clone();
+    let commit_info_field = commit_info_empty_struct_schema
+        .fields
+        .get_mut(COMMIT_INFO_NAME)
+        .ok_or_else(|| Error::missing_column(COMMIT_INFO_NAME))?;
+    let DataType::Struct(mut commit_info_data_type) = commit_info_field.data_type().clone() else {
+        return Err(Error::internal_error(
+            "commit_info_field should be a struct",
+        ));
+    };
+    let engine_commit_info_schema =
+        commit_info_data_type.project_as_struct(&["engineCommitInfo"])?;
+    let hack_data_type = DataType::Struct(Box::new(StructType::new(vec![StructField::nullable(
+        "hack_operation_parameter_int",
+        DataType::INTEGER,
+    )])));
+
+    commit_info_data_type
+        .fields
+        .get_mut("operationParameters")
+        .ok_or_else(|| Error::missing_column("operationParameters"))?
+        .data_type = hack_data_type;
+
+    // The in-commit timestamp is only written when in-commit timestamps are enabled, so otherwise we
+    // remove the field so it is not written to the log
+    if in_commit_timestamp.is_none() {
+        commit_info_data_type
+            .fields
+            .shift_remove("inCommitTimestamp");
+    }
+    commit_info_field.data_type = DataType::Struct(commit_info_data_type);
+
+    let commit_info_evaluator = engine.get_expression_handler().get_evaluator(
+        engine_commit_info_schema.into(),
+        commit_info_expr,
+        commit_info_empty_struct_schema.into(),
+    );
+
+    commit_info_evaluator.evaluate(engine_commit_info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::engine::arrow_data::ArrowEngineData;
+    use crate::engine::arrow_expression::ArrowExpressionHandler;
+    use crate::schema::MapType;
+    use crate::{ExpressionHandler, FileSystemClient, JsonHandler, ParquetHandler};
+
+    use crate::arrow::array::cast::AsArray;
+    use crate::arrow::array::types::Int64Type;
+    use crate::arrow::array::{
+        BooleanArray, Int64Array, MapArray, MapBuilder, MapFieldNames, StringArray, StringBuilder,
+    };
+    use crate::arrow::datatypes::{DataType as ArrowDataType, Field, Schema as ArrowSchema};
+    use crate::arrow::error::ArrowError;
+    use crate::arrow::json::writer::LineDelimitedWriter;
+    use crate::arrow::record_batch::RecordBatch;
+
+    struct ExprEngine(Arc<dyn ExpressionHandler>);
+
+    impl ExprEngine {
+        fn new() -> Self {
+            ExprEngine(Arc::new(ArrowExpressionHandler))
+        }
+    }
+
+    impl Engine for ExprEngine {
+        fn get_expression_handler(&self) -> Arc<dyn ExpressionHandler> {
+            self.0.clone()
+        }
+
+        fn get_json_handler(&self) -> Arc<dyn JsonHandler> {
+            unimplemented!()
+        }
+
+        fn get_parquet_handler(&self) -> Arc<dyn ParquetHandler> {
+            unimplemented!()
+        }
+
+        fn get_file_system_client(&self) -> Arc<dyn FileSystemClient> {
+            unimplemented!()
+        }
+    }
+
+    fn build_map(entries: Vec<(&str, &str)>) -> MapArray {
+        let key_builder = StringBuilder::new();
+        let val_builder = StringBuilder::new();
+        let names = MapFieldNames {
+            entry: "entries".to_string(),
+            key: "key".to_string(),
+            value: "value".to_string(),
+        };
+        let mut builder = MapBuilder::new(Some(names), key_builder, val_builder);
+        for (key, val) in entries {
+            builder.keys().append_value(key);
+            builder.values().append_value(val);
+            builder.append(true).unwrap();
+        }
+        builder.finish()
+    }
+
+    // convert it to JSON just for ease of comparison (and since we ultimately persist as JSON)
+    fn as_json_and_scrub_timestamp(data: Box<dyn EngineData>) -> serde_json::Value {
+        let record_batch: RecordBatch = data
+            .into_any()
+            .downcast::<ArrowEngineData>()
+            .unwrap()
+            .into();
+
+        let buf = Vec::new();
+        let mut writer = LineDelimitedWriter::new(buf);
+        writer.write_batches(&[&record_batch]).unwrap();
+        writer.finish().unwrap();
+        let buf = writer.into_inner();
+
+        let mut result: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+        *result
+            .get_mut("commitInfo")
+            .unwrap()
+            .get_mut("timestamp")
+            .unwrap() = serde_json::Value::Number(0.into());
+        result
+    }
+
+    #[test]
+    fn test_generate_commit_info() -> DeltaResult<()> {
+        let engine = ExprEngine::new();
+        let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "engineCommitInfo",
+            ArrowDataType::Map(
+                Arc::new(Field::new(
+                    "entries",
+                    ArrowDataType::Struct(
+                        vec![
+                            Field::new("key", ArrowDataType::Utf8, false),
+                            Field::new("value", ArrowDataType::Utf8, true),
+                        ]
+                        .into(),
+                    ),
+                    false,
+                )),
+                false,
+            ),
+            false,
+        )]));
+
+        let map_array = build_map(vec![("engineInfo", "default engine")]);
+        let commit_info_batch =
+            RecordBatch::try_new(engine_commit_info_schema, vec![Arc::new(map_array)])?;
+
+        let actions = generate_commit_info(
+            &engine,
+            0,
+            None,
+            Some("test operation"),
+            true,
+            &ArrowEngineData::new(commit_info_batch.clone()),
+        )?;
+
+        let mut expected = serde_json::json!({
+            "commitInfo": {
+                "timestamp": 0,
+                "operation": "test operation",
+                "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                "operationParameters": {},
+                "engineCommitInfo": {
+                    "engineInfo": "default engine"
+                },
+                "isBlindAppend": true
+            }
+        });
+
+        assert_eq!(actions.len(), 1);
+        let result = as_json_and_scrub_timestamp(actions);
+        assert_eq!(result, expected);
+
+        // the in-commit timestamp is only written when given
+        let actions = generate_commit_info(
+            &engine,
+            0,
+            Some(10),
+            Some("test operation"),
+            true,
+            &ArrowEngineData::new(commit_info_batch),
+        )?;
+        expected["commitInfo"]["inCommitTimestamp"] = 10.into();
+        assert_eq!(as_json_and_scrub_timestamp(actions), expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_commit_info_with_multiple_columns() -> DeltaResult<()> {
+        let engine = ExprEngine::new();
+        let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![
+            Field::new(
+                "engineCommitInfo",
+                ArrowDataType::Map(
+                    Arc::new(Field::new(
+                        "entries",
+                        ArrowDataType::Struct(
+                            vec![
+                                Field::new("key", ArrowDataType::Utf8, false),
+                                Field::new("value", ArrowDataType::Utf8, true),
+                            ]
+                            .into(),
+                        ),
+                        false,
+                    )),
+                    false,
+                ),
+                false,
+            ),
+            Field::new("operation", ArrowDataType::Utf8, true),
+        ]));
+
+        let map_array = build_map(vec![("engineInfo", "default engine")]);
+
+        let commit_info_batch = RecordBatch::try_new(
+            engine_commit_info_schema,
+            vec![
+                Arc::new(map_array),
+                Arc::new(StringArray::from(vec!["some_string"])),
+            ],
+        )?;
+
+        let actions = generate_commit_info(
+            &engine,
+            0,
+            None,
+            Some("test operation"),
+            true,
+            &ArrowEngineData::new(commit_info_batch),
+        )?;
+
+        let expected = serde_json::json!({
+            "commitInfo": {
+                "timestamp": 0,
+                "operation": "test operation",
+                "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                "operationParameters": {},
+                "engineCommitInfo": {
+                    "engineInfo": "default engine"
+                },
+                "isBlindAppend": true
+            }
+        });
+
+        assert_eq!(actions.len(), 1);
+        let result = as_json_and_scrub_timestamp(actions);
+        assert_eq!(result, expected);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_commit_info_missing_column() -> DeltaResult<()> {
+        let engine = ExprEngine::new();
+        let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "some_column_name",
+            ArrowDataType::Utf8,
+            true,
+        )]));
+        let commit_info_batch = RecordBatch::try_new(
+            engine_commit_info_schema,
+            vec![Arc::new(StringArray::new_null(1))],
+        )?;
+
+        let _ = generate_commit_info(
+            &engine,
+            0,
+            None,
+            Some("test operation"),
+            true,
+            &ArrowEngineData::new(commit_info_batch),
+        )
+        .map_err(|e| match e {
+            Error::Arrow(ArrowError::SchemaError(_)) => (),
+            Error::Backtraced { source, .. }
+                if matches!(&*source, Error::Arrow(ArrowError::SchemaError(_))) => {}
+            _ => panic!("expected arrow schema error error, got {:?}", e),
+        });
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_commit_info_invalid_column_type() -> DeltaResult<()> {
+        let engine = ExprEngine::new();
+        let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+            "engineCommitInfo",
+            ArrowDataType::Utf8,
+            true,
+        )]));
+        let commit_info_batch = RecordBatch::try_new(
+            engine_commit_info_schema,
+            vec![Arc::new(StringArray::new_null(1))],
+        )?;
+
+        let _ = generate_commit_info(
+            &engine,
+            0,
+            None,
+            Some("test operation"),
+            true,
+            &ArrowEngineData::new(commit_info_batch),
+        )
+        .map_err(|e| match e {
+            Error::Arrow(ArrowError::InvalidArgumentError(_)) => (),
+            Error::Backtraced { source, .. }
+                if matches!(&*source, Error::Arrow(ArrowError::InvalidArgumentError(_))) => {}
+            _ => panic!("expected arrow invalid arg error, got {:?}", e),
+        });
+
+        Ok(())
+    }
+
+    fn assert_empty_commit_info(
+        data: Box<dyn EngineData>,
+        write_engine_commit_info: bool,
+    ) -> DeltaResult<()> {
+        assert_eq!(data.len(), 1);
+        let expected = if write_engine_commit_info {
+            serde_json::json!({
+                "commitInfo": {
+                    "timestamp": 0,
+                    "operation": "test operation",
+                    "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                    "operationParameters": {},
+                    "engineCommitInfo": {},
+                    "isBlindAppend": true
+                }
+            })
+        } else {
+            serde_json::json!({
+                "commitInfo": {
+                    "timestamp": 0,
+                    "operation": "test operation",
+                    "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                    "operationParameters": {},
+                    "isBlindAppend": true
+                }
+            })
+        };
+        let result = as_json_and_scrub_timestamp(data);
+        assert_eq!(result, expected);
+        Ok(())
+    }
+
+    // Three cases for empty commit info:
+    // 1. `engineCommitInfo` column with an empty Map<string, string>
+    // 2. `engineCommitInfo` null column of type Map<string, string>
+    // 3. a column that has a name other than `engineCommitInfo`; Delta can detect that the column
+    //    is missing and substitute a null literal in its place. The type of that column doesn't
+    //    matter, Delta will ignore it.
+    #[test]
+    fn test_empty_commit_info() -> DeltaResult<()> {
+        // test with null map and empty map
+        for is_null in [true, false] {
+            let engine = ExprEngine::new();
+            let engine_commit_info_schema = Arc::new(ArrowSchema::new(vec![Field::new(
+                "engineCommitInfo",
+                ArrowDataType::Map(
+                    Arc::new(Field::new(
+                        "entries",
+                        ArrowDataType::Struct(
+                            vec![
+                                Field::new("key", ArrowDataType::Utf8, false),
+                                Field::new("value", ArrowDataType::Utf8, true),
+                            ]
+                            .into(),
+                        ),
+                        false,
+                    )),
+                    false,
+                ),
+                true,
+            )]));
+
+            let key_builder = StringBuilder::new();
+            let val_builder = StringBuilder::new();
+            let names = crate::arrow::array::MapFieldNames {
+                entry: "entries".to_string(),
+                key: "key".to_string(),
+                value: "value".to_string(),
+            };
+            let mut builder =
+                crate::arrow::array::MapBuilder::new(Some(names), key_builder, val_builder);
+            builder.append(is_null).unwrap();
+            let array = builder.finish();
+
+            let commit_info_batch =
+                RecordBatch::try_new(engine_commit_info_schema, vec![Arc::new(array)])?;
+
+            let actions = generate_commit_info(
+                &engine,
+                0,
+                None,
+                Some("test operation"),
+                true,
+                &ArrowEngineData::new(commit_info_batch),
+            )?;
+
+            assert_empty_commit_info(actions, is_null)?;
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_write_metadata_schema() {
+        let schema = get_write_metadata_schema();
+        let expected = StructType::new(vec![
+            StructField::not_null("path", DataType::STRING),
+            StructField::not_null(
+                "partitionValues",
+                MapType::new(DataType::STRING, DataType::STRING, true),
+            ),
+            StructField::not_null("size", DataType::LONG),
+            StructField::not_null("modificationTime", DataType::LONG),
+            StructField::not_null("dataChange", DataType::BOOLEAN),
+            StructField::nullable("stats", DataType::STRING),
+        ]);
+        assert_eq!(*schema, expected.into());
+    }
+
+    #[test]
+    fn test_generate_row_tracking_adds() -> DeltaResult<()> {
+        let engine = ExprEngine::new();
+        let batch = RecordBatch::try_from_iter(vec![
+            (
+                "path",
+                Arc::new(StringArray::from(vec![
+                    "a.parquet",
+                    "b.parquet",
+                    "c.parquet",
+                ])) as _,
+            ),
+            (
+                "partitionValues",
+                Arc::new(build_map(vec![("p", "1"), ("p", "2"), ("p", "3")])) as _,
+            ),
+            ("size", Arc::new(Int64Array::from(vec![10, 20, 30])) as _),
+            (
+                "modificationTime",
+                Arc::new(Int64Array::from(vec![1, 2, 3])) as _,
+            ),
+            (
+                "dataChange",
+                Arc::new(BooleanArray::from(vec![true; 3])) as _,
+            ),
+            (
+                "stats",
+                Arc::new(StringArray::from(vec![
+                    r#"{"numRecords":3}"#,
+                    r#"{"numRecords":2}"#,
+                    r#"{"numRecords":1}"#,
+                ])) as _,
+            ),
+        ])?;
+        let write_metadata: Vec<Box<dyn EngineData>> = vec![Box::new(ArrowEngineData::new(batch))];
+        let base_row_ids = vec![vec![0, 3, 5]];
+
+        // a single evaluation of the batch carries the base row ID of each file
+        let adds: Vec<_> =
+            generate_row_tracking_adds(&engine, false, &write_metadata, &base_row_ids, 7)
+                .collect::<DeltaResult<_>>()?;
+        assert_eq!(adds.len(), 1);
+        assert!(adds[0].selection_vector().iter().all(|selected| *selected));
+        let adds = adds[0]
+            .data()
+            .any_ref()
+            .downcast_ref::<ArrowEngineData>()
+            .unwrap()
+            .record_batch();
+        let add = adds.column(0).as_struct();
+        let column = |name: &str| {
+            add.column_by_name(name)
+                .unwrap()
+                .as_primitive::<Int64Type>()
+        };
+        assert_eq!(column("baseRowId").values(), &[0, 3, 5]);
+        assert_eq!(column("defaultRowCommitVersion").values(), &[7, 7, 7]);
+        let data_change = add.column_by_name("dataChange").unwrap().as_boolean();
+        assert_eq!(data_change.true_count(), 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_num_records_visitor() {
+        let num_records = |stats: Vec<Option<&str>>| {
+            let schema = ArrowSchema::new(vec![Field::new("stats", ArrowDataType::Utf8, true)]);
+            let batch =
+                RecordBatch::try_new(Arc::new(schema), vec![Arc::new(StringArray::from(stats))])
+                    .unwrap();
+            let mut visitor = NumRecordsVisitor::default();
+            visitor
+                .visit_rows_of(&ArrowEngineData::new(batch))
+                .map(|_| visitor.num_records)
+        };
+        assert_eq!(
+            num_records(vec![
+                Some(r#"{"numRecords":3}"#),
+                Some(r#"{"numRecords":1}"#)
+            ])
+            .unwrap(),
+            [3, 1]
+        );
+        assert!(matches!(
+            num_records(vec![None]),
+            Err(Error::InvalidTransaction(_))
+        ));
+        for stats in [r#"{"numRecords":"#, r#"{"nullCount":{}}"#] {
+            match num_records(vec![Some(stats)]) {
+                Err(Error::MalformedJson(_)) => {}
+                Err(Error::Backtraced { source, .. })
+                    if matches!(&*source, Error::MalformedJson(_)) => {}
+                result => panic!("expected malformed stats error, got {result:?}"),
+            }
+        }
+    }
+}
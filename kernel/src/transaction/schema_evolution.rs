@@ -0,0 +1,467 @@
+//! Validation of schema changes made by a transaction, recording of the type changes they make in
+//! the schema as required by the [type widening] table feature, and assignment of [column mapping]
+//! ids and physical names to the columns they add.
+//!
+//! [type widening]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#type-widening
+//! [column mapping]: https://github.com/delta-io/delta/blob/master/PROTOCOL.md#column-mapping
+
+use std::collections::HashMap;
+
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::schema::compare::SchemaComparison as _;
+use crate::schema::{
+    ArrayType, ColumnMetadataKey, DataType, MapType, MetadataValue, PrimitiveType, StructField,
+    StructType,
+};
+use crate::{DeltaResult, Error};
+
+/// The table property holding the largest column id assigned by column mapping.
+pub(crate) const COLUMN_MAPPING_MAX_COLUMN_ID: &str = "delta.columnMapping.maxColumnId";
+
+/// Check that the table schema can be changed from `current` to `new`: `new` may add nullable
+/// columns, relax the nullability of columns and, if `allow_type_widening` is set, widen the types
+/// of columns. Returns `new` with the type changes recorded in the metadata of the changed fields.
+///
+/// If column mapping is enabled, `max_column_id` is the largest column id of the table (see
+/// [`max_column_id`]): existing columns keep their id and physical name, and new columns are
+/// assigned the following ids and fresh physical names, bumping `max_column_id`.
+pub(crate) fn evolve_schema(
+    current: &StructType,
+    new: &StructType,
+    allow_type_widening: bool,
+    max_column_id: Option<&mut i64>,
+) -> DeltaResult<StructType> {
+    current
+        .check_read_as(new, allow_type_widening)
+        .map_err(|e| Error::invalid_transaction(format!("Invalid schema change: {e}")))?;
+    let schema = record_type_changes(current, new);
+    Ok(match max_column_id {
+        Some(max_column_id) => assign_column_mapping(Some(current), &schema, max_column_id),
+        None => schema,
+    })
+}
+
+/// The largest column id assigned by column mapping in a table with the given configuration and
+/// schema: the `delta.columnMapping.maxColumnId` table property, unless a field of the schema has
+/// a larger id.
+pub(crate) fn max_column_id(configuration: &HashMap<String, String>, schema: &StructType) -> i64 {
+    let max_id = configuration
+        .get(COLUMN_MAPPING_MAX_COLUMN_ID)
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(0);
+    max_field_id(schema).max(max_id)
+}
+
+fn max_field_id(schema: &StructType) -> i64 {
+    schema
+        .fields()
+        .map(|field| {
+            let id = match field.get_config_value(&ColumnMetadataKey::ColumnMappingId) {
+                Some(MetadataValue::Number(id)) => *id,
+                _ => 0,
+            };
+            let mut nested_types = vec![field.data_type()];
+            let mut nested_id = 0;
+            while let Some(nested_type) = nested_types.pop() {
+                match nested_type {
+                    DataType::Struct(struct_type) => {
+                        nested_id = nested_id.max(max_field_id(struct_type))
+                    }
+                    DataType::Array(array_type) => nested_types.push(array_type.element_type()),
+                    DataType::Map(map_type) => {
+                        nested_types.push(map_type.key_type());
+                        nested_types.push(map_type.value_type());
+                    }
+                    DataType::Primitive(_) => {}
+                }
+            }
+            id.max(nested_id)
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+// give the fields of `new` the column mapping id and physical name of the same field of `current`,
+// or the next id and a fresh physical name if they are new
+fn assign_column_mapping(
+    current: Option<&StructType>,
+    new: &StructType,
+    max_column_id: &mut i64,
+) -> StructType {
+    let id_key = ColumnMetadataKey::ColumnMappingId.as_ref();
+    let physical_name_key = ColumnMetadataKey::ColumnMappingPhysicalName.as_ref();
+    StructType::new(new.fields().map(|field| {
+        let current_field = current.and_then(|current| current.field(field.name()));
+        let mut field = field.clone();
+        field.metadata.remove(id_key);
+        field.metadata.remove(physical_name_key);
+        match current_field {
+            Some(current_field) => {
+                for key in [id_key, physical_name_key] {
+                    if let Some(value) = current_field.metadata.get(key) {
+                        field.metadata.insert(key.to_string(), value.clone());
+                    }
+                }
+            }
+            None => {
+                *max_column_id += 1;
+                field
+                    .metadata
+                    .insert(id_key.to_string(), MetadataValue::Number(*max_column_id));
+                field.metadata.insert(
+                    physical_name_key.to_string(),
+                    MetadataValue::String(format!("col-{}", Uuid::new_v4())),
+                );
+            }
+        }
+        field.data_type = assign_nested_column_mapping(
+            current_field.map(|current_field| current_field.data_type()),
+            field.data_type(),
+            max_column_id,
+        );
+        field
+    }))
+}
+
+// assign column mapping ids and physical names to the struct fields nested in `new`
+fn assign_nested_column_mapping(
+    current: Option<&DataType>,
+    new: &DataType,
+    max_column_id: &mut i64,
+) -> DataType {
+    match new {
+        DataType::Struct(new) => {
+            let current = match current {
+                Some(DataType::Struct(current)) => Some(current.as_ref()),
+                _ => None,
+            };
+            assign_column_mapping(current, new, max_column_id).into()
+        }
+        DataType::Array(new) => {
+            let current = match current {
+                Some(DataType::Array(current)) => Some(current.element_type()),
+                _ => None,
+            };
+            ArrayType::new(
+                assign_nested_column_mapping(current, new.element_type(), max_column_id),
+                new.contains_null(),
+            )
+            .into()
+        }
+        DataType::Map(new) => {
+            let (current_key, current_value) = match current {
+                Some(DataType::Map(current)) => {
+                    (Some(current.key_type()), Some(current.value_type()))
+                }
+                _ => (None, None),
+            };
+            MapType::new(
+                assign_nested_column_mapping(current_key, new.key_type(), max_column_id),
+                assign_nested_column_mapping(current_value, new.value_type(), max_column_id),
+                new.value_contains_null(),
+            )
+            .into()
+        }
+        DataType::Primitive(_) => new.clone(),
+    }
+}
+
+fn record_type_changes(current: &StructType, new: &StructType) -> StructType {
+    StructType::new(new.fields().map(|field| {
+        let Some(current_field) = current.field(field.name()) else {
+            return field.clone();
+        };
+        let mut type_changes = vec![];
+        collect_type_changes(
+            current_field.data_type(),
+            field.data_type(),
+            None,
+            &mut type_changes,
+        );
+        let mut field = StructField {
+            data_type: record_nested_type_changes(current_field.data_type(), field.data_type()),
+            ..field.clone()
+        };
+        // keep the type changes recorded by earlier schema changes
+        let key = ColumnMetadataKey::TypeChanges.as_ref();
+        if let Some(previous) = current_field.metadata.get(key) {
+            if !field.metadata.contains_key(key) {
+                field.metadata.insert(key.to_string(), previous.clone());
+            }
+        }
+        if type_changes.is_empty() {
+            field
+        } else {
+            with_type_changes(field, type_changes)
+        }
+    }))
+}
+
+// record the type changes of the struct fields nested in `new`
+fn record_nested_type_changes(current: &DataType, new: &DataType) -> DataType {
+    match (current, new) {
+        (DataType::Struct(current), DataType::Struct(new)) => {
+            record_type_changes(current, new).into()
+        }
+        (DataType::Array(current), DataType::Array(new)) => ArrayType::new(
+            record_nested_type_changes(current.element_type(), new.element_type()),
+            new.contains_null(),
+        )
+        .into(),
+        (DataType::Map(current), DataType::Map(new)) => MapType::new(
+            record_nested_type_changes(current.key_type(), new.key_type()),
+            record_nested_type_changes(current.value_type(), new.value_type()),
+            new.value_contains_null(),
+        )
+        .into(),
+        _ => new.clone(),
+    }
+}
+
+// collect the primitive type changes of a field's own type. Type changes of the elements of arrays
+// and maps belong to the field too, and are identified by their path (e.g. `element` or `value`).
+// Type changes of nested struct fields are recorded on the nested fields instead.
+fn collect_type_changes(
+    current: &DataType,
+    new: &DataType,
+    field_path: Option<String>,
+    type_changes: &mut Vec<Value>,
+) {
+    let nested_path = |name: &str| match &field_path {
+        Some(path) => format!("{path}.{name}"),
+        None => name.to_string(),
+    };
+    match (current, new) {
+        (DataType::Primitive(current), DataType::Primitive(new)) if current != new => {
+            type_changes.push(type_change(current, new, field_path));
+        }
+        (DataType::Array(current), DataType::Array(new)) => collect_type_changes(
+            current.element_type(),
+            new.element_type(),
+            Some(nested_path("element")),
+            type_changes,
+        ),
+        (DataType::Map(current), DataType::Map(new)) => {
+            collect_type_changes(
+                current.key_type(),
+                new.key_type(),
+                Some(nested_path("key")),
+                type_changes,
+            );
+            collect_type_changes(
+                current.value_type(),
+                new.value_type(),
+                Some(nested_path("value")),
+                type_changes,
+            );
+        }
+        _ => {}
+    }
+}
+
+fn type_change(from: &PrimitiveType, to: &PrimitiveType, field_path: Option<String>) -> Value {
+    let mut type_change = json!({ "toType": to, "fromType": from });
+    if let Some(field_path) = field_path {
+        type_change["fieldPath"] = field_path.into();
+    }
+    type_change
+}
+
+// append the type changes to the `delta.typeChanges` field metadata, keeping earlier changes
+fn with_type_changes(mut field: StructField, type_changes: Vec<Value>) -> StructField {
+    let key = ColumnMetadataKey::TypeChanges.as_ref();
+    let mut all_type_changes = match field.metadata.remove(key) {
+        Some(MetadataValue::Other(Value::Array(previous))) => previous,
+        _ => vec![],
+    };
+    all_type_changes.extend(type_changes);
+    field.metadata.insert(
+        key.to_string(),
+        MetadataValue::Other(Value::Array(all_type_changes)),
+    );
+    field
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evolve_schema() {
+        let current = StructType::new([
+            StructField::not_null("id", DataType::INTEGER),
+            StructField::nullable("name", DataType::STRING),
+        ]);
+        let new = StructType::new([
+            StructField::nullable("id", DataType::INTEGER),
+            StructField::nullable("name", DataType::STRING),
+            StructField::nullable("age", DataType::INTEGER),
+        ]);
+        assert_eq!(evolve_schema(&current, &new, false, None).unwrap(), new);
+
+        // columns can't be dropped, and new columns must be nullable
+        let dropped = StructType::new([StructField::not_null("id", DataType::INTEGER)]);
+        let not_null = StructType::new([
+            StructField::not_null("id", DataType::INTEGER),
+            StructField::nullable("name", DataType::STRING),
+            StructField::not_null("age", DataType::INTEGER),
+        ]);
+        for invalid in [dropped, not_null] {
+            assert!(matches!(
+                evolve_schema(&current, &invalid, true, None),
+                Err(Error::InvalidTransaction(_))
+            ));
+        }
+
+        // types can only be widened when allowed
+        let widened = StructType::new([
+            StructField::not_null("id", DataType::LONG),
+            StructField::nullable("name", DataType::STRING),
+        ]);
+        assert!(evolve_schema(&current, &widened, false, None).is_err());
+        assert!(evolve_schema(&current, &widened, true, None).is_ok());
+    }
+
+    #[test]
+    fn test_record_type_changes() {
+        let previous_change = json!({"toType": "short", "fromType": "byte"});
+        let current = StructType::new([
+            StructField::nullable("id", DataType::SHORT).with_metadata([(
+                ColumnMetadataKey::TypeChanges.as_ref(),
+                MetadataValue::Other(json!([previous_change])),
+            )]),
+            StructField::nullable(
+                "nested",
+                StructType::new([StructField::nullable("x", DataType::FLOAT)]),
+            ),
+            StructField::nullable(
+                "map",
+                MapType::new(
+                    DataType::STRING,
+                    ArrayType::new(DataType::INTEGER, true),
+                    true,
+                ),
+            ),
+        ]);
+        let new = StructType::new([
+            StructField::nullable("id", DataType::INTEGER),
+            StructField::nullable(
+                "nested",
+                StructType::new([StructField::nullable("x", DataType::DOUBLE)]),
+            ),
+            StructField::nullable(
+                "map",
+                MapType::new(DataType::STRING, ArrayType::new(DataType::LONG, true), true),
+            ),
+        ]);
+
+        let type_changes = |changes: Value| {
+            [(
+                ColumnMetadataKey::TypeChanges.as_ref(),
+                MetadataValue::Other(changes),
+            )]
+        };
+        let expected = StructType::new([
+            StructField::nullable("id", DataType::INTEGER).with_metadata(type_changes(json!([
+                previous_change,
+                {"toType": "integer", "fromType": "short"}
+            ]))),
+            StructField::nullable(
+                "nested",
+                StructType::new([StructField::nullable("x", DataType::DOUBLE).with_metadata(
+                    type_changes(json!([
+                        {"toType": "double", "fromType": "float"}
+                    ])),
+                )]),
+            ),
+            StructField::nullable(
+                "map",
+                MapType::new(DataType::STRING, ArrayType::new(DataType::LONG, true), true),
+            )
+            .with_metadata(type_changes(json!([
+                {"toType": "long", "fromType": "integer", "fieldPath": "value.element"}
+            ]))),
+        ]);
+        assert_eq!(evolve_schema(&current, &new, true, None).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_assign_column_mapping() {
+        let mapped = |name: &str, id: i64, data_type: DataType| {
+            StructField::nullable(name, data_type).with_metadata([
+                (
+                    ColumnMetadataKey::ColumnMappingId.as_ref(),
+                    MetadataValue::Number(id),
+                ),
+                (
+                    ColumnMetadataKey::ColumnMappingPhysicalName.as_ref(),
+                    MetadataValue::String(format!("col-{id}")),
+                ),
+            ])
+        };
+        let current = StructType::new([
+            mapped("id", 1, DataType::INTEGER),
+            mapped(
+                "nested",
+                3,
+                StructType::new([mapped("x", 2, DataType::INTEGER)]).into(),
+            ),
+        ]);
+        let configuration =
+            HashMap::from([(COLUMN_MAPPING_MAX_COLUMN_ID.to_string(), "5".to_string())]);
+        assert_eq!(max_column_id(&configuration, &current), 5);
+        assert_eq!(max_column_id(&HashMap::new(), &current), 3);
+
+        // existing fields keep their annotations even when given without them
+        let new = StructType::new([
+            StructField::nullable("id", DataType::INTEGER),
+            StructField::nullable(
+                "nested",
+                StructType::new([
+                    StructField::nullable("x", DataType::INTEGER),
+                    StructField::nullable("y", DataType::INTEGER),
+                ]),
+            ),
+            StructField::nullable(
+                "added",
+                ArrayType::new(
+                    StructType::new([StructField::nullable("z", DataType::INTEGER)]).into(),
+                    true,
+                ),
+            ),
+        ]);
+        let mut max_id = 5;
+        let evolved = evolve_schema(&current, &new, false, Some(&mut max_id)).unwrap();
+        assert_eq!(max_id, 8);
+
+        let id = |field: &StructField| {
+            field
+                .get_config_value(&ColumnMetadataKey::ColumnMappingId)
+                .cloned()
+        };
+        assert_eq!(evolved.field("id"), current.field("id"));
+        let DataType::Struct(nested) = evolved.field("nested").unwrap().data_type() else {
+            panic!("nested is not a struct");
+        };
+        assert_eq!(nested.field("x"), Some(&mapped("x", 2, DataType::INTEGER)));
+        assert_eq!(
+            id(nested.field("y").unwrap()),
+            Some(MetadataValue::Number(6))
+        );
+        let added = evolved.field("added").unwrap();
+        assert_eq!(id(added), Some(MetadataValue::Number(7)));
+        assert_ne!(added.physical_name(), "added");
+        let DataType::Array(array_type) = added.data_type() else {
+            panic!("added is not an array");
+        };
+        let DataType::Struct(element) = array_type.element_type() else {
+            panic!("the elements of added are not structs");
+        };
+        assert_eq!(
+            id(element.field("z").unwrap()),
+            Some(MetadataValue::Number(8))
+        );
+    }
+}
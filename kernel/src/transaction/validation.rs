@@ -0,0 +1,189 @@
+//! Checks of the data that a transaction writes, evaluated with the engine's
+//! [`ExpressionHandler`].
+//!
+//! [`ExpressionHandler`]: crate::ExpressionHandler
+
+use std::collections::HashMap;
+use std::sync::LazyLock;
+
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
+use crate::expressions::{
+    column_name, BinaryExpression, CastExpression, ColumnName, Expression, Scalar, UnaryExpression,
+    VariadicExpression,
+};
+use crate::schema::{ColumnNamesAndTypes, DataType, SchemaRef, StructField, StructType};
+use crate::{DeltaResult, Engine, EngineData, Error};
+
+const HOLDS_FIELD_NAME: &str = "holds";
+
+/// The values of the partition columns of the data written to a partition, parsed from the
+/// partition values that the data is written with. Missing partition values are nulls.
+pub(crate) fn partition_literals(
+    partition_fields: &[StructField],
+    partition_values: &HashMap<String, String>,
+) -> DeltaResult<HashMap<String, Scalar>> {
+    partition_fields
+        .iter()
+        .map(|field| {
+            let value = match (partition_values.get(field.name()), field.data_type()) {
+                (Some(value), DataType::Primitive(primitive)) => primitive.parse_scalar(value)?,
+                (None, data_type) => Scalar::Null(data_type.clone()),
+                (Some(_), data_type) => {
+                    return Err(Error::invalid_partition_column(format!(
+                        "{} has the non-primitive type {data_type}",
+                        field.name()
+                    )))
+                }
+            };
+            Ok((field.name().clone(), value))
+        })
+        .collect()
+}
+
+/// The expression with its references to the given (top-level) columns replaced by their values,
+/// literals or expressions. This lets the expressions over the logical columns of a table be
+/// evaluated on the data written to a partition, which doesn't have the partition columns.
+pub(crate) fn with_column_values<V: Clone + Into<Expression>>(
+    expr: &Expression,
+    values: &HashMap<String, V>,
+) -> Expression {
+    let recurse = |expr: &Expression| with_column_values(expr, values);
+    match expr {
+        Expression::Column(name) if name.len() == 1 && values.contains_key(&name[0]) => {
+            values[&name[0]].clone().into()
+        }
+        Expression::Literal(_) | Expression::Column(_) | Expression::RowIndex => expr.clone(),
+        Expression::Struct(exprs) => Expression::struct_from(exprs.iter().map(recurse)),
+        Expression::Unary(UnaryExpression { op, expr }) => Expression::unary(*op, recurse(expr)),
+        Expression::Binary(BinaryExpression { op, left, right }) => {
+            Expression::binary(*op, recurse(left), recurse(right))
+        }
+        Expression::Variadic(VariadicExpression { op, exprs }) => {
+            Expression::variadic(*op, exprs.iter().map(recurse))
+        }
+        Expression::Cast(CastExpression { expr, data_type }) => {
+            recurse(expr).cast(data_type.clone())
+        }
+    }
+}
+
+/// Whether `predicate` holds (is true) for every row of `data`, whose schema is `data_schema`.
+pub(crate) fn holds_for_all_rows(
+    engine: &dyn Engine,
+    data: &dyn EngineData,
+    data_schema: &SchemaRef,
+    predicate: Expression,
+) -> DeltaResult<bool> {
+    holds_for_selected_rows(engine, data, data_schema, predicate, &[])
+}
+
+/// Whether `predicate` holds (is true) for every row of `data` selected by `selection`. As with
+/// the masks of [`ScanResult`]s, the rows past the end of `selection` are selected.
+///
+/// [`ScanResult`]: crate::scan::ScanResult
+pub(crate) fn holds_for_selected_rows(
+    engine: &dyn Engine,
+    data: &dyn EngineData,
+    data_schema: &SchemaRef,
+    predicate: Expression,
+    selection: &[bool],
+) -> DeltaResult<bool> {
+    static OUTPUT_TYPE: LazyLock<DataType> = LazyLock::new(|| {
+        StructType::new([StructField::nullable(HOLDS_FIELD_NAME, DataType::BOOLEAN)]).into()
+    });
+    let evaluator = engine.get_expression_handler().get_evaluator(
+        data_schema.clone(),
+        Expression::struct_from([predicate]),
+        OUTPUT_TYPE.clone(),
+    );
+    let mut visitor = HoldsVisitor {
+        holds: true,
+        selection,
+        offset: 0,
+    };
+    visitor.visit_rows_of(evaluator.evaluate(data)?.as_ref())?;
+    Ok(visitor.holds)
+}
+
+// check whether the values of a boolean column are all true in the selected rows
+struct HoldsVisitor<'a> {
+    holds: bool,
+    selection: &'a [bool],
+    // the number of rows visited before the current batch of rows
+    offset: usize,
+}
+
+impl RowVisitor for HoldsVisitor<'_> {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> =
+            LazyLock::new(|| (vec![column_name!("holds")], vec![DataType::BOOLEAN]).into());
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        for row in 0..row_count {
+            if !self
+                .selection
+                .get(self.offset + row)
+                .copied()
+                .unwrap_or(true)
+            {
+                continue;
+            }
+            let holds: Option<bool> = getters[0].get_opt(row, HOLDS_FIELD_NAME)?;
+            self.holds &= holds == Some(true);
+        }
+        self.offset += row_count;
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "sync-engine"))]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::arrow::array::{Int32Array, RecordBatch};
+    use crate::arrow::datatypes::Schema as ArrowSchema;
+    use crate::engine::arrow_data::ArrowEngineData;
+    use crate::engine::sync::SyncEngine;
+    use crate::expressions::column_expr;
+
+    #[test]
+    fn test_holds_for_all_rows() {
+        let partition_fields = [StructField::nullable("part", DataType::INTEGER)];
+        let data_schema = Arc::new(StructType::new([StructField::nullable(
+            "value",
+            DataType::INTEGER,
+        )]));
+        let batch = RecordBatch::try_new(
+            Arc::new(ArrowSchema::try_from(data_schema.as_ref()).unwrap()),
+            vec![Arc::new(Int32Array::from(vec![Some(1), Some(2), None]))],
+        )
+        .unwrap();
+        let data = ArrowEngineData::new(batch);
+        let engine = SyncEngine::new();
+
+        let values = HashMap::from([("part".to_string(), "1".to_string())]);
+        let literals = partition_literals(&partition_fields, &values).unwrap();
+        assert_eq!(literals["part"], Scalar::Integer(1));
+        let predicate = column_expr!("value").ge(column_expr!("part"));
+        let predicate = with_column_values(&predicate, &literals);
+        assert_eq!(predicate, column_expr!("value").ge(Expression::literal(1)));
+        // nulls don't satisfy predicates
+        let holds = |predicate| holds_for_all_rows(&engine, &data, &data_schema, predicate);
+        assert!(!holds(predicate.clone()).unwrap());
+        assert!(holds(predicate.or(column_expr!("value").is_null())).unwrap());
+        assert!(!holds(column_expr!("value").lt(Expression::literal(2))).unwrap());
+
+        // only the selected rows must satisfy predicates
+        let predicate = column_expr!("value").lt(Expression::literal(2));
+        let holds = |selection| {
+            holds_for_selected_rows(&engine, &data, &data_schema, predicate.clone(), selection)
+                .unwrap()
+        };
+        assert!(holds(&[true, false, false]));
+        assert!(!holds(&[true, false]));
+        assert!(!holds(&[false, true, false]));
+    }
+}
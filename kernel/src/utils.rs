@@ -1,5 +1,9 @@
 //! Various utility functions/macros used throughout the kernel
 
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::{DeltaResult, Error};
+
 /// convenient way to return an error if a condition isn't true
 macro_rules! require {
     ( $cond:expr, $err:expr ) => {
@@ -11,6 +15,16 @@ macro_rules! require {
 
 pub(crate) use require;
 
+/// The current time in milliseconds since the Unix epoch
+pub(crate) fn current_time_ms() -> DeltaResult<i64> {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|_| Error::generic("time went backwards"))?
+        .as_millis()
+        .try_into()
+        .map_err(|_| Error::generic("milliseconds since unix_epoch exceeded i64 size"))
+}
+
 #[cfg(test)]
 pub(crate) mod test_utils {
     use crate::actions::get_log_schema;
@@ -0,0 +1,246 @@
+//! Planning of VACUUM operations, which delete the files in the table directory that the table no
+//! longer needs.
+//!
+//! A file can be deleted once it is not referenced by the table at its latest version, nor by any
+//! version that readers may still time travel to. Kernel only keeps the tombstones (`remove`
+//! actions) of the files removed within the deleted file retention duration of the table
+//! (`delta.deletedFileRetentionDuration`, one week by default), so a file is safe to delete when it
+//! is neither referenced by an `add` action nor by an unexpired `remove` action, and when it was
+//! last modified before the retention threshold, so that files of in-progress writes are kept too.
+//!
+//! [`Snapshot::plan_vacuum`] computes the files to delete, but doesn't delete them: a plan doubles
+//! as a dry run, and the engine deletes the files of the plan itself.
+//!
+//! [`Snapshot::plan_vacuum`]: crate::snapshot::Snapshot::plan_vacuum
+
+use std::collections::HashSet;
+use std::sync::LazyLock;
+
+use percent_encoding::percent_decode_str;
+use url::Url;
+
+use crate::actions::visitors::visit_deletion_vector_at;
+use crate::checkpoint::{minimum_file_retention_timestamp, replay_batches};
+use crate::engine_data::{GetData, RowVisitor, TypedGetData as _};
+use crate::log_segment::list_log_files;
+use crate::schema::{column_name, ColumnName, ColumnNamesAndTypes, DataType};
+use crate::snapshot::Snapshot;
+use crate::utils::require;
+use crate::{DeltaResult, Engine, Error, FileMeta};
+
+/// The files of a table that a VACUUM operation can delete. See the [module documentation] for
+/// which files are safe to delete.
+///
+/// [module documentation]: crate::vacuum
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VacuumPlan {
+    files: Vec<FileMeta>,
+}
+
+impl VacuumPlan {
+    /// The files to delete, sorted by location
+    pub fn files(&self) -> &[FileMeta] {
+        &self.files
+    }
+
+    /// The total size of the files to delete, in bytes
+    pub fn size_in_bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.size as u64).sum()
+    }
+}
+
+/// Plan a VACUUM of the table of the snapshot, which must be the latest version of the table.
+pub(crate) fn plan_vacuum(snapshot: &Snapshot, engine: &dyn Engine) -> DeltaResult<VacuumPlan> {
+    let table_configuration = snapshot.table_configuration();
+    // VACUUM deletes files, so tables with the vacuumProtocolCheck feature require the writer to
+    // support their protocol (reading the snapshot already checked read support)
+    if table_configuration.is_vacuum_protocol_check_supported() {
+        table_configuration.ensure_write_supported()?;
+    }
+    // the files added by newer versions aren't referenced by the snapshot
+    let fs_client = engine.get_file_system_client();
+    let log_root = &snapshot.log_segment().log_root;
+    for file in list_log_files(fs_client.as_ref(), log_root, snapshot.version() + 1, None)? {
+        if file?.is_commit() {
+            return Err(Error::generic(
+                "VACUUM must be planned on the latest version of the table",
+            ));
+        }
+    }
+    let retention_timestamp = minimum_file_retention_timestamp(snapshot.table_properties())?;
+
+    let table_root = snapshot.table_root();
+    let mut visitor = ReferencedFilesVisitor {
+        table_root,
+        selection_vector: vec![],
+        referenced_files: HashSet::new(),
+    };
+    for batch in replay_batches(
+        snapshot.log_segment(),
+        engine,
+        retention_timestamp,
+        None,
+        false,
+    )? {
+        let batch = batch?;
+        visitor.selection_vector = batch.selection_vector;
+        visitor.visit_rows_of(batch.actions.as_ref())?;
+    }
+
+    let partition_columns = partition_column_names(snapshot);
+    let mut files = vec![];
+    // listing the (directory-like) table root lists the files in all of its subdirectories too
+    for file in fs_client.list_from(table_root)? {
+        let file = file?;
+        let Some(relative_path) = file.location.path().strip_prefix(table_root.path()) else {
+            continue;
+        };
+        let hidden = relative_path
+            .split('/')
+            .any(|name| is_hidden(&percent_decode(name), &partition_columns));
+        if !hidden
+            && file.last_modified < retention_timestamp
+            && !visitor
+                .referenced_files
+                .contains(&percent_decode(file.location.path()))
+        {
+            files.push(file);
+        }
+    }
+    files.sort_by(|a, b| a.location.as_str().cmp(b.location.as_str()));
+    Ok(VacuumPlan { files })
+}
+
+// The names of the partition columns of the table, both logical and physical, since partition
+// directories are named after the physical names when column mapping is enabled
+fn partition_column_names(snapshot: &Snapshot) -> Vec<String> {
+    let schema = snapshot.schema();
+    snapshot
+        .metadata()
+        .partition_columns()
+        .iter()
+        .flat_map(|name| {
+            let physical_name = schema.field(name).map(|field| field.physical_name());
+            [Some(name.clone()), physical_name.map(str::to_string)]
+        })
+        .flatten()
+        .collect()
+}
+
+// Whether a file or directory of the table directory is hidden from VACUUM, i.e. whether its name
+// starts with `_` or `.`, except for the change data directory and partition directories, whose
+// files VACUUM deletes too. This keeps the Delta log and the files of other systems.
+fn is_hidden(name: &str, partition_columns: &[String]) -> bool {
+    (name.starts_with('_') || name.starts_with('.'))
+        && !name.starts_with("_change_data")
+        && !partition_columns
+            .iter()
+            .any(|column| name.starts_with(&format!("{column}=")))
+}
+
+// Decode the percent-encoded characters of a URL path, so that the paths of the log (which may
+// encode characters that the file system client doesn't) are compared with the listed paths
+fn percent_decode(path: &str) -> String {
+    percent_decode_str(path).decode_utf8_lossy().into_owned()
+}
+
+// Collects the (decoded) paths of the data and deletion vector files referenced by the selected
+// `add` and `remove` actions
+struct ReferencedFilesVisitor<'a> {
+    table_root: &'a Url,
+    selection_vector: Vec<bool>,
+    referenced_files: HashSet<String>,
+}
+
+impl ReferencedFilesVisitor<'_> {
+    fn visit_file<'a>(
+        &mut self,
+        row_index: usize,
+        path: &str,
+        dv_getters: &[&'a dyn GetData<'a>],
+    ) -> DeltaResult<()> {
+        let location = self.table_root.join(path)?;
+        self.referenced_files
+            .insert(percent_decode(location.path()));
+        if let Some(deletion_vector) = visit_deletion_vector_at(row_index, dv_getters)? {
+            if let Some(location) = deletion_vector.absolute_path(self.table_root)? {
+                self.referenced_files
+                    .insert(percent_decode(location.path()));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RowVisitor for ReferencedFilesVisitor<'_> {
+    fn selected_column_names_and_types(&self) -> (&'static [ColumnName], &'static [DataType]) {
+        static NAMES_AND_TYPES: LazyLock<ColumnNamesAndTypes> = LazyLock::new(|| {
+            const STRING: DataType = DataType::STRING;
+            const INTEGER: DataType = DataType::INTEGER;
+            const LONG: DataType = DataType::LONG;
+            let types_and_names = vec![
+                (STRING, column_name!("add.path")),
+                (STRING, column_name!("add.deletionVector.storageType")),
+                (STRING, column_name!("add.deletionVector.pathOrInlineDv")),
+                (INTEGER, column_name!("add.deletionVector.offset")),
+                (INTEGER, column_name!("add.deletionVector.sizeInBytes")),
+                (LONG, column_name!("add.deletionVector.cardinality")),
+                (STRING, column_name!("remove.path")),
+                (STRING, column_name!("remove.deletionVector.storageType")),
+                (STRING, column_name!("remove.deletionVector.pathOrInlineDv")),
+                (INTEGER, column_name!("remove.deletionVector.offset")),
+                (INTEGER, column_name!("remove.deletionVector.sizeInBytes")),
+                (LONG, column_name!("remove.deletionVector.cardinality")),
+            ];
+            let (types, names) = types_and_names.into_iter().unzip();
+            (names, types).into()
+        });
+        NAMES_AND_TYPES.as_ref()
+    }
+
+    fn visit<'a>(&mut self, row_count: usize, getters: &[&'a dyn GetData<'a>]) -> DeltaResult<()> {
+        require!(
+            getters.len() == 12,
+            Error::InternalError(format!(
+                "Wrong number of ReferencedFilesVisitor getters: {}",
+                getters.len()
+            ))
+        );
+        for i in 0..row_count {
+            if !self.selection_vector.get(i).copied().unwrap_or(false) {
+                continue;
+            }
+            if let Some(path) = getters[0].get_opt(i, "add.path")? {
+                let path: String = path;
+                self.visit_file(i, &path, &getters[1..6])?;
+            } else if let Some(path) = getters[6].get_opt(i, "remove.path")? {
+                let path: String = path;
+                self.visit_file(i, &path, &getters[7..12])?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_hidden() {
+        let partition_columns = ["letter".to_string()];
+        assert!(is_hidden("_delta_log", &partition_columns));
+        assert!(is_hidden(".part-0.parquet.crc", &partition_columns));
+        assert!(is_hidden("_other=a", &partition_columns));
+        assert!(!is_hidden("_change_data", &partition_columns));
+        assert!(!is_hidden("letter=a", &partition_columns));
+        assert!(!is_hidden("part-0.parquet", &partition_columns));
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("/a%20b/c%3Ad"), "/a b/c:d");
+        assert_eq!(percent_decode("/a%2"), "/a%2");
+        assert_eq!(percent_decode("/100%"), "/100%");
+    }
+}
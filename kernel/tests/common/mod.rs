@@ -4,7 +4,7 @@ use delta_kernel::arrow::util::pretty::pretty_format_batches;
 use itertools::Itertools;
 
 use crate::ArrowEngineData;
-use delta_kernel::scan::Scan;
+use delta_kernel::scan::{Scan, ScanResult};
 use delta_kernel::{DeltaResult, Engine, EngineData, Table};
 
 use std::sync::Arc;
@@ -87,7 +87,14 @@ pub(crate) fn test_read(
 // TODO (zach): this is listed as unused for acceptance crate
 #[allow(unused)]
 pub(crate) fn read_scan(scan: &Scan, engine: Arc<dyn Engine>) -> DeltaResult<Vec<RecordBatch>> {
-    let scan_results = scan.execute(engine)?;
+    read_scan_results(scan.execute(engine)?)
+}
+
+// read the data of scan results, without the rows masked by deletion vectors
+#[allow(unused)]
+pub(crate) fn read_scan_results(
+    scan_results: impl Iterator<Item = DeltaResult<ScanResult>>,
+) -> DeltaResult<Vec<RecordBatch>> {
     scan_results
         .map(|scan_result| -> DeltaResult<_> {
             let scan_result = scan_result?;
@@ -1,12 +1,18 @@
 use std::collections::HashMap;
+use std::num::NonZero;
 use std::sync::Arc;
 
 use delta_kernel::arrow::array::{
-    Int32Array, MapBuilder, MapFieldNames, StringArray, StringBuilder,
+    ArrayRef, AsArray, Int32Array, Int64Array, MapBuilder, MapFieldNames, StringArray,
+    StringBuilder,
+};
+use delta_kernel::arrow::compute::concat_batches;
+use delta_kernel::arrow::datatypes::{
+    DataType as ArrowDataType, Field, Int32Type, Int64Type, Schema as ArrowSchema,
 };
-use delta_kernel::arrow::datatypes::{DataType as ArrowDataType, Field, Schema as ArrowSchema};
 use delta_kernel::arrow::error::ArrowError;
 use delta_kernel::arrow::record_batch::RecordBatch;
+use futures::TryStreamExt;
 use itertools::Itertools;
 use object_store::local::LocalFileSystem;
 use object_store::memory::InMemory;
@@ -16,15 +22,29 @@ use serde_json::Deserializer;
 use serde_json::{json, to_vec};
 use url::Url;
 
+use delta_kernel::actions::deletion_vector::{DeletionVectorDescriptor, DeletionVectorWriter};
+use delta_kernel::checkpoint::V2CheckpointFormat;
+use delta_kernel::engine::arrow_clustering::cluster_record_batches;
 use delta_kernel::engine::arrow_data::ArrowEngineData;
 use delta_kernel::engine::default::executor::tokio::TokioBackgroundExecutor;
 use delta_kernel::engine::default::DefaultEngine;
-use delta_kernel::schema::{DataType, SchemaRef, StructField, StructType};
+use delta_kernel::expressions::{column_expr, column_name};
+use delta_kernel::history::TimestampSearchMode;
+use delta_kernel::optimize::{Clustering, SpaceFillingCurve};
+use delta_kernel::scan::state::{visit_scan_files, DvInfo, Stats};
+use delta_kernel::scan::Scan;
+use delta_kernel::schema::{DataType, MetadataValue, SchemaRef, StructField, StructType};
+use delta_kernel::table_features::{ReaderFeature, WriterFeature};
+use delta_kernel::transaction::{CommitResult, ConflictError, Transaction};
 use delta_kernel::Error as KernelError;
-use delta_kernel::{DeltaResult, Table};
+use delta_kernel::{
+    DeltaResult, Engine, Expression, ExpressionHandler, ExpressionRef, FileDataReadResultIterator,
+    FileMeta, FileSystemClient, FilteredEngineData, JsonHandler, ParquetHandler, Table,
+};
+use roaring::RoaringTreemap;
 
 mod common;
-use common::test_read;
+use common::{read_scan_results, test_read};
 
 // setup default engine with in-memory (=true) or local fs (=false) object store.
 fn setup(
@@ -237,7 +257,8 @@ async fn test_commit_info() -> Result<(), Box<dyn std::error::Error>> {
                 "operationParameters": {},
                 "engineCommitInfo": {
                     "engineInfo": "default engine"
-                }
+                },
+                "isBlindAppend": true
             }
         });
 
@@ -461,7 +482,8 @@ async fn test_append() -> Result<(), Box<dyn std::error::Error>> {
                     "operationParameters": {},
                     "engineCommitInfo": {
                         "engineInfo": "default engine"
-                    }
+                    },
+                    "isBlindAppend": true
                 }
             }),
             json!({
@@ -599,7 +621,8 @@ async fn test_append_partitioned() -> Result<(), Box<dyn std::error::Error>> {
                     "operationParameters": {},
                     "engineCommitInfo": {
                         "engineInfo": "default engine"
-                    }
+                    },
+                    "isBlindAppend": true
                 }
             }),
             json!({
@@ -704,3 +727,2657 @@ async fn test_append_invalid_schema() -> Result<(), Box<dyn std::error::Error>>
     }
     Ok(())
 }
+
+#[tokio::test]
+async fn test_remove_files() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+
+    let partition_col = "partition";
+
+    // create a simple partitioned table: one int column named 'number', partitioned by string
+    // column named 'partition'
+    let table_schema = Arc::new(StructType::new(vec![
+        StructField::nullable("number", DataType::INTEGER),
+        StructField::nullable("partition", DataType::STRING),
+    ]));
+    let data_schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+
+    for (table, engine, store, table_name) in
+        setup_tables(table_schema.clone(), &[partition_col]).await?
+    {
+        // append one file to each of the partitions 'a' and 'b'
+        let mut txn = table
+            .new_transaction(&engine)?
+            .with_commit_info(new_commit_info()?);
+        let write_context = txn.get_write_context();
+        for (data, partition_val) in [([1, 2, 3], "a"), ([4, 5, 6], "b")] {
+            let data = RecordBatch::try_new(
+                Arc::new(data_schema.as_ref().try_into()?),
+                vec![Arc::new(Int32Array::from(data.to_vec()))],
+            )?;
+            let write_metadata = engine
+                .write_parquet(
+                    &ArrowEngineData::new(data),
+                    &write_context,
+                    HashMap::from([(partition_col.to_string(), partition_val.to_string())]),
+                    true,
+                )
+                .await?;
+            txn.add_write_metadata(write_metadata);
+        }
+        txn.commit(&engine)?;
+
+        // scan for the files in partition 'a' and remove them
+        let predicate = Expression::column([partition_col]).eq(Expression::literal("a"));
+        let scan = table
+            .snapshot(&engine, None)?
+            .into_scan_builder()
+            .with_predicate(Arc::new(predicate))
+            .build()?;
+        let mut txn = table
+            .new_transaction(&engine)?
+            .with_commit_info(new_commit_info()?);
+        for scan_data in scan.scan_data(&engine)? {
+            let (data, selection_vector, _) = scan_data?;
+            txn.remove_files(FilteredEngineData::try_new(data, selection_vector)?);
+        }
+        txn.commit(&engine)?;
+
+        let commit2 = store
+            .get(&Path::from(format!(
+                "/{table_name}/_delta_log/00000000000000000002.json"
+            )))
+            .await?;
+
+        let mut parsed_commits: Vec<_> = Deserializer::from_slice(&commit2.bytes().await?)
+            .into_iter::<serde_json::Value>()
+            .try_collect()?;
+        assert_eq!(parsed_commits.len(), 2);
+
+        // the deletion timestamp is the commit timestamp
+        let commit_timestamp = parsed_commits[0].pointer("/commitInfo/timestamp").cloned();
+        assert!(commit_timestamp.is_some());
+        assert_eq!(
+            parsed_commits[1]
+                .pointer("/remove/deletionTimestamp")
+                .cloned(),
+            commit_timestamp
+        );
+        let path = parsed_commits[1]
+            .pointer("/remove/path")
+            .and_then(|path| path.as_str())
+            .expect("remove action should have a path");
+        assert!(path.ends_with(".parquet"));
+
+        let size =
+            get_and_check_all_parquet_sizes(store.clone(), format!("/{table_name}/").as_str())
+                .await;
+        set_value(&mut parsed_commits[0], "commitInfo.timestamp", json!(0))?;
+        set_value(&mut parsed_commits[1], "remove.deletionTimestamp", json!(0))?;
+        set_value(
+            &mut parsed_commits[1],
+            "remove.path",
+            json!("first.parquet"),
+        )?;
+
+        let expected_commit = vec![
+            json!({
+                "commitInfo": {
+                    "timestamp": 0,
+                    "operation": "UNKNOWN",
+                    "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                    "operationParameters": {},
+                    "engineCommitInfo": {
+                        "engineInfo": "default engine"
+                    },
+                    "isBlindAppend": false
+                }
+            }),
+            json!({
+                "remove": {
+                    "path": "first.parquet",
+                    "deletionTimestamp": 0,
+                    "dataChange": true,
+                    "extendedFileMetadata": true,
+                    "partitionValues": {
+                        "partition": "a"
+                    },
+                    "size": size
+                }
+            }),
+        ];
+
+        assert_eq!(parsed_commits, expected_commit);
+
+        test_read(
+            &ArrowEngineData::new(RecordBatch::try_new(
+                Arc::new(table_schema.as_ref().try_into()?),
+                vec![
+                    Arc::new(Int32Array::from(vec![4, 5, 6])),
+                    Arc::new(StringArray::from(vec!["b", "b", "b"])),
+                ],
+            )?),
+            &table,
+            Arc::new(engine),
+        )?;
+    }
+    Ok(())
+}
+
+// collect the path and DV info of all the files in the scan
+fn scan_files(
+    scan: &Scan,
+    engine: &dyn Engine,
+) -> Result<Vec<(String, DvInfo)>, Box<dyn std::error::Error>> {
+    fn callback(
+        files: &mut Vec<(String, DvInfo)>,
+        path: &str,
+        _: i64,
+        _: Option<Stats>,
+        dv_info: DvInfo,
+        _: Option<delta_kernel::ExpressionRef>,
+        _: HashMap<String, String>,
+    ) {
+        files.push((path.to_string(), dv_info));
+    }
+    let mut files = vec![];
+    for scan_data in scan.scan_data(engine)? {
+        let (data, selection_vector, transforms) = scan_data?;
+        files = visit_scan_files(
+            data.as_ref(),
+            &selection_vector,
+            &transforms,
+            files,
+            callback,
+        )?;
+    }
+    Ok(files)
+}
+
+// delete `deleted_rows` from the single file of the table by committing `new_dv`, which is built
+// from the union of the rows already deleted from the file and `deleted_rows`
+fn delete_rows(
+    table: &Table,
+    engine: &DefaultEngine<TokioBackgroundExecutor>,
+    deleted_rows: RoaringTreemap,
+    new_dv: impl Fn(&RoaringTreemap) -> DeltaResult<DeletionVectorDescriptor>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let scan = table.snapshot(engine, None)?.into_scan_builder().build()?;
+    let files = scan_files(&scan, engine)?;
+    assert_eq!(files.len(), 1);
+    let (path, dv_info) = &files[0];
+    let deleted_rows = dv_info.merge_deleted_rows(engine, table.location(), deleted_rows)?;
+
+    let mut txn = table
+        .new_transaction(engine)?
+        .with_commit_info(new_commit_info()?);
+    let existing_data_files = scan.scan_data(engine)?.map(|scan_data| {
+        let (data, selection_vector, _) = scan_data?;
+        FilteredEngineData::try_new(data, selection_vector)
+    });
+    txn.update_deletion_vectors(
+        HashMap::from([(path.clone(), new_dv(&deleted_rows)?)]),
+        existing_data_files,
+    )?;
+    txn.commit(engine)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_deletion_vectors() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    // create a simple table that supports deletion vectors: one int column named 'number'
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+    let (store, engine, table_location) = setup("test_table_dv", true);
+    let protocol = json!({
+        "protocol": {
+            "minReaderVersion": 3,
+            "minWriterVersion": 7,
+            "readerFeatures": ["deletionVectors"],
+            "writerFeatures": ["deletionVectors"]
+        }
+    });
+    let metadata = json!({
+        "metaData": {
+            "id": "test_id",
+            "format": {
+                "provider": "parquet",
+                "options": {}
+            },
+            "schemaString": serde_json::to_string(&schema)?,
+            "partitionColumns": [],
+            "configuration": {
+                "delta.enableDeletionVectors": "true"
+            },
+            "createdTime": 1677811175819u64
+        }
+    });
+    let data = [to_vec(&protocol)?, b"\n".to_vec(), to_vec(&metadata)?].concat();
+    store
+        .put(
+            &Path::from("/test_table_dv/_delta_log/00000000000000000000.json"),
+            data.into(),
+        )
+        .await?;
+    let table = Table::new(table_location);
+
+    // append a single file
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    let data = RecordBatch::try_new(
+        Arc::new(schema.as_ref().try_into()?),
+        vec![Arc::new(Int32Array::from(vec![1, 2, 3, 4, 5, 6]))],
+    )?;
+    let write_metadata = engine
+        .write_parquet(
+            &ArrowEngineData::new(data),
+            &txn.get_write_context(),
+            HashMap::new(),
+            true,
+        )
+        .await?;
+    // give the file statistics with tight bounds, like Spark does
+    let write_metadata = ArrowEngineData::try_from_engine_data(write_metadata)?;
+    let batch = write_metadata.record_batch();
+    let stats_index = batch.schema().index_of("stats")?;
+    let tight_stats = json!({
+        "numRecords": 6,
+        "minValues": {"number": 1},
+        "maxValues": {"number": 6},
+        "nullCount": {"number": 0},
+        "tightBounds": true
+    });
+    let mut columns = batch.columns().to_vec();
+    columns[stats_index] = Arc::new(StringArray::from(vec![tight_stats.to_string()]));
+    let write_metadata = RecordBatch::try_new(batch.schema(), columns)?;
+    txn.add_write_metadata(Box::new(ArrowEngineData::new(write_metadata)));
+    txn.commit(&engine)?;
+
+    // delete rows 1 and 3 with a DV stored in a file next to the data
+    delete_rows(
+        &table,
+        &engine,
+        RoaringTreemap::from_iter([1, 3]),
+        |deleted_rows| {
+            let mut writer =
+                DeletionVectorWriter::try_new_relative(table.location(), uuid::Uuid::new_v4(), "")?;
+            let dv = writer.add(deleted_rows)?;
+            writer.write(engine.get_file_system_client().as_ref())?;
+            Ok(dv)
+        },
+    )?;
+    let engine = Arc::new(engine);
+    test_read(
+        &ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int32Array::from(vec![1, 3, 5, 6]))],
+        )?),
+        &table,
+        engine.clone(),
+    )?;
+
+    // additionally delete row 0, merging with the existing DV into an inline DV
+    delete_rows(
+        &table,
+        &engine,
+        RoaringTreemap::from_iter([0]),
+        DeletionVectorDescriptor::try_new_inline,
+    )?;
+    test_read(
+        &ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int32Array::from(vec![3, 5, 6]))],
+        )?),
+        &table,
+        engine,
+    )?;
+
+    // the last commit replaces the file's DV: the remove carries the old one, the add the new one
+    let commit3 = store
+        .get(&Path::from(
+            "/test_table_dv/_delta_log/00000000000000000003.json",
+        ))
+        .await?;
+    let parsed_commits: Vec<_> = Deserializer::from_slice(&commit3.bytes().await?)
+        .into_iter::<serde_json::Value>()
+        .try_collect()?;
+    assert_eq!(parsed_commits.len(), 3);
+    let remove = &parsed_commits[1]["remove"];
+    let add = &parsed_commits[2]["add"];
+    assert_eq!(remove["path"], add["path"]);
+    assert_eq!(remove["dataChange"], json!(true));
+    assert_eq!(add["dataChange"], json!(true));
+    assert_eq!(remove["deletionVector"]["storageType"], json!("u"));
+    assert_eq!(remove["deletionVector"]["offset"], json!(1));
+    assert_eq!(remove["deletionVector"]["cardinality"], json!(2));
+    assert_eq!(add["deletionVector"]["storageType"], json!("i"));
+    assert_eq!(add["deletionVector"]["cardinality"], json!(3));
+    assert!(add["deletionVector"].get("offset").is_none());
+    // the deleted rows may hold the bounds of the statistics, which are no longer tight
+    let stats: serde_json::Value =
+        serde_json::from_str(add["stats"].as_str().ok_or("missing stats")?)?;
+    assert_eq!(
+        stats,
+        json!({
+            "numRecords": 6,
+            "minValues": {"number": 1},
+            "maxValues": {"number": 6},
+            "nullCount": {"number": 0},
+            "tightBounds": false
+        })
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_update_deletion_vectors_of_several_files() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+    let (store, engine, table_location) = setup("test_table_dv_several_files", true);
+    let engine = Arc::new(engine);
+    let table = Table::create(table_location, schema.clone())
+        .with_table_properties([("delta.enableDeletionVectors", "true")])
+        .with_commit_info(new_commit_info()?)
+        .commit(engine.as_ref())?;
+
+    // append three files in a single commit, so their scan rows are in the same batch
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    for values in [vec![1, 2, 3], vec![4, 5, 6], vec![7, 8, 9]] {
+        let data = RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int32Array::from(values))],
+        )?;
+        let write_metadata = engine
+            .write_parquet(
+                &ArrowEngineData::new(data),
+                &txn.get_write_context(),
+                HashMap::new(),
+                true,
+            )
+            .await?;
+        txn.add_write_metadata(write_metadata);
+    }
+    txn.commit(engine.as_ref())?;
+
+    // delete the first row of two of the files with a single update, whose new DVs are appended
+    // to the same batch of scan rows
+    let read_values = || -> DeltaResult<Vec<i32>> {
+        let scan = table
+            .snapshot(engine.as_ref(), None)?
+            .into_scan_builder()
+            .build()?;
+        let batches = common::read_scan(&scan, engine.clone())?;
+        let values = batches.iter().flat_map(|batch| {
+            batch
+                .column(0)
+                .as_primitive::<Int32Type>()
+                .values()
+                .to_vec()
+        });
+        Ok(values.sorted().collect())
+    };
+    assert_eq!(read_values()?, (1..=9).collect_vec());
+    let scan = table
+        .snapshot(engine.as_ref(), None)?
+        .into_scan_builder()
+        .build()?;
+    let files = scan_files(&scan, engine.as_ref())?;
+    assert_eq!(files.len(), 3);
+    let new_dvs: HashMap<_, _> = files[..2]
+        .iter()
+        .map(|(path, _)| {
+            let dv = DeletionVectorDescriptor::try_new_inline(&RoaringTreemap::from_iter([0]))?;
+            Ok::<_, KernelError>((path.clone(), dv))
+        })
+        .try_collect()?;
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    let existing_data_files = scan.scan_data(engine.as_ref())?.map(|scan_data| {
+        let (data, selection_vector, _) = scan_data?;
+        FilteredEngineData::try_new(data, selection_vector)
+    });
+    txn.update_deletion_vectors(new_dvs.clone(), existing_data_files)?;
+    txn.commit(engine.as_ref())?;
+
+    // exactly the first rows of the two updated files are deleted
+    let values = read_values()?;
+    assert_eq!(values.len(), 7);
+    assert!([2, 3, 5, 6, 8, 9].iter().all(|v| values.contains(v)));
+
+    // the commit removes the two files and adds them back with their new DVs
+    let commit2 = store
+        .get(&Path::from(
+            "/test_table_dv_several_files/_delta_log/00000000000000000002.json",
+        ))
+        .await?;
+    let parsed_commits: Vec<serde_json::Value> = Deserializer::from_slice(&commit2.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    let removes = parsed_commits
+        .iter()
+        .filter(|action| action.get("remove").is_some());
+    assert_eq!(removes.count(), 2);
+    let adds: Vec<_> = parsed_commits
+        .iter()
+        .filter_map(|action| action.get("add"))
+        .collect();
+    assert_eq!(adds.len(), 2);
+    for add in adds {
+        let path = add["path"].as_str().unwrap();
+        let dv = &new_dvs[path];
+        assert_eq!(add["deletionVector"]["storageType"], json!("i"));
+        assert_eq!(
+            add["deletionVector"]["pathOrInlineDv"],
+            json!(dv.path_or_inline_dv)
+        );
+        assert_eq!(add["deletionVector"]["cardinality"], json!(1));
+    }
+    Ok(())
+}
+
+// start a transaction that appends a single file with the given values to the table
+async fn append_transaction(
+    table: &Table,
+    engine: &DefaultEngine<TokioBackgroundExecutor>,
+    schema: &SchemaRef,
+    values: Vec<i32>,
+) -> Result<Transaction, Box<dyn std::error::Error>> {
+    let mut txn = table
+        .new_transaction(engine)?
+        .with_commit_info(new_commit_info()?);
+    let data = RecordBatch::try_new(
+        Arc::new(schema.as_ref().try_into()?),
+        vec![Arc::new(Int32Array::from(values))],
+    )?;
+    let write_metadata = engine
+        .write_parquet(
+            &ArrowEngineData::new(data),
+            &txn.get_write_context(),
+            HashMap::new(),
+            true,
+        )
+        .await?;
+    txn.add_write_metadata(write_metadata);
+    Ok(txn)
+}
+
+// start a transaction that removes all the files of the table
+fn remove_all_transaction(
+    table: &Table,
+    engine: &DefaultEngine<TokioBackgroundExecutor>,
+) -> Result<Transaction, Box<dyn std::error::Error>> {
+    let scan = table.snapshot(engine, None)?.into_scan_builder().build()?;
+    let mut txn = table
+        .new_transaction(engine)?
+        .with_commit_info(new_commit_info()?);
+    for scan_data in scan.scan_data(engine)? {
+        let (data, selection_vector, _) = scan_data?;
+        txn.remove_files(FilteredEngineData::try_new(data, selection_vector)?);
+    }
+    Ok(txn)
+}
+
+#[tokio::test]
+async fn test_concurrent_commits() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    // create a simple table: one int column named 'number'
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+
+    for (table, engine, _, _) in setup_tables(schema.clone(), &[]).await? {
+        // two concurrent blind appends: the second one is rebased and committed at version 2
+        let txn1 = append_transaction(&table, &engine, &schema, vec![1, 2, 3]).await?;
+        let txn2 = append_transaction(&table, &engine, &schema, vec![4, 5, 6]).await?;
+        assert!(matches!(txn1.commit(&engine)?, CommitResult::Committed(1)));
+        assert!(matches!(txn2.commit(&engine)?, CommitResult::Committed(2)));
+
+        // a delete doesn't conflict with a concurrent blind append under the default
+        // WriteSerializable isolation level, and only removes the files it read
+        let delete = remove_all_transaction(&table, &engine)?;
+        let append = append_transaction(&table, &engine, &schema, vec![7, 8, 9]).await?;
+        assert!(matches!(
+            append.commit(&engine)?,
+            CommitResult::Committed(3)
+        ));
+        assert!(matches!(
+            delete.commit(&engine)?,
+            CommitResult::Committed(4)
+        ));
+
+        // two deletes of the same file conflict
+        let delete1 = remove_all_transaction(&table, &engine)?;
+        let delete2 = remove_all_transaction(&table, &engine)?;
+        assert!(matches!(
+            delete1.commit(&engine)?,
+            CommitResult::Committed(5)
+        ));
+        assert!(matches!(
+            delete2.commit(&engine),
+            Err(KernelError::TransactionConflict(
+                ConflictError::ConcurrentDeleteDelete { version: 5, .. }
+            ))
+        ));
+
+        // appends that read the table conflict with each other
+        let txn1 = append_transaction(&table, &engine, &schema, vec![1])
+            .await?
+            .with_read_whole_table();
+        let txn2 = append_transaction(&table, &engine, &schema, vec![2])
+            .await?
+            .with_read_whole_table();
+        assert!(matches!(txn1.commit(&engine)?, CommitResult::Committed(6)));
+        assert!(matches!(
+            txn2.commit(&engine),
+            Err(KernelError::TransactionConflict(
+                ConflictError::ConcurrentAppend(6)
+            ))
+        ));
+
+        // a transaction that runs out of attempts is returned to be committed again
+        let txn1 = table
+            .new_transaction(&engine)?
+            .with_commit_info(new_commit_info()?);
+        let txn2 = table
+            .new_transaction(&engine)?
+            .with_commit_info(new_commit_info()?)
+            .with_max_commit_attempts(NonZero::new(1).unwrap());
+        assert!(matches!(txn1.commit(&engine)?, CommitResult::Committed(7)));
+        let CommitResult::Conflict(txn2, 7) = txn2.commit(&engine)? else {
+            panic!("expected the commit to run out of attempts at version 7");
+        };
+        assert!(matches!(txn2.commit(&engine)?, CommitResult::Committed(8)));
+
+        test_read(
+            &ArrowEngineData::new(RecordBatch::try_new(
+                Arc::new(schema.as_ref().try_into()?),
+                vec![Arc::new(Int32Array::from(vec![1]))],
+            )?),
+            &table,
+            Arc::new(engine),
+        )?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_table() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![
+        StructField::nullable("number", DataType::INTEGER),
+        StructField::nullable("partition", DataType::STRING),
+    ]));
+    let (store, engine, table_location) = setup("test_table_create", true);
+
+    let table = Table::create(table_location.clone(), schema.clone())
+        .with_partition_columns(["partition"])
+        .with_table_properties([("delta.appendOnly", "true")])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+
+    let commit0 = store
+        .get(&Path::from(
+            "/test_table_create/_delta_log/00000000000000000000.json",
+        ))
+        .await?;
+    let mut parsed_commits: Vec<_> = Deserializer::from_slice(&commit0.bytes().await?)
+        .into_iter::<serde_json::Value>()
+        .try_collect()?;
+    set_value(&mut parsed_commits[0], "commitInfo.timestamp", json!(0))?;
+    let created_time = parsed_commits[2].pointer("/metaData/createdTime").cloned();
+    assert!(created_time.is_some());
+    set_value(&mut parsed_commits[2], "metaData.createdTime", json!(0))?;
+    let table_id = parsed_commits[2].pointer("/metaData/id").cloned();
+    assert!(table_id.is_some_and(|id| id.is_string()));
+    set_value(&mut parsed_commits[2], "metaData.id", json!("id"))?;
+
+    let expected_commit = vec![
+        json!({
+            "commitInfo": {
+                "timestamp": 0,
+                "operation": "CREATE TABLE",
+                "kernelVersion": format!("v{}", env!("CARGO_PKG_VERSION")),
+                "operationParameters": {},
+                "engineCommitInfo": {
+                    "engineInfo": "default engine"
+                },
+                "isBlindAppend": true
+            }
+        }),
+        json!({
+            "protocol": {
+                "minReaderVersion": 1,
+                "minWriterVersion": 7,
+                "writerFeatures": ["appendOnly"]
+            }
+        }),
+        json!({
+            "metaData": {
+                "id": "id",
+                "format": {
+                    "provider": "parquet",
+                    "options": {}
+                },
+                "schemaString": serde_json::to_string(&schema)?,
+                "partitionColumns": ["partition"],
+                "createdTime": 0,
+                "configuration": {
+                    "delta.appendOnly": "true"
+                }
+            }
+        }),
+    ];
+    assert_eq!(parsed_commits, expected_commit);
+
+    // the new table can be read and written
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(snapshot.version(), 0);
+    assert_eq!(snapshot.schema(), schema);
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    let data_schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+    let data = RecordBatch::try_new(
+        Arc::new(data_schema.as_ref().try_into()?),
+        vec![Arc::new(Int32Array::from(vec![1, 2, 3]))],
+    )?;
+    let write_metadata = engine
+        .write_parquet(
+            &ArrowEngineData::new(data),
+            &txn.get_write_context(),
+            HashMap::from([("partition".to_string(), "a".to_string())]),
+            true,
+        )
+        .await?;
+    txn.add_write_metadata(write_metadata);
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(1)));
+
+    // a table can't be created twice
+    let result = Table::create(table_location, schema.clone())
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine);
+    assert!(matches!(result, Err(KernelError::TableAlreadyExists(_))));
+
+    // kernel can't write to tables with change data feed enabled, so it doesn't create them
+    let (_, cdf_engine, cdf_location) = setup("test_table_create_cdf", true);
+    let result = Table::create(cdf_location, schema.clone())
+        .with_table_properties([("delta.enableChangeDataFeed", "true")])
+        .with_commit_info(new_commit_info()?)
+        .commit(&cdf_engine);
+    assert!(matches!(result, Err(KernelError::InvalidTransaction(_))));
+
+    test_read(
+        &ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![
+                Arc::new(Int32Array::from(vec![1, 2, 3])),
+                Arc::new(StringArray::from(vec!["a", "a", "a"])),
+            ],
+        )?),
+        &table,
+        Arc::new(engine),
+    )?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_create_table_timestamp_ntz() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![
+        StructField::nullable("number", DataType::INTEGER),
+        StructField::nullable(
+            "nested",
+            StructType::new(vec![StructField::nullable("ts", DataType::TIMESTAMP_NTZ)]),
+        ),
+    ]));
+    let (store, engine, table_location) = setup("test_table_create_ntz", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+
+    // the timestamp_ntz column requires the timestampNtz reader and writer feature
+    let commit0 = store
+        .get(&Path::from(
+            "/test_table_create_ntz/_delta_log/00000000000000000000.json",
+        ))
+        .await?;
+    let parsed_commits: Vec<serde_json::Value> = Deserializer::from_slice(&commit0.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(
+        parsed_commits[1],
+        json!({
+            "protocol": {
+                "minReaderVersion": 3,
+                "minWriterVersion": 7,
+                "readerFeatures": ["timestampNtz"],
+                "writerFeatures": ["timestampNtz"]
+            }
+        })
+    );
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(snapshot.schema(), schema);
+    assert!(table.new_transaction(&engine).is_ok());
+
+    // kernel doesn't create tables with writer features that it can't write
+    let (_, cdf_engine, cdf_location) = setup("test_table_create_ntz_cdf", true);
+    let result = Table::create(cdf_location, schema)
+        .with_writer_features([WriterFeature::ChangeDataFeed])
+        .with_commit_info(new_commit_info()?)
+        .commit(&cdf_engine);
+    assert!(matches!(result, Err(KernelError::Unsupported(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schema_evolution() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+    let (store, engine, table_location) = setup("test_schema_evolution", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_table_properties([("delta.enableTypeWidening", "true")])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+
+    // write a file with the original schema
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    let data = RecordBatch::try_new(
+        Arc::new(schema.as_ref().try_into()?),
+        vec![Arc::new(Int32Array::from(vec![1, 2]))],
+    )?;
+    let write_metadata = engine
+        .write_parquet(
+            &ArrowEngineData::new(data),
+            &txn.get_write_context(),
+            HashMap::new(),
+            true,
+        )
+        .await?;
+    txn.add_write_metadata(write_metadata);
+    txn.commit(&engine)?;
+
+    // widen `number`, add a column and set a description, then write a file with the new schema
+    let new_schema = Arc::new(StructType::new(vec![
+        StructField::nullable("number", DataType::LONG),
+        StructField::nullable("name", DataType::STRING),
+    ]));
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.update_schema(new_schema.clone())?;
+    txn.set_description(Some("evolved".to_string()))?;
+    // dropping a column or adding a non-nullable one is not allowed
+    let result = txn.update_schema(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::LONG,
+    )]));
+    assert!(matches!(result, Err(KernelError::InvalidTransaction(_))));
+    let result = txn.add_columns([StructField::not_null("id", DataType::LONG)]);
+    assert!(matches!(result, Err(KernelError::InvalidTransaction(_))));
+
+    let write_context = txn.get_write_context();
+    assert_eq!(write_context.schema().fields().count(), 2);
+    let data = RecordBatch::try_new(
+        Arc::new(new_schema.as_ref().try_into()?),
+        vec![
+            Arc::new(Int64Array::from(vec![3])),
+            Arc::new(StringArray::from(vec!["three"])),
+        ],
+    )?;
+    let write_metadata = engine
+        .write_parquet(
+            &ArrowEngineData::new(data),
+            &write_context,
+            HashMap::new(),
+            true,
+        )
+        .await?;
+    txn.add_write_metadata(write_metadata);
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(2)));
+
+    // the commit contains the new metadata, with the type change recorded on `number`
+    let commit2 = store
+        .get(&Path::from(
+            "/test_schema_evolution/_delta_log/00000000000000000002.json",
+        ))
+        .await?;
+    let parsed_commits: Vec<serde_json::Value> = Deserializer::from_slice(&commit2.bytes().await?)
+        .into_iter::<serde_json::Value>()
+        .try_collect()?;
+    let metadata = &parsed_commits[1]["metaData"];
+    assert_eq!(metadata["description"], "evolved");
+    let schema_string = metadata["schemaString"].as_str().unwrap();
+    let committed_schema: serde_json::Value = serde_json::from_str(schema_string)?;
+    assert_eq!(
+        committed_schema["fields"][0]["metadata"],
+        json!({"delta.typeChanges": [{"toType": "long", "fromType": "integer"}]})
+    );
+
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(snapshot.schema().fields().count(), 2);
+    test_read(
+        &ArrowEngineData::new(RecordBatch::try_new(
+            Arc::new(new_schema.as_ref().try_into()?),
+            vec![
+                Arc::new(Int64Array::from(vec![3, 1, 2])),
+                Arc::new(StringArray::from(vec![Some("three"), None, None])),
+            ],
+        )?),
+        &table,
+        Arc::new(engine),
+    )?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_schema_evolution_timestamp_ntz() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "date",
+        DataType::DATE,
+    )]));
+    let add_ntz_column = |txn: &mut Transaction| {
+        txn.add_columns([StructField::nullable("ts", DataType::TIMESTAMP_NTZ)])
+    };
+    let widen_to_ntz = |txn: &mut Transaction| {
+        txn.update_schema(StructType::new(vec![StructField::nullable(
+            "date",
+            DataType::TIMESTAMP_NTZ,
+        )]))
+    };
+
+    // kernel doesn't upgrade the protocol, so without the timestampNtz feature neither adding a
+    // timestamp_ntz column nor widening a column to timestamp_ntz is allowed
+    let (_, engine, table_location) = setup("test_schema_evolution_ntz", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_table_properties([("delta.enableTypeWidening", "true")])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    for change in [add_ntz_column, widen_to_ntz] {
+        let result = change(&mut txn);
+        assert!(matches!(result, Err(KernelError::InvalidTransaction(_))));
+    }
+
+    // both are allowed on tables that support the feature
+    let (_, engine, table_location) = setup("test_schema_evolution_ntz_supported", true);
+    let table = Table::create(table_location, schema)
+        .with_table_properties([("delta.enableTypeWidening", "true")])
+        .with_reader_features([ReaderFeature::TimestampWithoutTimezone])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    for change in [widen_to_ntz, add_ntz_column] {
+        let mut txn = table
+            .new_transaction(&engine)?
+            .with_commit_info(new_commit_info()?);
+        change(&mut txn)?;
+        assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(_)));
+    }
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(
+        snapshot.schema().field("date").unwrap().data_type(),
+        &DataType::TIMESTAMP_NTZ
+    );
+    assert!(snapshot.schema().field("ts").is_some());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_set_table_properties() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+    for (table, engine, _store, _table_name) in setup_tables(schema, &[]).await? {
+        let mut txn = table
+            .new_transaction(&engine)?
+            .with_commit_info(new_commit_info()?);
+
+        // the protocol has to support the features enabled by the properties, and the column
+        // mapping mode can't be changed
+        for (key, value) in [
+            ("delta.appendOnly", "true"),
+            ("delta.enableChangeDataFeed", "true"),
+            ("delta.columnMapping.mode", "name"),
+        ] {
+            let result = txn.set_table_properties([(key, value)]);
+            assert!(matches!(result, Err(KernelError::InvalidTransaction(_))));
+        }
+
+        txn.set_table_properties([("delta.checkpointInterval", "10"), ("custom.key", "value")])?;
+        txn.unset_table_properties(["custom.key"])?;
+        txn.commit(&engine)?;
+
+        let snapshot = table.snapshot(&engine, None)?;
+        let table_properties = snapshot.table_properties();
+        assert_eq!(table_properties.checkpoint_interval, NonZero::new(10));
+        assert!(table_properties.unknown_properties.is_empty());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transaction_id() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+
+    for (table, engine, store, table_name) in setup_tables(schema.clone(), &[]).await? {
+        let snapshot = table.snapshot(&engine, None)?;
+        assert_eq!(snapshot.get_app_id_version("my-app", &engine)?, None);
+
+        let txn = append_transaction(&table, &engine, &schema, vec![1, 2, 3])
+            .await?
+            .with_transaction_id("my-app", 1);
+        assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(1)));
+
+        let commit1 = store
+            .get(&Path::from(format!(
+                "/{table_name}/_delta_log/00000000000000000001.json"
+            )))
+            .await?;
+        let parsed_commits: Vec<serde_json::Value> =
+            Deserializer::from_slice(&commit1.bytes().await?)
+                .into_iter::<serde_json::Value>()
+                .try_collect()?;
+        let txn_action = &parsed_commits[1]["txn"];
+        assert_eq!(txn_action["appId"], "my-app");
+        assert_eq!(txn_action["version"], 1);
+        assert_eq!(
+            txn_action["lastUpdated"],
+            parsed_commits[0]["commitInfo"]["timestamp"]
+        );
+
+        let snapshot = table.snapshot(&engine, None)?;
+        assert_eq!(snapshot.get_app_id_version("my-app", &engine)?, Some(1));
+        assert_eq!(snapshot.get_app_id_version("other-app", &engine)?, None);
+
+        // only one of two concurrent transactions of the same application can commit
+        let txn1 = append_transaction(&table, &engine, &schema, vec![4])
+            .await?
+            .with_transaction_id("my-app", 2);
+        let txn2 = append_transaction(&table, &engine, &schema, vec![4])
+            .await?
+            .with_transaction_id("my-app", 2);
+        assert!(matches!(txn1.commit(&engine)?, CommitResult::Committed(2)));
+        assert!(matches!(
+            txn2.commit(&engine),
+            Err(KernelError::TransactionConflict(
+                ConflictError::ConcurrentTransaction { version: 2, .. }
+            ))
+        ));
+        let snapshot = table.snapshot(&engine, None)?;
+        assert_eq!(snapshot.get_app_id_version("my-app", &engine)?, Some(2));
+
+        // transactions older than the retention duration are expired
+        let mut txn = table
+            .new_transaction(&engine)?
+            .with_commit_info(new_commit_info()?);
+        txn.set_table_properties([(
+            "delta.setTransactionRetentionDuration",
+            "interval 0 seconds",
+        )])?;
+        txn.commit(&engine)?;
+        let snapshot = table.snapshot(&engine, None)?;
+        assert_eq!(snapshot.get_app_id_version("my-app", &engine)?, None);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_checkpoint() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+
+    for (table, engine, store, table_name) in setup_tables(schema.clone(), &[]).await? {
+        let txn = append_transaction(&table, &engine, &schema, vec![1, 2, 3])
+            .await?
+            .with_transaction_id("my-app", 1);
+        txn.commit(&engine)?;
+        append_transaction(&table, &engine, &schema, vec![4, 5, 6])
+            .await?
+            .commit(&engine)?;
+        remove_all_transaction(&table, &engine)?.commit(&engine)?;
+        append_transaction(&table, &engine, &schema, vec![7])
+            .await?
+            .commit(&engine)?;
+
+        // the checkpoint has the protocol, metadata, txn, the tombstones of the two removed files
+        // and the file added by the last commit
+        let snapshot = table.snapshot(&engine, None)?;
+        snapshot.checkpoint(&engine)?;
+        let log_path = |file: &str| Path::from(format!("/{table_name}/_delta_log/{file}"));
+        let last_checkpoint = store.get(&log_path("_last_checkpoint")).await?;
+        let last_checkpoint: serde_json::Value =
+            serde_json::from_slice(&last_checkpoint.bytes().await?)?;
+        assert_eq!(
+            last_checkpoint,
+            json!({"version": 4, "size": 6, "numOfAddFiles": 1})
+        );
+
+        // the table can be loaded from the checkpoint alone
+        for version in 0..4 {
+            store
+                .delete(&log_path(&format!("{version:020}.json")))
+                .await?;
+        }
+        let snapshot = table.snapshot(&engine, None)?;
+        assert_eq!(snapshot.version(), 4);
+        assert_eq!(snapshot.get_app_id_version("my-app", &engine)?, Some(1));
+
+        // tombstones expire according to the deleted file retention duration
+        let mut txn = append_transaction(&table, &engine, &schema, vec![8]).await?;
+        txn.set_table_properties([("delta.deletedFileRetentionDuration", "interval 0 seconds")])?;
+        txn.commit(&engine)?;
+        table.snapshot(&engine, None)?.checkpoint(&engine)?;
+        let last_checkpoint = store.get(&log_path("_last_checkpoint")).await?;
+        let last_checkpoint: serde_json::Value =
+            serde_json::from_slice(&last_checkpoint.bytes().await?)?;
+        assert_eq!(
+            last_checkpoint,
+            json!({"version": 5, "size": 5, "numOfAddFiles": 2})
+        );
+        store.delete(&log_path(&format!("{:020}.json", 4))).await?;
+
+        test_read(
+            &ArrowEngineData::new(RecordBatch::try_new(
+                Arc::new(schema.as_ref().try_into()?),
+                vec![Arc::new(Int32Array::from(vec![8, 7]))],
+            )?),
+            &table,
+            Arc::new(engine),
+        )?;
+    }
+    Ok(())
+}
+
+// a parquet handler that can only read, like the handlers of engines that don't implement
+// `ParquetHandler::write_parquet_file`
+struct ReadOnlyParquetHandler(Arc<dyn ParquetHandler>);
+
+impl ParquetHandler for ReadOnlyParquetHandler {
+    fn read_parquet_files(
+        &self,
+        files: &[FileMeta],
+        physical_schema: SchemaRef,
+        predicate: Option<ExpressionRef>,
+    ) -> DeltaResult<FileDataReadResultIterator> {
+        self.0.read_parquet_files(files, physical_schema, predicate)
+    }
+}
+
+// an engine whose parquet handler can't write parquet files
+struct ReadOnlyParquetEngine(DefaultEngine<TokioBackgroundExecutor>);
+
+impl Engine for ReadOnlyParquetEngine {
+    fn get_expression_handler(&self) -> Arc<dyn ExpressionHandler> {
+        self.0.get_expression_handler()
+    }
+
+    fn get_file_system_client(&self) -> Arc<dyn FileSystemClient> {
+        self.0.get_file_system_client()
+    }
+
+    fn get_json_handler(&self) -> Arc<dyn JsonHandler> {
+        self.0.get_json_handler()
+    }
+
+    fn get_parquet_handler(&self) -> Arc<dyn ParquetHandler> {
+        Arc::new(ReadOnlyParquetHandler(self.0.get_parquet_handler()))
+    }
+}
+
+#[tokio::test]
+async fn test_checkpoint_without_parquet_writes() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+    let (store, engine, table_location) = setup("test_checkpoint_read_only_parquet", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    append_transaction(&table, &engine, &schema, vec![1, 2])
+        .await?
+        .commit(&engine)?;
+
+    // the checkpoint fails cleanly: no checkpoint file is written and `_last_checkpoint` is unset
+    let engine = ReadOnlyParquetEngine(engine);
+    let result = table.snapshot(&engine, None)?.checkpoint(&engine);
+    assert!(matches!(result, Err(KernelError::Unsupported(_))));
+    let log_files: Vec<_> = store
+        .list(Some(&Path::from(
+            "/test_checkpoint_read_only_parquet/_delta_log",
+        )))
+        .map_ok(|meta| meta.location.filename().unwrap_or_default().to_string())
+        .try_collect()
+        .await?;
+    assert_eq!(
+        log_files.iter().sorted().collect_vec(),
+        ["00000000000000000000.json", "00000000000000000001.json"]
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_v2_checkpoint() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+
+    for format in [V2CheckpointFormat::Parquet, V2CheckpointFormat::Json] {
+        let table_name = format!("test_v2_checkpoint_{format:?}");
+        let (store, engine, table_location) = setup(&table_name, true);
+        let table = Table::create(table_location, schema.clone())
+            .with_table_properties([("delta.checkpointPolicy", "v2")])
+            .with_commit_info(new_commit_info()?)
+            .commit(&engine)?;
+        for value in 1..=3 {
+            append_transaction(&table, &engine, &schema, vec![value])
+                .await?
+                .commit(&engine)?;
+        }
+
+        // the three files are split across two sidecars
+        table
+            .snapshot(&engine, None)?
+            .checkpoint_builder()
+            .with_v2_checkpoint_format(format)
+            .with_file_actions_per_sidecar(NonZero::new(2).unwrap())
+            .write(&engine)?;
+        let log_path = |file: &str| Path::from(format!("/{table_name}/_delta_log/{file}"));
+        let list = |prefix: &str| {
+            store
+                .list(Some(&log_path(prefix)))
+                .map_ok(|meta| meta.location.filename().unwrap().to_string())
+                .try_collect::<Vec<_>>()
+        };
+        assert_eq!(list("_sidecars").await?.len(), 2);
+        let checkpoints: Vec<_> = list("")
+            .await?
+            .into_iter()
+            .filter(|name| name.contains(".checkpoint."))
+            .collect();
+        assert_eq!(checkpoints.len(), 1);
+        assert!(checkpoints[0].starts_with("00000000000000000003.checkpoint."));
+        let extension = format!(".{}", format!("{format:?}").to_lowercase());
+        assert!(checkpoints[0].ends_with(&extension));
+
+        // the checkpoint has the protocol, metadata, checkpoint metadata, two sidecars and three
+        // files (in the sidecars)
+        let last_checkpoint = store.get(&log_path("_last_checkpoint")).await?;
+        let last_checkpoint: serde_json::Value =
+            serde_json::from_slice(&last_checkpoint.bytes().await?)?;
+        assert_eq!(
+            last_checkpoint,
+            json!({"version": 3, "size": 8, "numOfAddFiles": 3})
+        );
+
+        // the table can be loaded from the checkpoint alone
+        for version in 0..=3 {
+            store
+                .delete(&log_path(&format!("{version:020}.json")))
+                .await?;
+        }
+        test_read(
+            &ArrowEngineData::new(RecordBatch::try_new(
+                Arc::new(schema.as_ref().try_into()?),
+                vec![Arc::new(Int32Array::from(vec![3, 2, 1]))],
+            )?),
+            &table,
+            Arc::new(engine),
+        )?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_compact_log() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+
+    for (table, engine, store, table_name) in setup_tables(schema.clone(), &[]).await? {
+        append_transaction(&table, &engine, &schema, vec![1])
+            .await?
+            .with_transaction_id("my-app", 1)
+            .commit(&engine)?;
+        append_transaction(&table, &engine, &schema, vec![2])
+            .await?
+            .with_transaction_id("my-app", 2)
+            .commit(&engine)?;
+        remove_all_transaction(&table, &engine)?.commit(&engine)?;
+        append_transaction(&table, &engine, &schema, vec![3])
+            .await?
+            .commit(&engine)?;
+
+        // the commits can't be compacted beyond the snapshot version
+        let snapshot = table.snapshot(&engine, Some(3))?;
+        assert!(snapshot.compact_log(&engine, 1, 4).is_err());
+        assert!(snapshot.compact_log(&engine, 3, 3).is_err());
+
+        // only the tombstones of the two files and the most recent txn survive
+        snapshot.compact_log(&engine, 1, 3)?;
+        let log_path = |file: &str| Path::from(format!("/{table_name}/_delta_log/{file}"));
+        let compaction = store
+            .get(&log_path(
+                "00000000000000000001.00000000000000000003.compacted.json",
+            ))
+            .await?;
+        let actions: Vec<serde_json::Value> = Deserializer::from_slice(&compaction.bytes().await?)
+            .into_iter::<serde_json::Value>()
+            .try_collect()?;
+        let action_names = actions
+            .iter()
+            .map(|action| action.as_object().unwrap().keys().join(","))
+            .collect_vec();
+        assert_eq!(action_names, vec!["remove", "remove", "txn"]);
+        assert_eq!(actions[2]["txn"]["version"], json!(2));
+
+        // snapshots replay the compaction file instead of the commits
+        assert_eq!(
+            table
+                .snapshot(&engine, None)?
+                .get_app_id_version("my-app", &engine)?,
+            Some(2)
+        );
+        test_read(
+            &ArrowEngineData::new(RecordBatch::try_new(
+                Arc::new(schema.as_ref().try_into()?),
+                vec![Arc::new(Int32Array::from(vec![3]))],
+            )?),
+            &table,
+            Arc::new(engine),
+        )?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_in_commit_timestamps() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+    let (store, engine, table_location) = setup("test_in_commit_timestamps", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_writer_features([WriterFeature::InCommitTimestamp])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    append_transaction(&table, &engine, &schema, vec![1])
+        .await?
+        .commit(&engine)?;
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.set_table_properties([("delta.enableInCommitTimestamps", "true")])?;
+    txn.commit(&engine)?;
+    append_transaction(&table, &engine, &schema, vec![2])
+        .await?
+        .commit(&engine)?;
+
+    let commit_info = |version: u64| {
+        let store = store.clone();
+        async move {
+            let commit = store
+                .get(&Path::from(format!(
+                    "/test_in_commit_timestamps/_delta_log/{version:020}.json"
+                )))
+                .await?;
+            let first_action = Deserializer::from_slice(&commit.bytes().await?)
+                .into_iter::<serde_json::Value>()
+                .next()
+                .unwrap()?;
+            Ok::<_, Box<dyn std::error::Error>>(first_action["commitInfo"].clone())
+        }
+    };
+
+    // only the commits made since in-commit timestamps were enabled have one, and they increase
+    // with every commit
+    assert!(commit_info(1).await?.get("inCommitTimestamp").is_none());
+    let timestamp2 = commit_info(2).await?["inCommitTimestamp"].as_i64().unwrap();
+    let timestamp3 = commit_info(3).await?["inCommitTimestamp"].as_i64().unwrap();
+    assert!(
+        timestamp2
+            > table
+                .snapshot(&engine, Some(1))?
+                .commit_timestamp(&engine)?
+    );
+    assert!(timestamp3 > timestamp2);
+
+    // the commit that enabled in-commit timestamps is recorded in the table properties
+    let snapshot = table.snapshot(&engine, None)?;
+    let table_properties = snapshot.table_properties();
+    assert_eq!(
+        table_properties.in_commit_timestamp_enablement_version,
+        Some(2)
+    );
+    assert_eq!(
+        table_properties.in_commit_timestamp_enablement_timestamp,
+        Some(timestamp2)
+    );
+
+    // the snapshot and time travel use the in-commit timestamps
+    assert_eq!(snapshot.commit_timestamp(&engine)?, timestamp3);
+    let snapshot =
+        table.snapshot_at_timestamp(&engine, timestamp2, TimestampSearchMode::AtOrBefore)?;
+    assert_eq!(snapshot.version(), 2);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_history() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+
+    for (table, engine, store, table_name) in setup_tables(schema.clone(), &[]).await? {
+        append_transaction(&table, &engine, &schema, vec![1])
+            .await?
+            .with_operation("WRITE".to_string())
+            .commit(&engine)?;
+        remove_all_transaction(&table, &engine)?
+            .with_operation("DELETE".to_string())
+            .commit(&engine)?;
+
+        let history = table.history(&engine, ..)?;
+        let operations = history
+            .iter()
+            .map(|entry| {
+                (
+                    entry.version,
+                    entry.operation.as_deref(),
+                    entry.is_blind_append,
+                )
+            })
+            .collect_vec();
+        assert_eq!(
+            operations,
+            [
+                (2, Some("DELETE"), Some(false)),
+                (1, Some("WRITE"), Some(true)),
+                // the test table is created without a commitInfo action
+                (0, None, None),
+            ]
+        );
+        assert!(history[0].timestamp > history[1].timestamp);
+
+        // the history stops at the earliest commit retained in the log
+        store
+            .delete(&Path::from(format!(
+                "/{table_name}/_delta_log/00000000000000000001.json"
+            )))
+            .await?;
+        let versions = table
+            .history(&engine, ..)?
+            .iter()
+            .map(|entry| entry.version)
+            .collect_vec();
+        assert_eq!(versions, [2]);
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_plan_vacuum() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+    let (store, engine, table_location) = setup("test_plan_vacuum", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_table_properties([("delta.deletedFileRetentionDuration", "interval 0 seconds")])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    let data_files = |store: Arc<dyn ObjectStore>| async move {
+        store
+            .list(Some(&Path::from("test_plan_vacuum")))
+            .map_ok(|meta| meta.location.to_string())
+            .try_filter(|location| futures::future::ready(location.ends_with(".parquet")))
+            .try_collect::<Vec<_>>()
+            .await
+    };
+
+    // the removed files and the files that were never committed can be deleted, unless hidden
+    append_transaction(&table, &engine, &schema, vec![1])
+        .await?
+        .commit(&engine)?;
+    let removed_files = data_files(store.clone()).await?;
+    remove_all_transaction(&table, &engine)?.commit(&engine)?;
+    append_transaction(&table, &engine, &schema, vec![2])
+        .await?
+        .commit(&engine)?;
+    for orphan in [
+        "orphan.parquet",
+        "_change_data/cdc.parquet",
+        "_hidden/file.parquet",
+        ".hidden.parquet",
+    ] {
+        store
+            .put(
+                &Path::from(format!("test_plan_vacuum/{orphan}")),
+                vec![0u8; 10].into(),
+            )
+            .await?;
+    }
+    tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+
+    let snapshot = table.snapshot(&engine, None)?;
+    let plan = snapshot.plan_vacuum(&engine)?;
+    let mut expected = removed_files
+        .into_iter()
+        .chain([
+            "test_plan_vacuum/_change_data/cdc.parquet".to_string(),
+            "test_plan_vacuum/orphan.parquet".to_string(),
+        ])
+        .collect_vec();
+    expected.sort();
+    let planned = plan
+        .files()
+        .iter()
+        .map(|file| file.location.path().trim_start_matches('/').to_string())
+        .collect_vec();
+    assert_eq!(planned, expected);
+    assert!(plan.size_in_bytes() > 20);
+
+    // the files removed within the retention duration are kept
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.set_table_properties([("delta.deletedFileRetentionDuration", "interval 1 days")])?;
+    txn.commit(&engine)?;
+    let plan = table.snapshot(&engine, None)?.plan_vacuum(&engine)?;
+    assert!(plan.files().is_empty());
+
+    // vacuum can only be planned on the latest version of the table
+    assert!(snapshot.plan_vacuum(&engine).is_err());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_expired_log_cleanup() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+
+    for enable_cleanup in [true, false] {
+        let table_name = format!("test_expired_log_cleanup_{enable_cleanup}");
+        let (store, engine, table_location) = setup(&table_name, true);
+        let table = Table::create(table_location, schema.clone())
+            .with_table_properties([
+                ("delta.checkpointPolicy", "v2"),
+                ("delta.logRetentionDuration", "interval 0 seconds"),
+                ("delta.enableExpiredLogCleanup", &enable_cleanup.to_string()),
+            ])
+            .with_commit_info(new_commit_info()?)
+            .commit(&engine)?;
+        let log_path = |file: &str| Path::from(format!("/{table_name}/_delta_log/{file}"));
+        store
+            .put(&log_path("00000000000000000000.crc"), "{}".into())
+            .await?;
+        append_transaction(&table, &engine, &schema, vec![1])
+            .await?
+            .commit(&engine)?;
+        table.snapshot(&engine, None)?.checkpoint(&engine)?;
+        for value in 2..=3 {
+            append_transaction(&table, &engine, &schema, vec![value])
+                .await?
+                .commit(&engine)?;
+        }
+        tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        table.snapshot(&engine, None)?.checkpoint(&engine)?;
+
+        // the files of the versions before the last checkpoint are deleted, as well as the
+        // sidecar of the first checkpoint
+        let log_files = store
+            .list(Some(&log_path("")))
+            .map_ok(|meta| meta.location.to_string())
+            .try_collect::<Vec<_>>()
+            .await?;
+        let count = |pattern: &str| log_files.iter().filter(|f| f.contains(pattern)).count();
+        if enable_cleanup {
+            assert_eq!(count(".json"), 1);
+            assert_eq!(count(".crc"), 0);
+            assert_eq!(count("00000000000000000003.json"), 1);
+            assert_eq!(count(".checkpoint."), 1);
+            assert_eq!(count("_sidecars/"), 1);
+            assert!(table.snapshot(&engine, Some(2)).is_err());
+        } else {
+            assert_eq!(count(".json"), 4);
+            assert_eq!(count(".crc"), 1);
+            assert_eq!(count(".checkpoint."), 2);
+            assert_eq!(count("_sidecars/"), 2);
+            assert!(table.snapshot(&engine, Some(2)).is_ok());
+        }
+        test_read(
+            &ArrowEngineData::new(RecordBatch::try_new(
+                Arc::new(schema.as_ref().try_into()?),
+                vec![Arc::new(Int32Array::from(vec![3, 2, 1]))],
+            )?),
+            &table,
+            Arc::new(engine),
+        )?;
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_optimize() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+
+    for (table, engine, store, table_name) in setup_tables(schema.clone(), &[]).await? {
+        for value in 1..=3 {
+            append_transaction(&table, &engine, &schema, vec![value])
+                .await?
+                .commit(&engine)?;
+        }
+
+        // the three small files are compacted into one
+        let engine = Arc::new(engine);
+        let snapshot = Arc::new(table.snapshot(engine.as_ref(), None)?);
+        let plan = snapshot.optimize_builder().build(engine.as_ref())?;
+        assert_eq!(plan.tasks().len(), 1);
+        assert_eq!(plan.tasks()[0].paths().count(), 3);
+        let mut write_metadata = vec![];
+        for task in plan.tasks() {
+            let batches = read_scan_results(plan.execute_task(engine.clone(), task)?)?;
+            let data = concat_batches(&Arc::new(schema.as_ref().try_into()?), &batches)?;
+            write_metadata.push(
+                engine
+                    .write_parquet(
+                        &ArrowEngineData::new(data),
+                        &plan.write_context(),
+                        task.partition_values().clone(),
+                        // the transaction overrides the data change of the written files
+                        true,
+                    )
+                    .await?,
+            );
+        }
+        let mut txn = plan.into_transaction().with_commit_info(new_commit_info()?);
+        for metadata in write_metadata {
+            txn.add_write_metadata(metadata);
+        }
+        assert!(matches!(
+            txn.commit(engine.as_ref())?,
+            CommitResult::Committed(4)
+        ));
+
+        // the files are removed and added without changing the data
+        let commit = store
+            .get(&Path::from(format!(
+                "/{table_name}/_delta_log/00000000000000000004.json"
+            )))
+            .await?;
+        let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+            .into_iter()
+            .try_collect()?;
+        let commit_info = &actions[0]["commitInfo"];
+        assert_eq!(commit_info["operation"], "OPTIMIZE");
+        assert_eq!(commit_info["isBlindAppend"], false);
+        let file_actions = actions
+            .iter()
+            .filter_map(|action| action.get("add").or(action.get("remove")))
+            .collect_vec();
+        assert_eq!(file_actions.len(), 4);
+        assert!(file_actions
+            .iter()
+            .all(|action| action["dataChange"] == false));
+        let snapshot = table.snapshot(engine.as_ref(), None)?;
+        assert_eq!(snapshot.details(engine.as_ref())?.num_files, 1);
+        test_read(
+            &ArrowEngineData::new(RecordBatch::try_new(
+                Arc::new(schema.as_ref().try_into()?),
+                vec![Arc::new(Int32Array::from(vec![3, 2, 1]))],
+            )?),
+            &table,
+            engine.clone(),
+        )?;
+
+        // there is nothing left to compact
+        let plan = Arc::new(snapshot)
+            .optimize_builder()
+            .build(engine.as_ref())?;
+        assert!(plan.tasks().is_empty());
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_optimize_clustering() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![
+        StructField::nullable("x", DataType::INTEGER),
+        StructField::nullable("y", DataType::INTEGER),
+    ]));
+    let arrow_schema: Arc<ArrowSchema> = Arc::new(schema.as_ref().try_into()?);
+
+    for curve in [SpaceFillingCurve::ZOrder, SpaceFillingCurve::Hilbert] {
+        for (table, engine, store, table_name) in setup_tables(schema.clone(), &[]).await? {
+            // each of the four files holds points scattered over the whole 8x8 grid
+            let points = (0..8).cartesian_product(0..8).collect_vec();
+            for i in 0..4 {
+                let (x, y): (Vec<i32>, Vec<i32>) =
+                    points.iter().skip(i).step_by(4).copied().unzip();
+                let data = RecordBatch::try_new(
+                    arrow_schema.clone(),
+                    vec![Arc::new(Int32Array::from(x)), Arc::new(Int32Array::from(y))],
+                )?;
+                let mut txn = table
+                    .new_transaction(&engine)?
+                    .with_commit_info(new_commit_info()?);
+                let write_metadata = engine
+                    .write_parquet(
+                        &ArrowEngineData::new(data),
+                        &txn.get_write_context(),
+                        HashMap::new(),
+                        true,
+                    )
+                    .await?;
+                txn.add_write_metadata(write_metadata);
+                txn.commit(&engine)?;
+            }
+
+            // clustering columns must be non-partition columns of primitive types of the table
+            let engine = Arc::new(engine);
+            let snapshot = Arc::new(table.snapshot(engine.as_ref(), None)?);
+            let clustering = Clustering::new(curve, vec![column_name!("z")]);
+            assert!(snapshot
+                .clone()
+                .optimize_builder()
+                .with_clustering(clustering)
+                .build(engine.as_ref())
+                .is_err());
+
+            // the four files are clustered into four files of about the same size
+            let size_in_bytes = snapshot.details(engine.as_ref())?.size_in_bytes;
+            let clustering = Clustering::new(curve, vec![column_name!("x"), column_name!("y")]);
+            let plan = snapshot
+                .optimize_builder()
+                .with_clustering(clustering.clone())
+                .with_target_file_size(NonZero::new(size_in_bytes.div_ceil(4)).unwrap())
+                .build(engine.as_ref())?;
+            assert_eq!(plan.clustering(), Some(&clustering));
+            assert_eq!(plan.tasks().len(), 1);
+            assert_eq!(plan.tasks()[0].paths().count(), 4);
+            assert_eq!(plan.tasks()[0].num_output_files(), 4);
+            let mut write_metadata = vec![];
+            for task in plan.tasks() {
+                let batches = read_scan_results(plan.execute_task(engine.clone(), task)?)?;
+                let files = cluster_record_batches(&batches, &clustering, task.num_output_files())?;
+                for data in files {
+                    write_metadata.push(
+                        engine
+                            .write_parquet(
+                                &ArrowEngineData::new(data),
+                                &plan.write_context(),
+                                task.partition_values().clone(),
+                                false,
+                            )
+                            .await?,
+                    );
+                }
+            }
+            let mut txn = plan.into_transaction().with_commit_info(new_commit_info()?);
+            for metadata in write_metadata {
+                txn.add_write_metadata(metadata);
+            }
+            assert!(matches!(
+                txn.commit(engine.as_ref())?,
+                CommitResult::Committed(5)
+            ));
+
+            // each new file covers a quadrant of the grid
+            let commit = store
+                .get(&Path::from(format!(
+                    "/{table_name}/_delta_log/00000000000000000005.json"
+                )))
+                .await?;
+            let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+                .into_iter()
+                .try_collect()?;
+            let mut bounds: Vec<_> = actions
+                .iter()
+                .filter_map(|action| action.pointer("/add/stats")?.as_str())
+                .map(|stats| {
+                    let stats: serde_json::Value = serde_json::from_str(stats)?;
+                    assert_eq!(stats["numRecords"], 16);
+                    let bound = |pointer| stats.pointer(pointer).and_then(|v| v.as_i64());
+                    Ok::<_, serde_json::Error>([
+                        bound("/minValues/x"),
+                        bound("/maxValues/x"),
+                        bound("/minValues/y"),
+                        bound("/maxValues/y"),
+                    ])
+                })
+                .try_collect()?;
+            bounds.sort();
+            assert_eq!(
+                bounds,
+                [
+                    [Some(0), Some(3), Some(0), Some(3)],
+                    [Some(0), Some(3), Some(4), Some(7)],
+                    [Some(4), Some(7), Some(0), Some(3)],
+                    [Some(4), Some(7), Some(4), Some(7)],
+                ]
+            );
+
+            // queries filtering on the clustering columns skip the other quadrants
+            let predicate = column_expr!("x").lt(2).and(column_expr!("y").gt(5));
+            let scan = table
+                .snapshot(engine.as_ref(), None)?
+                .into_scan_builder()
+                .with_predicate(Arc::new(predicate))
+                .build()?;
+            let mut num_files = 0;
+            for scan_data in scan.scan_data(engine.as_ref())? {
+                let (_, selection_vector, _) = scan_data?;
+                num_files += selection_vector
+                    .iter()
+                    .filter(|selected| **selected)
+                    .count();
+            }
+            assert_eq!(num_files, 1);
+            let scan = table
+                .snapshot(engine.as_ref(), None)?
+                .into_scan_builder()
+                .build()?;
+            let batches = read_scan_results(scan.execute(engine.clone())?)?;
+            let data = concat_batches(&arrow_schema, &batches)?;
+            assert_eq!(data.num_rows(), 64);
+        }
+    }
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_clustered_table() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![
+        StructField::nullable("number", DataType::INTEGER),
+        StructField::nullable("letter", DataType::STRING),
+    ]));
+    let (store, engine, table_location) = setup("test_clustered_table", true);
+
+    // clustering columns must be non-partition columns of the schema
+    let invalid_tables = [
+        Table::create(table_location.clone(), schema.clone())
+            .with_clustering_columns([column_name!("other")]),
+        Table::create(table_location.clone(), schema.clone())
+            .with_partition_columns(["letter"])
+            .with_clustering_columns([column_name!("number")]),
+    ];
+    for create_table in invalid_tables {
+        let result = create_table
+            .with_commit_info(new_commit_info()?)
+            .commit(&engine);
+        assert!(matches!(
+            result,
+            Err(KernelError::InvalidClusteringColumn(_))
+        ));
+    }
+
+    let table = Table::create(table_location.clone(), schema.clone())
+        .with_clustering_columns([column_name!("number")])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    let log_path = |version: u64| {
+        Path::from(format!(
+            "/test_clustered_table/_delta_log/{version:020}.json"
+        ))
+    };
+    let commit0 = store.get(&log_path(0)).await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit0.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(
+        actions[1],
+        json!({
+            "protocol": {
+                "minReaderVersion": 1,
+                "minWriterVersion": 7,
+                "writerFeatures": ["domainMetadata", "clustering"]
+            }
+        })
+    );
+    assert_eq!(
+        actions[3],
+        json!({
+            "domainMetadata": {
+                "domain": "delta.clustering",
+                "configuration": r#"{"clusteringColumns":[["number"]]}"#,
+                "removed": false
+            }
+        })
+    );
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(
+        snapshot.clustering_columns(&engine)?,
+        Some(vec![column_name!("number")])
+    );
+
+    // writes collect the statistics of the clustering columns
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    let data = RecordBatch::try_new(
+        Arc::new(schema.as_ref().try_into()?),
+        vec![
+            Arc::new(Int32Array::from(vec![3, 1, 2])),
+            Arc::new(StringArray::from(vec!["a", "b", "c"])),
+        ],
+    )?;
+    let write_metadata = engine
+        .write_parquet(
+            &ArrowEngineData::new(data),
+            &txn.get_write_context(),
+            HashMap::new(),
+            true,
+        )
+        .await?;
+    txn.add_write_metadata(write_metadata);
+    txn.commit(&engine)?;
+    let commit1 = store.get(&log_path(1)).await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit1.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    let stats = actions[1]
+        .pointer("/add/stats")
+        .and_then(|stats| stats.as_str())
+        .ok_or("missing stats")?;
+    assert_eq!(
+        serde_json::from_str::<serde_json::Value>(stats)?,
+        json!({
+            "numRecords": 3,
+            "minValues": {"number": 1},
+            "maxValues": {"number": 3},
+            "nullCount": {"number": 0}
+        })
+    );
+
+    // clustering columns must keep their statistics
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.set_table_properties([("delta.dataSkippingNumIndexedCols", "0")])?;
+    assert!(matches!(
+        txn.commit(&engine),
+        Err(KernelError::InvalidClusteringColumn(_))
+    ));
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.set_table_properties([("delta.dataSkippingNumIndexedCols", "1")])?;
+    let result = txn.set_clustering_columns(vec![column_name!("letter")]);
+    assert!(matches!(
+        result,
+        Err(KernelError::InvalidClusteringColumn(_))
+    ));
+
+    // the clustering columns can be changed, and are kept by checkpoints
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.set_clustering_columns(vec![column_name!("number"), column_name!("letter")])?;
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(2)));
+    let snapshot = table.snapshot(&engine, None)?;
+    snapshot.checkpoint(&engine)?;
+    for version in 0..=2 {
+        store.delete(&log_path(version)).await?;
+    }
+    let snapshot = Arc::new(table.snapshot(&engine, None)?);
+    let clustering_columns = vec![column_name!("number"), column_name!("letter")];
+    assert_eq!(
+        snapshot.clustering_columns(&engine)?,
+        Some(clustering_columns.clone())
+    );
+
+    // OPTIMIZE only clusters the table over its clustering columns when requested, and otherwise
+    // compacts it, still collecting the statistics of the clustering columns
+    let plan = snapshot.clone().optimize_builder().build(&engine)?;
+    assert_eq!(plan.clustering(), None);
+    assert_eq!(plan.write_context().stats_columns(), clustering_columns);
+    let clustering = Clustering::new(SpaceFillingCurve::Hilbert, clustering_columns);
+    let plan = snapshot
+        .optimize_builder()
+        .with_clustering(clustering.clone())
+        .build(&engine)?;
+    assert_eq!(plan.clustering(), Some(&clustering));
+
+    // only clustered tables have clustering columns
+    let (_, engine, table_location) = setup("test_unclustered_table", true);
+    let table = Table::create(table_location, schema)
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    assert_eq!(
+        table.snapshot(&engine, None)?.clustering_columns(&engine)?,
+        None
+    );
+    let mut txn = table.new_transaction(&engine)?;
+    assert!(matches!(
+        txn.set_clustering_columns(vec![column_name!("number")]),
+        Err(KernelError::Unsupported(_))
+    ));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_domain_metadata() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+    let (store, engine, table_location) = setup("test_domain_metadata", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_writer_features([WriterFeature::DomainMetadata])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(snapshot.get_domain_metadata("my-app", &engine)?, None);
+    assert!(snapshot.domain_metadata(&engine)?.is_empty());
+
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.set_domain_metadata("my-app", r#"{"watermark":1}"#)?;
+    txn.set_domain_metadata("other-app", "x")?;
+    // system-controlled domains can't be changed directly
+    assert!(matches!(
+        txn.set_domain_metadata("delta.clustering", "{}"),
+        Err(KernelError::InvalidTransaction(_))
+    ));
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(1)));
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(
+        snapshot.get_domain_metadata("my-app", &engine)?.as_deref(),
+        Some(r#"{"watermark":1}"#)
+    );
+    assert_eq!(
+        snapshot.domain_metadata(&engine)?,
+        HashMap::from([
+            ("my-app".to_string(), r#"{"watermark":1}"#.to_string()),
+            ("other-app".to_string(), "x".to_string()),
+        ])
+    );
+
+    // removed domains are written with their last configuration, unless they don't exist
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn.set_domain_metadata("my-app", r#"{"watermark":2}"#)?;
+    txn.remove_domain_metadata("other-app")?;
+    txn.remove_domain_metadata("missing-app")?;
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(2)));
+    let commit2 = store
+        .get(&Path::from(
+            "/test_domain_metadata/_delta_log/00000000000000000002.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit2.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(
+        actions[1..],
+        [
+            json!({
+                "domainMetadata": {
+                    "domain": "my-app",
+                    "configuration": r#"{"watermark":2}"#,
+                    "removed": false
+                }
+            }),
+            json!({
+                "domainMetadata": {
+                    "domain": "other-app",
+                    "configuration": "x",
+                    "removed": true
+                }
+            }),
+        ]
+    );
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(snapshot.get_domain_metadata("other-app", &engine)?, None);
+    assert_eq!(
+        snapshot.domain_metadata(&engine)?,
+        HashMap::from([("my-app".to_string(), r#"{"watermark":2}"#.to_string())])
+    );
+
+    // only one of two concurrent changes to the same domain can commit
+    let mut txn1 = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn1.set_domain_metadata("my-app", r#"{"watermark":3}"#)?;
+    let mut txn2 = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    txn2.remove_domain_metadata("my-app")?;
+    assert!(matches!(txn1.commit(&engine)?, CommitResult::Committed(3)));
+    assert!(matches!(
+        txn2.commit(&engine),
+        Err(KernelError::TransactionConflict(
+            ConflictError::ConcurrentDomainMetadata { version: 3, .. }
+        ))
+    ));
+
+    // the domains are kept by checkpoints
+    table.snapshot(&engine, None)?.checkpoint(&engine)?;
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(
+        snapshot.get_domain_metadata("my-app", &engine)?.as_deref(),
+        Some(r#"{"watermark":3}"#)
+    );
+
+    // tables without the domainMetadata feature can't have domains
+    let (_, engine, table_location) = setup("test_no_domain_metadata", true);
+    let table = Table::create(table_location, schema)
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    let mut txn = table.new_transaction(&engine)?;
+    assert!(matches!(
+        txn.set_domain_metadata("my-app", "{}"),
+        Err(KernelError::Unsupported(_))
+    ));
+    Ok(())
+}
+
+// the numbers of the rows of the table with their row IDs and row commit versions, by row ID
+fn read_row_tracking(table: &Table, engine: Arc<dyn Engine>) -> DeltaResult<Vec<(i32, i64, i64)>> {
+    let scan = table
+        .snapshot(engine.as_ref(), None)?
+        .into_scan_builder()
+        .with_row_tracking_columns()
+        .build()?;
+    let mut rows = vec![];
+    for batch in read_scan_results(scan.execute(engine)?)? {
+        let numbers = batch.column(0).as_primitive::<Int32Type>();
+        let metadata = batch.column_by_name("_metadata").unwrap().as_struct();
+        let row_ids = metadata.column(0).as_primitive::<Int64Type>();
+        let row_commit_versions = metadata.column(1).as_primitive::<Int64Type>();
+        for row in 0..batch.num_rows() {
+            rows.push((
+                numbers.value(row),
+                row_ids.value(row),
+                row_commit_versions.value(row),
+            ));
+        }
+    }
+    rows.sort_by_key(|(_, row_id, _)| *row_id);
+    Ok(rows)
+}
+
+#[tokio::test]
+async fn test_row_tracking() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "number",
+        DataType::INTEGER,
+    )]));
+    let (store, engine, table_location) = setup("test_row_tracking", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_table_properties([("delta.enableRowTracking", "true")])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+
+    // new files get consecutive row IDs, and the high water mark is the last assigned row ID
+    for values in [vec![1, 2, 3], vec![4, 5]] {
+        append_transaction(&table, &engine, &schema, values)
+            .await?
+            .commit(&engine)?;
+    }
+    for (version, base_row_id, high_water_mark) in [(1, 0, 2), (2, 3, 4)] {
+        let commit = store
+            .get(&Path::from(format!(
+                "/test_row_tracking/_delta_log/0000000000000000000{version}.json"
+            )))
+            .await?;
+        let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+            .into_iter()
+            .try_collect()?;
+        assert_eq!(
+            actions[1],
+            json!({
+                "domainMetadata": {
+                    "domain": "delta.rowTracking",
+                    "configuration": format!(r#"{{"rowIdHighWaterMark":{high_water_mark}}}"#),
+                    "removed": false
+                }
+            })
+        );
+        assert_eq!(actions[2]["add"]["baseRowId"], base_row_id);
+        assert_eq!(actions[2]["add"]["defaultRowCommitVersion"], version);
+    }
+    let snapshot = table.snapshot(&engine, None)?;
+    assert_eq!(
+        snapshot
+            .get_domain_metadata("delta.rowTracking", &engine)?
+            .as_deref(),
+        Some(r#"{"rowIdHighWaterMark":4}"#)
+    );
+    let engine = Arc::new(engine);
+    let expected = vec![(1, 0, 1), (2, 1, 1), (3, 2, 1), (4, 3, 2), (5, 4, 2)];
+    assert_eq!(read_row_tracking(&table, engine.clone())?, expected);
+
+    // the compacted rows keep their row IDs and row commit versions
+    let snapshot = Arc::new(table.snapshot(engine.as_ref(), None)?);
+    let plan = snapshot.optimize_builder().build(engine.as_ref())?;
+    assert_eq!(plan.tasks().len(), 1);
+    let task = &plan.tasks()[0];
+    let batches = read_scan_results(plan.execute_task(engine.clone(), task)?)?;
+    let data = concat_batches(&batches[0].schema(), &batches)?;
+    let write_metadata = engine
+        .write_parquet(
+            &ArrowEngineData::new(data),
+            &plan.write_context(),
+            task.partition_values().clone(),
+            false,
+        )
+        .await?;
+    let mut txn = plan.into_transaction().with_commit_info(new_commit_info()?);
+    txn.add_write_metadata(write_metadata);
+    assert!(matches!(
+        txn.commit(engine.as_ref())?,
+        CommitResult::Committed(3)
+    ));
+    assert_eq!(
+        table
+            .snapshot(engine.as_ref(), None)?
+            .details(engine.as_ref())?
+            .num_files,
+        1
+    );
+    assert_eq!(read_row_tracking(&table, engine.clone())?, expected);
+
+    // rows appended after the compaction get row IDs after those of the compacted file
+    append_transaction(&table, engine.as_ref(), &schema, vec![6])
+        .await?
+        .commit(engine.as_ref())?;
+    let rows = read_row_tracking(&table, engine.clone())?;
+    assert_eq!(rows[..5], expected);
+    assert_eq!(rows[5], (6, 10, 4));
+
+    // row tracking can't be enabled on existing tables, even if they support it, nor read from
+    // tables without it
+    let (_, engine, table_location) = setup("test_no_row_tracking", true);
+    let table = Table::create(table_location, schema)
+        .with_writer_features([WriterFeature::RowTracking])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    let mut txn = table.new_transaction(&engine)?;
+    assert!(matches!(
+        txn.set_table_properties([("delta.enableRowTracking", "true")]),
+        Err(KernelError::InvalidTransaction(_))
+    ));
+    let result = table
+        .snapshot(&engine, None)?
+        .into_scan_builder()
+        .with_row_tracking_columns()
+        .build();
+    assert!(matches!(result, Err(KernelError::Unsupported(_))));
+    Ok(())
+}
+
+// the ids and numbers of the rows of a table with an `id` and a `number` column, by id
+fn read_ids(table: &Table, engine: Arc<dyn Engine>) -> DeltaResult<Vec<(i64, i32)>> {
+    let scan = table
+        .snapshot(engine.as_ref(), None)?
+        .into_scan_builder()
+        .build()?;
+    let mut rows = vec![];
+    for batch in read_scan_results(scan.execute(engine)?)? {
+        let ids = batch.column(0).as_primitive::<Int64Type>();
+        let numbers = batch.column(1).as_primitive::<Int32Type>();
+        rows.extend((0..batch.num_rows()).map(|row| (ids.value(row), numbers.value(row))));
+    }
+    rows.sort();
+    Ok(rows)
+}
+
+#[tokio::test]
+async fn test_identity_columns() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let identity_field = |allow_explicit_insert: bool| {
+        StructField::nullable("id", DataType::LONG).with_metadata([
+            ("delta.identity.start", MetadataValue::Number(100)),
+            ("delta.identity.step", MetadataValue::Number(-10)),
+            (
+                "delta.identity.allowExplicitInsert",
+                MetadataValue::Boolean(allow_explicit_insert),
+            ),
+        ])
+    };
+    let schema = Arc::new(StructType::new(vec![
+        identity_field(false),
+        StructField::nullable("number", DataType::INTEGER),
+    ]));
+    let (store, engine, table_location) = setup("test_identity_columns", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    let commit = store
+        .get(&Path::from(
+            "/test_identity_columns/_delta_log/00000000000000000000.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(
+        actions[1]["protocol"]["writerFeatures"],
+        json!(["identityColumns"])
+    );
+
+    // the rows of every batch get the next values of the identity column, which the data may
+    // omit
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    let write_context = txn.get_write_context();
+    let number_field = Field::new("number", ArrowDataType::Int32, true);
+    for (numbers, with_ids) in [(vec![1, 2, 3], true), (vec![4, 5], false)] {
+        let numbers: ArrayRef = Arc::new(Int32Array::from(numbers));
+        let data = if with_ids {
+            let ids = Int64Array::from(vec![None; numbers.len()]);
+            RecordBatch::try_new(
+                Arc::new(schema.as_ref().try_into()?),
+                vec![Arc::new(ids), numbers],
+            )?
+        } else {
+            RecordBatch::try_new(
+                Arc::new(ArrowSchema::new(vec![number_field.clone()])),
+                vec![numbers],
+            )?
+        };
+        let write_metadata = engine
+            .write_parquet(
+                &ArrowEngineData::new(data),
+                &write_context,
+                HashMap::new(),
+                true,
+            )
+            .await?;
+        txn.add_write_metadata(write_metadata);
+    }
+    assert!(matches!(txn.commit(&engine)?, CommitResult::Committed(1)));
+
+    // the commit records the high water mark in the schema
+    let commit = store
+        .get(&Path::from(
+            "/test_identity_columns/_delta_log/00000000000000000001.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    let schema_string = actions[1]["metaData"]["schemaString"].as_str().unwrap();
+    let committed_schema: StructType = serde_json::from_str(schema_string)?;
+    let id_metadata = &committed_schema.field("id").unwrap().metadata;
+    assert_eq!(
+        id_metadata["delta.identity.highWaterMark"],
+        MetadataValue::Number(60)
+    );
+    let engine = Arc::new(engine);
+    let expected = vec![(60, 5), (70, 4), (80, 3), (90, 2), (100, 1)];
+    assert_eq!(read_ids(&table, engine.clone())?, expected);
+
+    // explicit values are not allowed, and the identity columns can't be changed
+    let mut txn = table
+        .new_transaction(engine.as_ref())?
+        .with_commit_info(new_commit_info()?);
+    let data = RecordBatch::try_new(
+        Arc::new(schema.as_ref().try_into()?),
+        vec![
+            Arc::new(Int64Array::from(vec![Some(1), None])),
+            Arc::new(Int32Array::from(vec![6, 7])),
+        ],
+    )?;
+    let result = txn.get_write_context().logical_to_physical(
+        &ArrowEngineData::new(data),
+        &schema,
+        &HashMap::new(),
+    );
+    assert!(matches!(result, Err(KernelError::InvalidIdentityColumn(_))));
+    let result = txn.update_schema(StructType::new(vec![
+        identity_field(true),
+        StructField::nullable("number", DataType::INTEGER),
+    ]));
+    assert!(matches!(result, Err(KernelError::InvalidTransaction(_))));
+
+    // with explicit inserts allowed, explicit values are kept and don't move the high water mark
+    let schema = Arc::new(StructType::new(vec![
+        identity_field(true),
+        StructField::nullable("number", DataType::INTEGER),
+    ]));
+    let (_, engine, table_location) = setup("test_identity_columns_explicit_insert", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    for ids in [vec![Some(1), None, Some(2)], vec![None]] {
+        let mut txn = table
+            .new_transaction(&engine)?
+            .with_commit_info(new_commit_info()?);
+        let numbers = Int32Array::from(vec![ids.len() as i32; ids.len()]);
+        let data = RecordBatch::try_new(
+            Arc::new(schema.as_ref().try_into()?),
+            vec![Arc::new(Int64Array::from(ids)), Arc::new(numbers)],
+        )?;
+        let write_metadata = engine
+            .write_parquet(
+                &ArrowEngineData::new(data),
+                &txn.get_write_context(),
+                HashMap::new(),
+                true,
+            )
+            .await?;
+        txn.add_write_metadata(write_metadata);
+        txn.commit(&engine)?;
+    }
+    // the rows of data without the identity column get generated values
+    let mut txn = table
+        .new_transaction(&engine)?
+        .with_commit_info(new_commit_info()?);
+    let data = RecordBatch::try_new(
+        Arc::new(ArrowSchema::new(vec![number_field])),
+        vec![Arc::new(Int32Array::from(vec![1]))],
+    )?;
+    let write_metadata = engine
+        .write_parquet(
+            &ArrowEngineData::new(data),
+            &txn.get_write_context(),
+            HashMap::new(),
+            true,
+        )
+        .await?;
+    txn.add_write_metadata(write_metadata);
+    txn.commit(&engine)?;
+    // the rows with explicit values use up generated values too
+    let expected = vec![(1, 3), (2, 3), (60, 1), (70, 1), (90, 3)];
+    assert_eq!(read_ids(&table, Arc::new(engine))?, expected);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_generated_columns() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let generated_field = |name: &str, data_type: DataType, sql: &str| {
+        StructField::nullable(name, data_type)
+            .with_metadata([("delta.generationExpression", MetadataValue::from(sql))])
+    };
+    let schema = Arc::new(StructType::new(vec![
+        StructField::nullable("value", DataType::INTEGER),
+        generated_field("doubled", DataType::INTEGER, "value * 2"),
+        generated_field("big", DataType::BOOLEAN, "value > 10"),
+    ]));
+    let (store, engine, table_location) = setup("test_generated_columns", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_partition_columns(["big"])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    let commit = store
+        .get(&Path::from(
+            "/test_generated_columns/_delta_log/00000000000000000000.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(
+        actions[1]["protocol"]["writerFeatures"],
+        json!(["generatedColumns"])
+    );
+
+    // writes the given columns of the data to the given partition
+    let write = |columns: Vec<(&str, Vec<i32>)>, big: &str| {
+        let (fields, arrays): (Vec<_>, Vec<_>) = columns
+            .into_iter()
+            .map(|(name, values)| {
+                let field = Field::new(name, ArrowDataType::Int32, true);
+                let array: ArrayRef = Arc::new(Int32Array::from(values));
+                (field, array)
+            })
+            .unzip();
+        let data = RecordBatch::try_new(Arc::new(ArrowSchema::new(fields)), arrays);
+        let table = &table;
+        let engine = &engine;
+        let big = big.to_string();
+        async move {
+            let mut txn = table
+                .new_transaction(engine)?
+                .with_commit_info(new_commit_info()?);
+            let write_metadata = engine
+                .write_parquet(
+                    &ArrowEngineData::new(data?),
+                    &txn.get_write_context(),
+                    HashMap::from([("big".to_string(), big)]),
+                    true,
+                )
+                .await?;
+            txn.add_write_metadata(write_metadata);
+            txn.commit(engine)?;
+            Ok::<_, Box<dyn std::error::Error>>(())
+        }
+    };
+
+    // omitted generated columns get the values of their generation expression, and the values
+    // the data has must match it
+    write(vec![("value", vec![1, 2])], "false").await?;
+    write(vec![("value", vec![11]), ("doubled", vec![22])], "true").await?;
+    for (columns, big) in [
+        (
+            vec![("value", vec![3, 4]), ("doubled", vec![6, 7])],
+            "false",
+        ),
+        (vec![("value", vec![5])], "true"),
+    ] {
+        let result = write(columns, big).await;
+        let error = result.unwrap_err().downcast::<KernelError>()?;
+        assert!(matches!(*error, KernelError::InvalidGeneratedColumn(_)));
+    }
+
+    let engine = Arc::new(engine);
+    let scan = table
+        .snapshot(engine.as_ref(), None)?
+        .into_scan_builder()
+        .build()?;
+    let mut rows = vec![];
+    for batch in read_scan_results(scan.execute(engine.clone())?)? {
+        let values = batch.column(0).as_primitive::<Int32Type>();
+        let doubled = batch.column(1).as_primitive::<Int32Type>();
+        let big = batch.column(2).as_boolean();
+        rows.extend(
+            (0..batch.num_rows())
+                .map(|row| (values.value(row), doubled.value(row), big.value(row))),
+        );
+    }
+    rows.sort();
+    assert_eq!(rows, [(1, 2, false), (2, 4, false), (11, 22, true)]);
+
+    // the generated columns can't be changed
+    let mut txn = table.new_transaction(engine.as_ref())?;
+    let result = txn.update_schema(StructType::new(vec![
+        StructField::nullable("value", DataType::INTEGER),
+        generated_field("doubled", DataType::INTEGER, "value + value"),
+        generated_field("big", DataType::BOOLEAN, "value > 10"),
+    ]));
+    assert!(matches!(result, Err(KernelError::InvalidTransaction(_))));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_check_constraints() -> Result<(), Box<dyn std::error::Error>> {
+    // setup tracing
+    let _ = tracing_subscriber::fmt::try_init();
+    let invariant = json!({ "expression": { "expression": "value < 100" } });
+    let schema = Arc::new(StructType::new(vec![StructField::nullable(
+        "value",
+        DataType::INTEGER,
+    )
+    .with_metadata([("delta.invariants", invariant.to_string())])]));
+    let (store, engine, table_location) = setup("test_check_constraints", true);
+    let table = Table::create(table_location, schema.clone())
+        .with_table_properties([("delta.constraints.positive", "value > 0")])
+        .with_commit_info(new_commit_info()?)
+        .commit(&engine)?;
+    let commit = store
+        .get(&Path::from(
+            "/test_check_constraints/_delta_log/00000000000000000000.json",
+        ))
+        .await?;
+    let actions: Vec<serde_json::Value> = Deserializer::from_slice(&commit.bytes().await?)
+        .into_iter()
+        .try_collect()?;
+    assert_eq!(
+        actions[1]["protocol"]["writerFeatures"],
+        json!(["checkConstraints", "invariants"])
+    );
+
+    let engine = Arc::new(engine);
+    // writes the values to the table
+    let write = |values: Vec<Option<i32>>| {
+        let field = Field::new("value", ArrowDataType::Int32, true);
+        let data = RecordBatch::try_new(
+            Arc::new(ArrowSchema::new(vec![field])),
+            vec![Arc::new(Int32Array::from(values))],
+        );
+        let table = &table;
+        let engine = engine.clone();
+        async move {
+            let mut txn = table
+                .new_transaction(engine.as_ref())?
+                .with_commit_info(new_commit_info()?);
+            let write_metadata = engine
+                .write_parquet(
+                    &ArrowEngineData::new(data?),
+                    &txn.get_write_context(),
+                    HashMap::new(),
+                    true,
+                )
+                .await?;
+            txn.add_write_metadata(write_metadata);
+            txn.commit(engine.as_ref())?;
+            Ok::<_, Box<dyn std::error::Error>>(())
+        }
+    };
+    let assert_violation = |result: Result<(), Box<dyn std::error::Error>>| {
+        let error = result.unwrap_err().downcast::<KernelError>().unwrap();
+        assert!(matches!(*error, KernelError::InvalidConstraint(_)));
+    };
+
+    // every written row must satisfy the CHECK constraints and invariants, which nulls don't
+    write(vec![Some(1), Some(5)]).await?;
+    assert_violation(write(vec![Some(2), Some(-1)]).await);
+    assert_violation(write(vec![Some(100)]).await);
+    assert_violation(write(vec![None]).await);
+
+    // added constraints must hold for the existing rows
+    let mut txn = table.new_transaction(engine.as_ref())?;
+    let result = txn.add_constraint(engine.clone(), "small", "value < 5");
+    assert!(matches!(result, Err(KernelError::InvalidConstraint(_))));
+    let result = txn.add_constraint(engine.clone(), "positive", "value > 1");
+    assert!(matches!(result, Err(KernelError::InvalidTransaction(_))));
+    let result = txn.set_table_properties([("delta.constraints.small", "value < 10")]);
+    assert!(matches!(result, Err(KernelError::InvalidTransaction(_))));
+    txn.add_constraint(engine.clone(), "Small", "value < 10")?;
+    txn.with_commit_info(new_commit_info()?)
+        .commit(engine.as_ref())?;
+    let snapshot = table.snapshot(engine.as_ref(), None)?;
+    assert_eq!(
+        snapshot.table_properties().unknown_properties["delta.constraints.small"],
+        "value < 10"
+    );
+    assert_violation(write(vec![Some(20)]).await);
+
+    // dropped constraints no longer apply
+    let mut txn = table.new_transaction(engine.as_ref())?;
+    let result = txn.drop_constraint("missing");
+    assert!(matches!(result, Err(KernelError::InvalidTransaction(_))));
+    txn.drop_constraint("SMALL")?;
+    txn.with_commit_info(new_commit_info()?)
+        .commit(engine.as_ref())?;
+    write(vec![Some(20)]).await?;
+
+    // added constraints conflict with concurrent appends, whose rows weren't checked
+    let mut txn = table.new_transaction(engine.as_ref())?;
+    txn.add_constraint(engine.clone(), "small", "value < 30")?;
+    write(vec![Some(50)]).await?;
+    let result = txn
+        .with_commit_info(new_commit_info()?)
+        .commit(engine.as_ref());
+    assert!(matches!(
+        result,
+        Err(KernelError::TransactionConflict(
+            ConflictError::ConcurrentAppend(5)
+        ))
+    ));
+    Ok(())
+}